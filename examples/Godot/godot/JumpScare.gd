@@ -10,10 +10,10 @@ func _ready():
 func _process(delta):
 	pass
 
-func _on_twitch_event_chat_message(username, message):
-	message = message.to_lower();
+func _on_twitch_event_node_chat_message(message: Dictionary):
+	var text = message["message"]["text"].to_lower();
 
-	if message.contains("godot"):
+	if text.contains("godot"):
 		var shader = (material as ShaderMaterial);
 		var opacity = shader.get_shader_parameter("opacity");
 		shader.set_shader_parameter("opacity", 1.0 - opacity);
@@ -1,10 +1,6 @@
-use std::process;
+use std::iter;
 
-use godot::classes::ISprite2D;
-use godot::classes::Sprite2D;
-use godot::classes::{INode, INode2D, Node, Node2D};
-use godot::engine::display_server::ExWindowGetVSyncMode;
-use godot::engine::visual_shader_node_float_func;
+use godot::classes::{INode, Node};
 use godot::init::EditorRunBehavior;
 use godot::prelude::*;
 
@@ -19,178 +15,324 @@ unsafe impl ExtensionLibrary for TwitchApi {
   }
 }
 
+/// Add this node to a scene to receive Twitch events as signals. It generates tokens when
+/// there are none, saves them, and reconnects when the connection is lost.
+///
+/// Every event signal carries a Dictionary with the same fields Twitch documents for the
+/// event, e.g. `message["chatter_user_name"]` and `message["message"]["text"]` for chat.
 #[derive(GodotClass)]
 #[class(base=Node)]
-struct TwitchEvent {
+struct TwitchEventNode {
+  #[export]
+  redirect_url: GString,
+  #[export]
+  user_token_file: GString,
+  #[export]
+  refresh_token_file: GString,
+  #[export]
+  auto_reconnect: bool,
+  /// Seconds to wait before reconnecting.
+  #[export]
+  reconnect_delay: f64,
+
   twitch: Option<TwitchEventSubApi>,
+  reconnect_in: Option<f64>,
   base: Base<Node>,
 }
 
-#[derive(GodotClass, Debug, GodotConvert)]
-#[godot(transparent)]
-#[var]
-#[export]
-#[class(init)]
-pub struct GdRaidContainer {
-  pub data: Gd<GRaid>,
-}
+#[godot_api]
+impl TwitchEventNode {
+  #[signal]
+  fn connected();
+  #[signal]
+  fn disconnected(reason: GString);
+  #[signal]
+  fn error(message: GString);
 
-#[derive(GodotClass, Debug, Default)]
-#[var]
-#[export]
-#[class(init)]
-pub struct GRaid {
-  #[var]
-  pub raider_user_id: GString,
+  #[signal]
+  fn chat_message(message: Dictionary);
+  #[signal]
+  fn point_redeem(redeem: Dictionary);
+  #[signal]
+  fn raid(raid: Dictionary);
+  #[signal]
+  fn subscribe(subscription: Dictionary);
+  #[signal]
+  fn resubscribe(subscription: Dictionary);
+  #[signal]
+  fn gift_subscriptions(gift: Dictionary);
+  #[signal]
+  fn cheer(cheer: Dictionary);
+  #[signal]
+  fn ad_break_start(ad_break: Dictionary);
+  #[signal]
+  fn poll_begin(poll: Dictionary);
+  #[signal]
+  fn poll_progress(poll: Dictionary);
+  #[signal]
+  fn poll_end(poll: Dictionary);
+  #[signal]
+  fn prediction_begin(prediction: Dictionary);
+  #[signal]
+  fn prediction_progress(prediction: Dictionary);
+  #[signal]
+  fn prediction_lock(prediction: Dictionary);
+  #[signal]
+  fn prediction_end(prediction: Dictionary);
+  #[signal]
+  fn hype_train_begin(hype_train: Dictionary);
+  #[signal]
+  fn hype_train_progress(hype_train: Dictionary);
+  #[signal]
+  fn hype_train_end(hype_train: Dictionary);
+  /// Every event, including ones without their own signal.
+  #[signal]
+  fn event(data: Dictionary);
 
-  #[var]
-  pub raider_username: GString,
+  #[func]
+  fn connect_to_twitch(&mut self) {
+    self.reconnect_in = None;
+    let keys = match TwitchKeys::from_secrets_env() {
+      Ok(keys) => keys,
+      Err(e) => {
+        self.emit("error", &[GString::from(format!("{:?}", e)).to_variant()]);
+        return;
+      }
+    };
 
-  #[var]
-  pub viewers: u32,
-}
+    let twitch = TwitchEventSubApi::builder(keys)
+      .set_redirect_url(self.redirect_url.to_string())
+      .generate_new_token_if_insufficent_scope(true)
+      .generate_new_token_if_none(true)
+      .generate_access_token_on_expire(true)
+      .auto_save_load_created_tokens(
+        self.user_token_file.to_string(),
+        self.refresh_token_file.to_string(),
+      )
+      .add_subscriptions(vec![
+        Subscription::ChatMessage,
+        Subscription::ChannelPointsCustomRewardRedeem { reward_id: None },
+        Subscription::ChannelRaid {
+          direction: RaidDirection::Incoming,
+        },
+        Subscription::ChannelSubscribe,
+        Subscription::ChannelSubscriptionMessage,
+        Subscription::ChannelSubscriptionGift,
+        Subscription::ChannelCheer,
+        Subscription::AdBreakBegin,
+        Subscription::ChannelPollBegin,
+        Subscription::ChannelPollProgress,
+        Subscription::ChannelPollEnd,
+        Subscription::ChannelPredictionBegin,
+        Subscription::ChannelPredictionProgress,
+        Subscription::ChannelPredictionLock,
+        Subscription::ChannelPredictionEnd,
+        Subscription::ChannelHypeTrainBegin,
+        Subscription::ChannelHypeTrainProgress,
+        Subscription::ChannelHypeTrainEnd,
+        Subscription::BanTimeoutUser,
+        Subscription::DeleteMessage,
+      ])
+      .build();
 
-impl From<RaidData> for GRaid {
-  fn from(raid: RaidData) -> GRaid {
-    GRaid {
-      raider_user_id: raid.from_broadcaster.id.to_owned().into(),
-      raider_username: raid.from_broadcaster.name.to_owned().into(),
-      viewers: raid.viewers,
+    match twitch {
+      Ok(twitch) => self.twitch = Some(twitch),
+      Err(e) => {
+        self.emit("error", &[GString::from(e.to_string()).to_variant()]);
+        self.schedule_reconnect();
+      }
     }
   }
-}
 
-#[derive(GodotClass, Debug, GodotConvert)]
-#[godot(transparent)]
-#[var]
-#[export]
-#[class(init)]
-pub struct GdMessageContainer {
-  pub data: Gd<GMessageData>,
-}
-
-#[derive(GodotClass, Debug)]
-#[var]
-#[export]
-#[class(init)]
-pub struct GMessageData {
-  #[var]
-  pub user_id: GString,
+  #[func]
+  fn disconnect_from_twitch(&mut self) {
+    self.reconnect_in = None;
+    self.twitch = None;
+  }
 
-  #[var]
-  pub message_id: GString,
+  #[func]
+  fn is_connected(&self) -> bool {
+    self
+      .twitch
+      .as_ref()
+      .is_some_and(|twitch| twitch.connection_status().is_connected())
+  }
 
-  #[var]
-  pub message: GString,
+  #[func]
+  fn send_chat_message(&mut self, message: GString) -> bool {
+    match &mut self.twitch {
+      Some(twitch) => {
+        twitch.send_chat_message(message.to_string());
+        true
+      }
+      None => false,
+    }
+  }
 
-  #[var]
-  pub username: GString,
-}
+  #[func]
+  fn timeout_user(&mut self, user_id: GString, duration_secs: u32, reason: GString) -> bool {
+    let result = match &mut self.twitch {
+      Some(twitch) => twitch.timeout_user(user_id.to_string(), duration_secs, reason.to_string()),
+      None => return false,
+    };
 
-impl From<MessageData> for GMessageData {
-  fn from(msg: MessageData) -> GMessageData {
-    GMessageData {
-      user_id: msg.chatter_user.id.to_owned().into(),
-      message_id: msg.message_id.to_owned().into(),
-      message: msg.message.text.to_owned().into(),
-      username: msg.chatter_user.name.to_owned().into(),
+    match result {
+      Ok(()) => true,
+      Err(e) => {
+        self.emit("error", &[GString::from(e.to_string()).to_variant()]);
+        false
+      }
     }
   }
-}
 
-#[godot_api]
-impl TwitchEvent {
-  #[signal]
-  fn chat_message(message_data: GdMessageContainer);
+  #[func]
+  fn shoutout(&mut self, broadcaster_id: GString) -> bool {
+    let result = match &mut self.twitch {
+      Some(twitch) => twitch.send_shoutout(broadcaster_id.to_string()),
+      None => return false,
+    };
 
-  #[signal]
-  fn point_redeem(
-    username: GString,
-    input: GString,
-    redeem_cost: u32,
-    redeem_id: GString,
-    redeem_prompt: GString,
-    title: GString,
-  );
+    match result {
+      Ok(()) => true,
+      Err(e) => {
+        self.emit("error", &[GString::from(e.to_string()).to_variant()]);
+        false
+      }
+    }
+  }
 
-  #[signal]
-  fn ad_break_start(duration: u32);
+  fn handle_message(&mut self, message: MessageType) {
+    match message {
+      MessageType::Event { event, .. } => {
+        let data = event_dictionary(&event);
+        if let Some(signal) = event_signal(&event) {
+          self.emit(signal, &[data.to_variant()]);
+        }
+        self.emit("event", &[data.to_variant()]);
+      }
+      MessageType::ConnectionStatusChanged(status) if status.connection == 0 => {
+        match status.state {
+          ConnectionState::Connected { .. } => self.emit("connected", &[]),
+          ConnectionState::Disconnected { reason } => {
+            self.emit("disconnected", &[GString::from(reason).to_variant()]);
+            self.schedule_reconnect();
+          }
+          _ => {}
+        }
+      }
+      MessageType::Error(e) => {
+        self.emit("error", &[GString::from(e.to_string()).to_variant()]);
+      }
+      MessageType::Close => self.schedule_reconnect(),
+      _ => {}
+    }
+  }
 
-  #[signal]
-  fn raid(raid_info: GdRaidContainer);
+  fn schedule_reconnect(&mut self) {
+    self.twitch = None;
+    if self.auto_reconnect && self.reconnect_in.is_none() {
+      self.reconnect_in = Some(self.reconnect_delay);
+    }
+  }
+
+  fn emit(&mut self, signal: &str, args: &[Variant]) {
+    self.base_mut().emit_signal(signal.into(), args);
+  }
 }
 
 #[godot_api]
-impl INode for TwitchEvent {
+impl INode for TwitchEventNode {
   fn init(base: Base<Node>) -> Self {
-    Self { twitch: None, base }
+    Self {
+      redirect_url: "http://localhost:3000".into(),
+      user_token_file: ".user_token.env".into(),
+      refresh_token_file: ".refresh_token.env".into(),
+      auto_reconnect: true,
+      reconnect_delay: 5.0,
+      twitch: None,
+      reconnect_in: None,
+      base,
+    }
   }
 
   fn ready(&mut self) {
-    let keys = TwitchKeys::from_secrets_env().unwrap();
-    let redirect_url = "http://localhost:3000";
-
-    let twitch = TwitchEventSubApi::builder(keys)
-      .set_redirect_url(redirect_url)
-      .generate_new_token_if_insufficent_scope(true)
-      .generate_new_token_if_none(true)
-      .generate_access_token_on_expire(true)
-      .auto_save_load_created_tokens(".user_token.env", ".refresh_token.env")
-      .add_subscription(Subscription::ChatMessage)
-      .add_subscription(Subscription::ChannelPointsCustomRewardRedeem)
-      .add_subscription(Subscription::BanTimeoutUser)
-      .add_subscription(Subscription::DeleteMessage)
-      .add_subscription(Subscription::AdBreakBegin)
-      .add_subscription(Subscription::ChannelRaid)
-      .build()
-      .unwrap();
-    self.twitch = Some(twitch);
+    self.connect_to_twitch();
   }
 
   fn process(&mut self, delta: f64) {
-    if let Some(ref mut api) = &mut self.twitch {
-      for message in api.receive_messages() {
-        godot_print!("{:?}", message);
-        match message {
-          MessageType::Event(Event::ChatMessage(message_data)) => {
-            self.base_mut().emit_signal(
-              "chat_message".into(),
-              &[GdMessageContainer {
-                data: Gd::from_object(GMessageData::from(message_data)),
-              }
-              .to_variant()],
-            );
-          }
-          MessageType::Event(Event::Raid(raid_info)) => {
-            self.base_mut().emit_signal(
-              "raid".into(),
-              &[GdRaidContainer {
-                data: Gd::from_object(GRaid::from(raid_info)),
-              }
-              .to_variant()],
-            );
-          }
-          //MessageType::AdBreakNotification(duration) => {
-          //  self
-          //    .base_mut()
-          //    .emit_signal("ad_break_start".into(), &[duration.to_variant()]);
-          //}
-          //MessageType::CustomRedeem((username, input, reward)) => {
-          //  self.base_mut().emit_signal(
-          //    "point_redeem".into(),
-          //    &[
-          //      username.to_variant(),
-          //      input.to_variant(),
-          //      reward.cost.to_variant(),
-          //      reward.id.to_variant(),
-          //      reward.prompt.to_variant(),
-          //      reward.title.to_variant(),
-          //    ],
-          //  );
-          //}
-          _ => {}
-        }
+    if let Some(reconnect_in) = &mut self.reconnect_in {
+      *reconnect_in -= delta;
+      if *reconnect_in <= 0.0 {
+        self.connect_to_twitch();
+      }
+      return;
+    }
+
+    // Taken first so signals can be emitted while handling them
+    let messages = match &self.twitch {
+      Some(twitch) => iter::from_fn(|| twitch.try_recv()).collect::<Vec<_>>(),
+      None => return,
+    };
+    for message in messages {
+      self.handle_message(message);
+    }
+  }
+}
+
+fn event_signal(event: &Event) -> Option<&'static str> {
+  Some(match event {
+    Event::ChatMessage(_) => "chat_message",
+    Event::PointsCustomRewardRedeem(_) => "point_redeem",
+    Event::Raid(_) => "raid",
+    Event::Subscribe(_) => "subscribe",
+    Event::SubscriptionMessage(_) => "resubscribe",
+    Event::SubscriptionGift(_) => "gift_subscriptions",
+    Event::Cheer(_) => "cheer",
+    Event::AdBreakBegin(_) => "ad_break_start",
+    Event::PollBegin(_) => "poll_begin",
+    Event::PollProgress(_) => "poll_progress",
+    Event::PollEnd(_) => "poll_end",
+    Event::PredictionBegin(_) => "prediction_begin",
+    Event::PredictionProgress(_) => "prediction_progress",
+    Event::PredictionLock(_) => "prediction_lock",
+    Event::PredictionEnd(_) => "prediction_end",
+    Event::HypeTrainBegin(_) => "hype_train_begin",
+    Event::HypeTrainProgress(_) => "hype_train_progress",
+    Event::HypeTrainEnd(_) => "hype_train_end",
+    _ => return None,
+  })
+}
+
+// Events serialise to the json Twitch sent, which maps straight onto Godot types
+fn event_dictionary(event: &Event) -> Dictionary {
+  match serde_json::to_value(event).map(json_to_variant) {
+    Ok(data) => data.try_to::<Dictionary>().unwrap_or_default(),
+    Err(_) => Dictionary::new(),
+  }
+}
+
+fn json_to_variant(value: serde_json::Value) -> Variant {
+  match value {
+    serde_json::Value::Null => Variant::nil(),
+    serde_json::Value::Bool(b) => b.to_variant(),
+    serde_json::Value::Number(n) => match n.as_i64() {
+      Some(n) => n.to_variant(),
+      None => n.as_f64().unwrap_or_default().to_variant(),
+    },
+    serde_json::Value::String(s) => GString::from(s).to_variant(),
+    serde_json::Value::Array(values) => {
+      let mut array = VariantArray::new();
+      for value in values {
+        array.push(json_to_variant(value));
+      }
+      array.to_variant()
+    }
+    serde_json::Value::Object(fields) => {
+      let mut dictionary = Dictionary::new();
+      for (key, value) in fields {
+        dictionary.set(GString::from(key), json_to_variant(value));
       }
+      dictionary.to_variant()
     }
   }
 }
@@ -0,0 +1,54 @@
+using TwitchEventSub;
+using UnityEngine;
+
+// Spins the object it's on when chat mentions Unity, and thanks new followers
+public class ChatReactions : MonoBehaviour
+{
+    public TwitchEventSubClient twitch;
+    public float spinSpeed = 360.0f;
+
+    private float spinTime;
+
+    private void OnEnable()
+    {
+        twitch.ChatMessageReceived += OnChatMessage;
+        twitch.RewardRedeemed += OnRewardRedeemed;
+        twitch.Followed += OnFollowed;
+    }
+
+    private void OnDisable()
+    {
+        twitch.ChatMessageReceived -= OnChatMessage;
+        twitch.RewardRedeemed -= OnRewardRedeemed;
+        twitch.Followed -= OnFollowed;
+    }
+
+    private void Update()
+    {
+        if (spinTime > 0.0f)
+        {
+            spinTime -= Time.deltaTime;
+            transform.Rotate(0.0f, spinSpeed * Time.deltaTime, 0.0f);
+        }
+    }
+
+    private void OnChatMessage(ChatMessage message)
+    {
+        if (message.message.text.ToLower().Contains("unity"))
+        {
+            Debug.Log(message.chatter_user_name + " sent a message containing the word unity!");
+            spinTime = 2.0f;
+        }
+    }
+
+    private void OnRewardRedeemed(RewardRedeem redeem)
+    {
+        Debug.Log(redeem.user_name + " redeemed " + redeem.reward.title);
+        spinTime = 5.0f;
+    }
+
+    private void OnFollowed(Follow follow)
+    {
+        twitch.SendChatMessage("Thanks for the follow " + follow.user_name + "!");
+    }
+}
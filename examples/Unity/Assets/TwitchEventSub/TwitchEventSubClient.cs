@@ -0,0 +1,207 @@
+using System;
+using UnityEngine;
+
+namespace TwitchEventSub
+{
+    /// <summary>
+    /// Add this to a GameObject to receive Twitch events. Messages are polled every frame and
+    /// the events below are raised on the main thread, so handlers can touch the scene.
+    /// </summary>
+    public class TwitchEventSubClient : MonoBehaviour
+    {
+        [Tooltip("Read the keys from the environment and .env files instead of the fields below")]
+        public bool keysFromEnv = true;
+        public string clientId;
+        public string clientSecret;
+        public string broadcasterId;
+        [Tooltip("Leave empty to generate a token through the browser, which needs the redirect url")]
+        public string userAccessToken;
+        public string refreshToken;
+        [Tooltip("Where the browser returns to when generating a token, e.g. http://localhost:3000. With keysFromEnv it's read from TWITCH_REDIRECT_URL")]
+        public string redirectUrl = "http://localhost:3000";
+
+        [Tooltip("EventSub subscription types, e.g. channel.chat.message")]
+        public string[] subscriptions =
+        {
+            "channel.chat.message",
+            "channel.channel_points_custom_reward_redemption.add",
+            "channel.follow",
+        };
+
+        public bool connectOnStart = true;
+
+        public event Action<ChatMessage> ChatMessageReceived;
+        public event Action<RewardRedeem> RewardRedeemed;
+        public event Action<Follow> Followed;
+        /// <summary>Every event, with its subscription type and the JSON Twitch sent.</summary>
+        public event Action<string, string> EventReceived;
+        public event Action<bool> ConnectionChanged;
+        public event Action<string> Error;
+
+        private IntPtr client = IntPtr.Zero;
+
+        public bool IsConnected => client != IntPtr.Zero;
+
+        /// <summary>The access token in use, which may have been refreshed, so it can be saved.</summary>
+        public string AccessToken => IsConnected
+            ? TwitchEventSubNative.TakeString(TwitchEventSubNative.twitch_eventsub_access_token(client))
+            : null;
+
+        public string RefreshToken => IsConnected
+            ? TwitchEventSubNative.TakeString(TwitchEventSubNative.twitch_eventsub_refresh_token(client))
+            : null;
+
+        private void Start()
+        {
+            if (connectOnStart)
+            {
+                Connect();
+            }
+        }
+
+        /// <summary>Connects to Twitch, which blocks until the connection is made.</summary>
+        public bool Connect()
+        {
+            Disconnect();
+
+            UIntPtr count = (UIntPtr)subscriptions.Length;
+            client = keysFromEnv
+                ? TwitchEventSubNative.twitch_eventsub_create_from_env(subscriptions, count)
+                : TwitchEventSubNative.twitch_eventsub_create(
+                    clientId,
+                    clientSecret,
+                    broadcasterId,
+                    NullIfEmpty(userAccessToken),
+                    NullIfEmpty(refreshToken),
+                    NullIfEmpty(redirectUrl),
+                    subscriptions,
+                    count);
+
+            if (client == IntPtr.Zero)
+            {
+                RaiseError(TwitchEventSubNative.LastError());
+                return false;
+            }
+            return true;
+        }
+
+        public void Disconnect()
+        {
+            if (client == IntPtr.Zero)
+            {
+                return;
+            }
+
+            TwitchEventSubNative.twitch_eventsub_destroy(client);
+            client = IntPtr.Zero;
+            ConnectionChanged?.Invoke(false);
+        }
+
+        public bool SendChatMessage(string message)
+        {
+            if (!IsConnected)
+            {
+                return false;
+            }
+
+            if (!TwitchEventSubNative.twitch_eventsub_send_chat_message(client, message))
+            {
+                RaiseError(TwitchEventSubNative.LastError());
+                return false;
+            }
+            return true;
+        }
+
+        private void Update()
+        {
+            while (IsConnected)
+            {
+                string json = TwitchEventSubNative.TakeString(TwitchEventSubNative.twitch_eventsub_poll_event(client));
+                if (json == null)
+                {
+                    break;
+                }
+                Dispatch(json);
+            }
+        }
+
+        private void OnDestroy()
+        {
+            Disconnect();
+        }
+
+        private void Dispatch(string json)
+        {
+            PolledMessage polled = JsonUtility.FromJson<PolledMessage>(json);
+            if (!string.IsNullOrEmpty(polled.type))
+            {
+                HandleStatus(polled);
+                return;
+            }
+
+            string subscriptionType = polled.metadata.subscription_type;
+            EventReceived?.Invoke(subscriptionType, json);
+            switch (subscriptionType)
+            {
+                case "channel.chat.message":
+                    ChatMessageReceived?.Invoke(ReadEvent<ChatMessage>(json));
+                    break;
+                case "channel.channel_points_custom_reward_redemption.add":
+                    RewardRedeemed?.Invoke(ReadEvent<RewardRedeem>(json));
+                    break;
+                case "channel.follow":
+                    Followed?.Invoke(ReadEvent<Follow>(json));
+                    break;
+            }
+        }
+
+        private void HandleStatus(PolledMessage polled)
+        {
+            switch (polled.type)
+            {
+                case "error":
+                    RaiseError(polled.message);
+                    break;
+                case "connection_status":
+                    // The first connection stands for the client, more are only opened
+                    // when it runs out of room for subscriptions
+                    if (polled.connection == 0)
+                    {
+                        ConnectionChanged?.Invoke(polled.connected);
+                    }
+                    break;
+                case "events_dropped":
+                    RaiseError(polled.count + " events were dropped");
+                    break;
+                case "chat_message_dropped":
+                    RaiseError("Chat message wasn't sent: " + polled.reason);
+                    break;
+                case "close":
+                    Disconnect();
+                    break;
+            }
+        }
+
+        private void RaiseError(string message)
+        {
+            if (Error != null)
+            {
+                Error.Invoke(message);
+            }
+            else
+            {
+                Debug.LogError("Twitch: " + message);
+            }
+        }
+
+        private static T ReadEvent<T>(string json)
+        {
+            return JsonUtility.FromJson<Notification<T>>(json).payload.@event;
+        }
+
+        private static string NullIfEmpty(string value)
+        {
+            return string.IsNullOrEmpty(value) ? null : value;
+        }
+    }
+}
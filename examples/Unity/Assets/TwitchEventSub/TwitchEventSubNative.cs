@@ -0,0 +1,77 @@
+using System;
+using System.Runtime.InteropServices;
+
+namespace TwitchEventSub
+{
+    // P/Invoke declarations for include/twitch_eventsub.h. Strings the library returns are
+    // owned by the caller and must go back through twitch_eventsub_free_string.
+    internal static class TwitchEventSubNative
+    {
+        private const string Library = "twitch_eventsub";
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        internal static extern IntPtr twitch_eventsub_create(
+            [MarshalAs(UnmanagedType.LPUTF8Str)] string clientId,
+            [MarshalAs(UnmanagedType.LPUTF8Str)] string clientSecret,
+            [MarshalAs(UnmanagedType.LPUTF8Str)] string broadcasterId,
+            [MarshalAs(UnmanagedType.LPUTF8Str)] string userAccessToken,
+            [MarshalAs(UnmanagedType.LPUTF8Str)] string refreshToken,
+            [MarshalAs(UnmanagedType.LPUTF8Str)] string redirectUrl,
+            [MarshalAs(UnmanagedType.LPArray, ArraySubType = UnmanagedType.LPUTF8Str)] string[] subscriptions,
+            UIntPtr subscriptionCount);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        internal static extern IntPtr twitch_eventsub_create_from_env(
+            [MarshalAs(UnmanagedType.LPArray, ArraySubType = UnmanagedType.LPUTF8Str)] string[] subscriptions,
+            UIntPtr subscriptionCount);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        internal static extern void twitch_eventsub_destroy(IntPtr client);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        internal static extern IntPtr twitch_eventsub_poll_event(IntPtr client);
+
+        // Rust's bool is one byte, not the four byte BOOL C# assumes
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        [return: MarshalAs(UnmanagedType.I1)]
+        internal static extern bool twitch_eventsub_send_chat_message(
+            IntPtr client,
+            [MarshalAs(UnmanagedType.LPUTF8Str)] string message);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        internal static extern IntPtr twitch_eventsub_access_token(IntPtr client);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        internal static extern IntPtr twitch_eventsub_refresh_token(IntPtr client);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        internal static extern IntPtr twitch_eventsub_last_error();
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        internal static extern void twitch_eventsub_free_string(IntPtr value);
+
+        // Copies a string the library returned and frees it
+        internal static string TakeString(IntPtr value)
+        {
+            if (value == IntPtr.Zero)
+            {
+                return null;
+            }
+
+            try
+            {
+                return Marshal.PtrToStringUTF8(value);
+            }
+            finally
+            {
+                twitch_eventsub_free_string(value);
+            }
+        }
+
+        internal static string LastError()
+        {
+            IntPtr error = twitch_eventsub_last_error();
+            return error == IntPtr.Zero ? "Unknown error" : Marshal.PtrToStringUTF8(error);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+using System;
+
+namespace TwitchEventSub
+{
+    // Field names match the JSON Twitch sends, which is what JsonUtility reads them by.
+    // Anything not here is still in the raw JSON given to TwitchEventSubClient.EventReceived.
+
+    [Serializable]
+    public class ChatMessage
+    {
+        public string broadcaster_user_id;
+        public string broadcaster_user_login;
+        public string broadcaster_user_name;
+        public string chatter_user_id;
+        public string chatter_user_login;
+        public string chatter_user_name;
+        public string message_id;
+        public ChatMessageText message;
+        public string color;
+        public string message_type;
+    }
+
+    [Serializable]
+    public class ChatMessageText
+    {
+        public string text;
+    }
+
+    [Serializable]
+    public class RewardRedeem
+    {
+        public string id;
+        public string broadcaster_user_id;
+        public string broadcaster_user_login;
+        public string broadcaster_user_name;
+        public string user_id;
+        public string user_login;
+        public string user_name;
+        public string user_input;
+        public string status;
+        public Reward reward;
+        public string redeemed_at;
+    }
+
+    [Serializable]
+    public class Reward
+    {
+        public string id;
+        public string title;
+        public int cost;
+        public string prompt;
+    }
+
+    [Serializable]
+    public class Follow
+    {
+        public string user_id;
+        public string user_login;
+        public string user_name;
+        public string broadcaster_user_id;
+        public string broadcaster_user_login;
+        public string broadcaster_user_name;
+        public string followed_at;
+    }
+
+    // Notifications have metadata, the library's own messages have a type instead, see
+    // twitch_eventsub_poll_event
+    [Serializable]
+    internal class PolledMessage
+    {
+        public Metadata metadata;
+        public string type;
+        public string message;
+        public int connection;
+        public bool connected;
+        public int count;
+        public string reason;
+    }
+
+    [Serializable]
+    internal class Metadata
+    {
+        public string message_id;
+        public string message_type;
+        public string message_timestamp;
+        public string subscription_type;
+        public string subscription_version;
+    }
+
+    [Serializable]
+    internal class Notification<T>
+    {
+        public NotificationPayload<T> payload;
+    }
+
+    [Serializable]
+    internal class NotificationPayload<T>
+    {
+        public T @event;
+    }
+}
@@ -0,0 +1,74 @@
+/* C interface to twitch_eventsub, built with the `ffi` feature:
+ *
+ *   cargo rustc --release --features ffi --crate-type cdylib
+ *
+ * Regenerate with `cbindgen --config cbindgen.toml --output include/twitch_eventsub.h`
+ * after changing src/modules/ffi.rs.
+ *
+ * Strings returned by the library belong to the caller and are freed with
+ * twitch_eventsub_free_string. Functions that fail return NULL or false, and
+ * twitch_eventsub_last_error says why. A client may be used from one thread at a time.
+ */
+
+#ifndef TWITCH_EVENTSUB_H
+#define TWITCH_EVENTSUB_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque handle to a client. */
+typedef struct TwitchEventSubClient TwitchEventSubClient;
+
+/* Connects a client for the broadcaster. `subscriptions` are EventSub types such as
+ * "channel.chat.message". Without an access token one is generated through the browser,
+ * which needs redirect_url, such as "http://localhost:3000". The tokens and redirect_url
+ * may be NULL. */
+TwitchEventSubClient *twitch_eventsub_create(const char *client_id,
+                                             const char *client_secret,
+                                             const char *broadcaster_id,
+                                             const char *user_access_token,
+                                             const char *refresh_token,
+                                             const char *redirect_url,
+                                             const char *const *subscriptions,
+                                             size_t subscription_count);
+
+/* Like twitch_eventsub_create with the keys read from the environment and .env files,
+ * and the redirect url from TWITCH_REDIRECT_URL. */
+TwitchEventSubClient *twitch_eventsub_create_from_env(const char *const *subscriptions,
+                                                      size_t subscription_count);
+
+/* Closes the client and frees it. */
+void twitch_eventsub_destroy(TwitchEventSubClient *client);
+
+/* The next message as JSON without blocking, or NULL when there isn't one. Events are the
+ * notification exactly as Twitch sent it, chat read over IRC has just the metadata's
+ * "message_type" and "subscription_type" and the payload's "event". Anything else is an
+ * object with a "type", such as {"type": "error", "message": "..."}. */
+char *twitch_eventsub_poll_event(TwitchEventSubClient *client);
+
+/* Queues a chat message to the broadcaster's chat. */
+bool twitch_eventsub_send_chat_message(TwitchEventSubClient *client, const char *message);
+
+/* The access token in use, which may have been generated or refreshed since the client
+ * was created, so it can be saved. */
+char *twitch_eventsub_access_token(const TwitchEventSubClient *client);
+
+/* The refresh token in use, see twitch_eventsub_access_token. */
+char *twitch_eventsub_refresh_token(const TwitchEventSubClient *client);
+
+/* The last error on this thread, or NULL. Owned by the library and valid until the next
+ * call that fails. */
+const char *twitch_eventsub_last_error(void);
+
+/* Frees a string returned by this library. */
+void twitch_eventsub_free_string(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TWITCH_EVENTSUB_H */
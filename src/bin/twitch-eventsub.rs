@@ -0,0 +1,233 @@
+// Command line tool for setting up tokens, watching events and sending chat. Keys are read
+// from .secrets.env the same as `TwitchKeys::from_secrets_env`.
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use twitch_eventsub::*;
+
+#[derive(Parser)]
+#[command(
+  name = "twitch-eventsub",
+  about = "Watch Twitch EventSub events and send chat"
+)]
+struct Cli {
+  /// Where Twitch sends the browser back to when generating a token.
+  #[arg(long, default_value = "http://localhost:3000", global = true)]
+  redirect_url: String,
+  #[arg(long, default_value = ".user_token.env", global = true)]
+  token_file: String,
+  #[arg(long, default_value = ".refresh_token.env", global = true)]
+  refresh_token_file: String,
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Asks for the app's keys and which events to watch, then authorises them and saves
+  /// everything to .secrets.env.
+  Setup {
+    #[arg(long, default_value = ".secrets.env")]
+    env_file: String,
+  },
+  /// Generates a token with every scope the other commands need and saves it.
+  Auth,
+  /// Prints events as they arrive.
+  Watch {
+    /// Event types to subscribe to, e.g. channel.chat.message. By default the ones chosen in
+    /// `setup`, or chat and alerts.
+    /// `twitch-eventsub events` lists them.
+    #[arg(long, value_delimiter = ',')]
+    events: Vec<String>,
+    /// Prints each event's json instead of a summary.
+    #[arg(long)]
+    json: bool,
+  },
+  /// Sends a chat message to the broadcaster's chat.
+  Chat { message: String },
+  /// Times a user out.
+  Timeout {
+    login: String,
+    /// How long for, in seconds.
+    #[arg(default_value_t = 600)]
+    seconds: u32,
+    #[arg(long, default_value = "")]
+    reason: String,
+  },
+  /// Lists the event types that can be watched.
+  Events,
+}
+
+fn main() -> ExitCode {
+  let cli = Cli::parse();
+  match run(cli) {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(e) => {
+      eprintln!("{}", e);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+  match &cli.command {
+    Command::Setup { env_file } => {
+      let _ = setup_wizard(env_file).map_err(|e| e.to_string())?;
+      println!("Run `twitch-eventsub watch` to see events as they arrive");
+    }
+    Command::Auth => {
+      connect(&cli, default_subscriptions())?;
+      println!("Token saved to {}", cli.token_file);
+    }
+    Command::Watch { events, json } => {
+      let subscriptions = events
+        .iter()
+        .map(|tag| Subscription::from_string(tag).ok_or(format!("Unknown event type {}", tag)))
+        .collect::<Result<_, _>>()?;
+      watch(connect(&cli, subscriptions)?, *json);
+    }
+    Command::Chat { message } => {
+      let mut twitch = connect(&cli, vec![Subscription::ChatMessage])?;
+      let ids = twitch
+        .send_chat_message(message.as_str())
+        .message_ids()
+        .map_err(|e| e.to_string())?;
+      println!("Sent {}", ids.join(", "));
+    }
+    Command::Timeout {
+      login,
+      seconds,
+      reason,
+    } => {
+      let mut twitch = connect(&cli, vec![Subscription::BanTimeoutUser])?;
+      let user = twitch
+        .lookup_user_by_login(login)
+        .map_err(|e| e.to_string())?
+        .ok_or(format!("No user called {}", login))?;
+      twitch
+        .timeout_user(user.id, *seconds, reason.as_str())
+        .map_err(|e| e.to_string())?;
+      println!("Timed out {} for {}s", user.display_name, seconds);
+    }
+    Command::Events => {
+      for subscription in Subscription::all() {
+        let tag = subscription.tag();
+        if !tag.is_empty() {
+          println!("{}", tag);
+        }
+      }
+    }
+  }
+
+  Ok(())
+}
+
+// Chat, timeouts and the events an alerts overlay shows
+fn default_subscriptions() -> Vec<Subscription> {
+  vec![
+    Subscription::ChatMessage,
+    Subscription::BanTimeoutUser,
+    Subscription::StreamOnline,
+    Subscription::StreamOffline,
+    Subscription::ChannelFollow,
+    Subscription::ChannelRaid {
+      direction: RaidDirection::Incoming,
+    },
+    Subscription::ChannelSubscribe,
+    Subscription::ChannelSubscriptionGift,
+    Subscription::ChannelSubscriptionMessage,
+    Subscription::ChannelCheer,
+    Subscription::ChannelPointsCustomRewardRedeem { reward_id: None },
+  ]
+}
+
+fn connect(cli: &Cli, subscriptions: Vec<Subscription>) -> Result<TwitchEventSubApi, String> {
+  let keys = TwitchKeys::from_secrets_env().map_err(|e| e.to_string())?;
+  // Every command's scopes are asked for so the saved token works for all of them
+  let scopes = default_subscriptions()
+    .iter()
+    .flat_map(Subscription::required_scope)
+    .collect();
+  let mut builder = TwitchEventSubApi::builder(keys).add_subscriptions(subscriptions);
+  // The events chosen in `setup`, or chat and alerts
+  if builder.subscriptions().is_empty() {
+    builder = builder.add_subscriptions_from_env();
+  }
+  if builder.subscriptions().is_empty() {
+    builder = builder.add_subscriptions(default_subscriptions());
+  }
+
+  builder
+    .set_redirect_url(&cli.redirect_url)
+    .generate_new_token_if_insufficent_scope(true)
+    .generate_new_token_if_none(true)
+    .generate_access_token_on_expire(true)
+    .auto_save_load_created_tokens(&cli.token_file, &cli.refresh_token_file)
+    .add_scopes(scopes)
+    .build()
+    .map_err(|e| e.to_string())
+}
+
+fn watch(twitch: TwitchEventSubApi, json: bool) {
+  for message in &twitch {
+    match message {
+      MessageType::Event { event, raw } => {
+        if json {
+          // Chat read over IRC has no json from Twitch, so the event is printed instead
+          let json = raw.unwrap_or_else(|| serde_json::to_string(&event).unwrap_or_default());
+          println!("{}", json);
+        } else {
+          println!("{}", describe(&event));
+        }
+      }
+      MessageType::ConnectionStatusChanged(status) => {
+        eprintln!("Connection {}: {:?}", status.connection, status.state)
+      }
+      MessageType::Error(e) => eprintln!("Error: {}", e),
+      MessageType::Close => break,
+      _ => {}
+    }
+  }
+}
+
+fn describe(event: &Event) -> String {
+  match event {
+    Event::ChatMessage(message) => {
+      format!("{}: {}", message.chatter_user.name, message.message.text)
+    }
+    Event::Raid(raid) => format!(
+      "{} raided with {} viewers",
+      raid.from_broadcaster.name, raid.viewers
+    ),
+    Event::Follow(follow) => format!("{} followed", follow.user.name),
+    Event::ChannelUpdate(update) => format!(
+      "{} is now playing {}: {}",
+      update.broadcaster.name, update.category_name, update.title
+    ),
+    Event::Cheer(cheer) => {
+      let cheerer = cheer.user.as_ref().map_or("Anonymous", |user| &user.name);
+      format!("{} cheered {} bits: {}", cheerer, cheer.bits, cheer.message)
+    }
+    Event::Subscribe(sub) => format!("{} subscribed at tier {}", sub.user.name, sub.tier),
+    Event::SubscriptionEnd(sub) => format!("{}'s tier {} sub ended", sub.user.name, sub.tier),
+    Event::SubscriptionMessage(sub) => format!(
+      "{} resubscribed for {} months: {}",
+      sub.user.name, sub.cumulative_months, sub.message.text
+    ),
+    Event::SubscriptionGift(gift) => {
+      let gifter = gift.user.as_ref().map_or("Anonymous", |user| &user.name);
+      format!("{} gifted {} subs", gifter, gift.total)
+    }
+    Event::PointsCustomRewardRedeem(redeem) => format!(
+      "{} redeemed {}: {}",
+      redeem.user.name, redeem.reward.title, redeem.user_input
+    ),
+    Event::StreamOnline(stream) => format!("{} went live", stream.broadcaster.name),
+    Event::StreamOffline(stream) => format!("{} went offline", stream.broadcaster.name),
+    _ => format!(
+      "[{}] {}",
+      event.subscription_type(),
+      serde_json::to_string(event).unwrap_or_default()
+    ),
+  }
+}
@@ -1,65 +1,190 @@
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs;
-use std::net::TcpStream;
-use std::sync::mpsc::{channel, Receiver as SyncReceiver, Sender as SyncSender};
+#[cfg(not(target_arch = "wasm32"))]
+use std::process::Command;
+#[cfg(all(feature = "chat", not(target_arch = "wasm32")))]
+use std::sync::mpsc::channel;
+#[cfg(not(target_arch = "wasm32"))]
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
 
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
 use std::sync::{Arc, Mutex};
 
+#[cfg(not(target_arch = "wasm32"))]
 use crate::modules::consts::*;
-use open;
-use serde::Serialize;
+#[cfg(not(target_arch = "wasm32"))]
 use std::io::{ErrorKind, Read};
 
+#[cfg(not(target_arch = "wasm32"))]
 use websocket::client::ClientBuilder;
-use websocket::stream::sync::TlsStream;
+#[cfg(not(target_arch = "wasm32"))]
+use websocket::result::WebSocketOtherError;
+#[cfg(not(target_arch = "wasm32"))]
+use websocket::stream::sync::NetworkStream;
+#[cfg(not(target_arch = "wasm32"))]
 pub use websocket::WebSocketError;
+#[cfg(not(target_arch = "wasm32"))]
 use websocket::{sync::Client, OwnedMessage};
 
+#[cfg(not(target_arch = "wasm32"))]
 use std::net::TcpListener;
-
 mod modules;
 
-use crate::modules::{errors::*, generic_message::*, token::Token};
+#[cfg(all(feature = "chat", not(target_arch = "wasm32")))]
+use crate::modules::chat_queue::ChatQueue;
+#[cfg(all(not(feature = "only_raw_responses"), not(target_arch = "wasm32")))]
+use crate::modules::dedup::MessageDeduplicator;
+#[cfg(all(not(feature = "only_raw_responses"), not(target_arch = "wasm32")))]
+use crate::modules::pagination::wait_until;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::modules::{
+  errors::*,
+  event_stream::StreamFilter,
+  generic_message::*,
+  message_channel::{message_channel, MessageReceiver, MessageSender},
+  metrics,
+  sinks::EventSinks,
+  twitch_http::{refresh_user_token, set_url_overrides, websocket_url, FromHttpRequest},
+};
 
+#[cfg(not(feature = "tracing"))]
 pub use log::{error, info, warn};
+#[cfg(feature = "tracing")]
+pub use tracing::{error, info, warn};
+
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub use crate::modules::testing;
+
+#[cfg(all(feature = "irc", not(target_arch = "wasm32")))]
+pub use crate::modules::irc::{parse_chat_line, IrcAuth, IrcChat};
+#[cfg(all(feature = "discord", not(target_arch = "wasm32")))]
+pub use crate::modules::sinks::discord::{DiscordAlert, DiscordEmbed, DiscordWebhookSink};
+#[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+pub use crate::modules::sinks::mqtt::MqttSink;
+#[cfg(all(feature = "redis", not(target_arch = "wasm32")))]
+pub use crate::modules::sinks::redis::RedisSink;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub use crate::modules::sinks::sqlite::{ChatterCount, RewardCount, SqliteSink, SQLITE_SCHEMA};
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub use crate::modules::testing::MockEventSubServer;
+#[cfg(feature = "encrypted_storage")]
+pub use crate::modules::token_storage::EncryptedFileStorage;
+#[cfg(feature = "keyring")]
+pub use crate::modules::token_storage::KeyringTokenStorage;
+#[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+pub use rumqttc::{MqttOptions, QoS};
 
 use serde_derive::{Deserialize as Deserialise, Serialize as Serialise};
 
+#[cfg(all(feature = "chat", not(target_arch = "wasm32")))]
+pub use crate::modules::{
+  chat_queue::{ChatMessageBuilder, ChatRateLimit, ChatSendResult, PendingChatMessage},
+  commands::{ChatCommand, CommandContext, CommandOutcome, Commands, Permission},
+};
+
+#[cfg(target_arch = "wasm32")]
+pub use crate::modules::browser::{BrowserEventSub, BrowserKeys};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::modules::{
+  batch::{batched, batched_parallel},
+  event_stream::EventStream,
+  gifts::{CommunityGift, GiftTracker},
+  message_channel::OverflowPolicy,
+  pagination::{Page, Paginated},
+  recording::{EventRecorder, EventReplayer},
+  setup::setup_wizard,
+  sinks::overlay_server::OverlayServer,
+  subscriptions::SubscriptionHandle,
+  twitch_http::{
+    add_http_middleware, clear_http_middleware, set_default_http_timeouts, ApiUrls, AuthType,
+    CancellationToken, Header, HttpMiddleware, HttpTimeouts, RateLimit, RefreshTokenProvider,
+    RequestBuilder, RequestType, TokenProvider, TwitchApi, TwitchApiResponse, TwitchError,
+    TwitchHttpRequest,
+  },
+};
+
 pub use crate::modules::{
-  errors::EventSubError,
-  generic_message::{Event, Reward, Transport},
-  messages::{MessageData, MessageType, RaidData},
-  subscriptions::{Condition, EventSubscription, Subscription},
-  token::{TokenAccess, TwitchKeys},
-  twitch_http::{AuthType, RequestType, TwitchApi, TwitchHttpRequest},
+  errors::{EventSubError, TwitchKeysError},
+  event_stream::FromEvent,
+  generic_message::{
+    Badge, Cheer, CheerMote, DeviceCode, Emote, EmoteFormat, EmoteScale, EmoteTheme, Event,
+    Fragments, GMSubscription, GenericMessage, Mention, Message, MetaData, Payload, Reward,
+    Session, Transport, Validation,
+  },
+  helix::*,
+  ids::{BroadcasterId, MessageId, RewardId, SubscriptionId, UserId},
+  messages::*,
+  scope::Scope,
+  secret::SecretString,
+  session_stats::{SessionStats, StreamSummary},
+  sinks::{ChannelSink, EventSink, JsonLinesSink},
+  status::{ConnectionState, ConnectionStatus, DeliveryGuarantee, ReconnectPolicy},
+  subscriptions::{
+    Condition, ConditionFields, EventSubscription, RaidDirection, RegisteredSubscription,
+    Subscription, SubscriptionRegistry,
+  },
+  token::{Token, TokenAccess, TwitchKeys},
+  token_storage::{FileTokenStorage, TokenStorage},
+  user_cache::{CachedUser, UserCache},
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+type DeviceCodeDisplay = Box<dyn Fn(&DeviceCode)>;
+#[cfg(not(target_arch = "wasm32"))]
+type AuthorisationPrompt = Box<dyn Fn(&str) -> String>;
+
+#[cfg(not(target_arch = "wasm32"))]
+type WebsocketClient = Client<Box<dyn NetworkStream + Send>>;
+
+#[cfg(not(target_arch = "wasm32"))]
 #[must_use]
 pub struct TwitchEventSubApiBuilder {
   twitch_keys: TwitchKeys,
   subscriptions: Vec<Subscription>,
+  broadcaster_subscriptions: Vec<(String, Subscription)>,
   redirect_url: Option<String>,
+  device_code_display: Option<DeviceCodeDisplay>,
+  authorisation_prompt: Option<AuthorisationPrompt>,
 
   generate_token_if_none: bool,
   generate_token_on_scope_error: bool,
   generate_access_token_on_expire: bool,
   auto_save_load_created_tokens: Option<(String, String)>,
   only_raw_responses: bool,
+  api_urls: Option<ApiUrls>,
+  no_tls: bool,
+  http_middleware: Vec<Arc<dyn HttpMiddleware>>,
+  http_timeouts: Option<HttpTimeouts>,
+  replayer: Option<EventReplayer>,
+  options: ClientOptions,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl TwitchEventSubApiBuilder {
   pub fn new(tk: TwitchKeys) -> TwitchEventSubApiBuilder {
     TwitchEventSubApiBuilder {
       twitch_keys: tk,
       subscriptions: Vec::new(),
+      broadcaster_subscriptions: Vec::new(),
       redirect_url: None,
+      device_code_display: None,
+      authorisation_prompt: None,
 
       generate_token_if_none: false,
       generate_token_on_scope_error: false,
       generate_access_token_on_expire: false,
       auto_save_load_created_tokens: None,
       only_raw_responses: false,
+      api_urls: None,
+      no_tls: false,
+      http_middleware: Vec::new(),
+      http_timeouts: None,
+      replayer: None,
+      options: ClientOptions::default(),
     }
   }
 
@@ -73,11 +198,156 @@ impl TwitchEventSubApiBuilder {
     self
   }
 
+  /// Adds the event types in `TWITCH_SUBSCRIPTIONS`, separated by commas, as saved by
+  /// `setup_wizard`. Load the env file first, e.g. with `TwitchKeys::from_secrets_env`.
+  /// Unknown event types are logged and skipped.
+  pub fn add_subscriptions_from_env(self) -> TwitchEventSubApiBuilder {
+    let event_types = std::env::var("TWITCH_SUBSCRIPTIONS").unwrap_or_default();
+    let subs = event_types
+      .split(',')
+      .map(str::trim)
+      .filter(|tag| !tag.is_empty())
+      .filter_map(|tag| {
+        let sub = Subscription::from_string(tag);
+        if sub.is_none() {
+          warn!("Unknown event type {} in TWITCH_SUBSCRIPTIONS", tag);
+        }
+        sub
+      })
+      .collect();
+    self.add_missing_subscriptions(subs)
+  }
+
+  /// Reads and sends chat messages, including announcements. Long messages are split rather
+  /// than failing to send.
+  pub fn preset_chat_bot(self) -> TwitchEventSubApiBuilder {
+    let builder = self.add_missing_subscriptions(vec![
+      Subscription::ChatMessage,
+      Subscription::AdditionalScope(Scope::ModeratorManageAnnouncements),
+    ]);
+    #[cfg(feature = "chat")]
+    let builder = builder.split_long_chat_messages();
+    builder
+  }
+
+  /// Follows, raids, subs, cheers, channel point redemptions and hype trains, the events an
+  /// alerts overlay shows.
+  pub fn preset_alerts_overlay(self) -> TwitchEventSubApiBuilder {
+    self.add_missing_subscriptions(vec![
+      Subscription::ChannelFollow,
+      Subscription::ChannelRaid {
+        direction: RaidDirection::Incoming,
+      },
+      Subscription::ChannelSubscribe,
+      Subscription::ChannelSubscriptionGift,
+      Subscription::ChannelSubscriptionMessage,
+      Subscription::ChannelCheer,
+      Subscription::ChannelPointsCustomRewardRedeem { reward_id: None },
+      Subscription::ChannelHypeTrainBegin,
+      Subscription::ChannelHypeTrainProgress,
+      Subscription::ChannelHypeTrainEnd,
+    ])
+  }
+
+  /// Reads chat and AutoMod's held messages and can delete messages, time out, ban and warn
+  /// users, turn on shield mode and resolve unban requests. Chat is sent at the moderator
+  /// rate limit.
+  pub fn preset_moderation(self) -> TwitchEventSubApiBuilder {
+    let builder = self.add_missing_subscriptions(vec![
+      Subscription::ChatMessage,
+      Subscription::DeleteMessage,
+      Subscription::BanTimeoutUser,
+      Subscription::WarnUser,
+      Subscription::AutoModMessageHold,
+      Subscription::AutoModMessageUpdate,
+      Subscription::ChannelShieldModeBegin,
+      Subscription::ChannelShieldModeEnd,
+      Subscription::ManageShieldMode,
+      Subscription::ChannelUnbanRequestCreate,
+      Subscription::ChannelUnbanRequestResolve,
+      Subscription::ManageUnbanRequests,
+    ]);
+    #[cfg(feature = "chat")]
+    let builder = builder.chat_rate_limit(ChatRateLimit::MODERATOR);
+    builder
+  }
+
+  // Presets can overlap, so only add what isn't there already
+  fn add_missing_subscriptions(mut self, subs: Vec<Subscription>) -> TwitchEventSubApiBuilder {
+    for sub in subs {
+      if !self.subscriptions.contains(&sub) {
+        self.subscriptions.push(sub);
+      }
+    }
+    self
+  }
+
+  /// Subscribes to events from another broadcaster's channel on the same connection, the
+  /// account the token belongs to needs to be a moderator there for moderator events.
+  pub fn add_subscription_for_broadcaster<S: Into<BroadcasterId>>(
+    mut self,
+    broadcaster_id: S,
+    sub: Subscription,
+  ) -> TwitchEventSubApiBuilder {
+    self
+      .broadcaster_subscriptions
+      .push((broadcaster_id.into().into_string(), sub));
+    self
+  }
+
+  pub fn add_subscriptions_for_broadcaster<S: Into<BroadcasterId>>(
+    mut self,
+    broadcaster_id: S,
+    subs: Vec<Subscription>,
+  ) -> TwitchEventSubApiBuilder {
+    let broadcaster_id = broadcaster_id.into().into_string();
+    self
+      .broadcaster_subscriptions
+      .extend(subs.into_iter().map(|sub| (broadcaster_id.to_owned(), sub)));
+    self
+  }
+
+  /// Asks for a scope when generating a token even though no subscription needs it.
+  pub fn add_scope(mut self, scope: Scope) -> TwitchEventSubApiBuilder {
+    self
+      .subscriptions
+      .push(Subscription::AdditionalScope(scope));
+    self
+  }
+
+  pub fn add_scopes(mut self, scopes: Vec<Scope>) -> TwitchEventSubApiBuilder {
+    self
+      .subscriptions
+      .extend(scopes.into_iter().map(Subscription::AdditionalScope));
+    self
+  }
+
   pub fn set_redirect_url<S: Into<String>>(mut self, url: S) -> TwitchEventSubApiBuilder {
     self.redirect_url = Some(url.into());
     self
   }
 
+  /// Generates tokens with the device code flow instead of opening a browser, so no
+  /// redirect url is needed. `display` is given the code the user has to enter.
+  pub fn generate_token_with_device_code<F: Fn(&DeviceCode) + 'static>(
+    mut self,
+    display: F,
+  ) -> TwitchEventSubApiBuilder {
+    self.device_code_display = Some(Box::new(display));
+    self
+  }
+
+  /// Generates tokens without opening a browser or listening on the redirect url's port,
+  /// for SSH sessions and containers. `prompt` is given the url the user has to visit and
+  /// returns the url they were redirected to, or the code in it.
+  pub fn enter_authorisation_code_manually<F: Fn(&str) -> String + 'static>(
+    mut self,
+    prompt: F,
+  ) -> TwitchEventSubApiBuilder {
+    self.authorisation_prompt = Some(Box::new(prompt));
+    self
+  }
+
   pub fn generate_new_token_if_insufficent_scope(
     mut self,
     should_generate: bool,
@@ -108,6 +378,176 @@ impl TwitchEventSubApiBuilder {
     self
   }
 
+  /// Loads tokens from the storage when none are given, and saves newly generated and
+  /// refreshed tokens to it.
+  pub fn token_storage<T: TokenStorage + 'static>(
+    mut self,
+    storage: T,
+  ) -> TwitchEventSubApiBuilder {
+    self.twitch_keys.token_storage = Some(Arc::new(storage));
+    self
+  }
+
+  /// How long notification message ids are remembered so redelivered notifications can be
+  /// dropped, a window of zero disables deduplication. Defaults to 10 minutes.
+  pub fn deduplication_window(mut self, window: Duration) -> TwitchEventSubApiBuilder {
+    self.options.deduplication_window = window;
+    self
+  }
+
+  /// Whether redelivered notifications are dropped or passed on, defaults to
+  /// `DeliveryGuarantee::AtMostOnce`.
+  pub fn delivery_guarantee(mut self, guarantee: DeliveryGuarantee) -> TwitchEventSubApiBuilder {
+    self.options.delivery_guarantee = guarantee;
+    self
+  }
+
+  /// How long users seen in chat or looked up are remembered, and how many at most. Defaults
+  /// to an hour and 10,000 users, a ttl of zero disables the cache.
+  pub fn user_cache(mut self, ttl: Duration, max_users: usize) -> TwitchEventSubApiBuilder {
+    self.options.user_cache_ttl = ttl;
+    self.options.user_cache_size = max_users;
+    self
+  }
+
+  /// Reads the channel's chat over IRC if the chat message subscription can't be created,
+  /// such as when the token lacks the chat scopes. IRC chat is read anonymously, the
+  /// messages arrive as the usual `Event::ChatMessage`.
+  #[cfg(all(feature = "irc", not(feature = "only_raw_responses")))]
+  pub fn irc_chat_fallback<S: Into<String>>(
+    mut self,
+    channel_login: S,
+  ) -> TwitchEventSubApiBuilder {
+    self
+      .options
+      .irc_fallback_channels
+      .push(channel_login.into());
+    self
+  }
+
+  /// Holds at most `capacity` events that haven't been received yet, instead of an
+  /// unlimited number, so a stalled consumer can't use up memory. What happens to events
+  /// past that depends on the policy, dropped events are reported with a
+  /// `MessageType::EventsDropped`.
+  pub fn message_buffer(
+    mut self,
+    capacity: usize,
+    policy: OverflowPolicy,
+  ) -> TwitchEventSubApiBuilder {
+    self.options.message_buffer = Some(capacity);
+    self.options.overflow_policy = policy;
+    self
+  }
+
+  /// Messages that fail to parse are sent as `MessageType::ParseError` with the raw json and
+  /// the reason, instead of as a `MessageType::RawResponse`.
+  pub fn lenient_parsing(mut self) -> TwitchEventSubApiBuilder {
+    self.options.lenient_parsing = true;
+    self
+  }
+
+  /// How fast chat messages can be sent, defaults to `ChatRateLimit::NORMAL`. Use
+  /// `ChatRateLimit::MODERATOR` if the sender moderates the channel.
+  #[cfg(feature = "chat")]
+  pub fn chat_rate_limit(mut self, limit: ChatRateLimit) -> TwitchEventSubApiBuilder {
+    self.options.chat_rate_limit = limit;
+    self
+  }
+
+  /// How many chat messages can wait to be sent before new ones are dropped, defaults to 50.
+  #[cfg(feature = "chat")]
+  pub fn max_queued_chat_messages(mut self, max: usize) -> TwitchEventSubApiBuilder {
+    self.options.max_queued_chat_messages = max;
+    self
+  }
+
+  /// Splits every chat message over 500 characters into several between words, rather than
+  /// failing to send it with `EventSubError::MessageTooLong`.
+  #[cfg(feature = "chat")]
+  pub fn split_long_chat_messages(mut self) -> TwitchEventSubApiBuilder {
+    self.options.split_long_chat_messages = true;
+    self
+  }
+
+  /// How often and how quickly to try reconnecting after the websocket connection is lost,
+  /// defaults to 5 attempts starting a second apart.
+  pub fn reconnect_policy(mut self, policy: ReconnectPolicy) -> TwitchEventSubApiBuilder {
+    self.options.reconnect_policy = policy;
+    self
+  }
+
+  /// Writes every notification received to the recorder, to be replayed later.
+  pub fn record_events(mut self, recorder: EventRecorder) -> TwitchEventSubApiBuilder {
+    self.options.recorder = Some(Arc::new(recorder));
+    self
+  }
+
+  /// Sends every event to the sink as well, see `EventSink`. Sinks are called in the order
+  /// they were added.
+  pub fn add_sink<T: EventSink + 'static>(self, sink: T) -> TwitchEventSubApiBuilder {
+    self.options.sinks.add(Box::new(sink));
+    self
+  }
+
+  /// Plays back a recorded session instead of connecting to Twitch, the client works as
+  /// normal otherwise so bots and overlays can be developed offline.
+  pub fn replay(mut self, replayer: EventReplayer) -> TwitchEventSubApiBuilder {
+    self.replayer = Some(replayer);
+    self
+  }
+
+  /// Connects to a different EventSub websocket, such as a local mock server. Urls are
+  /// shared by every client in the process.
+  pub fn websocket_url<S: Into<String>>(mut self, url: S) -> TwitchEventSubApiBuilder {
+    self.api_urls.get_or_insert_with(ApiUrls::default).websocket = url.into();
+    self
+  }
+
+  /// Sends Helix requests to a different server, replacing `https://api.twitch.tv/helix`.
+  pub fn helix_url<S: Into<String>>(mut self, url: S) -> TwitchEventSubApiBuilder {
+    self.api_urls.get_or_insert_with(ApiUrls::default).helix = url.into();
+    self
+  }
+
+  /// Sends token requests to a different server, replacing `https://id.twitch.tv/oauth2`.
+  pub fn auth_url<S: Into<String>>(mut self, url: S) -> TwitchEventSubApiBuilder {
+    self.api_urls.get_or_insert_with(ApiUrls::default).auth = url.into();
+    self
+  }
+
+  /// Uses `ws://` and `http://` for the custom urls, for servers running without TLS.
+  pub fn no_tls(mut self, no_tls: bool) -> TwitchEventSubApiBuilder {
+    self.no_tls = no_tls;
+    self
+  }
+
+  /// How long Helix and token requests can take before failing with
+  /// `EventSubError::TimedOut`, shared by every client in the process like the urls.
+  pub fn http_timeouts(mut self, timeouts: HttpTimeouts) -> TwitchEventSubApiBuilder {
+    self.http_timeouts = Some(timeouts);
+    self
+  }
+
+  /// Runs the middleware around every Helix and token request, see `HttpMiddleware`. Like
+  /// the urls it's shared by every client in the process, added when the client is built.
+  pub fn add_http_middleware<M: HttpMiddleware + 'static>(
+    mut self,
+    middleware: M,
+  ) -> TwitchEventSubApiBuilder {
+    self.http_middleware.push(Arc::new(middleware));
+    self
+  }
+
+  /// Talks to the Twitch CLI's local websocket server, started with
+  /// `twitch event websocket start-server`, instead of Twitch. Tokens are still
+  /// validated against Twitch.
+  pub fn twitch_cli(self) -> TwitchEventSubApiBuilder {
+    self
+      .websocket_url(TWITCH_CLI_WEBSOCKET_URL)
+      .helix_url(TWITCH_CLI_URL)
+      .no_tls(true)
+  }
+
   pub fn subscriptions(&self) -> Vec<Subscription> {
     self.subscriptions.clone()
   }
@@ -120,17 +560,83 @@ impl TwitchEventSubApiBuilder {
     self.only_raw_responses = recieve_raw_data;
   }
 
+  fn generate_user_token(&self, subscriptions: &[Subscription]) -> Result<Token, EventSubError> {
+    if let Some(display) = &self.device_code_display {
+      return TwitchApi::generate_user_token_from_device_code(
+        self.twitch_keys.client_id.to_owned(),
+        subscriptions,
+        |device_code| display(device_code),
+      );
+    }
+
+    match &self.authorisation_prompt {
+      Some(prompt) => TwitchApi::generate_user_token_manually(
+        self.twitch_keys.client_id.to_owned(),
+        self.twitch_keys.client_secret.to_owned(),
+        self.redirect_url.clone().unwrap(),
+        subscriptions,
+        |url| prompt(url),
+      ),
+      None => TwitchApi::generate_user_token(
+        self.twitch_keys.client_id.to_owned(),
+        self.twitch_keys.client_secret.to_owned(),
+        self.redirect_url.clone().unwrap(),
+        subscriptions,
+      ),
+    }
+  }
+
   pub fn build(mut self) -> Result<TwitchEventSubApi, EventSubError> {
     log_builder();
+
+    #[cfg(feature = "chat")]
+    self.options.chat_rate_limit.validate()?;
+
+    if let Some(replayer) = self.replayer.take() {
+      info!("Replaying recorded events instead of connecting to Twitch.");
+      return Ok(TwitchEventSubApi::start_replay(
+        self.twitch_keys,
+        replayer,
+        self.options,
+      ));
+    }
+
+    if let Some(timeouts) = self.http_timeouts {
+      set_default_http_timeouts(timeouts);
+    }
+    for middleware in self.http_middleware.drain(..) {
+      add_http_middleware(middleware);
+    }
+    if let Some(api_urls) = self.api_urls.take() {
+      set_url_overrides(Some(if self.no_tls {
+        api_urls.without_tls()
+      } else {
+        api_urls
+      }));
+    }
     let mut newly_generated_token = None;
 
-    if self.subscriptions.is_empty() {
+    // The token needs the scopes for every broadcaster's subscriptions too
+    let required_subscriptions = self
+      .subscriptions
+      .iter()
+      .cloned()
+      .chain(
+        self
+          .broadcaster_subscriptions
+          .iter()
+          .map(|(_, s)| s.to_owned()),
+      )
+      .collect::<Vec<_>>();
+
+    if required_subscriptions.is_empty() {
       error!("No Subscriptions selected.");
       return Err(EventSubError::NoSubscriptionsRequested);
     }
 
     if (self.generate_token_if_none || self.generate_token_on_scope_error)
       && self.redirect_url.is_none()
+      && self.device_code_display.is_none()
     {
       error!("No redirect url given when generate token is enabled.");
       return Err(EventSubError::UnhandledError(
@@ -139,6 +645,16 @@ impl TwitchEventSubApiBuilder {
     }
 
     let mut save_new_tokens = false;
+    if self.twitch_keys.access_token.is_none() {
+      if let Some(storage) = &self.twitch_keys.token_storage {
+        if let Some(token) = storage.load()? {
+          info!("Loaded tokens from token storage.");
+          self.twitch_keys.access_token = Some(token.access);
+          self.twitch_keys.refresh_token = Some(token.refresh);
+        }
+      }
+    }
+
     // If there is no access token
     if self.twitch_keys.access_token.is_none() {
       // If auto save and load created tokens is enabled
@@ -167,85 +683,106 @@ impl TwitchEventSubApiBuilder {
 
         if !new_token.is_empty() {
           info!("Found user access token!");
-          self.twitch_keys.access_token = Some(TokenAccess::User(new_token));
+          self.twitch_keys.access_token = Some(TokenAccess::User(new_token.into()));
         }
         if !refresh_token.is_empty() {
           info!("Found refresh token!");
-          self.twitch_keys.refresh_token = Some(refresh_token);
+          self.twitch_keys.refresh_token = Some(refresh_token.into());
         }
+      }
 
-        let mut generate_token =
-          self.twitch_keys.refresh_token.is_none() || self.twitch_keys.access_token.is_none();
+      let mut generate_token =
+        self.twitch_keys.refresh_token.is_none() || self.twitch_keys.access_token.is_none();
+
+      if self.generate_token_if_none {
+        if generate_token {
+          if let Some(refresh_token) = &self.twitch_keys.refresh_token {
+            info!(
+              "No access token provided, attempting to generate access token from refresh token."
+            );
+            // Try to create new token from refresh token
+            if let Ok(token) = TwitchApi::generate_token_from_refresh_token(
+              self.twitch_keys.client_id.to_owned(),
+              self.twitch_keys.client_secret.to_owned(),
+              refresh_token,
+            ) {
+              metrics::token_refresh(true);
+              info!("Generated user access token from refresh key.");
+              self.twitch_keys.access_token = Some(token.access.clone());
+              self.twitch_keys.refresh_token = Some(token.refresh.to_owned());
+              newly_generated_token = Some(token);
+              save_new_tokens = true;
+              generate_token = false;
+            } else {
+              metrics::token_refresh(false);
+              warn!("Couldn't generate access token from refresh token.");
+            }
+          }
 
-        if self.generate_token_if_none {
+          // If there are no refresh tokens or refresh token could created
+          // a new access token, then get a completely new user token
           if generate_token {
-            if let Some(refresh_token) = &self.twitch_keys.refresh_token {
-              info!(
-                "No access token provided, attempting to generate access token from refresh token."
-              );
-              // Try to create new token from refresh token
-              if let Ok(token) = TwitchApi::generate_token_from_refresh_token(
-                self.twitch_keys.client_id.to_owned(),
-                self.twitch_keys.client_secret.to_owned(),
-                refresh_token,
-              ) {
-                info!("Generated user access token from refresh key.");
-                self.twitch_keys.access_token = Some(token.access.clone());
-                self.twitch_keys.refresh_token = Some(token.refresh.to_owned());
-                newly_generated_token = Some(token);
+            info!("Generating new user token.");
+            // Returns app access token
+            match self.generate_user_token(&required_subscriptions) {
+              Ok(user_token) => {
+                info!("Token created!");
+                self.twitch_keys.access_token = Some(user_token.access.clone());
+                self.twitch_keys.refresh_token = Some(user_token.refresh.to_owned());
+                newly_generated_token = Some(user_token);
                 save_new_tokens = true;
-                generate_token = false;
-              } else {
-                warn!("Couldn't generate access token from refresh token.");
               }
-            }
-
-            // If there are no refresh tokens or refresh token could created
-            // a new access token, then get a completely new user token
-            if generate_token {
-              info!("Generating new user token.");
-              // Returns app access token
-              match TwitchApi::generate_user_token(
-                self.twitch_keys.client_id.to_owned(),
-                self.twitch_keys.client_secret.to_owned(),
-                self.redirect_url.clone().unwrap(),
-                &self.subscriptions,
-              ) {
-                Ok(user_token) => {
-                  info!("Token created!");
-                  self.twitch_keys.access_token = Some(user_token.access.clone());
-                  self.twitch_keys.refresh_token = Some(user_token.refresh.to_owned());
-                  newly_generated_token = Some(user_token);
-                  save_new_tokens = true;
-                }
-                Err(e) => {
-                  error!("Failed to generate token: {:?}", e);
-                  return Err(e);
-                }
+              Err(e) => {
+                error!("Failed to generate token: {:?}", e);
+                return Err(e);
               }
             }
           }
-        } else {
-          error!("No access token provided");
-          return Err(EventSubError::NoAccessTokenProvided);
+        }
+      } else {
+        error!("No access token provided");
+        return Err(EventSubError::NoAccessTokenProvided);
+      }
+    }
+
+    // Saved tokens have usually expired by the next run, so refresh them before checking scopes
+    if let (Some(access_token), Some(refresh_token)) = (
+      &self.twitch_keys.access_token,
+      &self.twitch_keys.refresh_token,
+    ) {
+      if TwitchEventSubApi::validate_token(access_token.get_token())?.is_error() {
+        info!("Access token is no longer valid, refreshing it.");
+        match refresh_user_token(
+          &self.twitch_keys.client_id,
+          &self.twitch_keys.client_secret,
+          refresh_token,
+          self.twitch_keys.token_storage.as_deref(),
+        ) {
+          Ok(token) => {
+            self.twitch_keys.access_token = Some(token.access.clone());
+            self.twitch_keys.refresh_token = Some(token.refresh.to_owned());
+            newly_generated_token = Some(token);
+            save_new_tokens = true;
+          }
+          Err(e) => warn!("Couldn't refresh the access token: {:?}", e),
         }
       }
     }
 
+    let Some(access_token) = self.twitch_keys.access_token.clone() else {
+      error!("No access token provided");
+      return Err(EventSubError::NoAccessTokenProvided);
+    };
+
     match TwitchEventSubApi::check_token_meets_requirements(
-      self.twitch_keys.access_token.clone().unwrap(),
-      &self.subscriptions,
+      access_token.clone(),
+      &required_subscriptions,
     ) {
       Ok(token_meets_requirements) => {
         if !token_meets_requirements {
           if self.generate_token_on_scope_error {
             info!("Generating new token because current token doesn't have correct scope.");
-            match TwitchApi::generate_user_token(
-              self.twitch_keys.client_id.to_owned(),
-              self.twitch_keys.client_secret.to_owned(),
-              self.redirect_url.clone().unwrap(),
-              &self.subscriptions,
-            ) {
+            match self.generate_user_token(&required_subscriptions) {
               Ok(user_token) => {
                 info!("Token Generated!");
                 self.twitch_keys.refresh_token = Some(user_token.refresh.clone());
@@ -259,8 +796,15 @@ impl TwitchEventSubApiBuilder {
               }
             }
           } else {
-            error!("Token missing required scope!");
-            return Err(EventSubError::TokenMissingScope);
+            return Err(
+              match TwitchEventSubApi::missing_scopes(access_token, &required_subscriptions) {
+                Ok(missing) => {
+                  error!("Token missing required scopes: {:?}", missing);
+                  EventSubError::MissingScopes(missing)
+                }
+                Err(e) => e,
+              },
+            );
           }
         }
       }
@@ -271,340 +815,2539 @@ impl TwitchEventSubApiBuilder {
     }
 
     if save_new_tokens {
-      if let Some((token_file, refresh_file)) = self.auto_save_load_created_tokens {
-        info!("saving tokens");
-        if let Some(new_token) = newly_generated_token {
+      if let Some(new_token) = newly_generated_token {
+        if let Some((token_file, refresh_file)) = self.auto_save_load_created_tokens {
+          info!("saving tokens");
           if let Err(e) = new_token.save_to_file(token_file, refresh_file) {
             warn!("Failed to save tokens to file!");
             return Err(e);
           }
         }
+
+        if let Some(storage) = &self.twitch_keys.token_storage {
+          storage.save(&new_token)?;
+        }
       }
     }
 
-    TwitchEventSubApi::new(self.twitch_keys, self.subscriptions, Vec::new())
-      .map_err(|e| EventSubError::UnhandledError(e.to_string()))
+    TwitchEventSubApi::start(
+      self.twitch_keys,
+      self.subscriptions,
+      self.broadcaster_subscriptions,
+      Vec::new(),
+      self.options,
+    )
+    .map_err(|e| EventSubError::UnhandledError(e.to_string()))
   }
 }
 
-pub struct TwitchEventSubApi {
-  _receive_thread: JoinHandle<()>,
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug)]
+struct ClientOptions {
+  deduplication_window: Duration,
+  delivery_guarantee: DeliveryGuarantee,
+  lenient_parsing: bool,
+  recorder: Option<Arc<EventRecorder>>,
+  #[cfg(feature = "chat")]
+  chat_rate_limit: ChatRateLimit,
+  #[cfg(feature = "chat")]
+  max_queued_chat_messages: usize,
+  #[cfg(feature = "chat")]
+  split_long_chat_messages: bool,
+  reconnect_policy: ReconnectPolicy,
+  user_cache_ttl: Duration,
+  user_cache_size: usize,
+  message_buffer: Option<usize>,
+  overflow_policy: OverflowPolicy,
+  #[cfg(all(feature = "irc", not(feature = "only_raw_responses")))]
+  irc_fallback_channels: Vec<String>,
+  sinks: EventSinks,
+}
 
-  messages_received: SyncReceiver<MessageType>,
-  twitch_keys: TwitchKeys,
-  _token: Arc<Mutex<Token>>,
+#[cfg(not(target_arch = "wasm32"))]
+impl ClientOptions {
+  #[cfg(not(feature = "only_raw_responses"))]
+  fn unparsed_message<T: ToString>(&self, raw: String, error: T) -> MessageType {
+    metrics::parse_failed();
+    if self.lenient_parsing {
+      MessageType::ParseError(ParseFailure::new(raw, error))
+    } else {
+      MessageType::RawResponse(raw)
+    }
+  }
 }
 
-impl TwitchEventSubApi {
-  pub fn builder(twitch_keys: TwitchKeys) -> TwitchEventSubApiBuilder {
-    TwitchEventSubApiBuilder::new(twitch_keys)
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for ClientOptions {
+  fn default() -> ClientOptions {
+    ClientOptions {
+      deduplication_window: DEFAULT_DEDUPLICATION_WINDOW,
+      delivery_guarantee: DeliveryGuarantee::default(),
+      lenient_parsing: false,
+      recorder: None,
+      #[cfg(feature = "chat")]
+      chat_rate_limit: ChatRateLimit::default(),
+      #[cfg(feature = "chat")]
+      max_queued_chat_messages: DEFAULT_MAX_QUEUED_CHAT_MESSAGES,
+      #[cfg(feature = "chat")]
+      split_long_chat_messages: false,
+      reconnect_policy: ReconnectPolicy::default(),
+      user_cache_ttl: DEFAULT_USER_CACHE_TTL,
+      user_cache_size: DEFAULT_USER_CACHE_SIZE,
+      message_buffer: None,
+      overflow_policy: OverflowPolicy::DropOldest,
+      #[cfg(all(feature = "irc", not(feature = "only_raw_responses")))]
+      irc_fallback_channels: Vec::new(),
+      sinks: EventSinks::default(),
+    }
   }
+}
 
-  pub fn new(
-    twitch_keys: TwitchKeys,
-    subscriptions: Vec<Subscription>,
-    custom_subscription_data: Vec<String>,
-  ) -> Result<TwitchEventSubApi, WebSocketError> {
-    log_info();
-    info!("Starting websocket client.");
-    let client = match ClientBuilder::new(CONNECTION_EVENTS)
-      .unwrap()
-      .add_protocol("rust-websocket-events")
-      .connect_secure(None)
-    {
-      Ok(c) => c,
-      Err(e) => return Err(e),
-    };
+/// Blocking iterator over a client's messages, see [`TwitchEventSubApi::messages`].
+#[cfg(not(target_arch = "wasm32"))]
+pub struct Messages<'a> {
+  api: &'a TwitchEventSubApi,
+  closed: bool,
+}
 
-    let receiver = Arc::new(Mutex::new(client));
+#[cfg(not(target_arch = "wasm32"))]
+impl Iterator for Messages<'_> {
+  type Item = MessageType;
 
-    let (transmit_messages, receive_message) = channel();
+  fn next(&mut self) -> Option<MessageType> {
+    if self.closed {
+      return None;
+    }
 
-    let token = Arc::new(Mutex::new(Token::new(
-      twitch_keys
-        .access_token
-        .to_owned()
-        .unwrap_or(TokenAccess::User("".to_owned())),
-      twitch_keys
-        .refresh_token
-        .to_owned()
-        .unwrap_or("".to_owned()),
-      0.0,
-    )));
+    match self.api.recv() {
+      MessageType::Close => {
+        self.closed = true;
+        None
+      }
+      message => Some(message),
+    }
+  }
+}
 
-    let keys_clone = twitch_keys.clone();
-    let receive_thread = thread::spawn(move || {
-      TwitchEventSubApi::event_sub_events(
-        receiver,
-        transmit_messages,
-        subscriptions,
-        custom_subscription_data,
-        keys_clone,
-      )
-    });
+#[cfg(not(target_arch = "wasm32"))]
+impl<'a> IntoIterator for &'a TwitchEventSubApi {
+  type Item = MessageType;
+  type IntoIter = Messages<'a>;
 
-    Ok(TwitchEventSubApi {
-      _receive_thread: receive_thread,
-      messages_received: receive_message,
-      twitch_keys,
-      _token: token,
-    })
+  fn into_iter(self) -> Messages<'a> {
+    self.messages()
   }
+}
 
-  pub fn validate_token<S: Into<String>>(token: S) -> Result<Validation, EventSubError> {
-    TwitchHttpRequest::new(VALIDATION_TOKEN_URL)
-      .header_authorisation(token.into(), AuthType::OAuth)
-      .run()
-      .and_then(|data| {
-        serde_json::from_str::<Validation>(&data)
-          .map_err(|e| EventSubError::ParseError(e.to_string()))
-      })
+/// State shared between the client and its receive threads.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+struct SharedState {
+  // Session id of each websocket connection, `None` until its welcome message arrives
+  sessions: Arc<Mutex<Vec<Option<String>>>>,
+  // `None` for connections that haven't been opened
+  statuses: Arc<Mutex<Vec<Option<ConnectionStatus>>>>,
+  registry: Arc<Mutex<SubscriptionRegistry>>,
+  event_streams: Arc<Mutex<Vec<(StreamFilter, MessageSender)>>>,
+  sinks: EventSinks,
+  budget: Arc<Mutex<SubscriptionBudget>>,
+  user_cache: Arc<Mutex<UserCache>>,
+  // Shared so redelivery is caught whichever connection it arrives on, and after reconnects
+  #[cfg(not(feature = "only_raw_responses"))]
+  deduplicator: Arc<Mutex<MessageDeduplicator>>,
+  #[cfg(all(feature = "irc", not(feature = "only_raw_responses")))]
+  irc_chat_started: Arc<AtomicBool>,
+  // Set when the client is shutting down, connections close on their next message
+  shutdown: Arc<AtomicBool>,
+}
+
+/// Subscription costs as last reported by Twitch.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug, Default)]
+struct SubscriptionBudget {
+  total_cost: u32,
+  // Unknown until Twitch has responded to a subscription request
+  max_total_cost: Option<u32>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SubscriptionBudget {
+  fn update(&mut self, subscriptions: &EventSubSubscriptions) {
+    self.total_cost = subscriptions.total_cost;
+    self.max_total_cost = Some(subscriptions.max_total_cost);
   }
+}
 
-  pub fn check_token_meets_requirements(
-    access_token: TokenAccess,
-    subs: &Vec<Subscription>,
-  ) -> Result<bool, EventSubError> {
-    TwitchEventSubApi::validate_token(access_token.get_token()).map(|validation| {
-      if validation.is_error() {
-        false
-      } else {
-        subs
-          .iter()
-          .filter(|s| !s.required_scope().is_empty())
-          .all(move |s| {
-            let r = s.required_scope();
+#[cfg(not(target_arch = "wasm32"))]
+impl SharedState {
+  fn new(
+    subscriptions: &[Subscription],
+    broadcaster_subscriptions: &[(String, Subscription)],
+    options: &ClientOptions,
+  ) -> SharedState {
+    SharedState {
+      sessions: Arc::new(Mutex::new(vec![None; MAX_WEBSOCKET_CONNECTIONS])),
+      statuses: Arc::new(Mutex::new(vec![None; MAX_WEBSOCKET_CONNECTIONS])),
+      registry: Arc::new(Mutex::new(SubscriptionRegistry::new(
+        subscriptions,
+        broadcaster_subscriptions,
+      ))),
+      event_streams: Arc::new(Mutex::new(Vec::new())),
+      sinks: options.sinks.clone(),
+      budget: Arc::new(Mutex::new(SubscriptionBudget::default())),
+      user_cache: Arc::new(Mutex::new(UserCache::new(
+        options.user_cache_ttl,
+        options.user_cache_size,
+      ))),
+      #[cfg(not(feature = "only_raw_responses"))]
+      deduplicator: Arc::new(Mutex::new(MessageDeduplicator::new(
+        match options.delivery_guarantee {
+          DeliveryGuarantee::AtMostOnce => options.deduplication_window,
+          DeliveryGuarantee::AtLeastOnce => Duration::ZERO,
+        },
+      ))),
+      #[cfg(all(feature = "irc", not(feature = "only_raw_responses")))]
+      irc_chat_started: Arc::new(AtomicBool::new(false)),
+      shutdown: Arc::new(AtomicBool::new(false)),
+    }
+  }
 
-            let requirements = r.split('+').map(ToString::to_string).collect::<Vec<_>>();
+  fn is_shutting_down(&self) -> bool {
+    self.shutdown.load(Ordering::Relaxed)
+  }
 
-            for req in requirements {
-              if !validation.scopes.as_ref().unwrap().contains(&req) {
-                return false;
-              }
+  fn session_id(&self, connection: usize) -> Option<String> {
+    self.sessions.lock().unwrap()[connection].clone()
+  }
+
+  #[cfg(not(feature = "only_raw_responses"))]
+  fn set_session_id(&self, connection: usize, session_id: Option<String>) {
+    self.sessions.lock().unwrap()[connection] = session_id;
+  }
+
+  fn connection_status(&self, connection: usize) -> Option<ConnectionStatus> {
+    self.statuses.lock().unwrap()[connection].clone()
+  }
+
+  /// Sends the connection's new status when its state changes.
+  fn set_connection_state(
+    &self,
+    connection: usize,
+    state: ConnectionState,
+    message_sender: &MessageSender,
+  ) {
+    let status = {
+      let mut statuses = self.statuses.lock().unwrap();
+      if statuses[connection].as_ref().map(|s| &s.state) == Some(&state) {
+        return;
+      }
+
+      let status = statuses[connection].get_or_insert_with(|| ConnectionStatus::new(connection));
+      status.state = state;
+      status.clone()
+    };
+
+    let _ = message_sender.send(MessageType::ConnectionStatusChanged(status));
+  }
+
+  #[cfg(not(feature = "only_raw_responses"))]
+  fn message_received(&self, connection: usize, message_timestamp: &str) {
+    if let Some(status) = &mut self.statuses.lock().unwrap()[connection] {
+      status.message_received(message_timestamp);
+    }
+  }
+
+  /// Hands the event to every event stream and sink, with the notification's message id
+  /// when it came from Twitch.
+  fn fan_out(&self, event: &Event, message_id: Option<&str>) {
+    self.sinks.consume(event, message_id);
+    // Streams whose receiver has been dropped are removed
+    self
+      .event_streams
+      .lock()
+      .unwrap()
+      .retain(|(accepts, sender)| {
+        if !(accepts.lock().unwrap())(event) {
+          return true;
+        }
+        sender
+          .send(MessageType::Event {
+            event: event.clone(),
+            raw: None,
+          })
+          .is_ok()
+      });
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct TwitchEventSubApi {
+  // One per websocket connection, more are opened as subscriptions are added
+  receive_threads: Vec<JoinHandle<()>>,
+
+  messages_received: MessageReceiver,
+  messages_sender: MessageSender,
+  #[cfg(feature = "chat")]
+  chat_queue: ChatQueue,
+  twitch_keys: TwitchKeys,
+  _token: Arc<Mutex<Token>>,
+  shared: SharedState,
+  options: ClientOptions,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for TwitchEventSubApi {
+  fn drop(&mut self) {
+    let _ = self.close(false);
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TwitchEventSubApi {
+  pub fn builder(twitch_keys: TwitchKeys) -> TwitchEventSubApiBuilder {
+    TwitchEventSubApiBuilder::new(twitch_keys)
+  }
+
+  /// Sets up a builder from environment variables alone: the keys from
+  /// `TwitchKeys::from_env`, the event types in `TWITCH_SUBSCRIPTIONS` and scopes in
+  /// `TWITCH_SCOPES`, both separated by commas, and `TWITCH_REDIRECT_URL`.
+  pub fn from_env() -> Result<TwitchEventSubApiBuilder, TwitchKeysError> {
+    let scopes = std::env::var("TWITCH_SCOPES")
+      .unwrap_or_default()
+      .split(',')
+      .map(str::trim)
+      .filter(|scope| !scope.is_empty())
+      .map(Scope::parse_lenient)
+      .collect();
+    let mut builder = TwitchKeys::from_env()
+      .map(TwitchEventSubApiBuilder::new)?
+      .add_subscriptions_from_env()
+      .add_scopes(scopes);
+    if let Ok(redirect_url) = std::env::var("TWITCH_REDIRECT_URL") {
+      builder = builder.set_redirect_url(redirect_url);
+    }
+    Ok(builder)
+  }
+
+  /// Loads keys, subscriptions and settings from a `.toml` or `.json` file, see the readme
+  /// for what goes in it. `${VAR}` in any value is replaced by that environment variable, or
+  /// `${VAR:-default}` when it might not be set, so secrets don't have to be in the file.
+  #[cfg(feature = "config")]
+  pub fn from_config<P: AsRef<std::path::Path>>(
+    path: P,
+  ) -> Result<TwitchEventSubApiBuilder, EventSubError> {
+    modules::config::load_config(path.as_ref())
+  }
+
+  pub fn new(
+    twitch_keys: TwitchKeys,
+    subscriptions: Vec<Subscription>,
+    custom_subscription_data: Vec<String>,
+  ) -> Result<TwitchEventSubApi, WebSocketError> {
+    TwitchEventSubApi::start(
+      twitch_keys,
+      subscriptions,
+      Vec::new(),
+      custom_subscription_data,
+      ClientOptions::default(),
+    )
+  }
+
+  fn start(
+    twitch_keys: TwitchKeys,
+    subscriptions: Vec<Subscription>,
+    broadcaster_subscriptions: Vec<(String, Subscription)>,
+    custom_subscription_data: Vec<String>,
+    options: ClientOptions,
+  ) -> Result<TwitchEventSubApi, WebSocketError> {
+    log_info();
+    info!("Starting websocket client.");
+
+    let (transmit_messages, receive_message) =
+      message_channel(options.message_buffer, options.overflow_policy);
+
+    let token = Arc::new(Mutex::new(Token::new(
+      twitch_keys
+        .access_token
+        .to_owned()
+        .unwrap_or(TokenAccess::User(SecretString::default())),
+      twitch_keys.refresh_token.to_owned().unwrap_or_default(),
+      0.0,
+    )));
+
+    let shared = SharedState::new(&subscriptions, &broadcaster_subscriptions, &options);
+
+    let connections = shared.registry.lock().unwrap().connections_needed();
+    let mut receive_threads = Vec::new();
+    for connection in 0..connections {
+      // Custom subscription data always goes on the first connection
+      let custom_subscription_data = if connection == 0 {
+        custom_subscription_data.clone()
+      } else {
+        Vec::new()
+      };
+
+      receive_threads.push(TwitchEventSubApi::spawn_connection(
+        connection,
+        transmit_messages.clone(),
+        shared.clone(),
+        custom_subscription_data,
+        twitch_keys.clone(),
+        options.clone(),
+      )?);
+    }
+
+    Ok(TwitchEventSubApi {
+      receive_threads,
+      messages_received: receive_message,
+      #[cfg(feature = "chat")]
+      chat_queue: TwitchEventSubApi::start_chat_queue(&twitch_keys, &options, &transmit_messages),
+      messages_sender: transmit_messages,
+      twitch_keys,
+      _token: token,
+      shared,
+      options,
+    })
+  }
+
+  fn start_replay(
+    twitch_keys: TwitchKeys,
+    replayer: EventReplayer,
+    options: ClientOptions,
+  ) -> TwitchEventSubApi {
+    let (transmit_messages, receive_message) =
+      message_channel(options.message_buffer, options.overflow_policy);
+    let shared = SharedState::new(&[], &[], &options);
+
+    shared.set_connection_state(
+      0,
+      ConnectionState::Connected {
+        session_id: "replay".to_owned(),
+        since: Instant::now(),
+      },
+      &transmit_messages,
+    );
+
+    let message_sender = transmit_messages.clone();
+    let replay_shared = shared.clone();
+    let replay_thread = thread::spawn(move || {
+      replayer.replay(|raw| {
+        if replay_shared.is_shutting_down() {
+          return false;
+        }
+
+        let (message_id, event) = match serde_json::from_str::<GenericMessage>(raw) {
+          Ok(message) => (
+            message.metadata.message_id,
+            message.payload.and_then(|payload| payload.event),
+          ),
+          Err(_) => (String::new(), None),
+        };
+        let message = match event {
+          Some(event) => {
+            replay_shared
+              .user_cache
+              .lock()
+              .unwrap()
+              .record_event(&event);
+            replay_shared.fan_out(&event, Some(&message_id));
+            MessageType::Event {
+              event,
+              raw: Some(raw.to_owned()),
             }
-            true
+          }
+          None => MessageType::RawResponse(raw.to_owned()),
+        };
+
+        message_sender.send(message).is_ok()
+      });
+
+      replay_shared.set_connection_state(
+        0,
+        ConnectionState::Disconnected {
+          reason: "Replay finished".to_owned(),
+        },
+        &message_sender,
+      );
+      let _ = message_sender.send(MessageType::Close);
+    });
+
+    TwitchEventSubApi {
+      receive_threads: vec![replay_thread],
+      messages_received: receive_message,
+      #[cfg(feature = "chat")]
+      chat_queue: TwitchEventSubApi::start_chat_queue(&twitch_keys, &options, &transmit_messages),
+      messages_sender: transmit_messages,
+      _token: Arc::new(Mutex::new(Token::new(
+        twitch_keys
+          .access_token
+          .to_owned()
+          .unwrap_or(TokenAccess::User(SecretString::default())),
+        twitch_keys.refresh_token.to_owned().unwrap_or_default(),
+        0.0,
+      ))),
+      twitch_keys,
+      shared,
+      options,
+    }
+  }
+
+  #[cfg(feature = "chat")]
+  fn start_chat_queue(
+    twitch_keys: &TwitchKeys,
+    options: &ClientOptions,
+    message_sender: &MessageSender,
+  ) -> ChatQueue {
+    let mut twitch_keys = twitch_keys.clone();
+    let error_sender = message_sender.clone();
+
+    ChatQueue::start(
+      options.chat_rate_limit,
+      options.max_queued_chat_messages,
+      message_sender.clone(),
+      move |queued| {
+        let access_token = twitch_keys
+          .access_token
+          .as_ref()
+          .map(TokenAccess::get_token)
+          .unwrap_or_default();
+        let client_id = twitch_keys.client_id.to_owned();
+        let broadcaster_account_id = twitch_keys.broadcaster_account_id.to_owned();
+        let sender_id = queued
+          .sender_id
+          .or(twitch_keys.sender_account_id.clone())
+          .unwrap_or(broadcaster_account_id.to_owned());
+
+        let result = match queued.announcement {
+          Some(colour) => TwitchEventSubApi::regen_token_if_401(
+            TwitchApi::send_chat_announcement(
+              access_token,
+              client_id,
+              broadcaster_account_id,
+              sender_id,
+              queued.message,
+              colour,
+            ),
+            &mut twitch_keys,
+          )
+          .map(|_| ChatSendResult::Announced),
+          None => TwitchEventSubApi::regen_token_if_401(
+            TwitchApi::send_chat_message(
+              queued.message,
+              access_token,
+              client_id,
+              broadcaster_account_id,
+              Some(sender_id),
+              queued.reply_parent_message_id,
+            ),
+            &mut twitch_keys,
+          )
+          .and_then(|response| {
+            response.data.data.into_iter().next().ok_or_else(|| {
+              EventSubError::ParseError(format!("No sent message in: {}", response.raw))
+            })
+          })
+          .map(|sent| match sent.into_result() {
+            Ok(message_id) => ChatSendResult::Sent { message_id },
+            Err(reason) => ChatSendResult::Dropped(reason),
+          }),
+        };
+
+        result.unwrap_or_else(|e| {
+          error!("Failed to send chat message: {}", e);
+          let _ = error_sender.send(MessageType::Error(e.clone()));
+          ChatSendResult::Failed(e)
+        })
+      },
+    )
+  }
+
+  fn spawn_connection(
+    connection: usize,
+    message_sender: MessageSender,
+    shared: SharedState,
+    custom_subscription_data: Vec<String>,
+    twitch_keys: TwitchKeys,
+    options: ClientOptions,
+  ) -> Result<JoinHandle<()>, WebSocketError> {
+    shared.set_connection_state(connection, ConnectionState::Connecting, &message_sender);
+    let client = match TwitchEventSubApi::connect(&websocket_url()) {
+      Ok(client) => Arc::new(Mutex::new(client)),
+      Err(e) => {
+        let reason = e.to_string();
+        shared.set_connection_state(
+          connection,
+          ConnectionState::Disconnected { reason },
+          &message_sender,
+        );
+        return Err(e);
+      }
+    };
+
+    Ok(thread::spawn(move || {
+      TwitchEventSubApi::event_sub_events(
+        client,
+        message_sender,
+        shared,
+        custom_subscription_data,
+        twitch_keys,
+        options,
+        connection,
+      )
+    }))
+  }
+
+  /// Waits for a connection's welcome message, new connections take a moment to get one.
+  fn wait_for_session(&self, connection: usize) -> Result<String, EventSubError> {
+    let started = Instant::now();
+    loop {
+      if let Some(session_id) = self.shared.session_id(connection) {
+        return Ok(session_id);
+      }
+
+      if started.elapsed() > NEW_CONNECTION_TIMEOUT {
+        return Err(EventSubError::NoSessionEstablished);
+      }
+
+      thread::sleep(Duration::from_millis(50));
+    }
+  }
+
+  fn connect(url: &str) -> Result<WebsocketClient, WebSocketError> {
+    let client = ClientBuilder::new(url)
+      .map_err(|e| WebSocketError::from(WebSocketOtherError::from(e)))?
+      .add_protocol("rust-websocket-events")
+      .connect(None)?;
+
+    client
+      .stream_ref()
+      .as_tcp()
+      .set_read_timeout(Some(SOCKET_READ_TIMEOUT))?;
+    Ok(client)
+  }
+
+  // Creates the subscriptions a few at a time, the results are in the same order. A request
+  // that is rate limited waits for the limit to reset and is tried once more.
+  #[cfg(not(feature = "only_raw_responses"))]
+  fn create_subscriptions(
+    subscriptions: &[(&Subscription, Option<&str>)],
+    session_id: &str,
+    twitch_keys: &mut TwitchKeys,
+    budget: &Mutex<SubscriptionBudget>,
+  ) -> Vec<Result<EventSubSubscriptionInfo, EventSubError>> {
+    let started = Instant::now();
+    let mut results = Vec::with_capacity(subscriptions.len());
+    for chunk in subscriptions.chunks(MAX_PARALLEL_SUBSCRIPTION_REQUESTS) {
+      let created = thread::scope(|scope| {
+        let requests = chunk
+          .iter()
+          .map(|(subscription, broadcaster_id)| {
+            let mut twitch_keys = twitch_keys.clone();
+            scope.spawn(move || {
+              let mut result = TwitchEventSubApi::create_subscription(
+                subscription,
+                *broadcaster_id,
+                session_id,
+                &mut twitch_keys,
+                budget,
+              );
+              if let Err(EventSubError::RateLimited { reset_at }) = result {
+                thread::sleep(wait_until(reset_at));
+                result = TwitchEventSubApi::create_subscription(
+                  subscription,
+                  *broadcaster_id,
+                  session_id,
+                  &mut twitch_keys,
+                  budget,
+                );
+              }
+              (result, twitch_keys)
+            })
           })
+          .collect::<Vec<_>>();
+
+        requests
+          .into_iter()
+          .map(|request| request.join().expect("Subscription request panicked"))
+          .collect::<Vec<_>>()
+      });
+
+      for (result, keys) in created {
+        // Any request may have refreshed the token, the rest of the chunks should use it
+        if keys.access_token.as_ref().map(TokenAccess::get_token)
+          != twitch_keys
+            .access_token
+            .as_ref()
+            .map(TokenAccess::get_token)
+        {
+          twitch_keys.access_token = keys.access_token;
+          twitch_keys.refresh_token = keys.refresh_token;
+        }
+        results.push(result);
+      }
+    }
+
+    info!(
+      "Created {} of {} subscriptions in {}ms",
+      results.iter().filter(|r| r.is_ok()).count(),
+      results.len(),
+      started.elapsed().as_millis()
+    );
+    results
+  }
+
+  fn create_subscription(
+    subscription: &Subscription,
+    broadcaster_id: Option<&str>,
+    session_id: &str,
+    twitch_keys: &mut TwitchKeys,
+    budget: &Mutex<SubscriptionBudget>,
+  ) -> Result<EventSubSubscriptionInfo, EventSubError> {
+    // Subscriptions to the token owner's own channel are free, other channels cost 1
+    // unless that broadcaster has also authorised the app
+    let expected_cost = if broadcaster_id.is_some() { 1 } else { 0 };
+    {
+      let budget = budget.lock().unwrap();
+      if let Some(max_total_cost) = budget.max_total_cost {
+        if budget.total_cost + expected_cost > max_total_cost {
+          return Err(EventSubError::SubscriptionCostLimitReached(max_total_cost));
+        }
+      }
+    }
+
+    let access_token = twitch_keys
+      .access_token
+      .clone()
+      .ok_or(EventSubError::NoAccessTokenProvided)?
+      .get_token();
+    let client_id = twitch_keys.client_id.to_string();
+    let event_subscription = match broadcaster_id {
+      Some(broadcaster_id) => {
+        // Other channels are read as the account the token belongs to unless told otherwise
+        let user_id = twitch_keys
+          .sender_account_id
+          .as_deref()
+          .unwrap_or(&twitch_keys.broadcaster_account_id);
+        let moderator_id = twitch_keys
+          .moderator_account_id
+          .as_deref()
+          .unwrap_or(user_id);
+        let chat_reader_id = twitch_keys
+          .chat_reader_account_id
+          .as_deref()
+          .unwrap_or(user_id);
+        subscription.construct_data_for_broadcaster(
+          session_id,
+          broadcaster_id,
+          moderator_id,
+          chat_reader_id,
+        )
+      }
+      None => subscription.construct_data(session_id, twitch_keys),
+    };
+    subscription.validate_condition(&event_subscription.condition)?;
+
+    let subscriptions = TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::create_eventsub_subscription(access_token, client_id, &event_subscription),
+      twitch_keys,
+    )
+    .map_err(|e| match e {
+      EventSubError::Unauthorized { .. } => {
+        let scope = subscription
+          .required_scope()
+          .iter()
+          .map(Scope::as_str)
+          .collect::<Vec<_>>()
+          .join("+");
+        EventSubError::Unauthorized {
+          missing_scope: (!scope.is_empty()).then_some(scope),
+        }
+      }
+      // Also returned when the account isn't a moderator, so scopes are only blamed when the
+      // token doesn't have them
+      EventSubError::Forbidden(reason) => {
+        let missing = twitch_keys
+          .access_token
+          .clone()
+          .and_then(|token| {
+            TwitchEventSubApi::missing_scopes(token, std::slice::from_ref(subscription)).ok()
+          })
+          .unwrap_or_default();
+        if missing.is_empty() {
+          EventSubError::Forbidden(reason)
+        } else {
+          EventSubError::MissingScopes(missing)
+        }
+      }
+      EventSubError::BadRequest(reason)
+      | EventSubError::Conflict(reason)
+      | EventSubError::HttpError {
+        twitch_message: reason,
+        ..
+      } => EventSubError::SubscriptionFailed {
+        kind: subscription.tag(),
+        reason,
+      },
+      e => e,
+    })?
+    .data;
+
+    budget.lock().unwrap().update(&subscriptions);
+
+    subscriptions
+      .data
+      .into_iter()
+      .next()
+      .ok_or(EventSubError::ParseError(
+        "Response contained no data".to_owned(),
+      ))
+  }
+
+  pub fn validate_token<S: Into<String>>(token: S) -> Result<Validation, EventSubError> {
+    TwitchHttpRequest::new(VALIDATION_TOKEN_URL)
+      .header_authorisation(token.into(), AuthType::OAuth)
+      .send()
+      // An invalid token's error body is still a Validation
+      .and_then(|response| {
+        serde_json::from_str::<Validation>(&response.raw)
+          .map_err(|e| EventSubError::ParseError(e.to_string()))
+      })
+  }
+
+  /// Scopes the subscriptions need that the token hasn't been granted.
+  pub fn missing_scopes(
+    access_token: TokenAccess,
+    subs: &[Subscription],
+  ) -> Result<Vec<Scope>, EventSubError> {
+    let validation = TwitchEventSubApi::validate_token(access_token.get_token())?;
+    if validation.is_error() {
+      return Err(EventSubError::InvalidAccessToken(validation.error_msg()));
+    }
+
+    let granted = validation.scopes.unwrap_or_default();
+    let mut missing = Vec::new();
+    for scope in subs.iter().flat_map(|sub| sub.required_scope()) {
+      if !granted.iter().any(|s| s == scope.as_str()) && !missing.contains(&scope) {
+        missing.push(scope);
+      }
+    }
+
+    Ok(missing)
+  }
+
+  pub fn check_token_meets_requirements(
+    access_token: TokenAccess,
+    subs: &[Subscription],
+  ) -> Result<bool, EventSubError> {
+    match TwitchEventSubApi::missing_scopes(access_token, subs) {
+      Ok(missing) => Ok(missing.is_empty()),
+      Err(EventSubError::InvalidAccessToken(_)) => Ok(false),
+      Err(e) => Err(e),
+    }
+  }
+
+  pub fn open_browser<S: Into<String>, T: Into<String>>(
+    browser_url: S,
+    redirect_url: T,
+  ) -> Result<String, EventSubError> {
+    #[cfg(feature = "auth-browser")]
+    if let Err(e) = open::that(browser_url.into()) {
+      error!("Failed to open browser: {}", e);
+      return Err(EventSubError::UnhandledError(e.to_string()));
+    }
+    #[cfg(not(feature = "auth-browser"))]
+    info!("Open {} in a browser to authorise", browser_url.into());
+
+    let mut redirect_url = redirect_url.into().to_ascii_lowercase();
+
+    if redirect_url.contains("http") {
+      redirect_url = redirect_url
+        .split('/')
+        .collect::<Vec<_>>()
+        .last()
+        .unwrap()
+        .to_string();
+    }
+
+    info!("Starting local tcp listener for token generation");
+    let listener = TcpListener::bind(&redirect_url).expect("Failed to create tcp listener.");
+
+    // accept connections and process them serially
+    match listener.accept() {
+      Ok((mut stream, _b)) => {
+        let mut http_output = String::new();
+        stream
+          .read_to_string(&mut http_output)
+          .expect("Failed to read tcp stream.");
+        Ok(http_output)
       }
+      Err(e) => Err(EventSubError::UnhandledError(e.to_string())),
+    }
+  }
+
+  fn regen_token_if_401<T: FromHttpRequest + std::fmt::Debug>(
+    result: Result<T, EventSubError>,
+    twitch_keys: &mut TwitchKeys,
+  ) -> Result<T, EventSubError> {
+    let Err(EventSubError::TokenRequiresRefreshing(mut http_request)) = result else {
+      return result;
+    };
+    // App tokens have nothing to refresh them with
+    let Some(refresh_token) = twitch_keys.refresh_token.to_owned() else {
+      return Err(EventSubError::Unauthorized {
+        missing_scope: None,
+      });
+    };
+
+    warn!("Access token was rejected, refreshing it and trying again");
+    let token = refresh_user_token(
+      &twitch_keys.client_id,
+      &twitch_keys.client_secret,
+      &refresh_token,
+      twitch_keys.token_storage.as_deref(),
+    )?;
+    http_request.update_token(token.access.get_token());
+    twitch_keys.access_token = Some(token.access);
+    twitch_keys.refresh_token = Some(token.refresh);
+
+    // Only retried once, a new token being rejected won't be fixed by another
+    T::from_request(&http_request).map_err(|e| match e {
+      EventSubError::TokenRequiresRefreshing(_) => EventSubError::Unauthorized {
+        missing_scope: None,
+      },
+      e => e,
     })
   }
 
-  pub fn open_browser<S: Into<String>, T: Into<String>>(
-    browser_url: S,
-    redirect_url: T,
-  ) -> Result<String, EventSubError> {
-    if let Err(e) = open::that(browser_url.into()) {
-      error!("Failed to open browser: {}", e);
-      return Err(EventSubError::UnhandledError(e.to_string()));
+  fn user_access_token(&self) -> String {
+    self
+      .twitch_keys
+      .access_token
+      .clone()
+      .expect("No Access Token set")
+      .get_token()
+  }
+
+  fn process_token_query<S: Into<String>>(post_data: S) -> Result<Token, EventSubError> {
+    TwitchHttpRequest::new(TWITCH_TOKEN_URL)
+      .url_encoded_content()
+      .is_post(post_data)
+      .send()
+      .and_then(|response| {
+        let twitch_response = response.raw;
+        serde_json::from_str::<NewAccessTokenResponse>(&twitch_response)
+          .map_err(|_| EventSubError::AuthorisationError(twitch_response))
+          .map(|new_token_data| {
+            Token::new_user_token(
+              new_token_data.access_token,
+              new_token_data.refresh_token.unwrap(),
+              new_token_data.expires_in as f32,
+            )
+          })
+      })
+  }
+
+  pub fn receive_messages(&mut self) -> Vec<MessageType> {
+    // check thread for new messages without waiting
+    //
+    // return new messages if any
+    let mut messages = Vec::new();
+
+    if let Ok(message) = self.messages_received.recv_timeout(Duration::ZERO) {
+      messages.push(message);
+    }
+
+    messages
+  }
+
+  /// Blocks until the next message arrives.
+  pub fn recv(&self) -> MessageType {
+    // The client holds a sender itself, so the channel can't disconnect
+    self
+      .messages_received
+      .recv()
+      .expect("Message channel closed while the client exists")
+  }
+
+  /// Returns the next message that has already arrived without blocking, for polling once
+  /// per frame.
+  pub fn try_recv(&self) -> Option<MessageType> {
+    self.messages_received.try_recv().ok()
+  }
+
+  /// Waits up to `timeout` for the next message.
+  pub fn recv_timeout(&self, timeout: Duration) -> Option<MessageType> {
+    self.messages_received.recv_timeout(timeout).ok()
+  }
+
+  /// Iterates over messages as they arrive, blocking for each one. Ends once the client
+  /// closes, without yielding the `MessageType::Close`.
+  pub fn messages(&self) -> Messages<'_> {
+    Messages {
+      api: self,
+      closed: false,
+    }
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn delete_message<S: Into<MessageId>>(&mut self, message_id: S) {
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+    let access_token = self
+      .twitch_keys
+      .access_token
+      .clone()
+      .expect("No Access Token set")
+      .get_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+
+    let _ = TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::delete_message(
+        broadcaster_account_id,
+        moderator_account_id,
+        message_id.into(),
+        access_token,
+        client_id,
+      ),
+      &mut self.twitch_keys,
+    );
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn timeout_user<S: Into<UserId>, T: Into<String>>(
+    &mut self,
+    user_id: S,
+    duration: u32,
+    reason: T,
+  ) -> Result<(), EventSubError> {
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::timeout_user(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+        user_id.into(),
+        duration,
+        reason.into(),
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_moderators(
+    &mut self,
+    after: Option<String>,
+  ) -> Result<HelixData<User>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_moderators(access_token, client_id, broadcaster_account_id, after),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn add_moderator<S: Into<UserId>>(&mut self, user_id: S) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::add_moderator(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        user_id.into(),
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn remove_moderator<S: Into<UserId>>(&mut self, user_id: S) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::remove_moderator(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        user_id.into(),
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_vips(&mut self, after: Option<String>) -> Result<HelixData<User>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_vips(access_token, client_id, broadcaster_account_id, after),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn add_vip<S: Into<UserId>>(&mut self, user_id: S) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::add_vip(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        user_id.into(),
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn remove_vip<S: Into<UserId>>(&mut self, user_id: S) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::remove_vip(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        user_id.into(),
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  /// Gets a page of the channel's blocked terms, pass the previous page's cursor as `after`
+  /// to keep paging through the list.
+  #[cfg(feature = "moderation")]
+  pub fn get_blocked_terms(
+    &mut self,
+    after: Option<String>,
+  ) -> Result<HelixData<BlockedTerm>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_blocked_terms(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+        after,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn add_blocked_term<S: Into<String>>(
+    &mut self,
+    text: S,
+  ) -> Result<BlockedTerm, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::add_blocked_term(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+        text.into(),
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<BlockedTerm>>)
+    .and_then(HelixData::into_first)
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn remove_blocked_term<S: Into<String>>(
+    &mut self,
+    blocked_term_id: S,
+  ) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::remove_blocked_term(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+        blocked_term_id.into(),
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_automod_settings(&mut self) -> Result<AutoModSettings, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_automod_settings(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<AutoModSettings>>)
+    .and_then(HelixData::into_first)
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn update_automod_settings(
+    &mut self,
+    settings: AutoModSettingsRequest,
+  ) -> Result<AutoModSettings, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::update_automod_settings(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+        &settings,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<AutoModSettings>>)
+    .and_then(HelixData::into_first)
+  }
+
+  /// Checks whether AutoMod would flag each message if it were sent in the channel.
+  #[cfg(feature = "moderation")]
+  pub fn check_automod_status(
+    &mut self,
+    messages: Vec<AutoModCheckMessage>,
+  ) -> Result<Vec<AutoModStatus>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::check_automod_status(access_token, client_id, broadcaster_account_id, messages),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<AutoModStatus>>)
+    .map(|statuses| statuses.data)
+  }
+
+  /// Approves or denies a message AutoMod is holding for review.
+  #[cfg(feature = "moderation")]
+  pub fn manage_held_automod_message<S: Into<MessageId>>(
+    &mut self,
+    message_id: S,
+    action: AutoModAction,
+  ) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::manage_held_automod_message(
+        access_token,
+        client_id,
+        moderator_account_id,
+        message_id.into(),
+        action,
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn warn_user<S: Into<UserId>, T: Into<String>>(
+    &mut self,
+    user_id: S,
+    reason: T,
+  ) -> Result<UserWarning, EventSubError> {
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::warn_user(
+        broadcaster_account_id,
+        moderator_account_id,
+        user_id.into(),
+        reason.into(),
+        access_token,
+        client_id,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<UserWarning>>)
+    .and_then(HelixData::into_first)
+  }
+
+  /// Shouts out another broadcaster in chat, Twitch allows one every 2 minutes and the same
+  /// broadcaster once an hour.
+  #[cfg(feature = "moderation")]
+  pub fn send_shoutout<S: Into<BroadcasterId>>(
+    &mut self,
+    to_broadcaster_id: S,
+  ) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::send_shoutout(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        to_broadcaster_id.into(),
+        moderator_account_id,
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  /// Gets the channel's custom rewards, or only the ones in `reward_ids` when it isn't empty.
+  ///
+  /// Only rewards created by this app's client id can be updated or deleted, set
+  /// `only_manageable_rewards` to leave the rest out.
+  #[cfg(feature = "channel-points")]
+  pub fn get_custom_rewards(
+    &mut self,
+    reward_ids: Vec<RewardId>,
+    only_manageable_rewards: bool,
+  ) -> Result<Vec<CustomReward>, EventSubError> {
+    let mut fetch = |reward_ids| {
+      let access_token = self.user_access_token();
+      let client_id = self.twitch_keys.client_id.to_string();
+      let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+      TwitchEventSubApi::regen_token_if_401(
+        TwitchApi::get_custom_rewards(
+          access_token,
+          client_id,
+          broadcaster_account_id,
+          reward_ids,
+          only_manageable_rewards,
+        ),
+        &mut self.twitch_keys,
+      )
+      .and_then(parse_response::<HelixData<CustomReward>>)
+      .map(|rewards| rewards.data)
+    };
+
+    // No ids asks for every reward, which is a single request
+    if reward_ids.is_empty() {
+      fetch(Vec::new())
+    } else {
+      batched(reward_ids, MAX_REWARD_IDS_PER_REQUEST, fetch)
+    }
+  }
+
+  #[cfg(feature = "channel-points")]
+  pub fn create_custom_reward(
+    &mut self,
+    reward: CustomRewardBuilder,
+  ) -> Result<CustomReward, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::create_custom_reward(access_token, client_id, broadcaster_account_id, &reward),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<CustomReward>>)
+    .and_then(HelixData::into_first)
+  }
+
+  #[cfg(feature = "channel-points")]
+  pub fn update_custom_reward<S: Into<RewardId>>(
+    &mut self,
+    reward_id: S,
+    reward: CustomRewardBuilder,
+  ) -> Result<CustomReward, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::update_custom_reward(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        reward_id.into(),
+        &reward,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<CustomReward>>)
+    .and_then(HelixData::into_first)
+  }
+
+  #[cfg(feature = "channel-points")]
+  pub fn delete_custom_reward<S: Into<RewardId>>(
+    &mut self,
+    reward_id: S,
+  ) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::delete_custom_reward(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        reward_id.into(),
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  #[cfg(feature = "channel-points")]
+  pub fn get_custom_reward_redemptions<S: Into<RewardId>>(
+    &mut self,
+    reward_id: S,
+    status: RewardRedemptionStatus,
+    after: Option<String>,
+  ) -> Result<HelixData<CustomRewardRedemption>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_custom_reward_redemptions(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        reward_id.into(),
+        status,
+        after,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  /// Fulfills or cancels redemptions, canceling refunds the viewer's channel points.
+  #[cfg(feature = "channel-points")]
+  pub fn update_redemption_status<S: Into<RewardId>>(
+    &mut self,
+    reward_id: S,
+    redemption_ids: Vec<String>,
+    status: RewardRedemptionStatus,
+  ) -> Result<Vec<CustomRewardRedemption>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::update_redemption_status(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        reward_id.into(),
+        redemption_ids,
+        status,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<CustomRewardRedemption>>)
+    .map(|redemptions| redemptions.data)
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_channel_stream_schedule(
+    &mut self,
+    after: Option<String>,
+  ) -> Result<StreamScheduleResponse, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_channel_stream_schedule(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        after,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn create_schedule_segment(
+    &mut self,
+    segment: ScheduleSegmentBuilder,
+  ) -> Result<StreamSchedule, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::create_schedule_segment(access_token, client_id, broadcaster_account_id, &segment),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<StreamScheduleResponse>)
+    .map(|schedule| schedule.data)
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn update_schedule_segment<S: Into<String>>(
+    &mut self,
+    segment_id: S,
+    segment: ScheduleSegmentBuilder,
+  ) -> Result<StreamSchedule, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::update_schedule_segment(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        segment_id.into(),
+        &segment,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<StreamScheduleResponse>)
+    .map(|schedule| schedule.data)
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn delete_schedule_segment<S: Into<String>>(
+    &mut self,
+    segment_id: S,
+  ) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::delete_schedule_segment(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        segment_id.into(),
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  /// Passing `None` turns vacation mode off.
+  #[cfg(feature = "helix-full")]
+  pub fn update_schedule_vacation(
+    &mut self,
+    vacation: Option<VacationSettings>,
+  ) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::update_schedule_vacation(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        vacation,
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_videos(
+    &mut self,
+    query: VideoQuery,
+    video_type: VideoType,
+    period: VideoPeriod,
+    after: Option<String>,
+  ) -> Result<HelixData<Video>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_videos(access_token, client_id, query, video_type, period, after),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_shield_mode_status(&mut self) -> Result<ShieldModeStatus, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_shield_mode_status(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<ShieldModeStatus>>)
+    .and_then(HelixData::into_first)
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn update_shield_mode_status(
+    &mut self,
+    is_active: bool,
+  ) -> Result<ShieldModeStatus, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::update_shield_mode_status(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+        is_active,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<ShieldModeStatus>>)
+    .and_then(HelixData::into_first)
+  }
+
+  /// Turns chat modes such as slow mode or followers only on and off, needs
+  /// `Subscription::ManageChatSettings`.
+  #[cfg(feature = "moderation")]
+  pub fn update_chat_settings(
+    &mut self,
+    settings: ChatSettingsUpdate,
+  ) -> Result<ChatSettings, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::update_chat_settings(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+        &settings,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<ChatSettings>>)
+    .and_then(HelixData::into_first)
+  }
+
+  /// Changes the stream's title, category and other details, needs
+  /// `Subscription::ManageBroadcast`.
+  #[cfg(feature = "helix-full")]
+  pub fn modify_channel_information(
+    &mut self,
+    information: ChannelInformationUpdate,
+  ) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::modify_channel_information(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        &information,
+      ),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  /// Returns `None` when the channel isn't running a charity campaign.
+  #[cfg(feature = "helix-full")]
+  pub fn get_charity_campaign(&mut self) -> Result<Option<CharityCampaign>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_charity_campaign(access_token, client_id, broadcaster_account_id),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<CharityCampaign>>)
+    .map(|campaigns| campaigns.data.into_iter().next())
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_charity_campaign_donations(
+    &mut self,
+    after: Option<String>,
+  ) -> Result<HelixData<CharityDonation>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_charity_campaign_donations(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        after,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_user_block_list(
+    &mut self,
+    after: Option<String>,
+  ) -> Result<HelixData<BlockedUser>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_user_block_list(access_token, client_id, broadcaster_account_id, after),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn block_user<S: Into<UserId>>(
+    &mut self,
+    target_user_id: S,
+    reason: Option<BlockReason>,
+  ) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::block_user(access_token, client_id, target_user_id.into(), reason),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn unblock_user<S: Into<UserId>>(&mut self, target_user_id: S) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::unblock_user(access_token, client_id, target_user_id.into()),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_unban_requests(
+    &mut self,
+    status: UnbanRequestStatus,
+    after: Option<String>,
+  ) -> Result<HelixData<UnbanRequest>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_unban_requests(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+        status,
+        after,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  /// Approving an unban request also unbans the user.
+  #[cfg(feature = "moderation")]
+  pub fn resolve_unban_request<S: Into<String>>(
+    &mut self,
+    unban_request_id: S,
+    resolution: UnbanRequestResolution,
+    resolution_text: Option<String>,
+  ) -> Result<UnbanRequest, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+    let moderator_account_id = self.twitch_keys.moderator_id().to_owned();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::resolve_unban_request(
+        access_token,
+        client_id,
+        broadcaster_account_id,
+        moderator_account_id,
+        unban_request_id.into(),
+        resolution,
+        resolution_text,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<UnbanRequest>>)
+    .and_then(HelixData::into_first)
+  }
+
+  /// Channels the given user is a moderator of, useful for working out which channels
+  /// a bot account can join.
+  #[cfg(feature = "moderation")]
+  pub fn get_moderated_channels<S: Into<UserId>>(
+    &mut self,
+    user_id: S,
+    after: Option<String>,
+  ) -> Result<HelixData<ModeratedChannel>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_moderated_channels(access_token, client_id, user_id.into(), after),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  /// Looks up users by id, any number at a time, in requests of 100.
+  #[cfg(feature = "helix-full")]
+  pub fn get_users(&mut self, user_ids: Vec<UserId>) -> Result<Vec<UserInfo>, EventSubError> {
+    batched(user_ids, MAX_IDS_PER_REQUEST, |user_ids| {
+      let access_token = self.user_access_token();
+      let client_id = self.twitch_keys.client_id.to_string();
+
+      TwitchEventSubApi::regen_token_if_401(
+        TwitchApi::get_users(access_token, client_id, user_ids, Vec::new()),
+        &mut self.twitch_keys,
+      )
+      .and_then(parse_response::<HelixData<UserInfo>>)
+      .map(|users| users.data)
+    })
+    .inspect(|users| self.cache_users(users))
+  }
+
+  /// Looks up users by login name, any number at a time, in requests of 100.
+  #[cfg(feature = "helix-full")]
+  pub fn get_users_by_login<S: Into<String>>(
+    &mut self,
+    logins: Vec<S>,
+  ) -> Result<Vec<UserInfo>, EventSubError> {
+    let logins = logins.into_iter().map(Into::into).collect();
+    batched(logins, MAX_IDS_PER_REQUEST, |logins| {
+      let access_token = self.user_access_token();
+      let client_id = self.twitch_keys.client_id.to_string();
+
+      TwitchEventSubApi::regen_token_if_401(
+        TwitchApi::get_users(access_token, client_id, Vec::new(), logins),
+        &mut self.twitch_keys,
+      )
+      .and_then(parse_response::<HelixData<UserInfo>>)
+      .map(|users| users.data)
+    })
+    .inspect(|users| self.cache_users(users))
+  }
+
+  /// Resolves login names, display names or `@mentions` to users, only asking Twitch about
+  /// the ones that aren't in the user cache. Unknown users are left out.
+  #[cfg(feature = "helix-full")]
+  pub fn lookup_users_by_login<S: AsRef<str>>(
+    &mut self,
+    logins: &[S],
+  ) -> Result<Vec<CachedUser>, EventSubError> {
+    let mut users = Vec::new();
+    let mut missing = Vec::new();
+    for login in logins {
+      match self.cached_user_by_login(login.as_ref()) {
+        Some(user) => users.push(user),
+        None => missing.push(login.as_ref().trim_start_matches('@').to_lowercase()),
+      }
+    }
+
+    if !missing.is_empty() {
+      let found = self.get_users_by_login(missing)?;
+      users.extend(found.into_iter().map(|user| CachedUser {
+        id: user.id,
+        login: user.login,
+        display_name: user.display_name,
+      }));
+    }
+
+    Ok(users)
+  }
+
+  /// Resolves a single login, display name or `@mention`, see `lookup_users_by_login`.
+  #[cfg(feature = "helix-full")]
+  pub fn lookup_user_by_login(&mut self, login: &str) -> Result<Option<CachedUser>, EventSubError> {
+    self
+      .lookup_users_by_login(&[login])
+      .map(|users| users.into_iter().next())
+  }
+
+  #[cfg(feature = "helix-full")]
+  fn cache_users(&self, users: &[UserInfo]) {
+    let mut cache = self.shared.user_cache.lock().unwrap();
+    for user in users {
+      cache.insert(&user.id, &user.login, &user.display_name);
+    }
+  }
+
+  /// The live streams of any number of users, in requests of 100. Users that aren't live
+  /// are left out.
+  #[cfg(feature = "helix-full")]
+  pub fn get_streams(&mut self, user_ids: Vec<UserId>) -> Result<Vec<StreamInfo>, EventSubError> {
+    batched(user_ids, MAX_IDS_PER_REQUEST, |user_ids| {
+      let access_token = self.user_access_token();
+      let client_id = self.twitch_keys.client_id.to_string();
+
+      TwitchEventSubApi::regen_token_if_401(
+        TwitchApi::get_streams(access_token, client_id, user_ids),
+        &mut self.twitch_keys,
+      )
+      .and_then(parse_response::<HelixData<StreamInfo>>)
+      .map(|streams| streams.data)
+    })
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_followed_channels<S: Into<UserId>>(
+    &mut self,
+    user_id: S,
+    broadcaster_id: Option<String>,
+    after: Option<String>,
+  ) -> Result<HelixData<FollowedChannel>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_followed_channels(
+        access_token,
+        client_id,
+        user_id.into(),
+        broadcaster_id,
+        after,
+      ),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  /// Most recent hype train events first, the first event can be used to recover the
+  /// progress of a hype train that is already running.
+  #[cfg(feature = "helix-full")]
+  pub fn get_hype_train_events(
+    &mut self,
+    after: Option<String>,
+  ) -> Result<HelixData<HypeTrainEvent>, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_hype_train_events(access_token, client_id, broadcaster_account_id, after),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response)
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_hype_train_status(&mut self) -> Result<HypeTrainStatus, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_hype_train_status(access_token, client_id, broadcaster_account_id),
+      &mut self.twitch_keys,
+    )
+    .and_then(parse_response::<HelixData<HypeTrainStatus>>)
+    .and_then(HelixData::into_first)
+  }
+
+  /// Lists the EventSub subscriptions created by this client id, along with their cost.
+  pub fn get_eventsub_subscriptions(
+    &mut self,
+    filter: EventSubSubscriptionFilter,
+    after: Option<String>,
+  ) -> Result<EventSubSubscriptions, EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+
+    let subscriptions: EventSubSubscriptions = TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::get_eventsub_subscriptions(access_token, client_id, filter, after),
+      &mut self.twitch_keys,
+    )?
+    .data;
+
+    self.shared.budget.lock().unwrap().update(&subscriptions);
+
+    Ok(subscriptions)
+  }
+
+  /// Total cost of the client id's subscriptions, as last reported by Twitch.
+  pub fn subscription_cost_total(&self) -> u32 {
+    self.shared.budget.lock().unwrap().total_cost
+  }
+
+  /// How much more cost can be subscribed to before Twitch's limit is reached, `None`
+  /// until Twitch has reported the limit in response to a subscription request.
+  pub fn remaining_budget(&self) -> Option<u32> {
+    let budget = self.shared.budget.lock().unwrap();
+    budget
+      .max_total_cost
+      .map(|max_total_cost| max_total_cost.saturating_sub(budget.total_cost))
+  }
+
+  pub fn delete_eventsub_subscription<S: Into<SubscriptionId>>(
+    &mut self,
+    subscription_id: S,
+  ) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
+    let client_id = self.twitch_keys.client_id.to_string();
+
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::delete_eventsub_subscription(access_token, client_id, subscription_id.into()),
+      &mut self.twitch_keys,
+    )
+    .map(|_| ())
+  }
+
+  /// Deletes every subscription that is no longer enabled, such as websocket subscriptions
+  /// left behind when a previous session crashed, and returns the ones that were removed.
+  pub fn cleanup_stale_subscriptions(
+    &mut self,
+  ) -> Result<Vec<EventSubSubscriptionInfo>, EventSubError> {
+    let stale = Paginated::new(|after| {
+      self.get_eventsub_subscriptions(EventSubSubscriptionFilter::All, after)
+    })
+    .filter(|s| !matches!(s, Ok(s) if s.status == EventSubSubscriptionStatus::Enabled))
+    .collect::<Result<Vec<_>, _>>()?;
+
+    for subscription in &stale {
+      self.delete_eventsub_subscription(subscription.id.to_owned())?;
+    }
+
+    Ok(stale)
+  }
+
+  /// Subscribes to an event on the current websocket session without reconnecting.
+  ///
+  /// The token must already have the subscription's scope.
+  pub fn add_subscription(
+    &mut self,
+    subscription: Subscription,
+  ) -> Result<SubscriptionHandle, EventSubError> {
+    self.register_subscription(subscription, None)
+  }
+
+  pub fn add_subscription_for_broadcaster<S: Into<BroadcasterId>>(
+    &mut self,
+    broadcaster_id: S,
+    subscription: Subscription,
+  ) -> Result<SubscriptionHandle, EventSubError> {
+    self.register_subscription(subscription, Some(broadcaster_id.into().into_string()))
+  }
+
+  /// Removes an event from the current websocket session.
+  pub fn remove_subscription(&mut self, subscription: Subscription) -> Result<(), EventSubError> {
+    self.unregister_subscription(subscription, None)
+  }
+
+  pub fn remove_subscription_for_broadcaster<S: Into<BroadcasterId>>(
+    &mut self,
+    broadcaster_id: S,
+    subscription: Subscription,
+  ) -> Result<(), EventSubError> {
+    self.unregister_subscription(subscription, Some(broadcaster_id.into().into_string()))
+  }
+
+  fn register_subscription(
+    &mut self,
+    subscription: Subscription,
+    broadcaster_id: Option<String>,
+  ) -> Result<SubscriptionHandle, EventSubError> {
+    if subscription.tag().is_empty() {
+      return Err(EventSubError::NotAnEventSubSubscription(format!(
+        "{:?}",
+        subscription
+      )));
+    }
+
+    let missing = TwitchEventSubApi::missing_scopes(
+      self
+        .twitch_keys
+        .access_token
+        .clone()
+        .ok_or(EventSubError::NoAccessTokenProvided)?,
+      std::slice::from_ref(&subscription),
+    )?;
+    if !missing.is_empty() {
+      return Err(EventSubError::MissingScopes(missing));
+    }
+
+    let connection = self
+      .shared
+      .registry
+      .lock()
+      .unwrap()
+      .available_connection()
+      .ok_or(EventSubError::TooManySubscriptions)?;
+
+    if connection >= self.receive_threads.len() {
+      info!("Opening websocket connection {}", connection + 1);
+      let receive_thread = TwitchEventSubApi::spawn_connection(
+        connection,
+        self.messages_sender.clone(),
+        self.shared.clone(),
+        Vec::new(),
+        self.twitch_keys.clone(),
+        self.options.clone(),
+      )
+      .map_err(|e| EventSubError::UnhandledError(e.to_string()))?;
+      self.receive_threads.push(receive_thread);
+    }
+
+    let session_id = self.wait_for_session(connection)?;
+
+    let info = TwitchEventSubApi::create_subscription(
+      &subscription,
+      broadcaster_id.as_deref(),
+      &session_id,
+      &mut self.twitch_keys,
+      &self.shared.budget,
+    )?;
+
+    let mut registry = self.shared.registry.lock().unwrap();
+    let registered = registry.insert(
+      subscription.to_owned(),
+      broadcaster_id.to_owned(),
+      connection,
+    );
+    registered.id = Some(info.id.to_owned());
+    registered.condition = Some(info.condition.to_owned());
+    registered.cost = info.cost;
+
+    Ok(SubscriptionHandle::new(subscription, broadcaster_id, &info))
+  }
+
+  fn unregister_subscription(
+    &mut self,
+    subscription: Subscription,
+    broadcaster_id: Option<String>,
+  ) -> Result<(), EventSubError> {
+    if subscription.tag().is_empty() {
+      return Err(EventSubError::NotAnEventSubSubscription(format!(
+        "{:?}",
+        subscription
+      )));
     }
 
-    let mut redirect_url = redirect_url.into().to_ascii_lowercase();
+    let ids = {
+      let mut registry = self.shared.registry.lock().unwrap();
+      let ids = registry
+        .iter_mut()
+        .filter(|r| r.subscription == subscription && r.broadcaster_id == broadcaster_id)
+        .filter_map(|r| r.id.to_owned().map(|id| (id, r.cost)))
+        .collect::<Vec<_>>();
+      registry.remove(&subscription, broadcaster_id.as_deref());
+      ids
+    };
 
-    if redirect_url.contains("http") {
-      redirect_url = redirect_url
-        .split('/')
-        .collect::<Vec<_>>()
-        .last()
-        .unwrap()
-        .to_string();
+    for (id, cost) in ids {
+      self.delete_eventsub_subscription(id)?;
+      let mut budget = self.shared.budget.lock().unwrap();
+      budget.total_cost = budget.total_cost.saturating_sub(cost);
     }
 
-    info!("Starting local tcp listener for token generation");
-    let listener = TcpListener::bind(&redirect_url).expect("Failed to create tcp listener.");
+    Ok(())
+  }
 
-    // accept connections and process them serially
-    match listener.accept() {
-      Ok((mut stream, _b)) => {
-        let mut http_output = String::new();
-        stream
-          .read_to_string(&mut http_output)
-          .expect("Failed to read tcp stream.");
-        Ok(http_output)
+  /// Creates a new stream that receives a copy of every event, independently of
+  /// `receive_messages` and any other streams.
+  pub fn event_stream(&self) -> EventStream {
+    let (sender, receiver) =
+      message_channel(self.options.message_buffer, self.options.overflow_policy);
+    let accepts: StreamFilter = Arc::new(Mutex::new(Box::new(|_| true)));
+    self
+      .shared
+      .event_streams
+      .lock()
+      .unwrap()
+      .push((accepts.clone(), sender));
+    EventStream::new(receiver, accepts)
+  }
+
+  /// Sends every event from now on to the sink as well.
+  pub fn add_sink<T: EventSink + 'static>(&self, sink: T) {
+    self.shared.sinks.add(Box::new(sink));
+  }
+
+  /// The user from the user cache, if they've been seen recently.
+  pub fn cached_user(&self, user_id: &str) -> Option<CachedUser> {
+    self.shared.user_cache.lock().unwrap().by_id(user_id)
+  }
+
+  /// The user from the user cache by login, display name or `@mention`.
+  pub fn cached_user_by_login(&self, login: &str) -> Option<CachedUser> {
+    self.shared.user_cache.lock().unwrap().by_login(login)
+  }
+
+  /// Status of the first websocket connection, which every client has.
+  pub fn connection_status(&self) -> ConnectionStatus {
+    self
+      .shared
+      .connection_status(0)
+      .unwrap_or_else(|| ConnectionStatus::new(0))
+  }
+
+  /// Status of every websocket connection the client has opened, see `connection_status`.
+  pub fn connection_statuses(&self) -> Vec<ConnectionStatus> {
+    self
+      .shared
+      .statuses
+      .lock()
+      .unwrap()
+      .iter()
+      .flatten()
+      .cloned()
+      .collect()
+  }
+
+  /// Subscriptions the client is keeping alive across reconnects and revocations.
+  pub fn registered_subscriptions(&self) -> Vec<RegisteredSubscription> {
+    self.shared.registry.lock().unwrap().subscriptions()
+  }
+
+  /// Handles for every subscription that has been created on the current sessions, including
+  /// the ones the client was built with.
+  pub fn subscription_handles(&self) -> Vec<SubscriptionHandle> {
+    self
+      .registered_subscriptions()
+      .into_iter()
+      .filter_map(|registered| {
+        Some(SubscriptionHandle {
+          id: registered.id.as_deref()?.into(),
+          kind: registered.kind(),
+          status: EventSubSubscriptionStatus::Enabled,
+          subscription: registered.subscription,
+          broadcaster_id: registered.broadcaster_id,
+        })
+      })
+      .collect()
+  }
+
+  /// Closes the websocket connections and waits a moment for the receive threads to stop.
+  /// Dropping the client does the same.
+  pub fn shutdown(mut self) -> Result<(), EventSubError> {
+    self.close(false)
+  }
+
+  /// Deletes the client's subscriptions from Twitch before shutting down, rather than
+  /// leaving Twitch to disable them once the connections close.
+  pub fn shutdown_and_unsubscribe(mut self) -> Result<(), EventSubError> {
+    self.close(true)
+  }
+
+  fn close(&mut self, delete_subscriptions: bool) -> Result<(), EventSubError> {
+    if self.shared.is_shutting_down() {
+      return Ok(());
+    }
+
+    let mut result = Ok(());
+    if delete_subscriptions {
+      let ids = self
+        .registered_subscriptions()
+        .into_iter()
+        .filter_map(|registered| registered.id)
+        .collect::<Vec<_>>();
+      for id in ids {
+        // Keep going so one failure doesn't leave the rest subscribed
+        if let Err(e) = self.delete_eventsub_subscription(id) {
+          error!("Failed to delete subscription: {}", e);
+          result = Err(e);
+        }
       }
-      Err(e) => Err(EventSubError::UnhandledError(e.to_string())),
     }
-  }
 
-  fn regen_token_if_401(
-    result: Result<String, EventSubError>,
-    twitch_keys: &mut TwitchKeys,
-  ) -> Result<String, EventSubError> {
-    //warn!("Token return 401!");
-    if let Err(EventSubError::TokenRequiresRefreshing(mut http_request)) = result {
-      warn!("Token requires refreshing return!");
-      if let Ok(token) = TwitchApi::generate_token_from_refresh_token(
-        twitch_keys.client_id.to_owned(),
-        twitch_keys.client_secret.to_owned(),
-        twitch_keys.refresh_token.clone().unwrap().to_owned(),
-      ) {
-        println!("Generated new keys as 401 was returned!");
-        info!("Generated new keys as 401 was returned!");
-        twitch_keys.access_token = Some(token.access);
-        twitch_keys.refresh_token = Some(token.refresh.to_owned());
-      }
-
-      let access_token = twitch_keys.access_token.as_ref().unwrap();
-      http_request.update_token(access_token.get_token());
-      http_request.run()
-    } else {
-      if result.is_err() {
-        warn!(
-          "regen 401 called with result being an error, but wasnt token refresh required: {:?}",
-          result
-        );
-        println!("BIG ERROR!");
+    let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+    #[cfg(feature = "chat")]
+    self.chat_queue.flush(deadline);
+
+    info!("Shutting down websocket client.");
+    self.shared.shutdown.store(true, Ordering::Relaxed);
+
+    for thread in self.receive_threads.drain(..) {
+      while !thread.is_finished() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+      }
+
+      if thread.is_finished() {
+        let _ = thread.join();
+      } else {
+        warn!("Receive thread didn't stop in time, leaving it to finish on its own.");
       }
-      result
     }
-  }
 
-  fn process_token_query<S: Into<String>>(post_data: S) -> Result<Token, EventSubError> {
-    TwitchHttpRequest::new(TWITCH_TOKEN_URL)
-      .url_encoded_content()
-      .is_post(post_data)
-      .run()
-      .and_then(|twitch_response| {
-        serde_json::from_str::<NewAccessTokenResponse>(&twitch_response)
-          .map_err(|_| EventSubError::AuthorisationError(twitch_response))
-          .map(|new_token_data| {
-            Token::new_user_token(
-              new_token_data.access_token,
-              new_token_data.refresh_token.unwrap(),
-              new_token_data.expires_in as f32,
-            )
-          })
-      })
+    result
   }
 
-  pub fn receive_messages(&mut self) -> Vec<MessageType> {
-    // check thread for new messages without waiting
-    //
-    // return new messages if any
-    let mut messages = Vec::new();
+  /// Closes the websocket connections, revokes the access token so it can't be used again,
+  /// and deletes it from the token storage. For logging out or uninstalling.
+  pub fn shutdown_and_revoke(mut self) -> Result<(), EventSubError> {
+    self.close(false)?;
 
-    if let Ok(message) = self.messages_received.recv_timeout(Duration::ZERO) {
-      messages.push(message);
+    if let Some(access_token) = &self.twitch_keys.access_token {
+      TwitchApi::revoke_token(
+        self.twitch_keys.client_id.to_owned(),
+        access_token.get_token(),
+      )?;
     }
 
-    messages
+    if let Some(storage) = &self.twitch_keys.token_storage {
+      storage.delete()?;
+    }
+
+    Ok(())
   }
 
-  pub fn delete_message<S: Into<String>>(&mut self, message_id: S) {
-    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
-    let moderator_account_id = broadcaster_account_id.to_owned();
-    let access_token = self
-      .twitch_keys
-      .access_token
-      .clone()
-      .expect("No Access Token set")
-      .get_token();
-    let client_id = self.twitch_keys.client_id.to_string();
+  /// Has the Twitch CLI send a test event for the subscription to this client, the CLI's
+  /// websocket server has to be running, see `twitch_cli`. Returns what the CLI printed.
+  pub fn trigger_cli_event(&self, subscription: &Subscription) -> Result<String, EventSubError> {
+    let kind = subscription.tag();
+    if kind.is_empty() {
+      return Err(EventSubError::NotAnEventSubSubscription(format!(
+        "{:?}",
+        subscription
+      )));
+    }
 
-    let _ = TwitchEventSubApi::regen_token_if_401(
-      TwitchApi::delete_message(
-        broadcaster_account_id,
-        moderator_account_id,
-        message_id.into(),
-        access_token,
-        client_id,
-      ),
-      &mut self.twitch_keys,
-    );
+    let session_id = self
+      .shared
+      .session_id(0)
+      .ok_or(EventSubError::NoSessionEstablished)?;
+
+    let output = Command::new("twitch")
+      .args(["event", "trigger", &kind, "--transport=websocket"])
+      .args(["--session", &session_id])
+      .output()
+      .map_err(|e| EventSubError::UnhandledError(format!("Failed to run the Twitch CLI: {}", e)))?;
+
+    if !output.status.success() {
+      return Err(EventSubError::UnhandledError(
+        String::from_utf8_lossy(&output.stderr).to_string(),
+      ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
   }
 
-  pub fn timeout_user<S: Into<String>, T: Into<String>>(
+  /// Runs the browser authorisation flow again, asking for the scopes of every registered
+  /// subscription along with those of `extra`. Use after `MissingScopes`, scope only
+  /// subscriptions such as `ManageVips` have to be passed in `extra`.
+  pub fn reauthorise<S: Into<String>>(
     &mut self,
-    user_id: S,
-    duration: u32,
-    reason: T,
-  ) {
-    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
-    let moderator_account_id = broadcaster_account_id.to_owned();
+    redirect_url: S,
+    extra: &[Subscription],
+  ) -> Result<(), EventSubError> {
+    let mut subscriptions = self
+      .registered_subscriptions()
+      .into_iter()
+      .map(|registered| registered.subscription)
+      .collect::<Vec<_>>();
+    subscriptions.extend_from_slice(extra);
+
+    let token = TwitchApi::generate_user_token(
+      self.twitch_keys.client_id.to_owned(),
+      self.twitch_keys.client_secret.to_owned(),
+      redirect_url,
+      &subscriptions,
+    )?;
+
+    if let Some(storage) = &self.twitch_keys.token_storage {
+      storage.save(&token)?;
+    }
+    self.twitch_keys.access_token = Some(token.access);
+    self.twitch_keys.refresh_token = Some(token.refresh);
 
-    let access_token = self
-      .twitch_keys
-      .access_token
-      .clone()
-      .expect("No Access Token set")
-      .get_token();
-    let client_id = self.twitch_keys.client_id.to_string();
-    let _ = TwitchEventSubApi::regen_token_if_401(
-      TwitchApi::timeout_user(
-        access_token,
-        client_id,
-        broadcaster_account_id,
-        moderator_account_id,
-        user_id.into(),
-        duration,
-        reason.into(),
-      ),
-      &mut self.twitch_keys,
-    );
+    Ok(())
   }
 
-  pub fn send_chat_message<S: Into<String>>(&mut self, message: S) {
-    self.send_chat_message_with_reply(message, None);
+  #[cfg(feature = "chat")]
+  pub fn get_user_chat_color(
+    &mut self,
+    user_ids: Vec<UserId>,
+  ) -> Result<Vec<UserChatColour>, EventSubError> {
+    batched(user_ids, MAX_IDS_PER_REQUEST, |user_ids| {
+      let access_token = self.user_access_token();
+      let client_id = self.twitch_keys.client_id.to_string();
+
+      TwitchEventSubApi::regen_token_if_401(
+        TwitchApi::get_user_chat_color(access_token, client_id, user_ids),
+        &mut self.twitch_keys,
+      )
+      .and_then(parse_response::<HelixData<UserChatColour>>)
+      .map(|colours| colours.data)
+    })
   }
 
-  pub fn send_chat_message_with_reply<S: Into<String>>(
-    &mut self,
-    message: S,
-    reply_message_parent_id: Option<String>,
-  ) {
-    let message: String = message.into();
-    let access_token = self
-      .twitch_keys
-      .access_token
-      .clone()
-      .expect("No Access Token set")
-      .get_token();
+  /// Updates the chat colour of the account messages are sent from.
+  #[cfg(feature = "chat")]
+  pub fn update_user_chat_color(&mut self, colour: ChatColour) -> Result<(), EventSubError> {
+    let access_token = self.user_access_token();
     let client_id = self.twitch_keys.client_id.to_string();
-    let broadcaster_account_id = self.twitch_keys.broadcaster_account_id.to_string();
     let sender_id = self
       .twitch_keys
       .sender_account_id
       .clone()
       .unwrap_or(self.twitch_keys.broadcaster_account_id.to_string());
 
-    let _ = TwitchEventSubApi::regen_token_if_401(
-      TwitchApi::send_chat_message(
-        message,
-        access_token,
-        client_id,
-        broadcaster_account_id,
-        Some(sender_id),
-        reply_message_parent_id,
-      ),
+    TwitchEventSubApi::regen_token_if_401(
+      TwitchApi::update_user_chat_color(access_token, client_id, sender_id, colour),
       &mut self.twitch_keys,
-    );
+    )
+    .map(|_| ())
+  }
+
+  /// Queues a chat message, taking either text or a [`ChatMessageBuilder`] for replies,
+  /// announcements and splitting long messages. The returned [`PendingChatMessage`] can be
+  /// waited on for how it went, or ignored.
+  #[cfg(feature = "chat")]
+  pub fn send_chat_message<M: Into<ChatMessageBuilder>>(
+    &mut self,
+    message: M,
+  ) -> PendingChatMessage {
+    let (result_sender, result_receiver) = channel();
+    let mut message = message.into();
+    if self.options.split_long_chat_messages {
+      message = message.split_long_message();
+    }
+
+    let queued = message.into_queued(result_sender.clone());
+    let pending = PendingChatMessage::new(result_receiver, queued.len());
+
+    for queued in queued {
+      if let Err((queued, reason)) = self.chat_queue.push(queued) {
+        warn!("Dropped chat message: {}", reason);
+        let _ = result_sender.send(ChatSendResult::queue_dropped(&reason));
+        let _ = self.messages_sender.send(MessageType::ChatMessageDropped {
+          message: queued.message,
+          reason,
+        });
+      }
+    }
+
+    pending
+  }
+
+  #[cfg(feature = "chat")]
+  #[deprecated(note = "use send_chat_message with ChatMessageBuilder::reply_to")]
+  pub fn send_chat_message_with_reply<S: Into<String>>(
+    &mut self,
+    message: S,
+    reply_message_parent_id: Option<String>,
+  ) -> PendingChatMessage {
+    let mut message = ChatMessageBuilder::new(message);
+    if let Some(reply_message_parent_id) = reply_message_parent_id {
+      message = message.reply_to(reply_message_parent_id);
+    }
+    self.send_chat_message(message)
   }
 
   #[cfg(feature = "only_raw_responses")]
   fn event_sub_events(
-    client: Arc<Mutex<Client<TlsStream<TcpStream>>>>,
-    message_sender: SyncSender<MessageType>,
-    subscriptions: Vec<Subscription>,
-    mut custom_subscriptions: Vec<String>,
-    twitch_keys: TwitchKeys,
+    client: Arc<Mutex<WebsocketClient>>,
+    message_sender: MessageSender,
+    shared: SharedState,
+    _custom_subscriptions: Vec<String>,
+    _twitch_keys: TwitchKeys,
+    _options: ClientOptions,
+    _connection: usize,
   ) {
     loop {
       let client = client.clone();
       let mut client = client.lock().unwrap();
+      if shared.is_shutting_down() {
+        let _ = client.send_message(&OwnedMessage::Close(None));
+        return;
+      }
+
       let message = match client.recv_message() {
         Ok(m) => m,
-        Err(WebSocketError::IoError(e)) if e.kind() == ErrorKind::WouldBlock => {
+        Err(WebSocketError::IoError(e))
+          if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+        {
           continue;
         }
         Err(e) => {
           error!("recv message error: {:?}", e);
           let _ = client.send_message(&OwnedMessage::Close(None));
-          message_sender.send(MessageType::Close).unwrap();
+          let _ = message_sender.send(MessageType::Close);
 
           return;
         }
       };
 
       if let OwnedMessage::Text(msg) = message.clone() {
-        message_sender.send(MessageType::RawResponse(msg)).unwrap();
+        let _ = message_sender.send(MessageType::RawResponse(msg));
         continue;
       }
     }
@@ -612,73 +3355,218 @@ impl TwitchEventSubApi {
 
   #[cfg(not(feature = "only_raw_responses"))]
   fn event_sub_events(
-    client: Arc<Mutex<Client<TlsStream<TcpStream>>>>,
-    message_sender: SyncSender<MessageType>,
-    subscriptions: Vec<Subscription>,
-    mut custom_subscriptions: Vec<String>,
+    client: Arc<Mutex<WebsocketClient>>,
+    message_sender: MessageSender,
+    shared: SharedState,
+    custom_subscriptions: Vec<String>,
     mut twitch_keys: TwitchKeys,
+    options: ClientOptions,
+    connection: usize,
   ) {
+    #[cfg(feature = "tracing")]
+    let session_span = tracing::info_span!(
+      "websocket_session",
+      connection,
+      session_id = tracing::field::Empty
+    )
+    .entered();
+
+    // Twitch moves subscriptions over when it asks us to reconnect, so the welcome
+    // message on the new connection mustn't subscribe again
+    let mut migrating_session = false;
+
     loop {
       let client = client.clone();
       let mut client = client.lock().unwrap();
+      if shared.is_shutting_down() {
+        let _ = client.send_message(&OwnedMessage::Close(None));
+        shared.set_session_id(connection, None);
+        shared.set_connection_state(connection, ConnectionState::shut_down(), &message_sender);
+        return;
+      }
+
       let message = match client.recv_message() {
         Ok(m) => m,
-        Err(WebSocketError::IoError(e)) if e.kind() == ErrorKind::WouldBlock => {
+        Err(WebSocketError::IoError(e))
+          if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+        {
           continue;
         }
         Err(e) => {
           error!("recv message error: {:?}", e);
           let _ = client.send_message(&OwnedMessage::Close(None));
-          message_sender.send(MessageType::Close).unwrap();
+          shared.set_session_id(connection, None);
+          if shared.is_shutting_down() {
+            shared.set_connection_state(connection, ConnectionState::shut_down(), &message_sender);
+            return;
+          }
 
-          return;
+          shared.set_connection_state(connection, ConnectionState::Reconnecting, &message_sender);
+          metrics::reconnect(false);
+          match TwitchEventSubApi::reconnect(&websocket_url(), &options.reconnect_policy) {
+            Some(new_client) => {
+              *client = new_client;
+              migrating_session = false;
+              shared.set_connection_state(connection, ConnectionState::Connecting, &message_sender);
+              continue;
+            }
+            None => {
+              shared.set_connection_state(
+                connection,
+                ConnectionState::Disconnected {
+                  reason: e.to_string(),
+                },
+                &message_sender,
+              );
+              // Only losing the first connection closes the client
+              if connection == 0 {
+                let _ = message_sender.send(MessageType::Close);
+              } else {
+                let _ =
+                  message_sender.send(MessageType::Error(EventSubError::WebsocketCreationFailed));
+              }
+              return;
+            }
+          }
         }
       };
 
       match message {
         OwnedMessage::Text(msg) => {
-          let message = serde_json::from_str(&msg);
-
-          if let Err(e) = message {
-            error!("Unimplemented twitch response: {}\n{}", msg, e);
-            message_sender.send(MessageType::RawResponse(msg)).unwrap();
+          let message: GenericMessage = match serde_json::from_str(&msg) {
+            Ok(message) => message,
+            Err(e) => {
+              error!("Unimplemented twitch response: {}\n{}", msg, e);
+              let _ = message_sender.send(options.unparsed_message(msg, e));
+              continue;
+            }
+          };
+
+          shared.message_received(connection, &message.metadata.message_timestamp);
+
+          if matches!(
+            message.event_type(),
+            EventMessageType::Notification | EventMessageType::Revocation
+          ) && shared
+            .deduplicator
+            .lock()
+            .unwrap()
+            .is_duplicate(&message.metadata.message_id)
+          {
+            info!("Dropping duplicate message {}", message.metadata.message_id);
             continue;
           }
 
-          let message: GenericMessage = message.unwrap();
-
           match message.event_type() {
             EventMessageType::Welcome => {
-              let session_id = message.clone().payload.unwrap().session.unwrap().id;
+              let Some(session_id) = message.payload.and_then(|p| p.session).map(|s| s.id) else {
+                error!("Welcome message without a session: {}", msg);
+                let _ = message_sender.send(options.unparsed_message(msg, "missing session"));
+                continue;
+              };
+              shared.set_session_id(connection, Some(session_id.to_owned()));
+              #[cfg(feature = "tracing")]
+              session_span.record("session_id", session_id.as_str());
+              shared.set_connection_state(
+                connection,
+                ConnectionState::Connected {
+                  session_id: session_id.to_owned(),
+                  since: Instant::now(),
+                },
+                &message_sender,
+              );
 
-              let mut sub_data = subscriptions
-                .iter()
-                .filter_map(|s| {
-                  serde_json::to_string(&s.construct_data(&session_id, &twitch_keys)).ok()
-                })
-                .collect::<Vec<_>>();
-              sub_data.append(&mut custom_subscriptions);
+              if migrating_session {
+                migrating_session = false;
+                continue;
+              }
 
               info!("Subscribing to events!");
-              let mut clone_twitch_keys = twitch_keys.clone();
               if let Some(TokenAccess::User(ref token)) = twitch_keys.access_token {
-                sub_data
+                let token = token.to_owned();
+                // Copied so the registry isn't locked while waiting on Twitch
+                let mut registered = shared
+                  .registry
+                  .lock()
+                  .unwrap()
+                  .iter_mut()
+                  .filter(|r| r.connection == connection)
+                  .map(|r| r.to_owned())
+                  .collect::<Vec<_>>();
+                let results = TwitchEventSubApi::create_subscriptions(
+                  &registered
+                    .iter()
+                    .map(|r| (&r.subscription, r.broadcaster_id.as_deref()))
+                    .collect::<Vec<_>>(),
+                  &session_id,
+                  &mut twitch_keys,
+                  &shared.budget,
+                );
+                #[cfg(feature = "irc")]
+                let chat_failed = registered.iter().zip(&results).any(|(r, result)| {
+                  r.subscription == Subscription::ChatMessage && result.is_err()
+                });
+                let mut ready = SubscriptionsReady {
+                  connection,
+                  succeeded: Vec::new(),
+                  failed: Vec::new(),
+                };
+                for (registered, result) in registered.iter_mut().zip(results) {
+                  match result {
+                    Ok(info) => {
+                      registered.id = Some(info.id);
+                      registered.condition = Some(info.condition);
+                      registered.cost = info.cost;
+                      ready.succeeded.push(registered.subscription.to_owned());
+                    }
+                    Err(error) => {
+                      registered.id = None;
+                      ready.failed.push((
+                        registered.subscription.to_owned(),
+                        FailureReason::new(&registered.subscription, error.to_owned()),
+                      ));
+                      let _ = message_sender.send(MessageType::Error(error));
+                    }
+                  }
+                }
+                let mut registry = shared.registry.lock().unwrap();
+                for registered in &registered {
+                  registry.update(registered);
+                }
+                drop(registry);
+                let _ = message_sender.send(MessageType::SubscriptionsReady(ready));
+
+                #[cfg(feature = "irc")]
+                if chat_failed
+                  && !options.irc_fallback_channels.is_empty()
+                  && !shared.irc_chat_started.swap(true, Ordering::Relaxed)
+                {
+                  warn!("Chat subscription failed, reading chat over IRC instead.");
+                  let channels = options.irc_fallback_channels.clone();
+                  let message_sender = message_sender.clone();
+                  let shared = shared.clone();
+                  let policy = options.reconnect_policy;
+                  thread::spawn(move || {
+                    TwitchEventSubApi::irc_chat_events(channels, message_sender, shared, policy)
+                  });
+                }
+
+                let mut clone_twitch_keys = twitch_keys.clone();
+                custom_subscriptions
                   .iter()
                   .map(|sub_data| {
-                    let a = TwitchHttpRequest::new(SUBSCRIBE_URL)
-                      .full_auth(token.to_owned(), twitch_keys.client_id.to_string())
+                    TwitchHttpRequest::new(SUBSCRIBE_URL)
+                      .full_auth(token.expose(), twitch_keys.client_id.to_string())
                       .json_content()
                       .is_post(sub_data)
-                      .run();
-                    a
+                      .run()
                   })
                   .map(|a| TwitchEventSubApi::regen_token_if_401(a, &mut clone_twitch_keys))
                   .filter_map(Result::err)
                   .for_each(|error| {
-                    message_sender
-                      .send(MessageType::Error(error))
-                      .expect("Failed to send error Message back to main thread.");
+                    let _ = message_sender.send(MessageType::Error(error));
                   });
+                twitch_keys = clone_twitch_keys;
               } else {
                 let _ = message_sender.send(MessageType::Error(EventSubError::InvalidAccessToken(
                   format!(
@@ -687,20 +3575,131 @@ impl TwitchEventSubApi {
                   ),
                 )));
               }
-
-              twitch_keys = clone_twitch_keys;
             }
             EventMessageType::KeepAlive => {
               //println!("Keep alive receive message sent, !implemented");
             }
             EventMessageType::Notification => {
-              message_sender
-                .send(MessageType::Event(message.payload.unwrap().event.unwrap()))
-                .unwrap();
+              #[cfg(feature = "tracing")]
+              let _dispatch_span = tracing::info_span!(
+                "notification",
+                subscription_type = message.metadata.subscription_type.as_deref(),
+                message_id = message.metadata.message_id.as_str(),
+                latency_ms = shared
+                  .connection_status(connection)
+                  .and_then(|status| status.latency)
+                  .map(|latency| latency.as_millis() as u64),
+              )
+              .entered();
+              metrics::notification_received(
+                message
+                  .metadata
+                  .subscription_type
+                  .as_deref()
+                  .unwrap_or_default(),
+              );
+              let Some(event) = message.payload.and_then(|p| p.event) else {
+                error!("Notification without an event: {}", msg);
+                let _ = message_sender.send(options.unparsed_message(msg, "missing event"));
+                continue;
+              };
+
+              if let Some(recorder) = &options.recorder {
+                recorder.record(&msg);
+              }
+
+              shared.user_cache.lock().unwrap().record_event(&event);
+              shared.fan_out(&event, Some(&message.metadata.message_id));
+
+              let _ = message_sender.send(MessageType::Event {
+                event,
+                raw: Some(msg),
+              });
+            }
+            EventMessageType::Reconnect => {
+              let reconnect_url = message
+                .payload
+                .and_then(|p| p.session)
+                .and_then(|s| s.reconnect_url)
+                .unwrap_or(websocket_url());
+
+              info!("Twitch requested a reconnect to {}", reconnect_url);
+              metrics::reconnect(true);
+              // The old connection stays up until this one is made, so there's no need to wait
+              let new_client = TwitchEventSubApi::connect(&reconnect_url).ok().or_else(|| {
+                TwitchEventSubApi::reconnect(&reconnect_url, &options.reconnect_policy)
+              });
+              if let Some(new_client) = new_client {
+                let _ = client.send_message(&OwnedMessage::Close(None));
+                *client = new_client;
+                migrating_session = true;
+                shared.set_connection_state(
+                  connection,
+                  ConnectionState::Connecting,
+                  &message_sender,
+                );
+              }
+            }
+            EventMessageType::Revocation => {
+              let revoked = match serde_json::from_str::<RevocationMessage>(&msg) {
+                Ok(revocation) => revocation.payload.subscription,
+                Err(e) => {
+                  error!("Failed to parse revocation: {}\n{}", msg, e);
+                  let _ = message_sender.send(options.unparsed_message(msg, e));
+                  continue;
+                }
+              };
+              warn!(
+                "Subscription {} revoked: {}",
+                revoked.kind,
+                revoked.status.as_str()
+              );
+
+              let registered = shared
+                .registry
+                .lock()
+                .unwrap()
+                .find_by_id(&revoked.id)
+                .map(|registered| registered.to_owned());
+              let can_resubscribe = revoked.status.can_resubscribe();
+
+              let _ = message_sender.send(MessageType::SubscriptionRevoked(SubscriptionRevoked {
+                subscription: registered.as_ref().map(|r| r.subscription.to_owned()),
+                reason: revoked.status.to_owned(),
+                details: revoked,
+              }));
+
+              let Some(mut registered) = registered else {
+                continue;
+              };
+
+              registered.id = None;
+              let session_id = shared
+                .session_id(registered.connection)
+                .filter(|_| can_resubscribe);
+              if let Some(session_id) = session_id {
+                match TwitchEventSubApi::create_subscription(
+                  &registered.subscription,
+                  registered.broadcaster_id.as_deref(),
+                  &session_id,
+                  &mut twitch_keys,
+                  &shared.budget,
+                ) {
+                  Ok(info) => {
+                    registered.id = Some(info.id);
+                    registered.condition = Some(info.condition);
+                    registered.cost = info.cost;
+                  }
+                  Err(error) => {
+                    let _ = message_sender.send(MessageType::Error(error));
+                  }
+                }
+              }
+              shared.registry.lock().unwrap().update(&registered);
             }
             EventMessageType::Unknown => {
               if !custom_subscriptions.is_empty() {
-                message_sender.send(MessageType::RawResponse(msg)).unwrap();
+                let _ = message_sender.send(MessageType::RawResponse(msg));
               }
             }
           }
@@ -709,6 +3708,13 @@ impl TwitchEventSubApi {
           warn!("Close message received: {:?}", a);
           // Got a close message, so send a close message and return
           let _ = client.send_message(&OwnedMessage::Close(None));
+          shared.set_connection_state(
+            connection,
+            ConnectionState::Disconnected {
+              reason: format!("Twitch closed the connection: {:?}", a),
+            },
+            &message_sender,
+          );
           return;
         }
         OwnedMessage::Ping(_) => {
@@ -717,6 +3723,13 @@ impl TwitchEventSubApi {
             Ok(()) => {}
             Err(e) => {
               error!("Received an Error from Server: {:?}", e);
+              shared.set_connection_state(
+                connection,
+                ConnectionState::Disconnected {
+                  reason: e.to_string(),
+                },
+                &message_sender,
+              );
               return;
             }
           }
@@ -725,4 +3738,78 @@ impl TwitchEventSubApi {
       }
     }
   }
+
+  // Reads chat over IRC until the client shuts down, reconnecting as the policy allows
+  #[cfg(all(feature = "irc", not(feature = "only_raw_responses")))]
+  fn irc_chat_events(
+    channels: Vec<String>,
+    message_sender: MessageSender,
+    shared: SharedState,
+    policy: ReconnectPolicy,
+  ) {
+    let connect = || {
+      let mut irc = IrcChat::connect(IrcAuth::Anonymous)?;
+      irc.set_read_timeout(Some(SOCKET_READ_TIMEOUT))?;
+      for channel in &channels {
+        irc.join(channel)?;
+      }
+      Ok::<_, EventSubError>(irc)
+    };
+
+    let mut attempt = 0;
+    while !shared.is_shutting_down() {
+      match connect() {
+        Ok(mut irc) => {
+          info!("Reading chat over IRC for {}", channels.join(", "));
+          attempt = 0;
+          while !shared.is_shutting_down() {
+            match irc.recv() {
+              Ok(Some((event, _))) => {
+                shared.user_cache.lock().unwrap().record_event(&event);
+                shared.fan_out(&event, None);
+                if message_sender
+                  .send(MessageType::Event { event, raw: None })
+                  .is_err()
+                {
+                  return;
+                }
+              }
+              Ok(None) => {}
+              Err(e) => {
+                warn!("IRC chat disconnected: {}", e);
+                break;
+              }
+            }
+          }
+        }
+        Err(e) => error!("Failed to connect to IRC chat: {}", e),
+      }
+
+      if shared.is_shutting_down() {
+        return;
+      }
+      if attempt >= policy.attempts {
+        let _ = message_sender.send(MessageType::Error(EventSubError::IrcError(
+          "Gave up reconnecting to IRC chat".to_owned(),
+        )));
+        return;
+      }
+      thread::sleep(policy.delay(attempt));
+      attempt += 1;
+    }
+  }
+
+  #[cfg(not(feature = "only_raw_responses"))]
+  fn reconnect(url: &str, policy: &ReconnectPolicy) -> Option<WebsocketClient> {
+    for attempt in 0..policy.attempts {
+      thread::sleep(policy.delay(attempt));
+      info!("Reconnecting to websocket, attempt {}", attempt + 1);
+      match TwitchEventSubApi::connect(url) {
+        Ok(client) => return Some(client),
+        Err(e) => error!("Failed to reconnect: {:?}", e),
+      }
+    }
+
+    None
+  }
 }
@@ -0,0 +1,66 @@
+use std::thread;
+
+use crate::EventSubError;
+
+/// Splits `ids` into chunks of at most `batch_size`, the most a Helix endpoint takes in one
+/// request, runs `fetch` for each chunk in order and joins the results. Stops at the first
+/// error.
+pub fn batched<I, T, F>(
+  ids: Vec<I>,
+  batch_size: usize,
+  mut fetch: F,
+) -> Result<Vec<T>, EventSubError>
+where
+  F: FnMut(Vec<I>) -> Result<Vec<T>, EventSubError>,
+{
+  let mut results = Vec::new();
+  for chunk in chunks(ids, batch_size) {
+    results.extend(fetch(chunk)?);
+  }
+
+  Ok(results)
+}
+
+/// Like [`batched`] but runs every chunk's request at once on its own thread. Results are
+/// joined in the same order as the ids, the first error is returned if any request fails.
+pub fn batched_parallel<I, T, F>(
+  ids: Vec<I>,
+  batch_size: usize,
+  fetch: F,
+) -> Result<Vec<T>, EventSubError>
+where
+  I: Send,
+  T: Send,
+  F: Fn(Vec<I>) -> Result<Vec<T>, EventSubError> + Sync,
+{
+  let fetch = &fetch;
+  thread::scope(|scope| {
+    let requests = chunks(ids, batch_size)
+      .into_iter()
+      .map(|chunk| scope.spawn(move || fetch(chunk)))
+      .collect::<Vec<_>>();
+
+    let mut results = Vec::new();
+    for request in requests {
+      let chunk = request.join().unwrap_or_else(|_| {
+        Err(EventSubError::UnhandledError(
+          "Batch request panicked".to_owned(),
+        ))
+      });
+      results.extend(chunk?);
+    }
+
+    Ok(results)
+  })
+}
+
+fn chunks<I>(ids: Vec<I>, batch_size: usize) -> Vec<Vec<I>> {
+  let batch_size = batch_size.max(1);
+  let mut chunks = Vec::new();
+  let mut ids = ids.into_iter().peekable();
+  while ids.peek().is_some() {
+    chunks.push(ids.by_ref().take(batch_size).collect());
+  }
+
+  chunks
+}
@@ -0,0 +1,333 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use js_sys::Object;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{CloseEvent, MessageEvent, Request, RequestInit, Response, WebSocket};
+
+use crate::modules::{
+  consts::{CONNECTION_EVENTS, SUBSCRIBE_URL},
+  generic_message::{EventMessageType, GenericMessage, RevocationMessage},
+};
+use crate::{
+  error, info, EventSubError, MessageType, ParseFailure, SecretString, Subscription,
+  SubscriptionRevoked,
+};
+
+/// The keys the browser client needs. Anything in the page can be read by whoever loads it,
+/// so only give it a user token, e.g. from the implicit grant flow, never the client secret.
+#[derive(Clone, Debug)]
+pub struct BrowserKeys {
+  pub client_id: String,
+  pub access_token: SecretString,
+  pub broadcaster_account_id: String,
+  /// The account that moderates the channel, when that isn't the broadcaster.
+  pub moderator_account_id: Option<String>,
+  /// The account chat is read as, when that isn't the broadcaster.
+  pub chat_reader_account_id: Option<String>,
+}
+
+impl BrowserKeys {
+  pub fn new<S: Into<String>, T: Into<SecretString>, U: Into<String>>(
+    client_id: S,
+    access_token: T,
+    broadcaster_account_id: U,
+  ) -> BrowserKeys {
+    BrowserKeys {
+      client_id: client_id.into(),
+      access_token: access_token.into(),
+      broadcaster_account_id: broadcaster_account_id.into(),
+      moderator_account_id: None,
+      chat_reader_account_id: None,
+    }
+  }
+}
+
+/// Receives events in the browser through its WebSocket and `fetch`, for overlays running
+/// in an OBS browser source. Nothing can block there, so messages are handed to a callback
+/// as they arrive instead of being received. The connection is closed when this is dropped.
+///
+/// Twitch's reconnect requests are followed, but a lost connection isn't reconnected,
+/// `MessageType::Close` is sent and `connect` can be called again.
+pub struct BrowserEventSub {
+  inner: Rc<Inner>,
+}
+
+struct Inner {
+  keys: BrowserKeys,
+  subscriptions: Vec<Subscription>,
+  on_message: RefCell<Box<dyn FnMut(MessageType)>>,
+  socket: RefCell<Option<WebSocket>>,
+  // The socket Twitch asked to move to, until its welcome message arrives
+  migrating: RefCell<Option<WebSocket>>,
+  closed: Cell<bool>,
+}
+
+impl BrowserEventSub {
+  pub fn connect<F: FnMut(MessageType) + 'static>(
+    keys: BrowserKeys,
+    subscriptions: Vec<Subscription>,
+    on_message: F,
+  ) -> Result<BrowserEventSub, EventSubError> {
+    if subscriptions.is_empty() {
+      return Err(EventSubError::NoSubscriptionsRequested);
+    }
+
+    let inner = Rc::new(Inner {
+      keys,
+      subscriptions,
+      on_message: RefCell::new(Box::new(on_message)),
+      socket: RefCell::new(None),
+      migrating: RefCell::new(None),
+      closed: Cell::new(false),
+    });
+    let socket = open(&inner, CONNECTION_EVENTS)?;
+    *inner.socket.borrow_mut() = Some(socket);
+
+    Ok(BrowserEventSub { inner })
+  }
+
+  pub fn close(&self) {
+    self.inner.close();
+  }
+}
+
+impl Drop for BrowserEventSub {
+  fn drop(&mut self) {
+    self.inner.close();
+  }
+}
+
+// The handlers only hold on weakly, so dropping the client frees everything and they're
+// left to the browser to collect
+fn open(inner: &Rc<Inner>, url: &str) -> Result<WebSocket, EventSubError> {
+  let socket = WebSocket::new(url).map_err(|_| EventSubError::WebsocketCreationFailed)?;
+
+  let weak = Rc::downgrade(inner);
+  let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+    if let (Some(inner), Some(text)) = (weak.upgrade(), event.data().as_string()) {
+      inner.handle_text(text);
+    }
+  });
+  socket.set_onmessage(Some(on_message.into_js_value().unchecked_ref()));
+
+  let weak = Rc::downgrade(inner);
+  let on_close = Closure::<dyn FnMut(CloseEvent)>::new(move |event: CloseEvent| {
+    if let Some(inner) = weak.upgrade() {
+      inner.socket_closed(event);
+    }
+  });
+  socket.set_onclose(Some(on_close.into_js_value().unchecked_ref()));
+
+  Ok(socket)
+}
+
+fn close_socket(socket: WebSocket) {
+  socket.set_onmessage(None);
+  socket.set_onclose(None);
+  let _ = socket.close();
+}
+
+fn js_error(value: JsValue) -> EventSubError {
+  EventSubError::UnhandledError(format!("{:?}", value))
+}
+
+impl Inner {
+  fn emit(&self, message: MessageType) {
+    (self.on_message.borrow_mut())(message);
+  }
+
+  fn close(&self) {
+    if self.closed.replace(true) {
+      return;
+    }
+    for socket in [self.socket.take(), self.migrating.take()]
+      .into_iter()
+      .flatten()
+    {
+      close_socket(socket);
+    }
+  }
+
+  fn socket_closed(&self, event: CloseEvent) {
+    let is_current = self
+      .socket
+      .borrow()
+      .as_ref()
+      .zip(event.target())
+      .is_some_and(|(socket, target)| Object::is(&target, socket));
+    if self.closed.get() || !is_current {
+      return;
+    }
+
+    error!("Websocket closed: {} {}", event.code(), event.reason());
+    self.close();
+    self.emit(MessageType::Close);
+  }
+
+  fn handle_text(self: &Rc<Inner>, text: String) {
+    let message: GenericMessage = match serde_json::from_str(&text) {
+      Ok(message) => message,
+      Err(e) => {
+        error!("Unimplemented twitch response: {}\n{}", text, e);
+        self.emit(MessageType::ParseError(ParseFailure::new(text, e)));
+        return;
+      }
+    };
+
+    match message.event_type() {
+      EventMessageType::Welcome => {
+        // Twitch sends the new connection's welcome once it's ready to take over
+        if let Some(socket) = self.migrating.take() {
+          if let Some(old) = self.socket.replace(Some(socket)) {
+            close_socket(old);
+          }
+          return;
+        }
+
+        let Some(session_id) = message.payload.and_then(|p| p.session).map(|s| s.id) else {
+          self.emit(MessageType::ParseError(ParseFailure::new(
+            text,
+            "missing session",
+          )));
+          return;
+        };
+        info!("Subscribing to events!");
+        self.subscribe(&session_id);
+      }
+      EventMessageType::Notification => {
+        #[cfg(feature = "only_raw_responses")]
+        self.emit(MessageType::RawResponse(text));
+        #[cfg(not(feature = "only_raw_responses"))]
+        match message.payload.and_then(|p| p.event) {
+          Some(event) => self.emit(MessageType::Event {
+            event,
+            raw: Some(text),
+          }),
+          None => self.emit(MessageType::ParseError(ParseFailure::new(
+            text,
+            "missing event",
+          ))),
+        }
+      }
+      EventMessageType::Reconnect => {
+        let Some(url) = message
+          .payload
+          .and_then(|p| p.session)
+          .and_then(|s| s.reconnect_url)
+        else {
+          return;
+        };
+
+        info!("Twitch requested a reconnect to {}", url);
+        match open(self, &url) {
+          Ok(socket) => {
+            if let Some(old) = self.migrating.replace(Some(socket)) {
+              close_socket(old);
+            }
+          }
+          Err(e) => self.emit(MessageType::Error(e)),
+        }
+      }
+      EventMessageType::Revocation => match serde_json::from_str::<RevocationMessage>(&text) {
+        Ok(revocation) => {
+          let revoked = revocation.payload.subscription;
+          self.emit(MessageType::SubscriptionRevoked(SubscriptionRevoked {
+            subscription: self
+              .subscriptions
+              .iter()
+              .find(|subscription| subscription.tag() == revoked.kind)
+              .cloned(),
+            reason: revoked.status.to_owned(),
+            details: revoked,
+          }));
+        }
+        Err(e) => self.emit(MessageType::ParseError(ParseFailure::new(text, e))),
+      },
+      EventMessageType::KeepAlive | EventMessageType::Unknown => {}
+    }
+  }
+
+  fn subscribe(self: &Rc<Inner>, session_id: &str) {
+    let keys = &self.keys;
+    for subscription in &self.subscriptions {
+      let data = subscription.construct_data_for_broadcaster(
+        session_id,
+        &keys.broadcaster_account_id,
+        keys
+          .moderator_account_id
+          .as_deref()
+          .unwrap_or(&keys.broadcaster_account_id),
+        keys
+          .chat_reader_account_id
+          .as_deref()
+          .unwrap_or(&keys.broadcaster_account_id),
+      );
+      let body = match serde_json::to_string(&data) {
+        Ok(body) => body,
+        Err(e) => {
+          self.emit(MessageType::Error(EventSubError::ParseError(e.to_string())));
+          continue;
+        }
+      };
+
+      let weak = Rc::downgrade(self);
+      let kind = subscription.tag();
+      let client_id = keys.client_id.to_owned();
+      let token = keys.access_token.to_owned();
+      spawn_local(async move {
+        if let Err(e) = create_subscription(kind, body, &client_id, &token).await {
+          if let Some(inner) = weak.upgrade() {
+            inner.emit(MessageType::Error(e));
+          }
+        }
+      });
+    }
+  }
+}
+
+async fn create_subscription(
+  kind: String,
+  body: String,
+  client_id: &str,
+  token: &SecretString,
+) -> Result<(), EventSubError> {
+  let init = RequestInit::new();
+  init.set_method("POST");
+  init.set_body(&JsValue::from_str(&body));
+  let request = Request::new_with_str_and_init(SUBSCRIBE_URL, &init).map_err(js_error)?;
+  let headers = request.headers();
+  headers
+    .set("Authorization", &format!("Bearer {}", token.expose()))
+    .map_err(js_error)?;
+  headers.set("Client-Id", client_id).map_err(js_error)?;
+  headers
+    .set("Content-Type", "application/json")
+    .map_err(js_error)?;
+
+  let window = web_sys::window()
+    .ok_or_else(|| EventSubError::UnhandledError("No window to fetch from".to_owned()))?;
+  let response: Response = JsFuture::from(window.fetch_with_request(&request))
+    .await
+    .map_err(js_error)?
+    .dyn_into()
+    .map_err(js_error)?;
+  if response.ok() {
+    return Ok(());
+  }
+
+  let reason = match response.text() {
+    Ok(text) => JsFuture::from(text)
+      .await
+      .ok()
+      .and_then(|text| text.as_string())
+      .unwrap_or_default(),
+    Err(_) => String::new(),
+  };
+  Err(EventSubError::SubscriptionFailed {
+    kind,
+    reason: format!("{} {}", response.status(), reason),
+  })
+}
@@ -0,0 +1,397 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender as SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::warn;
+
+use crate::modules::consts::MAX_CHAT_MESSAGE_LENGTH;
+use crate::modules::message_channel::MessageSender;
+use crate::{AnnouncementColour, DropReason, EventSubError, MessageType};
+
+/// How many chat messages can be sent in a window of time, messages past the limit are held
+/// in the queue until there's room.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChatRateLimit {
+  pub messages: usize,
+  pub per: Duration,
+}
+
+impl ChatRateLimit {
+  /// Twitch's limit for most users.
+  pub const NORMAL: ChatRateLimit = ChatRateLimit {
+    messages: 20,
+    per: Duration::from_secs(30),
+  };
+  /// Twitch's limit in channels the sender is a moderator or the broadcaster of.
+  pub const MODERATOR: ChatRateLimit = ChatRateLimit {
+    messages: 100,
+    per: Duration::from_secs(30),
+  };
+
+  /// Fails when `messages` or `per` is zero, as nothing could ever be sent.
+  pub fn new(messages: usize, per: Duration) -> Result<ChatRateLimit, EventSubError> {
+    let limit = ChatRateLimit { messages, per };
+    limit.validate()?;
+    Ok(limit)
+  }
+
+  pub(crate) fn validate(&self) -> Result<(), EventSubError> {
+    if self.messages == 0 || self.per.is_zero() {
+      return Err(EventSubError::ConfigError(format!(
+        "Chat rate limit of {} messages per {:?} never lets a message through",
+        self.messages, self.per
+      )));
+    }
+    Ok(())
+  }
+}
+
+impl Default for ChatRateLimit {
+  fn default() -> ChatRateLimit {
+    ChatRateLimit::NORMAL
+  }
+}
+
+/// A chat message to send, built up from `TwitchEventSubApi::send_chat_message`'s text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChatMessageBuilder {
+  message: String,
+  reply_parent_message_id: Option<String>,
+  sender_id: Option<String>,
+  split: bool,
+  announcement: Option<AnnouncementColour>,
+}
+
+impl ChatMessageBuilder {
+  pub fn new<S: Into<String>>(message: S) -> ChatMessageBuilder {
+    ChatMessageBuilder {
+      message: message.into(),
+      reply_parent_message_id: None,
+      sender_id: None,
+      split: false,
+      announcement: None,
+    }
+  }
+
+  pub fn reply_to<S: Into<String>>(mut self, message_id: S) -> ChatMessageBuilder {
+    self.reply_parent_message_id = Some(message_id.into());
+    self
+  }
+
+  /// Sends from this account rather than the client's sender account, the token must be
+  /// allowed to chat as it.
+  pub fn as_user<S: Into<String>>(mut self, sender_id: S) -> ChatMessageBuilder {
+    self.sender_id = Some(sender_id.into());
+    self
+  }
+
+  /// Sends messages over 500 characters as several messages, split between words, instead
+  /// of failing with `EventSubError::MessageTooLong`.
+  pub fn split_long_message(mut self) -> ChatMessageBuilder {
+    self.split = true;
+    self
+  }
+
+  /// Sends the message as an announcement, the sender has to be a moderator of the channel.
+  /// Announcements can't be replies, so `reply_to` is ignored.
+  pub fn announce(mut self, colour: AnnouncementColour) -> ChatMessageBuilder {
+    self.announcement = Some(colour);
+    self
+  }
+
+  pub(crate) fn into_queued(self, result: SyncSender<ChatSendResult>) -> Vec<QueuedChatMessage> {
+    let parts = if self.split {
+      split_message(&self.message, MAX_CHAT_MESSAGE_LENGTH)
+    } else {
+      vec![self.message]
+    };
+
+    parts
+      .into_iter()
+      .map(|message| QueuedChatMessage {
+        message,
+        reply_parent_message_id: self.reply_parent_message_id.to_owned(),
+        sender_id: self.sender_id.to_owned(),
+        announcement: self.announcement,
+        result: Some(result.clone()),
+      })
+      .collect()
+  }
+}
+
+impl From<&str> for ChatMessageBuilder {
+  fn from(message: &str) -> ChatMessageBuilder {
+    ChatMessageBuilder::new(message)
+  }
+}
+
+impl From<String> for ChatMessageBuilder {
+  fn from(message: String) -> ChatMessageBuilder {
+    ChatMessageBuilder::new(message)
+  }
+}
+
+/// What happened to a chat message once the queue got to it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChatSendResult {
+  Sent {
+    message_id: String,
+  },
+  Announced,
+  /// Twitch didn't post the message, such as for the channel being in followers only mode,
+  /// or it never left the queue.
+  Dropped(DropReason),
+  Failed(EventSubError),
+}
+
+impl ChatSendResult {
+  pub(crate) fn queue_dropped(reason: &str) -> ChatSendResult {
+    ChatSendResult::Dropped(DropReason {
+      code: "queue_dropped".to_owned(),
+      message: reason.to_owned(),
+    })
+  }
+}
+
+/// Chat messages waiting in the queue, one for each part a long message was split into.
+#[derive(Debug)]
+pub struct PendingChatMessage {
+  receiver: Receiver<ChatSendResult>,
+  parts: usize,
+}
+
+impl PendingChatMessage {
+  pub(crate) fn new(receiver: Receiver<ChatSendResult>, parts: usize) -> PendingChatMessage {
+    PendingChatMessage { receiver, parts }
+  }
+
+  pub fn parts(&self) -> usize {
+    self.parts
+  }
+
+  /// Blocks until every part has been sent or dropped.
+  pub fn wait(self) -> Vec<ChatSendResult> {
+    self.receiver.iter().take(self.parts).collect()
+  }
+
+  /// Blocks until every part has been sent, giving their message ids in order, or the first
+  /// reason one of them wasn't sent.
+  pub fn message_ids(self) -> Result<Vec<String>, EventSubError> {
+    let parts = self.parts;
+    let results = self.wait();
+    if results.len() < parts {
+      return Err(EventSubError::UnhandledError(
+        "Chat queue stopped before the message was sent".to_owned(),
+      ));
+    }
+
+    results
+      .into_iter()
+      .filter_map(|result| match result {
+        ChatSendResult::Sent { message_id } => Some(Ok(message_id)),
+        ChatSendResult::Announced => None,
+        ChatSendResult::Dropped(reason) => Some(Err(reason.into())),
+        ChatSendResult::Failed(e) => Some(Err(e)),
+      })
+      .collect()
+  }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct QueuedChatMessage {
+  pub message: String,
+  pub reply_parent_message_id: Option<String>,
+  pub sender_id: Option<String>,
+  pub announcement: Option<AnnouncementColour>,
+  pub result: Option<SyncSender<ChatSendResult>>,
+}
+
+// Splits between words, words too long for a message on their own are cut up
+fn split_message(message: &str, max_length: usize) -> Vec<String> {
+  let mut parts = Vec::new();
+  let mut current = String::new();
+  let mut current_length = 0;
+
+  for mut word in message.split_whitespace() {
+    loop {
+      let word_length = word.chars().count();
+      let separator = usize::from(!current.is_empty());
+      if current_length + separator + word_length <= max_length {
+        if separator == 1 {
+          current.push(' ');
+        }
+        current.push_str(word);
+        current_length += separator + word_length;
+        break;
+      }
+
+      if !current.is_empty() {
+        parts.push(std::mem::take(&mut current));
+        current_length = 0;
+        continue;
+      }
+
+      let (cut, _) = word.char_indices().nth(max_length).unwrap();
+      parts.push(word[..cut].to_owned());
+      word = &word[cut..];
+    }
+  }
+
+  if !current.is_empty() || parts.is_empty() {
+    parts.push(current);
+  }
+
+  parts
+}
+
+// Send times within the last window, messages can be sent in a burst until it's full
+struct RateWindow {
+  limit: ChatRateLimit,
+  sent: VecDeque<Instant>,
+}
+
+impl RateWindow {
+  fn wait_time(&mut self, now: Instant) -> Duration {
+    while self
+      .sent
+      .front()
+      .is_some_and(|sent| now.duration_since(*sent) >= self.limit.per)
+    {
+      self.sent.pop_front();
+    }
+
+    if self.sent.len() < self.limit.messages {
+      return Duration::ZERO;
+    }
+
+    (self.sent[self.sent.len() - self.limit.messages] + self.limit.per)
+      .saturating_duration_since(now)
+  }
+}
+
+/// Sends chat messages on its own thread, spacing them out to stay under the rate limit.
+pub(crate) struct ChatQueue {
+  sender: Option<SyncSender<QueuedChatMessage>>,
+  queued: Arc<AtomicUsize>,
+  max_queued: usize,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl ChatQueue {
+  pub fn start<F: FnMut(QueuedChatMessage) -> ChatSendResult + Send + 'static>(
+    limit: ChatRateLimit,
+    max_queued: usize,
+    message_sender: MessageSender,
+    mut send: F,
+  ) -> ChatQueue {
+    let (sender, receiver) = channel::<QueuedChatMessage>();
+    let queued = Arc::new(AtomicUsize::new(0));
+
+    let thread_queued = queued.clone();
+    let thread = thread::spawn(move || {
+      let mut window = RateWindow {
+        limit,
+        sent: VecDeque::new(),
+      };
+
+      // Ends once the queue is closed and everything in it has been sent
+      for mut queued in receiver {
+        let delay = window.wait_time(Instant::now());
+        if !delay.is_zero() {
+          let _ = message_sender.send(MessageType::ChatMessageDelayed {
+            message: queued.message.to_owned(),
+            delay,
+          });
+          thread::sleep(delay);
+        }
+
+        window.sent.push_back(Instant::now());
+        thread_queued.fetch_sub(1, Ordering::Relaxed);
+        let result = queued.result.take();
+        let message = queued.message.to_owned();
+        let sent = send(queued);
+
+        if let ChatSendResult::Dropped(reason) = &sent {
+          warn!("Twitch dropped chat message: {}", reason.message);
+          let _ = message_sender.send(MessageType::ChatMessageDropped {
+            message,
+            reason: reason.message.to_owned(),
+          });
+        }
+
+        if let Some(result) = result {
+          let _ = result.send(sent);
+        }
+      }
+    });
+
+    ChatQueue {
+      sender: Some(sender),
+      queued,
+      max_queued,
+      thread: Some(thread),
+    }
+  }
+
+  /// Hands the message back if the queue is full or closed.
+  pub fn push(&self, message: QueuedChatMessage) -> Result<(), (QueuedChatMessage, String)> {
+    let Some(sender) = &self.sender else {
+      return Err((message, "Client has shut down".to_owned()));
+    };
+
+    // Checked and counted in one step so two senders can't both take the last place
+    let reserved = self
+      .queued
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |queued| {
+        (queued < self.max_queued).then_some(queued + 1)
+      });
+    if reserved.is_err() {
+      return Err((message, "Chat queue is full".to_owned()));
+    }
+
+    sender.send(message).map_err(|e| {
+      self.queued.fetch_sub(1, Ordering::Relaxed);
+      (e.0, "Chat queue has stopped".to_owned())
+    })
+  }
+
+  /// Stops taking messages and waits until `deadline` for the ones already queued to send.
+  pub fn flush(&mut self, deadline: Instant) {
+    self.sender = None;
+
+    let Some(thread) = self.thread.take() else {
+      return;
+    };
+
+    while !thread.is_finished() && Instant::now() < deadline {
+      thread::sleep(Duration::from_millis(10));
+    }
+
+    if thread.is_finished() {
+      let _ = thread.join();
+    } else {
+      warn!(
+        "{} chat messages were still queued at shutdown.",
+        self.queued.load(Ordering::Relaxed)
+      );
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rate_limits_must_let_messages_through() {
+    assert!(ChatRateLimit::new(0, Duration::from_secs(30)).is_err());
+    assert!(ChatRateLimit::new(20, Duration::ZERO).is_err());
+    assert_eq!(
+      ChatRateLimit::new(20, Duration::from_secs(30)).unwrap(),
+      ChatRateLimit::NORMAL
+    );
+  }
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::{Event, MessageData, TwitchEventSubApi};
+
+/// Who can use a command, each level includes everyone above it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Permission {
+  Everyone,
+  Subscriber,
+  Vip,
+  Moderator,
+  Broadcaster,
+}
+
+impl Permission {
+  /// The highest permission the chatter has, going by their badges.
+  pub fn of(message: &MessageData) -> Permission {
+    if message.is_broadcaster() {
+      Permission::Broadcaster
+    } else if message.is_moderator() {
+      Permission::Moderator
+    } else if message.is_vip() {
+      Permission::Vip
+    } else if message.is_subscriber() {
+      Permission::Subscriber
+    } else {
+      Permission::Everyone
+    }
+  }
+}
+
+/// A command someone used in chat.
+pub struct CommandContext<'a> {
+  pub message: &'a MessageData,
+  /// The command's name, even when it was used through an alias.
+  pub command: &'a str,
+  /// Words after the command, words in double quotes are kept together.
+  pub args: Vec<String>,
+  rest: &'a str,
+}
+
+impl CommandContext<'_> {
+  pub fn arg(&self, index: usize) -> Option<&str> {
+    self.args.get(index).map(String::as_str)
+  }
+
+  /// Everything after the command as it was typed.
+  pub fn rest(&self) -> &str {
+    self.rest
+  }
+
+  pub fn chatter_name(&self) -> &str {
+    &self.message.chatter_user.name
+  }
+}
+
+type CommandHandler = Box<dyn FnMut(&mut TwitchEventSubApi, &CommandContext) + Send>;
+
+pub struct ChatCommand {
+  name: String,
+  aliases: Vec<String>,
+  permission: Permission,
+  cooldown: Duration,
+  user_cooldown: Duration,
+  handler: CommandHandler,
+}
+
+impl ChatCommand {
+  /// A command anyone can use as often as they like, `name` is given without the prefix.
+  pub fn new<
+    S: Into<String>,
+    F: FnMut(&mut TwitchEventSubApi, &CommandContext) + Send + 'static,
+  >(
+    name: S,
+    handler: F,
+  ) -> ChatCommand {
+    ChatCommand {
+      name: name.into().to_lowercase(),
+      aliases: Vec::new(),
+      permission: Permission::Everyone,
+      cooldown: Duration::ZERO,
+      user_cooldown: Duration::ZERO,
+      handler: Box::new(handler),
+    }
+  }
+
+  pub fn alias<S: Into<String>>(mut self, alias: S) -> ChatCommand {
+    self.aliases.push(alias.into().to_lowercase());
+    self
+  }
+
+  pub fn permission(mut self, permission: Permission) -> ChatCommand {
+    self.permission = permission;
+    self
+  }
+
+  /// How long after being used the command can't be used by anyone.
+  pub fn cooldown(mut self, cooldown: Duration) -> ChatCommand {
+    self.cooldown = cooldown;
+    self
+  }
+
+  /// How long after using the command each chatter has to wait to use it again.
+  pub fn user_cooldown(mut self, cooldown: Duration) -> ChatCommand {
+    self.user_cooldown = cooldown;
+    self
+  }
+
+  fn matches(&self, name: &str) -> bool {
+    self.name == name || self.aliases.iter().any(|alias| alias == name)
+  }
+}
+
+/// What happened to a chat message that used a command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandOutcome {
+  Ran(String),
+  NotPermitted {
+    command: String,
+    required: Permission,
+  },
+  OnCooldown {
+    command: String,
+    remaining: Duration,
+  },
+}
+
+/// Runs command handlers for chat messages that start with the prefix, e.g.
+///
+/// ```no_run
+/// # use twitch_eventsub::*;
+/// # use std::time::Duration;
+/// # fn run(mut api: TwitchEventSubApi) {
+/// let mut commands = Commands::new("!").command(
+///   ChatCommand::new("so", |api, ctx| {
+///     if let Some(user) = ctx.arg(0) {
+///       api.send_chat_message(format!("Go check out {}!", user.trim_start_matches('@')));
+///     }
+///   })
+///   .alias("shoutout")
+///   .permission(Permission::Moderator)
+///   .cooldown(Duration::from_secs(10)),
+/// );
+///
+/// loop {
+///   for message in api.receive_messages() {
+///     if let MessageType::Event { event, .. } = message {
+///       commands.handle(&mut api, &event);
+///     }
+///   }
+/// }
+/// # }
+/// ```
+pub struct Commands {
+  prefix: String,
+  commands: Vec<ChatCommand>,
+  last_used: HashMap<String, Instant>,
+  // Keyed by command name and chatter id
+  last_used_by: HashMap<(String, String), Instant>,
+}
+
+impl Commands {
+  pub fn new<S: Into<String>>(prefix: S) -> Commands {
+    Commands {
+      prefix: prefix.into(),
+      commands: Vec::new(),
+      last_used: HashMap::new(),
+      last_used_by: HashMap::new(),
+    }
+  }
+
+  pub fn command(mut self, command: ChatCommand) -> Commands {
+    self.register(command);
+    self
+  }
+
+  pub fn register(&mut self, command: ChatCommand) {
+    self.commands.push(command);
+  }
+
+  /// Runs the command in a chat message event, other events are ignored. Returns `None`
+  /// when the event isn't a known command.
+  pub fn handle(&mut self, api: &mut TwitchEventSubApi, event: &Event) -> Option<CommandOutcome> {
+    match event {
+      Event::ChatMessage(message) => self.handle_message(api, message),
+      _ => None,
+    }
+  }
+
+  pub fn handle_message(
+    &mut self,
+    api: &mut TwitchEventSubApi,
+    message: &MessageData,
+  ) -> Option<CommandOutcome> {
+    let text = message
+      .message
+      .text
+      .trim_start()
+      .strip_prefix(&self.prefix)?;
+    let (name, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let name = name.to_lowercase();
+
+    let now = Instant::now();
+    self.forget_expired(now);
+
+    let command = self.commands.iter_mut().find(|c| c.matches(&name))?;
+    let command_name = command.name.to_owned();
+
+    let permission = Permission::of(message);
+    if permission < command.permission {
+      return Some(CommandOutcome::NotPermitted {
+        command: command_name,
+        required: command.permission,
+      });
+    }
+
+    let user_key = (command_name.to_owned(), message.chatter_user.id.to_owned());
+    let remaining = [
+      (self.last_used.get(&command_name), command.cooldown),
+      (self.last_used_by.get(&user_key), command.user_cooldown),
+    ]
+    .into_iter()
+    .filter_map(|(used, cooldown)| used.map(|used| cooldown.saturating_sub(now - *used)))
+    .max()
+    .unwrap_or_default();
+
+    if !remaining.is_zero() {
+      return Some(CommandOutcome::OnCooldown {
+        command: command_name,
+        remaining,
+      });
+    }
+
+    self.last_used.insert(command_name.to_owned(), now);
+    if !command.user_cooldown.is_zero() {
+      self.last_used_by.insert(user_key, now);
+    }
+
+    let rest = rest.trim();
+    let context = CommandContext {
+      message,
+      command: &command_name,
+      args: split_args(rest),
+      rest,
+    };
+    (command.handler)(api, &context);
+
+    Some(CommandOutcome::Ran(command_name))
+  }
+
+  /// Drops the per chatter cooldowns that have passed, so they don't pile up for every
+  /// chatter that ever used a command.
+  fn forget_expired(&mut self, now: Instant) {
+    let commands = &self.commands;
+    self.last_used_by.retain(|(command_name, _), used| {
+      commands
+        .iter()
+        .find(|command| command.name == *command_name)
+        .is_some_and(|command| now - *used < command.user_cooldown)
+    });
+  }
+}
+
+fn split_args(text: &str) -> Vec<String> {
+  let mut args = Vec::new();
+  let mut current = String::new();
+  let mut quoted = false;
+
+  for c in text.chars() {
+    match c {
+      '"' => quoted = !quoted,
+      c if c.is_whitespace() && !quoted => {
+        if !current.is_empty() {
+          args.push(std::mem::take(&mut current));
+        }
+      }
+      c => current.push(c),
+    }
+  }
+
+  if !current.is_empty() {
+    args.push(current);
+  }
+
+  args
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn forgets_chatter_cooldowns_once_they_pass() {
+    let mut commands = Commands::new("!")
+      .command(ChatCommand::new("hug", |_, _| {}).user_cooldown(Duration::from_secs(30)));
+    let now = Instant::now();
+    let key = |user: &str| ("hug".to_owned(), user.to_owned());
+    commands.last_used_by.insert(key("1"), now);
+    commands
+      .last_used_by
+      .insert(key("2"), now - Duration::from_secs(31));
+
+    commands.forget_expired(now);
+
+    assert!(commands.last_used_by.contains_key(&key("1")));
+    assert!(!commands.last_used_by.contains_key(&key("2")));
+  }
+}
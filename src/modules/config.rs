@@ -0,0 +1,246 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use serde_json::Value;
+
+#[cfg(feature = "chat")]
+use crate::ChatRateLimit;
+use crate::{
+  Deserialise, EventSubError, ReconnectPolicy, Scope, SecretString, Subscription, TokenAccess,
+  TwitchEventSubApiBuilder, TwitchKeys,
+};
+
+#[derive(Deserialise)]
+#[serde(deny_unknown_fields)]
+struct Config {
+  keys: KeysConfig,
+  redirect_url: Option<String>,
+  #[serde(default)]
+  subscriptions: Vec<String>,
+  #[serde(default)]
+  scopes: Vec<String>,
+  #[serde(default)]
+  tokens: TokensConfig,
+  reconnect: Option<ReconnectConfig>,
+  #[cfg(feature = "chat")]
+  chat: Option<ChatConfig>,
+}
+
+#[derive(Deserialise)]
+#[serde(deny_unknown_fields)]
+struct KeysConfig {
+  client_id: String,
+  client_secret: String,
+  broadcaster_id: String,
+  bot_id: Option<String>,
+  moderator_id: Option<String>,
+  chat_reader_id: Option<String>,
+  access_token: Option<String>,
+  refresh_token: Option<String>,
+}
+
+#[derive(Default, Deserialise)]
+#[serde(deny_unknown_fields)]
+struct TokensConfig {
+  #[serde(default)]
+  generate_if_none: bool,
+  #[serde(default)]
+  generate_if_missing_scopes: bool,
+  #[serde(default)]
+  refresh_on_expire: bool,
+  token_file: Option<String>,
+  refresh_token_file: Option<String>,
+}
+
+#[derive(Deserialise)]
+#[serde(deny_unknown_fields)]
+struct ReconnectConfig {
+  attempts: Option<u32>,
+  initial_delay_secs: Option<f64>,
+  max_delay_secs: Option<f64>,
+}
+
+#[cfg(feature = "chat")]
+#[derive(Deserialise)]
+#[serde(deny_unknown_fields)]
+struct ChatConfig {
+  /// How many messages can be sent every `per_secs`.
+  messages: Option<usize>,
+  per_secs: Option<f64>,
+  max_queued: Option<usize>,
+  #[serde(default)]
+  split_long_messages: bool,
+}
+
+fn config_error<S: Into<String>>(e: S) -> EventSubError {
+  EventSubError::ConfigError(e.into())
+}
+
+// Empty values, such as a `${VAR:-}` that wasn't set, count as not given
+fn non_empty(value: Option<String>) -> Option<String> {
+  value.filter(|value| !value.is_empty())
+}
+
+fn seconds(field: &str, secs: f64) -> Result<Duration, EventSubError> {
+  Duration::try_from_secs_f64(secs).map_err(|_| config_error(format!("{} must be positive", field)))
+}
+
+pub(crate) fn load_config(path: &Path) -> Result<TwitchEventSubApiBuilder, EventSubError> {
+  let text = fs::read_to_string(path)
+    .map_err(|e| config_error(format!("Couldn't read {}: {}", path.display(), e)))?;
+  let mut value = match path.extension().and_then(|extension| extension.to_str()) {
+    Some("toml") => toml::from_str::<Value>(&text).map_err(|e| config_error(e.to_string()))?,
+    Some("json") => {
+      serde_json::from_str::<Value>(&text).map_err(|e| config_error(e.to_string()))?
+    }
+    _ => {
+      return Err(config_error(format!(
+        "{} isn't a .toml or .json file",
+        path.display()
+      )))
+    }
+  };
+  interpolate(&mut value)?;
+  let config = serde_json::from_value::<Config>(value).map_err(|e| config_error(e.to_string()))?;
+
+  let keys = config.keys;
+  let broadcaster_id = keys.broadcaster_id;
+  let twitch_keys = TwitchKeys {
+    authorisation_code: None,
+    access_token: non_empty(keys.access_token).map(|token| TokenAccess::User(token.into())),
+    refresh_token: non_empty(keys.refresh_token).map(SecretString::from),
+    client_id: keys.client_id,
+    client_secret: keys.client_secret.into(),
+    sender_account_id: Some(non_empty(keys.bot_id).unwrap_or(broadcaster_id.to_owned())),
+    broadcaster_account_id: broadcaster_id,
+    moderator_account_id: non_empty(keys.moderator_id),
+    chat_reader_account_id: non_empty(keys.chat_reader_id),
+    token_storage: None,
+  };
+
+  let subscriptions = config
+    .subscriptions
+    .iter()
+    .map(|tag| {
+      Subscription::from_string(tag).ok_or(config_error(format!("Unknown event type {}", tag)))
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  let scopes = config
+    .scopes
+    .iter()
+    .map(|scope| scope.parse::<Scope>())
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let tokens = config.tokens;
+  let mut builder = TwitchEventSubApiBuilder::new(twitch_keys)
+    .add_subscriptions(subscriptions)
+    .add_scopes(scopes)
+    .generate_new_token_if_none(tokens.generate_if_none)
+    .generate_new_token_if_insufficent_scope(tokens.generate_if_missing_scopes)
+    .generate_access_token_on_expire(tokens.refresh_on_expire);
+  if let Some(redirect_url) = non_empty(config.redirect_url) {
+    builder = builder.set_redirect_url(redirect_url);
+  }
+  match (tokens.token_file, tokens.refresh_token_file) {
+    (Some(token_file), Some(refresh_token_file)) => {
+      builder = builder.auto_save_load_created_tokens(token_file, refresh_token_file);
+    }
+    (None, None) => {}
+    _ => {
+      return Err(config_error(
+        "token_file and refresh_token_file have to be set together",
+      ))
+    }
+  }
+
+  if let Some(reconnect) = config.reconnect {
+    let default = ReconnectPolicy::default();
+    builder = builder.reconnect_policy(ReconnectPolicy {
+      attempts: reconnect.attempts.unwrap_or(default.attempts),
+      initial_delay: match reconnect.initial_delay_secs {
+        Some(secs) => seconds("initial_delay_secs", secs)?,
+        None => default.initial_delay,
+      },
+      max_delay: match reconnect.max_delay_secs {
+        Some(secs) => seconds("max_delay_secs", secs)?,
+        None => default.max_delay,
+      },
+    });
+  }
+
+  #[cfg(feature = "chat")]
+  if let Some(chat) = config.chat {
+    let default = ChatRateLimit::default();
+    let messages = chat.messages.unwrap_or(default.messages);
+    if messages == 0 {
+      return Err(config_error("messages must be at least 1"));
+    }
+    let per = match chat.per_secs {
+      Some(secs) => seconds("per_secs", secs)?,
+      None => default.per,
+    };
+    if per.is_zero() {
+      return Err(config_error("per_secs must be positive"));
+    }
+    builder = builder.chat_rate_limit(ChatRateLimit { messages, per });
+    if let Some(max_queued) = chat.max_queued {
+      builder = builder.max_queued_chat_messages(max_queued);
+    }
+    if chat.split_long_messages {
+      builder = builder.split_long_chat_messages();
+    }
+  }
+
+  Ok(builder)
+}
+
+// Replaces `${VAR}` in every string with the environment variable, or `${VAR:-default}` to
+// fall back to default when it isn't set
+fn interpolate(value: &mut Value) -> Result<(), EventSubError> {
+  match value {
+    Value::String(text) => *text = interpolate_text(text)?,
+    Value::Array(values) => {
+      for value in values {
+        interpolate(value)?;
+      }
+    }
+    Value::Object(fields) => {
+      for value in fields.values_mut() {
+        interpolate(value)?;
+      }
+    }
+    _ => {}
+  }
+  Ok(())
+}
+
+fn interpolate_text(text: &str) -> Result<String, EventSubError> {
+  let mut filled = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find("${") {
+    filled.push_str(&rest[..start]);
+    let after = &rest[start + 2..];
+    let end = after
+      .find('}')
+      .ok_or(config_error(format!("Unclosed ${{ in {}", text)))?;
+
+    let (name, default) = match after[..end].split_once(":-") {
+      Some((name, default)) => (name, Some(default)),
+      None => (&after[..end], None),
+    };
+    match (std::env::var(name), default) {
+      (Ok(value), _) => filled.push_str(&value),
+      (Err(_), Some(default)) => filled.push_str(default),
+      (Err(_), None) => {
+        return Err(config_error(format!(
+          "Environment variable {} isn't set",
+          name
+        )))
+      }
+    }
+    rest = &after[end + 1..];
+  }
+  filled.push_str(rest);
+  Ok(filled)
+}
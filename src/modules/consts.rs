@@ -1,9 +1,121 @@
-pub const CONNECTION_EVENTS: &'static str =
-  "wss://eventsub.wss.twitch.tv/ws?keepalive_timeout_seconds=30";
+// Most of these are only used by the blocking client
+#![cfg_attr(target_arch = "wasm32", allow(dead_code))]
+
+use std::time::Duration;
+
+pub const CONNECTION_EVENTS: &str = "wss://eventsub.wss.twitch.tv/ws?keepalive_timeout_seconds=30";
+pub const TWITCH_HELIX_URL: &str = "https://api.twitch.tv/helix";
+pub const TWITCH_OAUTH_URL: &str = "https://id.twitch.tv/oauth2";
+pub const TWITCH_CLI_WEBSOCKET_URL: &str = "ws://127.0.0.1:8080/ws";
+pub const TWITCH_CLI_URL: &str = "http://127.0.0.1:8080";
 pub const SUBSCRIBE_URL: &str = "https://api.twitch.tv/helix/eventsub/subscriptions";
 pub const VALIDATION_TOKEN_URL: &str = "https://id.twitch.tv/oauth2/validate";
+#[cfg(feature = "chat")]
 pub const SEND_MESSAGE_URL: &str = "https://api.twitch.tv/helix/chat/messages";
 pub const TWITCH_AUTHORISE_URL: &str = "https://id.twitch.tv/oauth2/";
 pub const TWITCH_TOKEN_URL: &str = "https://id.twitch.tv/oauth2/token";
+pub const TWITCH_DEVICE_URL: &str = "https://id.twitch.tv/oauth2/device";
+pub const TWITCH_REVOKE_URL: &str = "https://id.twitch.tv/oauth2/revoke";
+#[cfg(feature = "moderation")]
 pub const TWITCH_BAN_URL: &str = "https://api.twitch.tv/helix/moderation/bans";
+#[cfg(feature = "moderation")]
 pub const TWITCH_DELETE_MESSAGE_URL: &str = "https://api.twitch.tv/helix/moderation/chat";
+#[cfg(feature = "moderation")]
+pub const TWITCH_MODERATORS_URL: &str = "https://api.twitch.tv/helix/moderation/moderators";
+#[cfg(feature = "moderation")]
+pub const TWITCH_SHOUTOUTS_URL: &str = "https://api.twitch.tv/helix/chat/shoutouts";
+#[cfg(feature = "moderation")]
+pub const TWITCH_VIPS_URL: &str = "https://api.twitch.tv/helix/channels/vips";
+#[cfg(feature = "moderation")]
+pub const TWITCH_BLOCKED_TERMS_URL: &str = "https://api.twitch.tv/helix/moderation/blocked_terms";
+#[cfg(feature = "moderation")]
+pub const TWITCH_AUTOMOD_SETTINGS_URL: &str =
+  "https://api.twitch.tv/helix/moderation/automod/settings";
+#[cfg(feature = "moderation")]
+pub const TWITCH_AUTOMOD_MESSAGE_URL: &str =
+  "https://api.twitch.tv/helix/moderation/automod/message";
+#[cfg(feature = "moderation")]
+pub const TWITCH_ENFORCEMENTS_STATUS_URL: &str =
+  "https://api.twitch.tv/helix/moderation/enforcements/status";
+#[cfg(feature = "moderation")]
+pub const TWITCH_WARNINGS_URL: &str = "https://api.twitch.tv/helix/moderation/warnings";
+#[cfg(feature = "channel-points")]
+pub const TWITCH_CUSTOM_REWARDS_URL: &str =
+  "https://api.twitch.tv/helix/channel_points/custom_rewards";
+#[cfg(feature = "channel-points")]
+pub const TWITCH_CUSTOM_REWARD_REDEMPTIONS_URL: &str =
+  "https://api.twitch.tv/helix/channel_points/custom_rewards/redemptions";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_SCHEDULE_URL: &str = "https://api.twitch.tv/helix/schedule";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_SCHEDULE_SEGMENT_URL: &str = "https://api.twitch.tv/helix/schedule/segment";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_SCHEDULE_SETTINGS_URL: &str = "https://api.twitch.tv/helix/schedule/settings";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_VIDEOS_URL: &str = "https://api.twitch.tv/helix/videos";
+#[cfg(feature = "moderation")]
+pub const TWITCH_CHAT_SETTINGS_URL: &str = "https://api.twitch.tv/helix/chat/settings";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_CHANNELS_URL: &str = "https://api.twitch.tv/helix/channels";
+#[cfg(feature = "moderation")]
+pub const TWITCH_SHIELD_MODE_URL: &str = "https://api.twitch.tv/helix/moderation/shield_mode";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_CHARITY_CAMPAIGNS_URL: &str = "https://api.twitch.tv/helix/charity/campaigns";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_CHARITY_DONATIONS_URL: &str = "https://api.twitch.tv/helix/charity/donations";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_USER_BLOCKS_URL: &str = "https://api.twitch.tv/helix/users/blocks";
+#[cfg(feature = "chat")]
+pub const TWITCH_CHAT_COLOUR_URL: &str = "https://api.twitch.tv/helix/chat/color";
+#[cfg(feature = "chat")]
+pub const TWITCH_ANNOUNCEMENTS_URL: &str = "https://api.twitch.tv/helix/chat/announcements";
+#[cfg(feature = "moderation")]
+pub const TWITCH_UNBAN_REQUESTS_URL: &str = "https://api.twitch.tv/helix/moderation/unban_requests";
+#[cfg(feature = "moderation")]
+pub const TWITCH_MODERATED_CHANNELS_URL: &str = "https://api.twitch.tv/helix/moderation/channels";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_FOLLOWED_CHANNELS_URL: &str = "https://api.twitch.tv/helix/channels/followed";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_HYPE_TRAIN_EVENTS_URL: &str = "https://api.twitch.tv/helix/hypetrain/events";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_USERS_URL: &str = "https://api.twitch.tv/helix/users";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_STREAMS_URL: &str = "https://api.twitch.tv/helix/streams";
+#[cfg(feature = "helix-full")]
+pub const TWITCH_HYPE_TRAIN_STATUS_URL: &str = "https://api.twitch.tv/helix/hypetrain/status";
+pub const RECONNECT_ATTEMPTS: u32 = 5;
+// Most ids Helix takes in a single request
+#[cfg(any(feature = "chat", feature = "helix-full"))]
+pub const MAX_IDS_PER_REQUEST: usize = 100;
+#[cfg(feature = "channel-points")]
+pub const MAX_REWARD_IDS_PER_REQUEST: usize = 50;
+pub const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+// Twitch recommends remembering message ids for 10 minutes
+pub const DEFAULT_DEDUPLICATION_WINDOW: Duration = Duration::from_secs(10 * 60);
+pub const DEFAULT_USER_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+pub const DEFAULT_USER_CACHE_SIZE: usize = 10_000;
+// Subscriptions created at once when a session starts
+#[cfg(not(feature = "only_raw_responses"))]
+pub const MAX_PARALLEL_SUBSCRIPTION_REQUESTS: usize = 8;
+// Idle curl handles kept around so their connections can be reused
+pub const MAX_POOLED_HTTP_HANDLES: usize = 4;
+#[cfg(feature = "irc")]
+pub const IRC_HOST: &str = "irc.chat.twitch.tv";
+#[cfg(feature = "irc")]
+pub const IRC_PORT: u16 = 6697;
+pub const MAX_WEBSOCKET_CONNECTIONS: usize = 3;
+pub const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 300;
+pub const NEW_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+// Reads time out this often so receive threads notice the client shutting down
+pub const SOCKET_READ_TIMEOUT: Duration = Duration::from_millis(500);
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+#[cfg(feature = "chat")]
+pub const DEFAULT_MAX_QUEUED_CHAT_MESSAGES: usize = 50;
+// Twitch counts characters, not bytes
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 500;
+#[cfg(feature = "discord")]
+pub const DEFAULT_DISCORD_MIN_CHEER_BITS: u32 = 1000;
+#[cfg(feature = "discord")]
+pub const MAX_DISCORD_WEBHOOK_RETRIES: usize = 3;
+#[cfg(feature = "discord")]
+pub const TWITCH_PURPLE: u32 = 0x9146FF;
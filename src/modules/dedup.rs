@@ -0,0 +1,71 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Remembers recently seen message ids, Twitch may deliver the same notification more
+/// than once.
+#[derive(Debug)]
+pub struct MessageDeduplicator {
+  window: Duration,
+  seen: VecDeque<(String, Instant)>,
+  ids: HashSet<String>,
+}
+
+impl MessageDeduplicator {
+  /// A window of zero disables deduplication.
+  pub fn new(window: Duration) -> MessageDeduplicator {
+    MessageDeduplicator {
+      window,
+      seen: VecDeque::new(),
+      ids: HashSet::new(),
+    }
+  }
+
+  /// Returns true if the id was already seen within the window, otherwise records it.
+  pub fn is_duplicate(&mut self, message_id: &str) -> bool {
+    if self.window.is_zero() {
+      return false;
+    }
+
+    let now = Instant::now();
+    while let Some((id, received_at)) = self.seen.front() {
+      if now.duration_since(*received_at) < self.window {
+        break;
+      }
+      self.ids.remove(id);
+      self.seen.pop_front();
+    }
+
+    if self.ids.contains(message_id) {
+      return true;
+    }
+
+    self.ids.insert(message_id.to_owned());
+    self.seen.push_back((message_id.to_owned(), now));
+    false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::thread;
+
+  use super::*;
+
+  #[test]
+  fn forgets_ids_after_the_window() {
+    let mut deduplicator = MessageDeduplicator::new(Duration::from_millis(50));
+    assert!(!deduplicator.is_duplicate("a"));
+    assert!(deduplicator.is_duplicate("a"));
+    assert!(!deduplicator.is_duplicate("b"));
+
+    thread::sleep(Duration::from_millis(60));
+    assert!(!deduplicator.is_duplicate("a"));
+  }
+
+  #[test]
+  fn zero_window_keeps_everything() {
+    let mut deduplicator = MessageDeduplicator::new(Duration::ZERO);
+    assert!(!deduplicator.is_duplicate("a"));
+    assert!(!deduplicator.is_duplicate("a"));
+  }
+}
@@ -1,39 +1,230 @@
+use std::{error::Error, fmt};
+
+#[cfg(not(target_arch = "wasm32"))]
 use log::LevelFilter;
+#[cfg(not(target_arch = "wasm32"))]
 use simple_logging;
 
+#[cfg(not(target_arch = "wasm32"))]
 use crate::modules::twitch_http::TwitchHttpRequest;
+use crate::modules::{consts::MAX_CHAT_MESSAGE_LENGTH, scope::Scope};
 
+#[cfg(not(target_arch = "wasm32"))]
 pub const LOG_FILE: &str = "twitch_events.log";
+#[cfg(not(target_arch = "wasm32"))]
 pub const LOG_FILE_BUILDER: &str = "twitch_event_builder.log";
 
+#[cfg(not(target_arch = "wasm32"))]
 pub fn log_info() {
   let _ = simple_logging::log_to_file(LOG_FILE, LevelFilter::Info);
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 pub fn log_builder() {
   let _ = simple_logging::log_to_file(LOG_FILE_BUILDER, LevelFilter::Info);
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum EventSubError {
   TokenMissingScope,
   NoSubscriptionsRequested,
   AuthorisationError(String),
   WebsocketCreationFailed,
   MessageTooLong,
+  /// Twitch didn't post the chat message, such as for the channel being in followers only
+  /// mode or the message being a duplicate.
+  MessageDropped {
+    code: String,
+    message: String,
+  },
   UnhandledError(String),
   NoAccessTokenProvided,
   WriteError(String),
+  ReadError(String),
   // status 401 = invalid access token
   InvalidAccessToken(String),
   InvalidOauthToken(String),
+  #[cfg(not(target_arch = "wasm32"))]
   CurlFailed(curl::Error),
   ParseError(String),
+  #[cfg(not(target_arch = "wasm32"))]
   TokenRequiresRefreshing(TwitchHttpRequest),
+  // The websocket hasn't received its welcome message yet
+  NoSessionEstablished,
+  NotAnEventSubSubscription(String),
+  // Every websocket connection already has the maximum number of subscriptions
+  TooManySubscriptions,
+  // Creating the subscription would go over the max total cost Twitch allows, given here
+  SubscriptionCostLimitReached(u32),
+  // The request took longer than its HttpTimeouts allow
+  TimedOut,
+  // The request's CancellationToken was cancelled
+  Cancelled,
+  // Twitch responded with 400, the request's parameters or body weren't valid
+  BadRequest(String),
+  // Twitch responded with 403, the user isn't allowed to do this, such as not being a moderator
+  Forbidden(String),
+  // Twitch responded with 404, the user, message or whatever was asked for doesn't exist
+  NotFound(String),
+  // Twitch responded with 409, the change conflicts with another, such as one in progress
+  Conflict(String),
+  // Twitch responded with a 5xx status, it should work if tried again later
+  ServerError {
+    status: u32,
+    twitch_message: String,
+  },
+  // Twitch responded with an error status that has no more specific variant
+  HttpError {
+    status: u32,
+    twitch_message: String,
+  },
+  // The token was refused, missing_scope is set when the scope that was needed is known
+  Unauthorized {
+    missing_scope: Option<String>,
+  },
+  // Unix timestamp of when the rate limit bucket refills
+  RateLimited {
+    reset_at: u64,
+  },
+  SubscriptionFailed {
+    kind: String,
+    reason: String,
+  },
+  // Scopes the token needs but wasn't granted
+  MissingScopes(Vec<Scope>),
+  // The subscription's condition is missing fields Twitch needs or has ones it doesn't take
+  InvalidCondition {
+    kind: String,
+    missing: Vec<String>,
+    extraneous: Vec<String>,
+  },
+  // The IRC chat connection failed or was closed
+  IrcError(String),
+  // The SQLite event database couldn't be opened, written or queried
+  DatabaseError(String),
+  // The config file couldn't be read, or it or the builder has settings that aren't valid
+  ConfigError(String),
+}
+
+impl fmt::Display for EventSubError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      EventSubError::TokenMissingScope => write!(f, "Token is missing a required scope"),
+      EventSubError::NoSubscriptionsRequested => write!(f, "No subscriptions were requested"),
+      EventSubError::AuthorisationError(e) => write!(f, "Authorisation failed: {}", e),
+      EventSubError::WebsocketCreationFailed => write!(f, "Failed to create websocket"),
+      EventSubError::MessageTooLong => write!(
+        f,
+        "Message is longer than {} characters",
+        MAX_CHAT_MESSAGE_LENGTH
+      ),
+      EventSubError::MessageDropped { code, message } => {
+        write!(f, "Chat message dropped: {} ({})", message, code)
+      }
+      EventSubError::UnhandledError(e) => write!(f, "{}", e),
+      EventSubError::NoAccessTokenProvided => write!(f, "No access token provided"),
+      EventSubError::WriteError(e) => write!(f, "Failed to write: {}", e),
+      EventSubError::ReadError(e) => write!(f, "Failed to read: {}", e),
+      EventSubError::InvalidAccessToken(e) => write!(f, "Invalid access token: {}", e),
+      EventSubError::InvalidOauthToken(e) => write!(f, "Invalid oauth token: {}", e),
+      #[cfg(not(target_arch = "wasm32"))]
+      EventSubError::CurlFailed(e) => write!(f, "Curl failed: {}", e),
+      EventSubError::ParseError(e) => write!(f, "Failed to parse response: {}", e),
+      #[cfg(not(target_arch = "wasm32"))]
+      EventSubError::TokenRequiresRefreshing(_) => write!(f, "Token requires refreshing"),
+      EventSubError::NoSessionEstablished => write!(f, "Websocket has no session yet"),
+      EventSubError::NotAnEventSubSubscription(tag) => {
+        write!(f, "{} is not an EventSub subscription", tag)
+      }
+      EventSubError::TooManySubscriptions => {
+        write!(f, "Every websocket connection is full of subscriptions")
+      }
+      EventSubError::SubscriptionCostLimitReached(max) => {
+        write!(
+          f,
+          "Subscription would go over the max total cost of {}",
+          max
+        )
+      }
+      EventSubError::TimedOut => write!(f, "Request timed out"),
+      EventSubError::Cancelled => write!(f, "Request was cancelled"),
+      EventSubError::BadRequest(message) => write!(f, "Bad request: {}", message),
+      EventSubError::Forbidden(message) => write!(f, "Forbidden: {}", message),
+      EventSubError::NotFound(message) => write!(f, "Not found: {}", message),
+      EventSubError::Conflict(message) => write!(f, "Conflict: {}", message),
+      EventSubError::ServerError {
+        status,
+        twitch_message,
+      } => write!(f, "Twitch server error {}: {}", status, twitch_message),
+      EventSubError::HttpError {
+        status,
+        twitch_message,
+      } => write!(f, "Twitch returned {}: {}", status, twitch_message),
+      EventSubError::Unauthorized {
+        missing_scope: Some(scope),
+      } => write!(f, "Unauthorised, token is missing scope {}", scope),
+      EventSubError::Unauthorized {
+        missing_scope: None,
+      } => write!(f, "Unauthorised"),
+      EventSubError::RateLimited { reset_at } => {
+        write!(f, "Rate limited until {}", reset_at)
+      }
+      EventSubError::SubscriptionFailed { kind, reason } => {
+        write!(f, "Failed to subscribe to {}: {}", kind, reason)
+      }
+      EventSubError::MissingScopes(scopes) => {
+        let scopes = scopes.iter().map(Scope::as_str).collect::<Vec<_>>();
+        write!(f, "Token is missing scopes: {}", scopes.join(", "))
+      }
+      EventSubError::InvalidCondition {
+        kind,
+        missing,
+        extraneous,
+      } => {
+        write!(f, "Invalid condition for {}", kind)?;
+        if !missing.is_empty() {
+          write!(f, ", missing: {}", missing.join(", "))?;
+        }
+        if !extraneous.is_empty() {
+          write!(f, ", not taken: {}", extraneous.join(", "))?;
+        }
+        Ok(())
+      }
+      EventSubError::IrcError(e) => write!(f, "IRC chat error: {}", e),
+      EventSubError::DatabaseError(e) => write!(f, "Event database error: {}", e),
+      EventSubError::ConfigError(e) => write!(f, "Invalid config: {}", e),
+    }
+  }
+}
+
+impl Error for EventSubError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      #[cfg(not(target_arch = "wasm32"))]
+      EventSubError::CurlFailed(e) => Some(e),
+      _ => None,
+    }
+  }
 }
 
 #[derive(Debug)]
 pub enum TwitchKeysError {
   ClientIdNotFound,
   ClientSecretNotFound,
+  /// The required environment variables that aren't set.
+  MissingVariables(Vec<String>),
+}
+
+impl fmt::Display for TwitchKeysError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TwitchKeysError::ClientIdNotFound => write!(f, "Client id not found"),
+      TwitchKeysError::ClientSecretNotFound => write!(f, "Client secret not found"),
+      TwitchKeysError::MissingVariables(names) => {
+        write!(f, "Missing environment variables {}", names.join(", "))
+      }
+    }
+  }
 }
+
+impl Error for TwitchKeysError {}
@@ -0,0 +1,231 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::modules::message_channel::MessageReceiver;
+use crate::{modules::messages::*, Event};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{warn, MessageType};
+
+/// Event data that can be pulled out of an [`Event`], used by [`EventStream::only`].
+pub trait FromEvent: Sized {
+  fn from_event(event: Event) -> Option<Self>;
+
+  /// Whether `from_event` would return the event's data, checked before an event is sent
+  /// to a stream so it only queues the events it wants.
+  fn matches(_event: &Event) -> bool {
+    true
+  }
+}
+
+macro_rules! from_event {
+  ($($variant:ident => $data:ident),* $(,)?) => {
+    $(
+      impl FromEvent for $data {
+        fn from_event(event: Event) -> Option<$data> {
+          match event {
+            Event::$variant(data) => Some(data),
+            _ => None,
+          }
+        }
+
+        fn matches(event: &Event) -> bool {
+          matches!(event, Event::$variant(_))
+        }
+      }
+    )*
+  };
+}
+
+// Every Event variant needs an entry here to be usable with EventStream::only
+from_event!(
+  ChatMessage => MessageData,
+  Raid => RaidData,
+  Follow => FollowData,
+  ChannelUpdate => ChannelUpdateData,
+  UserUpdate => UserUpdateData,
+  PointsCustomRewardRedeem => CustomPointsRewardRedeemData,
+  AdBreakBegin => AdBreakBeginData,
+  Subscribe => SubscribeData,
+  SubscriptionEnd => SubscriptionEndData,
+  SubscriptionGift => GiftData,
+  SubscriptionMessage => SubscribeMessageData,
+  Cheer => CheerData,
+  ChannelPointsAutoRewardRedeem => AutoRewardData,
+  PollBegin => PollBeginData,
+  PollProgress => PollProgressData,
+  PollEnd => PollEndData,
+  PredictionBegin => PredictionBeginData,
+  PredictionProgress => PredictionProgressData,
+  PredictionLock => PredictionLockData,
+  PredictionEnd => PredictionEndData,
+  HypeTrainBegin => HypeTrainBeginData,
+  HypeTrainProgress => HypeTrainProgressData,
+  HypeTrainEnd => HypeTrainEndData,
+  AutoModMessageUpdate => AutoModMessageUpdateData,
+  AutoModMessageHold => AutoModMessageHoldData,
+  AutoModSettingsUpdate => AutoModSettingsUpdateData,
+  AutoModTermsUpdate => AutoModTermsUpdateData,
+  ShieldModeBegin => ShieldModeBeginData,
+  ShieldModeEnd => ShieldModeEndData,
+  CharityDonation => CharityDonationData,
+  UnbanRequestResolve => UnbanRequestResolveData,
+  UnbanRequestCreate => UnbanRequestCreateData,
+  CharityCampaignStart => CharityCampaignStartData,
+  CharityCampaignStop => CharityCampaignStopData,
+  CharityCampaignProgress => CharityCampaignProgressData,
+  StreamOnline => StreamOnlineData,
+  StreamOffline => StreamOfflineData,
+);
+
+// Goals share their data between variants, any of them will do
+impl FromEvent for GoalData {
+  fn from_event(event: Event) -> Option<GoalData> {
+    match event {
+      Event::GoalBegin(data) | Event::GoalProgress(data) | Event::GoalEnd(data) => Some(data),
+      _ => None,
+    }
+  }
+
+  fn matches(event: &Event) -> bool {
+    matches!(
+      event,
+      Event::GoalBegin(_) | Event::GoalProgress(_) | Event::GoalEnd(_)
+    )
+  }
+}
+
+/// Decides which events are sent to a stream, shared between the stream and the client.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) type StreamFilter = Arc<Mutex<Box<dyn Fn(&Event) -> bool + Send>>>;
+
+/// A receiver of events with its own channel, so different event types can be handed to
+/// different threads, e.g. `api.event_stream().only::<MessageData>()` for chat.
+///
+/// `only` and `from_broadcaster` are checked before events are queued, so a stream only
+/// holds the events it wants. The stream has the same buffer limit and overflow policy as
+/// the client's messages.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct EventStream<T = Event> {
+  receiver: MessageReceiver,
+  accepts: StreamFilter,
+  transform: Box<dyn Fn(Event) -> Option<T> + Send>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl EventStream<Event> {
+  pub(crate) fn new(receiver: MessageReceiver, accepts: StreamFilter) -> EventStream<Event> {
+    EventStream {
+      receiver,
+      accepts,
+      transform: Box::new(Some),
+    }
+  }
+
+  /// Only receive events from one broadcaster's channel.
+  pub fn from_broadcaster<S: Into<String>>(self, broadcaster_id: S) -> EventStream<Event> {
+    let broadcaster_id = broadcaster_id.into();
+    self.narrow(move |event| event.broadcaster_id() == Some(broadcaster_id.as_str()));
+    self
+  }
+
+  /// Only receive events of one type, e.g. `only::<MessageData>()`.
+  pub fn only<T: FromEvent + 'static>(self) -> EventStream<T> {
+    self.narrow(T::matches);
+    EventStream {
+      receiver: self.receiver,
+      accepts: self.accepts,
+      transform: Box::new(T::from_event),
+    }
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: 'static> EventStream<T> {
+  // Stops events the predicate rejects from being sent to the stream at all
+  fn narrow<F: Fn(&Event) -> bool + Send + 'static>(&self, predicate: F) {
+    let mut accepts = self.accepts.lock().unwrap();
+    let previous = std::mem::replace(&mut *accepts, Box::new(|_| true));
+    *accepts = Box::new(move |event| previous(event) && predicate(event));
+  }
+
+  pub fn filter<F: Fn(&T) -> bool + Send + 'static>(self, predicate: F) -> EventStream<T> {
+    let transform = self.transform;
+    EventStream {
+      receiver: self.receiver,
+      accepts: self.accepts,
+      transform: Box::new(move |event| transform(event).filter(|t| predicate(t))),
+    }
+  }
+
+  pub fn map<U, F: Fn(T) -> U + Send + 'static>(self, f: F) -> EventStream<U> {
+    let transform = self.transform;
+    EventStream {
+      receiver: self.receiver,
+      accepts: self.accepts,
+      transform: Box::new(move |event| transform(event).map(&f)),
+    }
+  }
+
+  pub fn filter_map<U, F: Fn(T) -> Option<U> + Send + 'static>(self, f: F) -> EventStream<U> {
+    let transform = self.transform;
+    EventStream {
+      receiver: self.receiver,
+      accepts: self.accepts,
+      transform: Box::new(move |event| transform(event).and_then(&f)),
+    }
+  }
+
+  fn take(&self, message: MessageType) -> Option<T> {
+    match message {
+      MessageType::Event { event, .. } => (self.transform)(event),
+      MessageType::EventsDropped(count) => {
+        warn!("Event stream was full, {} events were dropped", count);
+        None
+      }
+      _ => None,
+    }
+  }
+
+  /// Blocks until a matching event arrives, `None` once the client has been dropped.
+  pub fn recv(&self) -> Option<T> {
+    loop {
+      let message = self.receiver.recv().ok()?;
+      if let Some(t) = self.take(message) {
+        return Some(t);
+      }
+    }
+  }
+
+  /// Returns the next matching event that has already arrived without blocking.
+  pub fn try_recv(&self) -> Option<T> {
+    loop {
+      let message = self.receiver.try_recv().ok()?;
+      if let Some(t) = self.take(message) {
+        return Some(t);
+      }
+    }
+  }
+
+  pub fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+    let deadline = Instant::now() + timeout;
+    loop {
+      let remaining = deadline.saturating_duration_since(Instant::now());
+      let message = self.receiver.recv_timeout(remaining).ok()?;
+      if let Some(t) = self.take(message) {
+        return Some(t);
+      }
+    }
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: 'static> Iterator for EventStream<T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<T> {
+    self.recv()
+  }
+}
@@ -0,0 +1,352 @@
+// C interface for using the client from other languages, see include/twitch_eventsub.h.
+// Strings returned to C are owned by the caller and freed with twitch_eventsub_free_string,
+// functions that fail return NULL or false and set the error read by
+// twitch_eventsub_last_error.
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use serde_json::json;
+
+use crate::{
+  ConnectionState, MessageType, SecretString, Subscription, TokenAccess, TwitchEventSubApi,
+  TwitchKeys,
+};
+
+/// Opaque handle to a client.
+pub struct TwitchEventSubClient {
+  api: TwitchEventSubApi,
+}
+
+thread_local! {
+  static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error<S: Into<String>>(error: S) {
+  let error = CString::new(error.into().replace('\0', "")).unwrap_or_default();
+  LAST_ERROR.with(|last| *last.borrow_mut() = Some(error));
+}
+
+// Panics mustn't unwind into C, they're turned into an error instead
+fn guard<T, F: FnOnce() -> Result<T, String>>(default: T, f: F) -> T {
+  match panic::catch_unwind(AssertUnwindSafe(f)) {
+    Ok(Ok(value)) => value,
+    Ok(Err(e)) => {
+      set_last_error(e);
+      default
+    }
+    Err(_) => {
+      set_last_error("Panicked inside twitch_eventsub");
+      default
+    }
+  }
+}
+
+unsafe fn read_string(string: *const c_char, name: &str) -> Result<String, String> {
+  if string.is_null() {
+    return Err(format!("{} is NULL", name));
+  }
+  CStr::from_ptr(string)
+    .to_str()
+    .map(str::to_owned)
+    .map_err(|_| format!("{} isn't valid UTF-8", name))
+}
+
+unsafe fn read_optional_string(
+  string: *const c_char,
+  name: &str,
+) -> Result<Option<String>, String> {
+  if string.is_null() {
+    Ok(None)
+  } else {
+    read_string(string, name).map(Some)
+  }
+}
+
+unsafe fn read_subscriptions(
+  subscriptions: *const *const c_char,
+  count: usize,
+) -> Result<Vec<Subscription>, String> {
+  if count == 0 {
+    return Ok(Vec::new());
+  }
+  if subscriptions.is_null() {
+    return Err("subscriptions is NULL".to_owned());
+  }
+
+  std::slice::from_raw_parts(subscriptions, count)
+    .iter()
+    .map(|tag| {
+      let tag = read_string(*tag, "subscription")?;
+      Subscription::from_string(&tag).ok_or(format!("Unknown subscription type {}", tag))
+    })
+    .collect()
+}
+
+fn into_c_string(string: String) -> *mut c_char {
+  CString::new(string.replace('\0', ""))
+    .map(CString::into_raw)
+    .unwrap_or(ptr::null_mut())
+}
+
+fn build(
+  keys: TwitchKeys,
+  subscriptions: Vec<Subscription>,
+  redirect_url: Option<String>,
+) -> Result<*mut TwitchEventSubClient, String> {
+  let mut builder = TwitchEventSubApi::builder(keys)
+    .add_subscriptions(subscriptions)
+    .generate_access_token_on_expire(true);
+  // Generating a token needs somewhere for the browser to come back to
+  if let Some(redirect_url) = redirect_url {
+    builder = builder
+      .set_redirect_url(redirect_url)
+      .generate_new_token_if_none(true);
+  }
+
+  builder
+    .build()
+    .map(|api| Box::into_raw(Box::new(TwitchEventSubClient { api })))
+    .map_err(|e| e.to_string())
+}
+
+/// Connects a client for the broadcaster. `subscriptions` are EventSub types such as
+/// `channel.chat.message`. Without an access token one is generated through the browser,
+/// which needs `redirect_url`, such as `http://localhost:3000`.
+///
+/// # Safety
+/// Strings must be NULL or valid NUL terminated strings, `subscriptions` must point to
+/// `subscription_count` of them.
+#[no_mangle]
+pub unsafe extern "C" fn twitch_eventsub_create(
+  client_id: *const c_char,
+  client_secret: *const c_char,
+  broadcaster_id: *const c_char,
+  user_access_token: *const c_char,
+  refresh_token: *const c_char,
+  redirect_url: *const c_char,
+  subscriptions: *const *const c_char,
+  subscription_count: usize,
+) -> *mut TwitchEventSubClient {
+  guard(ptr::null_mut(), || {
+    let redirect_url = read_optional_string(redirect_url, "redirect_url")?;
+    let broadcaster_id = read_string(broadcaster_id, "broadcaster_id")?;
+    let keys = TwitchKeys {
+      authorisation_code: None,
+      access_token: read_optional_string(user_access_token, "user_access_token")?
+        .map(|token| TokenAccess::User(token.into())),
+      refresh_token: read_optional_string(refresh_token, "refresh_token")?.map(SecretString::from),
+      client_id: read_string(client_id, "client_id")?,
+      client_secret: read_string(client_secret, "client_secret")?.into(),
+      sender_account_id: Some(broadcaster_id.to_owned()),
+      broadcaster_account_id: broadcaster_id,
+      moderator_account_id: None,
+      chat_reader_account_id: None,
+      token_storage: None,
+    };
+
+    build(
+      keys,
+      read_subscriptions(subscriptions, subscription_count)?,
+      redirect_url,
+    )
+  })
+}
+
+/// Like `twitch_eventsub_create` with the keys read from the environment and `.env` files,
+/// the same as `TwitchKeys::from_secrets_env`. The redirect url is read from
+/// `TWITCH_REDIRECT_URL`.
+///
+/// # Safety
+/// `subscriptions` must point to `subscription_count` valid NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn twitch_eventsub_create_from_env(
+  subscriptions: *const *const c_char,
+  subscription_count: usize,
+) -> *mut TwitchEventSubClient {
+  guard(ptr::null_mut(), || {
+    let keys = TwitchKeys::from_secrets_env().map_err(|e| format!("{:?}", e))?;
+    let redirect_url = std::env::var("TWITCH_REDIRECT_URL").ok();
+    build(
+      keys,
+      read_subscriptions(subscriptions, subscription_count)?,
+      redirect_url,
+    )
+  })
+}
+
+/// Closes the client and frees it.
+///
+/// # Safety
+/// `client` must have come from a create function and not been destroyed already.
+#[no_mangle]
+pub unsafe extern "C" fn twitch_eventsub_destroy(client: *mut TwitchEventSubClient) {
+  if !client.is_null() {
+    guard((), || {
+      drop(Box::from_raw(client));
+      Ok(())
+    });
+  }
+}
+
+/// The next message as JSON without blocking, or NULL when there isn't one. Events are the
+/// notification exactly as Twitch sent it, anything else is an object with a `type`, such
+/// as `{"type": "error", "message": "..."}`.
+///
+/// # Safety
+/// `client` must be a live client from a create function.
+#[no_mangle]
+pub unsafe extern "C" fn twitch_eventsub_poll_event(
+  client: *mut TwitchEventSubClient,
+) -> *mut c_char {
+  let Some(client) = client.as_mut() else {
+    set_last_error("client is NULL");
+    return ptr::null_mut();
+  };
+
+  guard(ptr::null_mut(), || {
+    while let Some(message) = client.api.try_recv() {
+      if let Some(json) = message_json(message) {
+        return Ok(into_c_string(json));
+      }
+    }
+    Ok(ptr::null_mut())
+  })
+}
+
+fn message_json(message: MessageType) -> Option<String> {
+  // Notifications that failed to parse are passed on too, C callers parse them themselves
+  if let Some(raw) = message.raw_json() {
+    return Some(raw.to_owned());
+  }
+
+  let json = match message {
+    // Chat read over IRC is shaped like a notification so callers can read it the same way
+    MessageType::Event { event, raw: None } => json!({
+      "metadata": {
+        "message_type": "notification",
+        "subscription_type": event.subscription_type(),
+      },
+      "payload": { "event": event },
+    }),
+    MessageType::Error(e) => json!({ "type": "error", "message": e.to_string() }),
+    MessageType::ConnectionStatusChanged(status) => json!({
+      "type": "connection_status",
+      "connection": status.connection,
+      "connected": matches!(status.state, ConnectionState::Connected { .. }),
+    }),
+    MessageType::EventsDropped(count) => json!({ "type": "events_dropped", "count": count }),
+    MessageType::ChatMessageDropped { message, reason } => json!({
+      "type": "chat_message_dropped",
+      "message": message,
+      "reason": reason,
+    }),
+    MessageType::SubscriptionsReady(ready) => json!({
+      "type": "subscriptions_ready",
+      "connection": ready.connection,
+      "succeeded": ready.succeeded.iter().map(Subscription::tag).collect::<Vec<_>>(),
+      "failed": ready
+        .failed
+        .iter()
+        .map(|(subscription, reason)| json!({
+          "type": subscription.tag(),
+          "reason": format!("{:?}", reason),
+        }))
+        .collect::<Vec<_>>(),
+    }),
+    MessageType::Close => json!({ "type": "close" }),
+    _ => return None,
+  };
+
+  Some(json.to_string())
+}
+
+/// Queues a chat message to the broadcaster's chat.
+///
+/// # Safety
+/// `client` must be a live client and `message` a valid NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn twitch_eventsub_send_chat_message(
+  client: *mut TwitchEventSubClient,
+  message: *const c_char,
+) -> bool {
+  let Some(client) = client.as_mut() else {
+    set_last_error("client is NULL");
+    return false;
+  };
+
+  guard(false, || {
+    let message = read_string(message, "message")?;
+    client.api.send_chat_message(message);
+    Ok(true)
+  })
+}
+
+/// The access token in use, which may have been generated or refreshed since the client
+/// was created, so it can be saved.
+///
+/// # Safety
+/// `client` must be a live client from a create function.
+#[no_mangle]
+pub unsafe extern "C" fn twitch_eventsub_access_token(
+  client: *const TwitchEventSubClient,
+) -> *mut c_char {
+  let Some(client) = client.as_ref() else {
+    set_last_error("client is NULL");
+    return ptr::null_mut();
+  };
+
+  match &client.api.twitch_keys.access_token {
+    Some(token) => into_c_string(token.get_token()),
+    None => {
+      set_last_error("No access token");
+      ptr::null_mut()
+    }
+  }
+}
+
+/// The refresh token in use, see `twitch_eventsub_access_token`.
+///
+/// # Safety
+/// `client` must be a live client from a create function.
+#[no_mangle]
+pub unsafe extern "C" fn twitch_eventsub_refresh_token(
+  client: *const TwitchEventSubClient,
+) -> *mut c_char {
+  let Some(client) = client.as_ref() else {
+    set_last_error("client is NULL");
+    return ptr::null_mut();
+  };
+
+  match &client.api.twitch_keys.refresh_token {
+    Some(token) => into_c_string(token.expose().to_owned()),
+    None => {
+      set_last_error("No refresh token");
+      ptr::null_mut()
+    }
+  }
+}
+
+/// The last error on this thread, or NULL. Owned by the library and valid until the next
+/// call that fails.
+#[no_mangle]
+pub extern "C" fn twitch_eventsub_last_error() -> *const c_char {
+  LAST_ERROR.with(|last| {
+    last
+      .borrow()
+      .as_ref()
+      .map_or(ptr::null(), |error| error.as_ptr())
+  })
+}
+
+/// Frees a string returned by this library.
+///
+/// # Safety
+/// `string` must be NULL or have come from this library and not been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn twitch_eventsub_free_string(string: *mut c_char) {
+  if !string.is_null() {
+    drop(CString::from_raw(string));
+  }
+}
@@ -1,10 +1,16 @@
+use std::ops::Range;
+
 use crate::{
   modules::messages::{MessageData, RaidData},
-  Condition, Deserialise, EventSubError, Serialise, Subscription, Token,
+  Condition, Deserialise, EventSubSubscriptionInfo, Serialise, Subscription,
 };
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{EventSubError, Token};
 
 use super::messages::*;
 
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(dead_code)]
 #[derive(Deserialise)]
 pub struct NewAccessTokenResponse {
   pub access_token: String,
@@ -14,6 +20,7 @@ pub struct NewAccessTokenResponse {
   scope: Option<Vec<String>>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl NewAccessTokenResponse {
   pub fn _get_token_from_data(raw_data: &str) -> Result<Token, EventSubError> {
     serde_json::from_str::<NewAccessTokenResponse>(raw_data)
@@ -28,15 +35,27 @@ impl NewAccessTokenResponse {
   }
 }
 
+/// A code for the user to enter at `verification_uri` to authorise a headless device.
+#[derive(Deserialise, Debug, Clone)]
+pub struct DeviceCode {
+  pub device_code: String,
+  pub user_code: String,
+  pub verification_uri: String,
+  /// Seconds until the code stops working.
+  pub expires_in: u64,
+  /// Seconds to wait between checks for the user having entered the code.
+  pub interval: u64,
+}
+
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct Validation {
-  client_id: Option<String>,
-  login: Option<String>,
+  pub client_id: Option<String>,
+  pub login: Option<String>,
   pub scopes: Option<Vec<String>>,
-  user_id: Option<String>,
+  pub user_id: Option<String>,
   pub expires_in: Option<u32>,
   pub status: Option<u32>,
-  message: Option<String>,
+  pub message: Option<String>,
 }
 
 impl Validation {
@@ -57,6 +76,7 @@ impl Validation {
   }
 }
 
+#[cfg(all(feature = "moderation", not(target_arch = "wasm32")))]
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct TimeoutRequestData {
   pub user_id: String,
@@ -64,11 +84,13 @@ pub struct TimeoutRequestData {
   pub reason: String,
 }
 
+#[cfg(all(feature = "moderation", not(target_arch = "wasm32")))]
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct SendTimeoutRequest {
   pub data: TimeoutRequestData,
 }
 
+#[cfg(all(feature = "chat", not(target_arch = "wasm32")))]
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct SendMessage {
   pub broadcaster_id: String,
@@ -77,7 +99,7 @@ pub struct SendMessage {
   pub reply_parent_message_id: Option<String>,
 }
 
-#[derive(Serialise, Deserialise, Debug, Clone)]
+#[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
 pub struct Transport {
   pub method: String,
   pub session_id: String,
@@ -118,41 +140,121 @@ pub struct GMSubscription {
 
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct Mention {
-  user_id: String,
-  user_login: String,
-  user_name: String,
+  pub user_id: String,
+  pub user_login: String,
+  pub user_name: String,
 }
 
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct Emote {
-  id: String,
-  emote_set_id: String,
-  owner_id: String,
-  format: Vec<String>,
+  pub id: String,
+  pub emote_set_id: String,
+  pub owner_id: String,
+  pub format: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmoteScale {
+  Small,
+  Medium,
+  Large,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmoteTheme {
+  Light,
+  Dark,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmoteFormat {
+  Static,
+  Animated,
+}
+
+impl Emote {
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+
+  pub fn emote_set_id(&self) -> &str {
+    &self.emote_set_id
+  }
+
+  pub fn owner_id(&self) -> &str {
+    &self.owner_id
+  }
+
+  pub fn is_animated(&self) -> bool {
+    self.format.iter().any(|format| format == "animated")
+  }
+
+  /// The emote's image on Twitch's CDN. Asking for an animated image of an emote that
+  /// isn't animated gives the static one.
+  pub fn url(&self, scale: EmoteScale, theme: EmoteTheme, format: EmoteFormat) -> String {
+    let format = match format {
+      EmoteFormat::Animated if self.is_animated() => "animated",
+      _ => "static",
+    };
+    let theme = match theme {
+      EmoteTheme::Light => "light",
+      EmoteTheme::Dark => "dark",
+    };
+    let scale = match scale {
+      EmoteScale::Small => "1.0",
+      EmoteScale::Medium => "2.0",
+      EmoteScale::Large => "3.0",
+    };
+
+    format!(
+      "https://static-cdn.jtvnw.net/emoticons/v2/{}/{}/{}/{}",
+      self.id, format, theme, scale
+    )
+  }
 }
 
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct CheerMote {
-  prefix: String,
-  bits: u32,
-  tier: u32,
+  pub prefix: String,
+  pub bits: u32,
+  pub tier: u32,
+}
+
+impl CheerMote {
+  /// The cheermote's name, such as `Cheer` in `Cheer100`.
+  pub fn prefix(&self) -> &str {
+    &self.prefix
+  }
+
+  pub fn bits(&self) -> u32 {
+    self.bits
+  }
+
+  /// Which tier of the cheermote's images the amount of bits unlocks.
+  pub fn tier(&self) -> u32 {
+    self.tier
+  }
 }
 
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct Fragments {
   #[serde(rename = "type")]
-  kind: String,
-  text: String,
-  cheermote: Option<CheerMote>,
-  emote: Option<Emote>,
-  mention: Option<Mention>,
+  pub kind: String,
+  pub text: String,
+  pub cheermote: Option<CheerMote>,
+  pub emote: Option<Emote>,
+  pub mention: Option<Mention>,
 }
 
 impl Fragments {
-  pub fn _is_text(&self) -> bool {
+  pub fn is_text(&self) -> bool {
     self.kind == "text"
   }
 
+  pub fn is_emote(&self) -> bool {
+    self.kind == "emote"
+  }
+
   pub fn is_mention(&self) -> bool {
     self.kind == "mention"
   }
@@ -160,6 +262,22 @@ impl Fragments {
   pub fn text(&self) -> String {
     self.text.to_string()
   }
+
+  pub fn emote(&self) -> Option<&Emote> {
+    self.emote.as_ref()
+  }
+
+  pub fn mention(&self) -> Option<&Mention> {
+    self.mention.as_ref()
+  }
+
+  pub fn is_cheermote(&self) -> bool {
+    self.kind == "cheermote"
+  }
+
+  pub fn cheermote(&self) -> Option<&CheerMote> {
+    self.cheermote.as_ref()
+  }
 }
 
 impl Message {
@@ -176,6 +294,67 @@ impl Message {
     }
     text
   }
+
+  /// Everyone @-mentioned in the message, in the order they're mentioned.
+  pub fn mentions(&self) -> Vec<Mention> {
+    self
+      .fragments
+      .iter()
+      .filter_map(|fragment| fragment.mention.clone())
+      .collect()
+  }
+
+  pub fn mentions_user(&self, user_id: &str) -> bool {
+    self
+      .fragments
+      .iter()
+      .filter_map(|fragment| fragment.mention.as_ref())
+      .any(|mention| mention.user_id == user_id)
+  }
+
+  /// Total bits cheered across every cheermote in the message.
+  pub fn bits(&self) -> u32 {
+    self
+      .cheermotes()
+      .iter()
+      .map(|cheermote| cheermote.bits)
+      .sum()
+  }
+
+  pub fn cheermotes(&self) -> Vec<&CheerMote> {
+    self
+      .fragments
+      .iter()
+      .filter_map(|fragment| fragment.cheermote.as_ref())
+      .collect()
+  }
+
+  /// The message text with the cheermote codes taken out, for showing in cheer alerts.
+  pub fn text_without_cheermotes(&self) -> String {
+    self
+      .fragments
+      .iter()
+      .filter(|fragment| !fragment.is_cheermote())
+      .map(|fragment| fragment.text.as_str())
+      .collect::<String>()
+      .split_whitespace()
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+
+  /// Where each emote is in `text`, as byte ranges.
+  pub fn emote_positions(&self) -> Vec<(Range<usize>, &Emote)> {
+    let mut start = 0;
+    let mut positions = Vec::new();
+    for fragment in &self.fragments {
+      let end = start + fragment.text.len();
+      if let Some(emote) = &fragment.emote {
+        positions.push((start..end, emote));
+      }
+      start = end;
+    }
+    positions
+  }
 }
 
 #[derive(Serialise, Deserialise, Debug, Clone)]
@@ -186,20 +365,26 @@ pub struct Message {
 
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct Badge {
-  set_id: String,
-  id: String,
-  info: String,
+  pub set_id: String,
+  pub id: String,
+  pub info: String,
 }
 
-#[derive(Serialise, Deserialise, Debug, Clone)]
-pub struct Reply {
-  #[serde(flatten)]
-  thread: ThreadUser,
-  #[serde(flatten)]
-  parent_user: ParentUser,
-  parent_message_id: String,
-  parent_message_body: String,
-  thread_message_id: String,
+impl Badge {
+  /// The kind of badge, such as `moderator` or `subscriber`.
+  pub fn set_id(&self) -> &str {
+    &self.set_id
+  }
+
+  /// Which badge in the set, for subscriber badges this is the tier and months.
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+
+  /// Extra details, for subscriber badges this is the number of months subscribed.
+  pub fn info(&self) -> &str {
+    &self.info
+  }
 }
 
 #[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
@@ -212,17 +397,23 @@ pub struct Reward {
 
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct Cheer {
-  bits: u32,
+  pub bits: u32,
 }
 
 #[derive(Serialise, Deserialise, Debug, Clone)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum Event {
   ChatMessage(MessageData),
   Raid(RaidData),
+  Follow(FollowData),
+  ChannelUpdate(ChannelUpdateData),
+  UserUpdate(UserUpdateData),
   PointsCustomRewardRedeem(CustomPointsRewardRedeemData),
   AdBreakBegin(AdBreakBeginData),
   Subscribe(SubscribeData),
+  // Identical to subscribe, so only reachable through the subscription type
+  SubscriptionEnd(SubscriptionEndData),
   SubscriptionGift(GiftData),
   SubscriptionMessage(SubscribeMessageData),
   Cheer(CheerData),
@@ -231,21 +422,69 @@ pub enum Event {
   PollProgress(PollProgressData),
   PollEnd(PollEndData),
   PredictionBegin(PredictionBeginData),
-  PredictionProgress(PredicitonProgressData),
+  PredictionProgress(PredictionProgressData),
   PredictionLock(PredictionLockData),
-  PredictionEnd(PredicitionEndData),
+  PredictionEnd(PredictionEndData),
   HypeTrainBegin(HypeTrainBeginData),
   HypeTrainProgress(HypeTrainProgressData),
   HypeTrainEnd(HypeTrainEndData),
+  // Update carries every field hold does, so it has to be tried first
+  AutoModMessageUpdate(AutoModMessageUpdateData),
+  AutoModMessageHold(AutoModMessageHoldData),
+  AutoModSettingsUpdate(AutoModSettingsUpdateData),
+  AutoModTermsUpdate(AutoModTermsUpdateData),
+  ShieldModeBegin(ShieldModeBeginData),
+  ShieldModeEnd(ShieldModeEndData),
+  CharityDonation(CharityDonationData),
+  UnbanRequestResolve(UnbanRequestResolveData),
+  UnbanRequestCreate(UnbanRequestCreateData),
+  // Progress carries a subset of start and stop, so it has to be tried last
+  CharityCampaignStart(CharityCampaignStartData),
+  CharityCampaignStop(CharityCampaignStopData),
+  CharityCampaignProgress(CharityCampaignProgressData),
+  // Goals carry every field stream online does, so they have to be tried first
+  GoalBegin(GoalData),
+  GoalProgress(GoalData),
+  GoalEnd(GoalData),
+  StreamOnline(StreamOnlineData),
+  // Only carries the broadcaster, so anything else would match it
+  StreamOffline(StreamOfflineData),
 }
 
 #[derive(Serialise, Deserialise, Debug, Clone)]
+#[serde(try_from = "RawPayload")]
 pub struct Payload {
   pub session: Option<Session>,
   pub subscription: Option<GMSubscription>,
   pub event: Option<Event>,
 }
 
+// Events are parsed after the subscription, so its type can pick the right variant
+#[derive(Deserialise)]
+struct RawPayload {
+  session: Option<Session>,
+  subscription: Option<GMSubscription>,
+  event: Option<serde_json::Value>,
+}
+
+impl TryFrom<RawPayload> for Payload {
+  type Error = serde_json::Error;
+
+  fn try_from(raw: RawPayload) -> Result<Payload, serde_json::Error> {
+    let kind = raw.subscription.as_ref().map(|s| s.kind.as_str());
+    let event = raw
+      .event
+      .map(|event| Event::from_json(kind, event))
+      .transpose()?;
+
+    Ok(Payload {
+      session: raw.session,
+      subscription: raw.subscription,
+      event,
+    })
+  }
+}
+
 #[derive(Serialise, Deserialise, Debug, Clone)]
 pub struct MetaData {
   pub message_id: String,
@@ -263,11 +502,24 @@ pub struct GenericMessage {
   pub subscription_version: Option<String>,
 }
 
+#[cfg_attr(feature = "only_raw_responses", allow(dead_code))]
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct RevocationPayload {
+  pub subscription: EventSubSubscriptionInfo,
+}
+
+#[cfg_attr(feature = "only_raw_responses", allow(dead_code))]
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct RevocationMessage {
+  pub payload: RevocationPayload,
+}
+
 pub enum EventMessageType {
   Welcome,
   KeepAlive,
   Notification,
-  //  Reconnect,
+  Reconnect,
+  Revocation,
   Unknown,
 }
 
@@ -277,11 +529,170 @@ impl EventMessageType {
       "session_welcome" => EventMessageType::Welcome,
       "session_keepalive" => EventMessageType::KeepAlive,
       "notification" => EventMessageType::Notification,
+      "session_reconnect" => EventMessageType::Reconnect,
+      "revocation" => EventMessageType::Revocation,
       _ => EventMessageType::Unknown,
     }
   }
 }
 
+impl Event {
+  /// Parses event data as the variant for the subscription type, several events share the
+  /// same fields so the type is needed to tell them apart. Unknown types try every variant.
+  pub fn from_json(
+    subscription_type: Option<&str>,
+    event: serde_json::Value,
+  ) -> Result<Event, serde_json::Error> {
+    use serde_json::from_value;
+
+    match subscription_type.unwrap_or_default() {
+      "channel.chat.message" => from_value(event).map(Event::ChatMessage),
+      "channel.raid" => from_value(event).map(Event::Raid),
+      "channel.follow" => from_value(event).map(Event::Follow),
+      "channel.update" => from_value(event).map(Event::ChannelUpdate),
+      "user.update" => from_value(event).map(Event::UserUpdate),
+      "channel.channel_points_custom_reward_redemption.add" => {
+        from_value(event).map(Event::PointsCustomRewardRedeem)
+      }
+      "channel.ad_break.begin" => from_value(event).map(Event::AdBreakBegin),
+      "channel.subscribe" => from_value(event).map(Event::Subscribe),
+      "channel.subscription.end" => from_value(event).map(Event::SubscriptionEnd),
+      "channel.subscription.gift" => from_value(event).map(Event::SubscriptionGift),
+      "channel.subscription.message" => from_value(event).map(Event::SubscriptionMessage),
+      "channel.cheer" => from_value(event).map(Event::Cheer),
+      "channel.channel_points_automatic_reward_redemption.add" => {
+        from_value(event).map(Event::ChannelPointsAutoRewardRedeem)
+      }
+      "channel.poll.begin" => from_value(event).map(Event::PollBegin),
+      "channel.poll.progress" => from_value(event).map(Event::PollProgress),
+      "channel.poll.end" => from_value(event).map(Event::PollEnd),
+      "channel.prediction.begin" => from_value(event).map(Event::PredictionBegin),
+      "channel.prediction.progress" => from_value(event).map(Event::PredictionProgress),
+      "channel.prediction.lock" => from_value(event).map(Event::PredictionLock),
+      "channel.prediction.end" => from_value(event).map(Event::PredictionEnd),
+      "channel.hype_train.begin" => from_value(event).map(Event::HypeTrainBegin),
+      "channel.hype_train.progress" => from_value(event).map(Event::HypeTrainProgress),
+      "channel.hype_train.end" => from_value(event).map(Event::HypeTrainEnd),
+      "automod.message.hold" => from_value(event).map(Event::AutoModMessageHold),
+      "automod.message.update" => from_value(event).map(Event::AutoModMessageUpdate),
+      "automod.settings.update" => from_value(event).map(Event::AutoModSettingsUpdate),
+      "automod.terms.update" => from_value(event).map(Event::AutoModTermsUpdate),
+      "channel.shield_mode.begin" => from_value(event).map(Event::ShieldModeBegin),
+      "channel.shield_mode.end" => from_value(event).map(Event::ShieldModeEnd),
+      "channel.charity_campaign.donate" => from_value(event).map(Event::CharityDonation),
+      "channel.charity_campaign.start" => from_value(event).map(Event::CharityCampaignStart),
+      "channel.charity_campaign.progress" => from_value(event).map(Event::CharityCampaignProgress),
+      "channel.charity_campaign.stop" => from_value(event).map(Event::CharityCampaignStop),
+      "channel.unban_request.create" => from_value(event).map(Event::UnbanRequestCreate),
+      "channel.unban_request.resolve" => from_value(event).map(Event::UnbanRequestResolve),
+      "channel.goal.begin" => from_value(event).map(Event::GoalBegin),
+      "channel.goal.progress" => from_value(event).map(Event::GoalProgress),
+      "channel.goal.end" => from_value(event).map(Event::GoalEnd),
+      "stream.online" => from_value(event).map(Event::StreamOnline),
+      "stream.offline" => from_value(event).map(Event::StreamOffline),
+      _ => from_value(event),
+    }
+  }
+
+  /// The EventSub subscription type the event came from, e.g. `channel.chat.message`.
+  pub fn subscription_type(&self) -> &'static str {
+    match self {
+      Event::ChatMessage(_) => "channel.chat.message",
+      Event::Raid(_) => "channel.raid",
+      Event::Follow(_) => "channel.follow",
+      Event::ChannelUpdate(_) => "channel.update",
+      Event::UserUpdate(_) => "user.update",
+      Event::PointsCustomRewardRedeem(_) => "channel.channel_points_custom_reward_redemption.add",
+      Event::AdBreakBegin(_) => "channel.ad_break.begin",
+      Event::Subscribe(_) => "channel.subscribe",
+      Event::SubscriptionEnd(_) => "channel.subscription.end",
+      Event::SubscriptionGift(_) => "channel.subscription.gift",
+      Event::SubscriptionMessage(_) => "channel.subscription.message",
+      Event::Cheer(_) => "channel.cheer",
+      Event::ChannelPointsAutoRewardRedeem(_) => {
+        "channel.channel_points_automatic_reward_redemption.add"
+      }
+      Event::PollBegin(_) => "channel.poll.begin",
+      Event::PollProgress(_) => "channel.poll.progress",
+      Event::PollEnd(_) => "channel.poll.end",
+      Event::PredictionBegin(_) => "channel.prediction.begin",
+      Event::PredictionProgress(_) => "channel.prediction.progress",
+      Event::PredictionLock(_) => "channel.prediction.lock",
+      Event::PredictionEnd(_) => "channel.prediction.end",
+      Event::HypeTrainBegin(_) => "channel.hype_train.begin",
+      Event::HypeTrainProgress(_) => "channel.hype_train.progress",
+      Event::HypeTrainEnd(_) => "channel.hype_train.end",
+      Event::AutoModMessageUpdate(_) => "automod.message.update",
+      Event::AutoModMessageHold(_) => "automod.message.hold",
+      Event::AutoModSettingsUpdate(_) => "automod.settings.update",
+      Event::AutoModTermsUpdate(_) => "automod.terms.update",
+      Event::ShieldModeBegin(_) => "channel.shield_mode.begin",
+      Event::ShieldModeEnd(_) => "channel.shield_mode.end",
+      Event::CharityDonation(_) => "channel.charity_campaign.donate",
+      Event::UnbanRequestResolve(_) => "channel.unban_request.resolve",
+      Event::UnbanRequestCreate(_) => "channel.unban_request.create",
+      Event::CharityCampaignStart(_) => "channel.charity_campaign.start",
+      Event::CharityCampaignStop(_) => "channel.charity_campaign.stop",
+      Event::CharityCampaignProgress(_) => "channel.charity_campaign.progress",
+      Event::GoalBegin(_) => "channel.goal.begin",
+      Event::GoalProgress(_) => "channel.goal.progress",
+      Event::GoalEnd(_) => "channel.goal.end",
+      Event::StreamOnline(_) => "stream.online",
+      Event::StreamOffline(_) => "stream.offline",
+    }
+  }
+
+  /// The channel the event happened in, events from every broadcaster arrive on the same
+  /// receiver so this tells them apart. For raids this is the channel being raided, and for
+  /// user updates it's the user that changed.
+  pub fn broadcaster_id(&self) -> Option<&str> {
+    let broadcaster = match self {
+      Event::ChatMessage(data) => &data.broadcaster_user,
+      Event::Raid(data) => return Some(&data.to_broadcaster.id),
+      Event::Follow(data) => &data.broadcaster,
+      Event::ChannelUpdate(data) => &data.broadcaster,
+      Event::UserUpdate(data) => return Some(&data.user.id),
+      Event::PointsCustomRewardRedeem(data) => &data.broadcaster,
+      Event::AdBreakBegin(data) => &data.broadcast_user,
+      Event::Subscribe(data) => &data.braodcaster,
+      Event::SubscriptionEnd(data) => &data.broadcaster,
+      Event::SubscriptionGift(data) => &data.broadcaster,
+      Event::SubscriptionMessage(data) => &data.broadcaster,
+      Event::Cheer(data) => &data.broadcaster,
+      Event::ChannelPointsAutoRewardRedeem(data) => data.broadcaster.as_ref()?,
+      Event::PollBegin(data) => &data.broadcaster,
+      Event::PollProgress(data) => &data.broadcaster,
+      Event::PollEnd(data) => &data.broadcaster,
+      Event::PredictionBegin(data) => &data.broadcaster,
+      Event::PredictionProgress(data) => &data.broadcaster,
+      Event::PredictionLock(data) => &data.broadcaster,
+      Event::PredictionEnd(data) => &data.broadcaster,
+      Event::HypeTrainBegin(data) => &data.broadcaster,
+      Event::HypeTrainProgress(data) => &data.broadcaster,
+      Event::HypeTrainEnd(data) => &data.broadcaster,
+      Event::AutoModMessageUpdate(data) => &data.broadcaster,
+      Event::AutoModMessageHold(data) => &data.broadcaster,
+      Event::AutoModSettingsUpdate(data) => &data.broadcaster,
+      Event::AutoModTermsUpdate(data) => &data.broadcaster,
+      Event::ShieldModeBegin(data) => &data.broadcaster,
+      Event::ShieldModeEnd(data) => &data.broadcaster,
+      Event::CharityDonation(data) => &data.broadcaster,
+      Event::UnbanRequestResolve(data) => &data.broadcaster,
+      Event::UnbanRequestCreate(data) => &data.broadcaster,
+      Event::CharityCampaignStart(data) => &data.broadcaster,
+      Event::CharityCampaignStop(data) => &data.broadcaster,
+      Event::CharityCampaignProgress(data) => &data.broadcaster,
+      Event::GoalBegin(data) | Event::GoalProgress(data) | Event::GoalEnd(data) => {
+        &data.broadcaster
+      }
+      Event::StreamOnline(data) => &data.broadcaster,
+      Event::StreamOffline(data) => &data.broadcaster,
+    };
+
+    Some(&broadcaster.id)
+  }
+}
+
 impl GenericMessage {
   pub fn event_type(&self) -> EventMessageType {
     EventMessageType::from_string(&self.metadata.message_type)
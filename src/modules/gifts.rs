@@ -0,0 +1,217 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::{BroadcasterUser, Event, GiftData, SubscribeData, User};
+
+/// Gift subs rolled up into one, instead of a subscribe event per recipient.
+#[derive(Clone, Debug)]
+pub struct CommunityGift {
+  /// `None` when the gift was anonymous.
+  pub gifter: Option<User>,
+  pub broadcaster: BroadcasterUser,
+  pub count: u32,
+  pub tier: String,
+  pub recipients: Vec<User>,
+}
+
+impl CommunityGift {
+  pub fn is_anonymous(&self) -> bool {
+    self.gifter.is_none()
+  }
+}
+
+#[derive(Debug)]
+struct PendingGift {
+  gift: CommunityGift,
+  received_at: Instant,
+}
+
+/// Matches `channel.subscription.gift` events up with the gifted `channel.subscribe` events
+/// that follow them.
+///
+/// Twitch doesn't promise which arrives first, so gifted subs without a gift to claim them
+/// are held for the window as well.
+#[derive(Debug)]
+pub struct GiftTracker {
+  window: Duration,
+  pending: VecDeque<PendingGift>,
+  unclaimed: VecDeque<(SubscribeData, Instant)>,
+}
+
+impl GiftTracker {
+  pub fn new(window: Duration) -> GiftTracker {
+    GiftTracker {
+      window,
+      pending: VecDeque::new(),
+      unclaimed: VecDeque::new(),
+    }
+  }
+
+  /// Keeps track of gift and subscribe events, returning the community gift once every
+  /// recipient has arrived.
+  pub fn update(&mut self, event: &Event) -> Option<CommunityGift> {
+    self.forget_unclaimed();
+
+    match event {
+      Event::SubscriptionGift(gift) => self.gifted(gift),
+      Event::Subscribe(sub) if sub.is_gift => self.recipient(sub),
+      _ => None,
+    }
+  }
+
+  /// Community gifts whose window passed before every recipient arrived, with the
+  /// recipients that did.
+  pub fn expired(&mut self) -> Vec<CommunityGift> {
+    let now = Instant::now();
+    let mut expired = Vec::new();
+    while let Some(pending) = self.pending.front() {
+      if now.duration_since(pending.received_at) < self.window {
+        break;
+      }
+      expired.extend(self.pending.pop_front().map(|pending| pending.gift));
+    }
+    expired
+  }
+
+  /// How many community gifts are still waiting on recipients.
+  pub fn pending(&self) -> usize {
+    self.pending.len()
+  }
+
+  fn gifted(&mut self, gift: &GiftData) -> Option<CommunityGift> {
+    let mut community_gift = CommunityGift {
+      gifter: gift.user.clone().filter(|_| !gift.is_anonymous),
+      broadcaster: gift.broadcaster.clone(),
+      count: gift.total,
+      tier: gift.tier.to_owned(),
+      recipients: Vec::new(),
+    };
+
+    let mut i = 0;
+    while i < self.unclaimed.len() && community_gift.recipients.len() < gift.total as usize {
+      let (sub, _) = &self.unclaimed[i];
+      if sub.braodcaster.id == gift.broadcaster.id && sub.tier == gift.tier {
+        let (sub, _) = self.unclaimed.remove(i).unwrap();
+        community_gift.recipients.push(sub.user);
+      } else {
+        i += 1;
+      }
+    }
+
+    if community_gift.recipients.len() >= gift.total as usize {
+      return Some(community_gift);
+    }
+
+    self.pending.push_back(PendingGift {
+      gift: community_gift,
+      received_at: Instant::now(),
+    });
+    None
+  }
+
+  fn recipient(&mut self, sub: &SubscribeData) -> Option<CommunityGift> {
+    let Some(i) = self.pending.iter().position(|pending| {
+      pending.gift.broadcaster.id == sub.braodcaster.id && pending.gift.tier == sub.tier
+    }) else {
+      self.unclaimed.push_back((sub.clone(), Instant::now()));
+      return None;
+    };
+
+    let gift = &mut self.pending[i].gift;
+    gift.recipients.push(sub.user.clone());
+    if gift.recipients.len() < gift.count as usize {
+      return None;
+    }
+    self.pending.remove(i).map(|pending| pending.gift)
+  }
+
+  fn forget_unclaimed(&mut self) {
+    let now = Instant::now();
+    while let Some((_, received_at)) = self.unclaimed.front() {
+      if now.duration_since(*received_at) < self.window {
+        break;
+      }
+      self.unclaimed.pop_front();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  fn gift(gifter: Option<&str>, total: u32) -> Event {
+    let event = json!({
+      "user_id": gifter.map(|_| "1"),
+      "user_login": gifter.map(str::to_lowercase),
+      "user_name": gifter,
+      "broadcaster_user_id": "1337",
+      "broadcaster_user_login": "cool_user",
+      "broadcaster_user_name": "Cool_User",
+      "total": total,
+      "tier": "1000",
+      "cumulative_total": null,
+      "is_anonymous": gifter.is_none(),
+    });
+    Event::from_json(Some("channel.subscription.gift"), event).unwrap()
+  }
+
+  fn gifted_sub(user_id: &str) -> Event {
+    let event = json!({
+      "user_id": user_id,
+      "user_login": "viewer",
+      "user_name": "Viewer",
+      "broadcaster_user_id": "1337",
+      "broadcaster_user_login": "cool_user",
+      "broadcaster_user_name": "Cool_User",
+      "tier": "1000",
+      "is_gift": true,
+    });
+    Event::from_json(Some("channel.subscribe"), event).unwrap()
+  }
+
+  #[test]
+  fn rolls_up_gifted_subs() {
+    let mut tracker = GiftTracker::new(Duration::from_secs(30));
+    // A recipient arriving before the gift event is still counted
+    assert!(tracker.update(&gifted_sub("2")).is_none());
+    assert!(tracker.update(&gift(Some("Cool_Viewer"), 2)).is_none());
+    assert_eq!(tracker.pending(), 1);
+
+    let community_gift = tracker.update(&gifted_sub("3")).unwrap();
+    assert_eq!(community_gift.count, 2);
+    assert_eq!(community_gift.recipients.len(), 2);
+    assert_eq!(community_gift.gifter.unwrap().name, "Cool_Viewer");
+    assert_eq!(tracker.pending(), 0);
+  }
+
+  #[test]
+  fn handles_anonymous_gifts() {
+    let mut tracker = GiftTracker::new(Duration::from_secs(30));
+    assert!(tracker.update(&gift(None, 2)).is_none());
+    assert!(tracker.update(&gifted_sub("2")).is_none());
+
+    let community_gift = tracker.update(&gifted_sub("3")).unwrap();
+    assert!(community_gift.is_anonymous());
+    assert_eq!(community_gift.recipients.len(), 2);
+  }
+
+  #[test]
+  fn expires_gifts_missing_recipients() {
+    let mut tracker = GiftTracker::new(Duration::from_secs(30));
+    tracker.update(&gift(Some("Cool_Viewer"), 2));
+    tracker.update(&gifted_sub("2"));
+    // Still inside the window
+    assert!(tracker.expired().is_empty());
+
+    let mut tracker = GiftTracker::new(Duration::ZERO);
+    tracker.update(&gift(Some("Cool_Viewer"), 2));
+    let expired = tracker.expired();
+    assert_eq!(expired.len(), 1);
+    assert!(expired[0].recipients.is_empty());
+    assert_eq!(tracker.pending(), 0);
+    assert!(tracker.expired().is_empty());
+  }
+}
@@ -0,0 +1,2420 @@
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+
+#[cfg(all(
+  any(
+    feature = "chat",
+    feature = "moderation",
+    feature = "channel-points",
+    feature = "helix-full"
+  ),
+  not(target_arch = "wasm32")
+))]
+use crate::BroadcasterId;
+#[cfg(all(feature = "moderation", not(target_arch = "wasm32")))]
+use crate::MessageId;
+#[cfg(all(feature = "channel-points", not(target_arch = "wasm32")))]
+use crate::RewardId;
+#[cfg(all(
+  any(feature = "chat", feature = "moderation", feature = "helix-full"),
+  not(target_arch = "wasm32")
+))]
+use crate::UserId;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+  modules::{
+    consts::*,
+    twitch_http::{RequestBuilder, TwitchApiResponse, TwitchHttpRequest},
+  },
+  EventSubscription, SubscriptionId, TwitchApi,
+};
+use crate::{
+  CharityAmount, Condition, Deserialise, EventSubError, Reward, Serialise, TopContributions, User,
+};
+
+#[derive(Serialise, Deserialise, Debug, Clone, Default)]
+pub struct Pagination {
+  pub cursor: Option<String>,
+}
+
+/// The `data` envelope that Helix wraps every response in.
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct HelixData<T> {
+  pub data: Vec<T>,
+  pub pagination: Option<Pagination>,
+  pub total: Option<u32>,
+}
+
+impl<T> HelixData<T> {
+  /// Cursor to pass as `after` to get the next page, if there is one.
+  pub fn cursor(&self) -> Option<String> {
+    self
+      .pagination
+      .as_ref()
+      .and_then(|pagination| pagination.cursor.clone())
+  }
+
+  /// Takes the single entry that create and update endpoints respond with.
+  pub fn into_first(self) -> Result<T, EventSubError> {
+    self
+      .data
+      .into_iter()
+      .next()
+      .ok_or(EventSubError::ParseError(
+        "Response contained no data".to_owned(),
+      ))
+  }
+}
+
+/// Why Twitch refused to send a chat message.
+#[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
+pub struct DropReason {
+  pub code: String,
+  pub message: String,
+}
+
+impl From<DropReason> for EventSubError {
+  fn from(reason: DropReason) -> EventSubError {
+    EventSubError::MessageDropped {
+      code: reason.code,
+      message: reason.message,
+    }
+  }
+}
+
+/// Twitch's answer to a sent chat message.
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct SentMessage {
+  pub message_id: String,
+  pub is_sent: bool,
+  pub drop_reason: Option<DropReason>,
+}
+
+impl SentMessage {
+  /// The message's id, or why Twitch didn't send it.
+  pub fn into_result(self) -> Result<String, DropReason> {
+    match (self.is_sent, self.drop_reason) {
+      (true, _) => Ok(self.message_id),
+      (false, reason) => Err(reason.unwrap_or(DropReason {
+        code: "unknown".to_owned(),
+        message: "Twitch didn't say why".to_owned(),
+      })),
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct BlockedTerm {
+  pub broadcaster_id: String,
+  pub moderator_id: String,
+  pub id: String,
+  pub text: String,
+  pub created_at: String,
+  pub updated_at: String,
+  pub expires_at: Option<String>,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct AddBlockedTermRequest {
+  pub text: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct AutoModSettings {
+  pub broadcaster_id: String,
+  pub moderator_id: String,
+  pub overall_level: Option<u32>,
+  pub disability: u32,
+  pub aggression: u32,
+  pub sexuality_sex_or_gender: u32,
+  pub misogyny: u32,
+  pub bullying: u32,
+  pub swearing: u32,
+  pub race_ethnicity_or_religion: u32,
+  pub sex_based_terms: u32,
+}
+
+/// Either set `overall_level` or the individual levels, Twitch rejects requests with both.
+///
+/// The update overwrites every setting, so any level left as `None` is reset to its default.
+#[derive(Serialise, Deserialise, Debug, Clone, Default)]
+pub struct AutoModSettingsRequest {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub overall_level: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub disability: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub aggression: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub sexuality_sex_or_gender: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub misogyny: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub bullying: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub swearing: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub race_ethnicity_or_religion: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub sex_based_terms: Option<u32>,
+}
+
+impl AutoModSettingsRequest {
+  pub fn overall_level(level: u32) -> AutoModSettingsRequest {
+    AutoModSettingsRequest {
+      overall_level: Some(level),
+      ..Default::default()
+    }
+  }
+}
+
+impl From<AutoModSettings> for AutoModSettingsRequest {
+  fn from(settings: AutoModSettings) -> AutoModSettingsRequest {
+    match settings.overall_level {
+      Some(level) => AutoModSettingsRequest::overall_level(level),
+      None => AutoModSettingsRequest {
+        overall_level: None,
+        disability: Some(settings.disability),
+        aggression: Some(settings.aggression),
+        sexuality_sex_or_gender: Some(settings.sexuality_sex_or_gender),
+        misogyny: Some(settings.misogyny),
+        bullying: Some(settings.bullying),
+        swearing: Some(settings.swearing),
+        race_ethnicity_or_religion: Some(settings.race_ethnicity_or_religion),
+        sex_based_terms: Some(settings.sex_based_terms),
+      },
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct AutoModCheckMessage {
+  pub msg_id: String,
+  pub msg_text: String,
+}
+
+impl AutoModCheckMessage {
+  pub fn new<S: Into<String>, T: Into<String>>(msg_id: S, msg_text: T) -> AutoModCheckMessage {
+    AutoModCheckMessage {
+      msg_id: msg_id.into(),
+      msg_text: msg_text.into(),
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct AutoModCheckRequest {
+  pub data: Vec<AutoModCheckMessage>,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct AutoModStatus {
+  pub msg_id: String,
+  pub is_permitted: bool,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AutoModAction {
+  Allow,
+  Deny,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct ManageHeldMessageRequest {
+  pub user_id: String,
+  pub msg_id: String,
+  pub action: AutoModAction,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct WarnRequestData {
+  pub user_id: String,
+  pub reason: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct SendWarnRequest {
+  pub data: WarnRequestData,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct UserWarning {
+  pub broadcaster_id: String,
+  pub user_id: String,
+  pub moderator_id: String,
+  pub reason: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct RewardImage {
+  pub url_1x: String,
+  pub url_2x: String,
+  pub url_4x: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct MaxPerStreamSetting {
+  pub is_enabled: bool,
+  pub max_per_stream: u32,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct MaxPerUserPerStreamSetting {
+  pub is_enabled: bool,
+  pub max_per_user_per_stream: u32,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct GlobalCooldownSetting {
+  pub is_enabled: bool,
+  pub global_cooldown_seconds: u32,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct CustomReward {
+  pub broadcaster_id: String,
+  pub broadcaster_login: String,
+  pub broadcaster_name: String,
+  pub id: String,
+  pub title: String,
+  pub prompt: String,
+  pub cost: u32,
+  pub image: Option<RewardImage>,
+  pub default_image: RewardImage,
+  #[serde(rename = "background_color")]
+  pub background_colour: String,
+  pub is_enabled: bool,
+  pub is_user_input_required: bool,
+  pub max_per_stream_setting: MaxPerStreamSetting,
+  pub max_per_user_per_stream_setting: MaxPerUserPerStreamSetting,
+  pub global_cooldown_setting: GlobalCooldownSetting,
+  pub is_paused: bool,
+  pub is_in_stock: bool,
+  pub should_redemptions_skip_request_queue: bool,
+  pub redemptions_redeemed_current_stream: Option<u32>,
+  pub cooldown_expires_at: Option<String>,
+}
+
+/// Body for creating or updating a custom reward, only the fields that are set get sent.
+///
+/// Creating a reward requires at least a title and cost.
+#[derive(Serialise, Deserialise, Debug, Clone, Default)]
+pub struct CustomRewardBuilder {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub cost: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub prompt: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_enabled: Option<bool>,
+  #[serde(rename = "background_color", skip_serializing_if = "Option::is_none")]
+  pub background_colour: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_user_input_required: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_max_per_stream_enabled: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub max_per_stream: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_max_per_user_per_stream_enabled: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub max_per_user_per_stream: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_global_cooldown_enabled: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub global_cooldown_seconds: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_paused: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub should_redemptions_skip_request_queue: Option<bool>,
+}
+
+impl CustomRewardBuilder {
+  pub fn new() -> CustomRewardBuilder {
+    CustomRewardBuilder {
+      ..Default::default()
+    }
+  }
+
+  pub fn title<S: Into<String>>(mut self, title: S) -> CustomRewardBuilder {
+    self.title = Some(title.into());
+    self
+  }
+
+  pub fn cost(mut self, cost: u32) -> CustomRewardBuilder {
+    self.cost = Some(cost);
+    self
+  }
+
+  pub fn prompt<S: Into<String>>(mut self, prompt: S) -> CustomRewardBuilder {
+    self.prompt = Some(prompt.into());
+    self
+  }
+
+  pub fn enabled(mut self, is_enabled: bool) -> CustomRewardBuilder {
+    self.is_enabled = Some(is_enabled);
+    self
+  }
+
+  /// Hex colour such as `#9147FF`.
+  pub fn background_colour<S: Into<String>>(mut self, colour: S) -> CustomRewardBuilder {
+    self.background_colour = Some(colour.into());
+    self
+  }
+
+  pub fn user_input_required(mut self, is_required: bool) -> CustomRewardBuilder {
+    self.is_user_input_required = Some(is_required);
+    self
+  }
+
+  /// `None` removes the limit.
+  pub fn max_per_stream(mut self, max: Option<u32>) -> CustomRewardBuilder {
+    self.is_max_per_stream_enabled = Some(max.is_some());
+    self.max_per_stream = max;
+    self
+  }
+
+  /// `None` removes the limit.
+  pub fn max_per_user_per_stream(mut self, max: Option<u32>) -> CustomRewardBuilder {
+    self.is_max_per_user_per_stream_enabled = Some(max.is_some());
+    self.max_per_user_per_stream = max;
+    self
+  }
+
+  /// `None` removes the cooldown.
+  pub fn global_cooldown(mut self, seconds: Option<u32>) -> CustomRewardBuilder {
+    self.is_global_cooldown_enabled = Some(seconds.is_some());
+    self.global_cooldown_seconds = seconds;
+    self
+  }
+
+  /// Only applies when updating a reward, new rewards are never paused.
+  pub fn paused(mut self, is_paused: bool) -> CustomRewardBuilder {
+    self.is_paused = Some(is_paused);
+    self
+  }
+
+  pub fn skip_request_queue(mut self, should_skip: bool) -> CustomRewardBuilder {
+    self.should_redemptions_skip_request_queue = Some(should_skip);
+    self
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RewardRedemptionStatus {
+  // EventSub sends these in lowercase, Helix in uppercase
+  #[serde(alias = "unfulfilled")]
+  Unfulfilled,
+  #[serde(alias = "fulfilled")]
+  Fulfilled,
+  #[serde(alias = "canceled")]
+  Canceled,
+}
+
+impl RewardRedemptionStatus {
+  pub fn as_str(&self) -> &str {
+    match self {
+      RewardRedemptionStatus::Unfulfilled => "UNFULFILLED",
+      RewardRedemptionStatus::Fulfilled => "FULFILLED",
+      RewardRedemptionStatus::Canceled => "CANCELED",
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct CustomRewardRedemption {
+  pub broadcaster_id: String,
+  pub broadcaster_login: String,
+  pub broadcaster_name: String,
+  pub id: String,
+  #[serde(flatten)]
+  pub user: User,
+  pub user_input: String,
+  pub status: RewardRedemptionStatus,
+  pub redeemed_at: String,
+  pub reward: Reward,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct UpdateRedemptionStatusRequest {
+  pub status: RewardRedemptionStatus,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct ScheduleCategory {
+  pub id: String,
+  pub name: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct ScheduleSegment {
+  pub id: String,
+  pub start_time: String,
+  pub end_time: String,
+  pub title: String,
+  pub canceled_until: Option<String>,
+  pub category: Option<ScheduleCategory>,
+  pub is_recurring: bool,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct ScheduleVacation {
+  pub start_time: String,
+  pub end_time: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct StreamSchedule {
+  pub segments: Option<Vec<ScheduleSegment>>,
+  pub broadcaster_id: String,
+  pub broadcaster_name: String,
+  pub broadcaster_login: String,
+  pub vacation: Option<ScheduleVacation>,
+}
+
+/// Unlike most endpoints the schedule is returned as a single object rather than a list.
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct StreamScheduleResponse {
+  pub data: StreamSchedule,
+  pub pagination: Option<Pagination>,
+}
+
+/// Body for creating or updating a schedule segment, only the fields that are set get sent.
+///
+/// Creating a segment requires a start time, timezone and duration.
+#[derive(Serialise, Deserialise, Debug, Clone, Default)]
+pub struct ScheduleSegmentBuilder {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub start_time: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub timezone: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub duration: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_recurring: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub category_id: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_canceled: Option<bool>,
+}
+
+impl ScheduleSegmentBuilder {
+  pub fn new() -> ScheduleSegmentBuilder {
+    ScheduleSegmentBuilder {
+      ..Default::default()
+    }
+  }
+
+  /// RFC3339 timestamp such as `2024-07-01T18:00:00Z`.
+  pub fn start_time<S: Into<String>>(mut self, start_time: S) -> ScheduleSegmentBuilder {
+    self.start_time = Some(start_time.into());
+    self
+  }
+
+  /// IANA timezone such as `Australia/Sydney`.
+  pub fn timezone<S: Into<String>>(mut self, timezone: S) -> ScheduleSegmentBuilder {
+    self.timezone = Some(timezone.into());
+    self
+  }
+
+  pub fn duration_minutes(mut self, minutes: u32) -> ScheduleSegmentBuilder {
+    self.duration = Some(minutes.to_string());
+    self
+  }
+
+  /// Only applies when creating a segment.
+  pub fn recurring(mut self, is_recurring: bool) -> ScheduleSegmentBuilder {
+    self.is_recurring = Some(is_recurring);
+    self
+  }
+
+  pub fn category_id<S: Into<String>>(mut self, category_id: S) -> ScheduleSegmentBuilder {
+    self.category_id = Some(category_id.into());
+    self
+  }
+
+  pub fn title<S: Into<String>>(mut self, title: S) -> ScheduleSegmentBuilder {
+    self.title = Some(title.into());
+    self
+  }
+
+  /// Only applies when updating a segment.
+  pub fn canceled(mut self, is_canceled: bool) -> ScheduleSegmentBuilder {
+    self.is_canceled = Some(is_canceled);
+    self
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct VacationSettings {
+  pub start_time: String,
+  pub end_time: String,
+  pub timezone: String,
+}
+
+impl VacationSettings {
+  pub fn new<S: Into<String>, T: Into<String>, V: Into<String>>(
+    start_time: S,
+    end_time: T,
+    timezone: V,
+  ) -> VacationSettings {
+    VacationSettings {
+      start_time: start_time.into(),
+      end_time: end_time.into(),
+      timezone: timezone.into(),
+    }
+  }
+}
+
+/// Which videos to get, period and type filters only apply to user and game queries.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VideoQuery {
+  Ids(Vec<String>),
+  User(String),
+  Game(String),
+}
+
+// Twitch rejects the filters and paging when videos are asked for by id
+#[cfg(all(feature = "helix-full", not(target_arch = "wasm32")))]
+fn videos_url(
+  query: VideoQuery,
+  video_type: VideoType,
+  period: VideoPeriod,
+  after: Option<String>,
+) -> String {
+  let url = match query {
+    VideoQuery::Ids(ids) => {
+      return RequestBuilder::new()
+        .add_key_values("id", ids)
+        .build(TWITCH_VIDEOS_URL)
+    }
+    VideoQuery::User(user_id) => RequestBuilder::new().add_key_value("user_id", user_id),
+    VideoQuery::Game(game_id) => RequestBuilder::new().add_key_value("game_id", game_id),
+  };
+
+  url
+    .add_key_value("type", video_type.as_str())
+    .add_key_value("period", period.as_str())
+    .add_key_value("first", "100")
+    .add_optional_key_value("after", after)
+    .build(TWITCH_VIDEOS_URL)
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoType {
+  All,
+  Archive,
+  Highlight,
+  Upload,
+}
+
+impl VideoType {
+  pub fn as_str(&self) -> &str {
+    match self {
+      VideoType::All => "all",
+      VideoType::Archive => "archive",
+      VideoType::Highlight => "highlight",
+      VideoType::Upload => "upload",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VideoPeriod {
+  All,
+  Day,
+  Week,
+  Month,
+}
+
+impl VideoPeriod {
+  pub fn as_str(&self) -> &str {
+    match self {
+      VideoPeriod::All => "all",
+      VideoPeriod::Day => "day",
+      VideoPeriod::Week => "week",
+      VideoPeriod::Month => "month",
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct MutedSegment {
+  pub duration: u32,
+  pub offset: u32,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct Video {
+  pub id: String,
+  pub stream_id: Option<String>,
+  #[serde(flatten)]
+  pub user: User,
+  pub title: String,
+  pub description: String,
+  pub created_at: String,
+  pub published_at: String,
+  pub url: String,
+  pub thumbnail_url: String,
+  pub viewable: String,
+  pub view_count: u32,
+  pub language: String,
+  #[serde(rename = "type")]
+  pub kind: VideoType,
+  /// Twitch formats this as `1h2m3s`, see [`Video::parsed_duration`].
+  pub duration: String,
+  pub muted_segments: Option<Vec<MutedSegment>>,
+}
+
+impl Video {
+  pub fn parsed_duration(&self) -> Option<Duration> {
+    parse_twitch_duration(&self.duration)
+  }
+}
+
+/// Parses durations in Twitch's `1h2m3s` format, any of the units can be missing.
+pub fn parse_twitch_duration(duration: &str) -> Option<Duration> {
+  let mut total_secs = 0;
+  let mut number = String::new();
+
+  for c in duration.chars() {
+    if c.is_ascii_digit() {
+      number.push(c);
+      continue;
+    }
+
+    let value = number.parse::<u64>().ok()?;
+    number.clear();
+    total_secs += match c {
+      'h' => value * 60 * 60,
+      'm' => value * 60,
+      's' => value,
+      _ => return None,
+    };
+  }
+
+  if !number.is_empty() {
+    return None;
+  }
+
+  Some(Duration::from_secs(total_secs))
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct ShieldModeStatus {
+  pub is_active: bool,
+  pub moderator_id: String,
+  pub moderator_login: String,
+  pub moderator_name: String,
+  pub last_activated_at: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct UpdateShieldModeRequest {
+  pub is_active: bool,
+}
+
+/// A channel's chat modes. Durations are `None` while their mode is off, `moderator_id` and
+/// the chat delay are only there when read by a moderator.
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct ChatSettings {
+  pub broadcaster_id: String,
+  pub emote_mode: bool,
+  pub follower_mode: bool,
+  /// Minutes a user has to have followed for, 0 for any follower.
+  pub follower_mode_duration: Option<u32>,
+  pub moderator_id: Option<String>,
+  pub non_moderator_chat_delay: Option<bool>,
+  pub non_moderator_chat_delay_duration: Option<u32>,
+  pub slow_mode: bool,
+  pub slow_mode_wait_time: Option<u32>,
+  pub subscriber_mode: bool,
+  pub unique_chat_mode: bool,
+}
+
+/// The chat modes to change, anything left as `None` stays as it is.
+#[derive(Serialise, Deserialise, Debug, Clone, Default)]
+pub struct ChatSettingsUpdate {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub emote_mode: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub follower_mode: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub follower_mode_duration: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub non_moderator_chat_delay: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub non_moderator_chat_delay_duration: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub slow_mode: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub slow_mode_wait_time: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub subscriber_mode: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub unique_chat_mode: Option<bool>,
+}
+
+impl ChatSettingsUpdate {
+  pub fn new() -> ChatSettingsUpdate {
+    ChatSettingsUpdate {
+      ..Default::default()
+    }
+  }
+
+  pub fn emote_mode(mut self, on: bool) -> ChatSettingsUpdate {
+    self.emote_mode = Some(on);
+    self
+  }
+
+  /// Minutes a user has to have followed for before chatting, `None` turns it off.
+  pub fn follower_mode(mut self, minutes: Option<u32>) -> ChatSettingsUpdate {
+    self.follower_mode = Some(minutes.is_some());
+    self.follower_mode_duration = minutes;
+    self
+  }
+
+  /// Holds messages from non-moderators for 2, 4 or 6 seconds, `None` turns it off.
+  pub fn non_moderator_chat_delay(mut self, seconds: Option<u32>) -> ChatSettingsUpdate {
+    self.non_moderator_chat_delay = Some(seconds.is_some());
+    self.non_moderator_chat_delay_duration = seconds;
+    self
+  }
+
+  /// Seconds between a user's messages, `None` turns it off.
+  pub fn slow_mode(mut self, seconds: Option<u32>) -> ChatSettingsUpdate {
+    self.slow_mode = Some(seconds.is_some());
+    self.slow_mode_wait_time = seconds;
+    self
+  }
+
+  pub fn subscriber_mode(mut self, on: bool) -> ChatSettingsUpdate {
+    self.subscriber_mode = Some(on);
+    self
+  }
+
+  pub fn unique_chat_mode(mut self, on: bool) -> ChatSettingsUpdate {
+    self.unique_chat_mode = Some(on);
+    self
+  }
+}
+
+/// The channel details to change, anything left as `None` stays as it is.
+#[derive(Serialise, Deserialise, Debug, Clone, Default)]
+pub struct ChannelInformationUpdate {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  /// The category's id, an empty string clears it.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub game_id: Option<String>,
+  /// An ISO 639-1 code such as `en`, or `other`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub broadcaster_language: Option<String>,
+  /// Seconds the stream is delayed by, partners only.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub delay: Option<u32>,
+  /// Replaces every tag, an empty list removes them.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tags: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_branded_content: Option<bool>,
+}
+
+impl ChannelInformationUpdate {
+  pub fn new() -> ChannelInformationUpdate {
+    ChannelInformationUpdate {
+      ..Default::default()
+    }
+  }
+
+  pub fn title<S: Into<String>>(mut self, title: S) -> ChannelInformationUpdate {
+    self.title = Some(title.into());
+    self
+  }
+
+  pub fn game_id<S: Into<String>>(mut self, game_id: S) -> ChannelInformationUpdate {
+    self.game_id = Some(game_id.into());
+    self
+  }
+
+  pub fn broadcaster_language<S: Into<String>>(mut self, language: S) -> ChannelInformationUpdate {
+    self.broadcaster_language = Some(language.into());
+    self
+  }
+
+  pub fn delay(mut self, seconds: u32) -> ChannelInformationUpdate {
+    self.delay = Some(seconds);
+    self
+  }
+
+  pub fn tags(mut self, tags: Vec<String>) -> ChannelInformationUpdate {
+    self.tags = Some(tags);
+    self
+  }
+
+  pub fn branded_content(mut self, is_branded: bool) -> ChannelInformationUpdate {
+    self.is_branded_content = Some(is_branded);
+    self
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct CharityCampaign {
+  pub id: String,
+  pub broadcaster_id: String,
+  pub broadcaster_login: String,
+  pub broadcaster_name: String,
+  pub charity_name: String,
+  pub charity_description: String,
+  pub charity_logo: String,
+  pub charity_website: String,
+  pub current_amount: CharityAmount,
+  pub target_amount: Option<CharityAmount>,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct CharityDonation {
+  pub id: String,
+  pub campaign_id: String,
+  #[serde(flatten)]
+  pub user: User,
+  pub amount: CharityAmount,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct BlockedUser {
+  pub user_id: String,
+  pub user_login: String,
+  pub display_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockReason {
+  Harassment,
+  Spam,
+  Other,
+}
+
+impl BlockReason {
+  pub fn as_str(&self) -> &str {
+    match self {
+      BlockReason::Harassment => "harassment",
+      BlockReason::Spam => "spam",
+      BlockReason::Other => "other",
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnbanRequestStatus {
+  Pending,
+  Approved,
+  Denied,
+  Acknowledged,
+  Canceled,
+}
+
+impl UnbanRequestStatus {
+  pub fn as_str(&self) -> &str {
+    match self {
+      UnbanRequestStatus::Pending => "pending",
+      UnbanRequestStatus::Approved => "approved",
+      UnbanRequestStatus::Denied => "denied",
+      UnbanRequestStatus::Acknowledged => "acknowledged",
+      UnbanRequestStatus::Canceled => "canceled",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnbanRequestResolution {
+  Approve,
+  Deny,
+}
+
+impl UnbanRequestResolution {
+  pub fn as_str(&self) -> &str {
+    match self {
+      UnbanRequestResolution::Approve => "approved",
+      UnbanRequestResolution::Deny => "denied",
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct UnbanRequest {
+  pub id: String,
+  pub broadcaster_id: String,
+  pub broadcaster_login: String,
+  pub broadcaster_name: String,
+  pub moderator_id: Option<String>,
+  pub moderator_login: Option<String>,
+  pub moderator_name: Option<String>,
+  #[serde(flatten)]
+  pub user: User,
+  pub text: String,
+  pub status: UnbanRequestStatus,
+  pub created_at: String,
+  pub resolved_at: Option<String>,
+  pub resolution_text: Option<String>,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct ModeratedChannel {
+  pub broadcaster_id: String,
+  pub broadcaster_login: String,
+  pub broadcaster_name: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct FollowedChannel {
+  pub broadcaster_id: String,
+  pub broadcaster_login: String,
+  pub broadcaster_name: String,
+  pub followed_at: String,
+}
+
+/// A user from the get users endpoint.
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct UserInfo {
+  pub id: String,
+  pub login: String,
+  pub display_name: String,
+  #[serde(rename = "type")]
+  pub kind: String,
+  /// `partner`, `affiliate` or empty.
+  pub broadcaster_type: String,
+  pub description: String,
+  pub profile_image_url: String,
+  pub offline_image_url: String,
+  pub created_at: String,
+}
+
+/// A channel that is currently live.
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct StreamInfo {
+  pub id: String,
+  pub user_id: String,
+  pub user_login: String,
+  pub user_name: String,
+  pub game_id: String,
+  pub game_name: String,
+  pub title: String,
+  #[serde(default)]
+  pub tags: Vec<String>,
+  pub viewer_count: u32,
+  pub started_at: String,
+  pub language: String,
+  pub thumbnail_url: String,
+  pub is_mature: bool,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct HypeTrainContribution {
+  /// Id of the contributing user.
+  pub user: String,
+  #[serde(rename = "type")]
+  pub kind: String,
+  pub total: u32,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct HypeTrainEventData {
+  pub id: String,
+  pub broadcaster_id: String,
+  pub level: u32,
+  pub total: u32,
+  pub goal: u32,
+  pub top_contributions: Vec<HypeTrainContribution>,
+  pub last_contribution: HypeTrainContribution,
+  pub started_at: String,
+  pub expires_at: String,
+  pub cooldown_end_time: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct HypeTrainEvent {
+  pub id: String,
+  pub event_type: String,
+  pub event_timestamp: String,
+  pub version: String,
+  pub event_data: HypeTrainEventData,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct CurrentHypeTrain {
+  pub id: String,
+  pub broadcaster_user_id: String,
+  pub broadcaster_user_login: String,
+  pub broadcaster_user_name: String,
+  pub level: u32,
+  pub total: u32,
+  pub progress: u32,
+  pub goal: u32,
+  pub top_contributions: Vec<TopContributions>,
+  pub started_at: String,
+  pub expires_at: String,
+  #[serde(rename = "type")]
+  pub kind: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct HypeTrainRecord {
+  pub level: u32,
+  pub total: u32,
+  pub achieved_at: String,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct HypeTrainStatus {
+  /// `None` when there is no hype train running.
+  pub current: Option<CurrentHypeTrain>,
+  pub all_time_high: Option<HypeTrainRecord>,
+  pub shared_all_time_high: Option<HypeTrainRecord>,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSubSubscriptionStatus {
+  Enabled,
+  WebhookCallbackVerificationPending,
+  WebhookCallbackVerificationFailed,
+  NotificationFailuresExceeded,
+  AuthorizationRevoked,
+  ModeratorRemoved,
+  UserRemoved,
+  VersionRemoved,
+  BetaMaintenance,
+  WebsocketDisconnected,
+  WebsocketFailedPingPong,
+  WebsocketReceivedInboundTraffic,
+  WebsocketConnectionUnused,
+  WebsocketInternalError,
+  WebsocketNetworkTimeout,
+  WebsocketNetworkError,
+  WebsocketFailedToReconnect,
+  #[serde(other)]
+  Unknown,
+}
+
+impl EventSubSubscriptionStatus {
+  pub fn as_str(&self) -> &str {
+    match self {
+      EventSubSubscriptionStatus::Enabled => "enabled",
+      EventSubSubscriptionStatus::WebhookCallbackVerificationPending => {
+        "webhook_callback_verification_pending"
+      }
+      EventSubSubscriptionStatus::WebhookCallbackVerificationFailed => {
+        "webhook_callback_verification_failed"
+      }
+      EventSubSubscriptionStatus::NotificationFailuresExceeded => "notification_failures_exceeded",
+      EventSubSubscriptionStatus::AuthorizationRevoked => "authorization_revoked",
+      EventSubSubscriptionStatus::ModeratorRemoved => "moderator_removed",
+      EventSubSubscriptionStatus::UserRemoved => "user_removed",
+      EventSubSubscriptionStatus::VersionRemoved => "version_removed",
+      EventSubSubscriptionStatus::BetaMaintenance => "beta_maintenance",
+      EventSubSubscriptionStatus::WebsocketDisconnected => "websocket_disconnected",
+      EventSubSubscriptionStatus::WebsocketFailedPingPong => "websocket_failed_ping_pong",
+      EventSubSubscriptionStatus::WebsocketReceivedInboundTraffic => {
+        "websocket_received_inbound_traffic"
+      }
+      EventSubSubscriptionStatus::WebsocketConnectionUnused => "websocket_connection_unused",
+      EventSubSubscriptionStatus::WebsocketInternalError => "websocket_internal_error",
+      EventSubSubscriptionStatus::WebsocketNetworkTimeout => "websocket_network_timeout",
+      EventSubSubscriptionStatus::WebsocketNetworkError => "websocket_network_error",
+      EventSubSubscriptionStatus::WebsocketFailedToReconnect => "websocket_failed_to_reconnect",
+      EventSubSubscriptionStatus::Unknown => "",
+    }
+  }
+
+  /// Whether creating the subscription again could work after it was revoked with this
+  /// status. It can't once the user took back access, was removed or no longer moderates
+  /// the channel, or the version was removed.
+  pub fn can_resubscribe(&self) -> bool {
+    !matches!(
+      self,
+      EventSubSubscriptionStatus::AuthorizationRevoked
+        | EventSubSubscriptionStatus::ModeratorRemoved
+        | EventSubSubscriptionStatus::UserRemoved
+        | EventSubSubscriptionStatus::VersionRemoved
+    )
+  }
+}
+
+/// Twitch only allows filtering by one of these at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventSubSubscriptionFilter {
+  All,
+  Status(EventSubSubscriptionStatus),
+  Type(String),
+  UserId(String),
+  SubscriptionId(String),
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct EventSubTransport {
+  pub method: String,
+  pub callback: Option<String>,
+  pub session_id: Option<String>,
+  pub connected_at: Option<String>,
+  pub disconnected_at: Option<String>,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct EventSubSubscriptionInfo {
+  pub id: String,
+  pub status: EventSubSubscriptionStatus,
+  #[serde(rename = "type")]
+  pub kind: String,
+  pub version: String,
+  pub condition: Condition,
+  pub created_at: String,
+  pub transport: EventSubTransport,
+  pub cost: u32,
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct EventSubSubscriptions {
+  pub data: Vec<EventSubSubscriptionInfo>,
+  pub total: u32,
+  pub total_cost: u32,
+  pub max_total_cost: u32,
+  pub pagination: Option<Pagination>,
+}
+
+impl EventSubSubscriptions {
+  pub fn cursor(&self) -> Option<String> {
+    self.pagination.as_ref().and_then(|p| p.cursor.to_owned())
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct UserChatColour {
+  #[serde(flatten)]
+  pub user: User,
+  /// Hex colour, empty if the user has never set one.
+  #[serde(rename = "color")]
+  pub colour: String,
+}
+
+/// Named colours are available to everyone, hex colours require Turbo or Prime.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatColour {
+  Blue,
+  BlueViolet,
+  CadetBlue,
+  Chocolate,
+  Coral,
+  DodgerBlue,
+  Firebrick,
+  GoldenRod,
+  Green,
+  HotPink,
+  OrangeRed,
+  Red,
+  SeaGreen,
+  SpringGreen,
+  YellowGreen,
+  Hex(String),
+}
+
+impl ChatColour {
+  pub fn as_str(&self) -> &str {
+    match self {
+      ChatColour::Blue => "blue",
+      ChatColour::BlueViolet => "blue_violet",
+      ChatColour::CadetBlue => "cadet_blue",
+      ChatColour::Chocolate => "chocolate",
+      ChatColour::Coral => "coral",
+      ChatColour::DodgerBlue => "dodger_blue",
+      ChatColour::Firebrick => "firebrick",
+      ChatColour::GoldenRod => "golden_rod",
+      ChatColour::Green => "green",
+      ChatColour::HotPink => "hot_pink",
+      ChatColour::OrangeRed => "orange_red",
+      ChatColour::Red => "red",
+      ChatColour::SeaGreen => "sea_green",
+      ChatColour::SpringGreen => "spring_green",
+      ChatColour::YellowGreen => "yellow_green",
+      ChatColour::Hex(hex) => hex,
+    }
+  }
+}
+
+/// Colour of the border around an announcement, primary is the channel's accent colour.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnnouncementColour {
+  Primary,
+  Blue,
+  Green,
+  Orange,
+  Purple,
+}
+
+impl AnnouncementColour {
+  pub fn as_str(&self) -> &str {
+    match self {
+      AnnouncementColour::Primary => "primary",
+      AnnouncementColour::Blue => "blue",
+      AnnouncementColour::Green => "green",
+      AnnouncementColour::Orange => "orange",
+      AnnouncementColour::Purple => "purple",
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Debug, Clone)]
+pub struct SendAnnouncementRequest {
+  pub message: String,
+  #[serde(rename = "color")]
+  pub colour: String,
+}
+
+pub fn parse_response<T: DeserializeOwned>(response: String) -> Result<T, EventSubError> {
+  serde_json::from_str::<T>(&response)
+    .map_err(|e| EventSubError::ParseError(format!("{}: {}", e, response)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TwitchApi {
+  #[cfg(feature = "moderation")]
+  pub fn get_moderators<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_optional_key_value("after", after)
+      .build(TWITCH_MODERATORS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn add_moderator<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    V: Into<UserId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    user_id: V,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("user_id", user_id.into())
+      .build(TWITCH_MODERATORS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_post("")
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn remove_moderator<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    V: Into<UserId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    user_id: V,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("user_id", user_id.into())
+      .build(TWITCH_MODERATORS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_delete()
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_vips<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_optional_key_value("after", after)
+      .build(TWITCH_VIPS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn add_vip<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>, V: Into<UserId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    user_id: V,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("user_id", user_id.into())
+      .build(TWITCH_VIPS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_post("")
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn remove_vip<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>, V: Into<UserId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    user_id: V,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("user_id", user_id.into())
+      .build(TWITCH_VIPS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_delete()
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_blocked_terms<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .add_key_value("first", "100")
+      .add_optional_key_value("after", after)
+      .build(TWITCH_BLOCKED_TERMS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn add_blocked_term<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+    V: Into<String>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+    text: V,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .build(TWITCH_BLOCKED_TERMS_URL);
+
+    let post_data = serde_json::to_string(&AddBlockedTermRequest { text: text.into() }).unwrap();
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_post(post_data)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn remove_blocked_term<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+    V: Into<String>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+    blocked_term_id: V,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .add_key_value("id", blocked_term_id.into())
+      .build(TWITCH_BLOCKED_TERMS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_delete()
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_automod_settings<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .build(TWITCH_AUTOMOD_SETTINGS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn update_automod_settings<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+    settings: &AutoModSettingsRequest,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .build(TWITCH_AUTOMOD_SETTINGS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_put(serde_json::to_string(settings).unwrap())
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn check_automod_status<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    messages: Vec<AutoModCheckMessage>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .build(TWITCH_ENFORCEMENTS_STATUS_URL);
+
+    let post_data = serde_json::to_string(&AutoModCheckRequest { data: messages }).unwrap();
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_post(post_data)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn manage_held_automod_message<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<UserId>,
+    V: Into<MessageId>,
+  >(
+    access_token: T,
+    client_id: S,
+    moderator_id: X,
+    message_id: V,
+    action: AutoModAction,
+  ) -> Result<String, EventSubError> {
+    let post_data = serde_json::to_string(&ManageHeldMessageRequest {
+      user_id: moderator_id.into().into_string(),
+      msg_id: message_id.into().into_string(),
+      action,
+    })
+    .unwrap();
+
+    TwitchHttpRequest::new(TWITCH_AUTOMOD_MESSAGE_URL)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_post(post_data)
+      .run()
+  }
+
+  /// Issues an official Twitch warning, the user has to acknowledge it before they can chat again.
+  #[cfg(feature = "moderation")]
+  pub fn warn_user<
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+    V: Into<UserId>,
+    O: Into<String>,
+    U: Into<String>,
+    F: Into<String>,
+  >(
+    broadcaster_id: X,
+    moderator_id: Z,
+    user_id: V,
+    reason: O,
+    access_token: U,
+    client_id: F,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .build(TWITCH_WARNINGS_URL);
+
+    let post_data = serde_json::to_string(&SendWarnRequest {
+      data: WarnRequestData {
+        user_id: user_id.into().into_string(),
+        reason: reason.into(),
+      },
+    })
+    .unwrap();
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_post(post_data)
+      .run()
+  }
+
+  #[cfg(feature = "channel-points")]
+  pub fn get_custom_rewards<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    reward_ids: Vec<RewardId>,
+    only_manageable_rewards: bool,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_values("id", reward_ids)
+      .add_key_value(
+        "only_manageable_rewards",
+        only_manageable_rewards.to_string(),
+      )
+      .build(TWITCH_CUSTOM_REWARDS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "channel-points")]
+  pub fn create_custom_reward<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    reward: &CustomRewardBuilder,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .build(TWITCH_CUSTOM_REWARDS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_post(serde_json::to_string(reward).unwrap())
+      .run()
+  }
+
+  #[cfg(feature = "channel-points")]
+  pub fn update_custom_reward<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    V: Into<RewardId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    reward_id: V,
+    reward: &CustomRewardBuilder,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("id", reward_id.into())
+      .build(TWITCH_CUSTOM_REWARDS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_patch(serde_json::to_string(reward).unwrap())
+      .run()
+  }
+
+  #[cfg(feature = "channel-points")]
+  pub fn delete_custom_reward<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    V: Into<RewardId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    reward_id: V,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("id", reward_id.into())
+      .build(TWITCH_CUSTOM_REWARDS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_delete()
+      .run()
+  }
+
+  #[cfg(feature = "channel-points")]
+  pub fn get_custom_reward_redemptions<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    V: Into<RewardId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    reward_id: V,
+    status: RewardRedemptionStatus,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("reward_id", reward_id.into())
+      .add_key_value("status", status.as_str())
+      .add_key_value("first", "50")
+      .add_optional_key_value("after", after)
+      .build(TWITCH_CUSTOM_REWARD_REDEMPTIONS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  /// Marking a redemption as canceled refunds the viewer's channel points.
+  ///
+  /// Only redemptions of rewards created by this app's client id can be updated.
+  #[cfg(feature = "channel-points")]
+  pub fn update_redemption_status<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    V: Into<RewardId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    reward_id: V,
+    redemption_ids: Vec<String>,
+    status: RewardRedemptionStatus,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("reward_id", reward_id.into())
+      .add_key_values("id", redemption_ids)
+      .build(TWITCH_CUSTOM_REWARD_REDEMPTIONS_URL);
+
+    let patch_data = serde_json::to_string(&UpdateRedemptionStatusRequest { status }).unwrap();
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_patch(patch_data)
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_channel_stream_schedule<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_optional_key_value("after", after)
+      .build(TWITCH_SCHEDULE_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn create_schedule_segment<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    segment: &ScheduleSegmentBuilder,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .build(TWITCH_SCHEDULE_SEGMENT_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_post(serde_json::to_string(segment).unwrap())
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn update_schedule_segment<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    V: Into<String>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    segment_id: V,
+    segment: &ScheduleSegmentBuilder,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("id", segment_id.into())
+      .build(TWITCH_SCHEDULE_SEGMENT_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_patch(serde_json::to_string(segment).unwrap())
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn delete_schedule_segment<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    V: Into<String>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    segment_id: V,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("id", segment_id.into())
+      .build(TWITCH_SCHEDULE_SEGMENT_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_delete()
+      .run()
+  }
+
+  /// Passing `None` turns vacation mode off.
+  #[cfg(feature = "helix-full")]
+  pub fn update_schedule_vacation<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    vacation: Option<VacationSettings>,
+  ) -> Result<String, EventSubError> {
+    let mut url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("is_vacation_enabled", vacation.is_some().to_string());
+    if let Some(vacation) = vacation {
+      url = url
+        .add_key_value("vacation_start_time", vacation.start_time)
+        .add_key_value("vacation_end_time", vacation.end_time)
+        .add_key_value("timezone", vacation.timezone);
+    }
+    let url = url.build(TWITCH_SCHEDULE_SETTINGS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_patch("")
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_videos<T: Into<String>, S: Into<String>>(
+    access_token: T,
+    client_id: S,
+    query: VideoQuery,
+    video_type: VideoType,
+    period: VideoPeriod,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = videos_url(query, video_type, period, after);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_shield_mode_status<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .build(TWITCH_SHIELD_MODE_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn update_shield_mode_status<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+    is_active: bool,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .build(TWITCH_SHIELD_MODE_URL);
+
+    let put_data = serde_json::to_string(&UpdateShieldModeRequest { is_active }).unwrap();
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_put(put_data)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn update_chat_settings<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+    settings: &ChatSettingsUpdate,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .build(TWITCH_CHAT_SETTINGS_URL);
+
+    let patch_data = serde_json::to_string(settings).unwrap();
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_patch(patch_data)
+      .run()
+  }
+
+  /// Changes the stream's title, category, language, delay, tags or branded content flag.
+  #[cfg(feature = "helix-full")]
+  pub fn modify_channel_information<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    information: &ChannelInformationUpdate,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .build(TWITCH_CHANNELS_URL);
+
+    let patch_data = serde_json::to_string(information).unwrap();
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_patch(patch_data)
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_charity_campaign<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .build(TWITCH_CHARITY_CAMPAIGNS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_charity_campaign_donations<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("first", "100")
+      .add_optional_key_value("after", after)
+      .build(TWITCH_CHARITY_DONATIONS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_user_block_list<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("first", "100")
+      .add_optional_key_value("after", after)
+      .build(TWITCH_USER_BLOCKS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  /// Blocks the user on behalf of the user the access token belongs to.
+  #[cfg(feature = "helix-full")]
+  pub fn block_user<T: Into<String>, S: Into<String>, V: Into<UserId>>(
+    access_token: T,
+    client_id: S,
+    target_user_id: V,
+    reason: Option<BlockReason>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("target_user_id", target_user_id.into())
+      .add_optional_key_value("reason", reason.as_ref().map(BlockReason::as_str))
+      .build(TWITCH_USER_BLOCKS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_put("")
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn unblock_user<T: Into<String>, S: Into<String>, V: Into<UserId>>(
+    access_token: T,
+    client_id: S,
+    target_user_id: V,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("target_user_id", target_user_id.into())
+      .build(TWITCH_USER_BLOCKS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_delete()
+      .run()
+  }
+
+  #[cfg(feature = "chat")]
+  pub fn get_user_chat_color<T: Into<String>, S: Into<String>>(
+    access_token: T,
+    client_id: S,
+    user_ids: Vec<UserId>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_values("user_id", user_ids)
+      .build(TWITCH_CHAT_COLOUR_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "chat")]
+  pub fn update_user_chat_color<T: Into<String>, S: Into<String>, V: Into<UserId>>(
+    access_token: T,
+    client_id: S,
+    user_id: V,
+    colour: ChatColour,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("user_id", user_id.into())
+      .add_key_value("color", colour.as_str())
+      .build(TWITCH_CHAT_COLOUR_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_put("")
+      .run()
+  }
+
+  #[cfg(feature = "chat")]
+  pub fn send_chat_announcement<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+    M: Into<String>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+    message: M,
+    colour: AnnouncementColour,
+  ) -> Result<String, EventSubError> {
+    let message = message.into();
+    if message.chars().count() > MAX_CHAT_MESSAGE_LENGTH {
+      return Err(EventSubError::MessageTooLong);
+    }
+
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .build(TWITCH_ANNOUNCEMENTS_URL);
+
+    let post_data = serde_json::to_string(&SendAnnouncementRequest {
+      message,
+      colour: colour.as_str().to_owned(),
+    })
+    .unwrap();
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_post(post_data)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn send_shoutout<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Y: Into<BroadcasterId>,
+    Z: Into<UserId>,
+  >(
+    access_token: T,
+    client_id: S,
+    from_broadcaster_id: X,
+    to_broadcaster_id: Y,
+    moderator_id: Z,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("from_broadcaster_id", from_broadcaster_id.into())
+      .add_key_value("to_broadcaster_id", to_broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .build(TWITCH_SHOUTOUTS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_post("")
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_unban_requests<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+    status: UnbanRequestStatus,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .add_key_value("status", status.as_str())
+      .add_key_value("first", "100")
+      .add_optional_key_value("after", after)
+      .build(TWITCH_UNBAN_REQUESTS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn resolve_unban_request<
+    T: Into<String>,
+    S: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+    V: Into<String>,
+  >(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    moderator_id: Z,
+    unban_request_id: V,
+    resolution: UnbanRequestResolution,
+    resolution_text: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("moderator_id", moderator_id.into())
+      .add_key_value("unban_request_id", unban_request_id.into())
+      .add_key_value("status", resolution.as_str())
+      .add_optional_key_value("resolution_text", resolution_text)
+      .build(TWITCH_UNBAN_REQUESTS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_patch("")
+      .run()
+  }
+
+  #[cfg(feature = "moderation")]
+  pub fn get_moderated_channels<T: Into<String>, S: Into<String>, X: Into<UserId>>(
+    access_token: T,
+    client_id: S,
+    user_id: X,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("user_id", user_id.into())
+      .add_key_value("first", "100")
+      .add_optional_key_value("after", after)
+      .build(TWITCH_MODERATED_CHANNELS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  /// Passing a broadcaster id only checks whether the user follows that channel.
+  #[cfg(feature = "helix-full")]
+  pub fn get_followed_channels<T: Into<String>, S: Into<String>, X: Into<UserId>>(
+    access_token: T,
+    client_id: S,
+    user_id: X,
+    broadcaster_id: Option<String>,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("user_id", user_id.into())
+      .add_optional_key_value("broadcaster_id", broadcaster_id)
+      .add_key_value("first", "100")
+      .add_optional_key_value("after", after)
+      .build(TWITCH_FOLLOWED_CHANNELS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  /// Looks up to 100 users by id and login together.
+  #[cfg(feature = "helix-full")]
+  pub fn get_users<T: Into<String>, S: Into<String>>(
+    access_token: T,
+    client_id: S,
+    user_ids: Vec<UserId>,
+    logins: Vec<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_values("id", user_ids)
+      .add_key_values("login", logins)
+      .build(TWITCH_USERS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  /// The live streams of up to 100 users, users that aren't live are left out.
+  #[cfg(feature = "helix-full")]
+  pub fn get_streams<T: Into<String>, S: Into<String>>(
+    access_token: T,
+    client_id: S,
+    user_ids: Vec<UserId>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_values("user_id", user_ids)
+      .add_key_value("first", "100")
+      .build(TWITCH_STREAMS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_hype_train_events<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+    after: Option<String>,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .add_key_value("first", "100")
+      .add_optional_key_value("after", after)
+      .build(TWITCH_HYPE_TRAIN_EVENTS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  #[cfg(feature = "helix-full")]
+  pub fn get_hype_train_status<T: Into<String>, S: Into<String>, X: Into<BroadcasterId>>(
+    access_token: T,
+    client_id: S,
+    broadcaster_id: X,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", broadcaster_id.into())
+      .build(TWITCH_HYPE_TRAIN_STATUS_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .run()
+  }
+
+  pub fn get_eventsub_subscriptions<T: Into<String>, S: Into<String>>(
+    access_token: T,
+    client_id: S,
+    filter: EventSubSubscriptionFilter,
+    after: Option<String>,
+  ) -> Result<TwitchApiResponse<EventSubSubscriptions>, EventSubError> {
+    let url = match filter {
+      EventSubSubscriptionFilter::All => RequestBuilder::new(),
+      EventSubSubscriptionFilter::Status(status) => {
+        RequestBuilder::new().add_key_value("status", status.as_str())
+      }
+      EventSubSubscriptionFilter::Type(kind) => RequestBuilder::new().add_key_value("type", kind),
+      EventSubSubscriptionFilter::UserId(user_id) => {
+        RequestBuilder::new().add_key_value("user_id", user_id)
+      }
+      EventSubSubscriptionFilter::SubscriptionId(id) => {
+        RequestBuilder::new().add_key_value("subscription_id", id)
+      }
+    };
+
+    let url = url
+      .add_optional_key_value("after", after)
+      .build(SUBSCRIBE_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .send()
+      .and_then(TwitchApiResponse::parse)
+  }
+
+  pub fn delete_eventsub_subscription<T: Into<String>, S: Into<String>, X: Into<SubscriptionId>>(
+    access_token: T,
+    client_id: S,
+    subscription_id: X,
+  ) -> Result<String, EventSubError> {
+    let url = RequestBuilder::new()
+      .add_key_value("id", subscription_id.into())
+      .build(SUBSCRIBE_URL);
+
+    TwitchHttpRequest::new(url)
+      .full_auth(access_token, client_id)
+      .is_delete()
+      .run()
+  }
+
+  pub fn create_eventsub_subscription<T: Into<String>, S: Into<String>>(
+    access_token: T,
+    client_id: S,
+    subscription: &EventSubscription,
+  ) -> Result<TwitchApiResponse<EventSubSubscriptions>, EventSubError> {
+    TwitchHttpRequest::new(SUBSCRIBE_URL)
+      .full_auth(access_token, client_id)
+      .json_content()
+      .is_post(serde_json::to_string(subscription).unwrap())
+      .send()
+      .and_then(TwitchApiResponse::parse)
+  }
+}
+
+#[cfg(all(test, feature = "helix-full", not(target_arch = "wasm32")))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn video_ids_are_sent_without_filters() {
+    let url = videos_url(
+      VideoQuery::Ids(vec!["1".to_owned(), "2".to_owned()]),
+      VideoType::Archive,
+      VideoPeriod::Week,
+      Some("cursor".to_owned()),
+    );
+    assert_eq!(url, format!("{}?id=1&id=2", TWITCH_VIDEOS_URL));
+
+    let url = videos_url(
+      VideoQuery::User("3".to_owned()),
+      VideoType::Archive,
+      VideoPeriod::Week,
+      None,
+    );
+    assert_eq!(
+      url,
+      format!(
+        "{}?user_id=3&type=archive&period=week&first=100",
+        TWITCH_VIDEOS_URL
+      )
+    );
+  }
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+use std::ops::Deref;
+
+use crate::{Deserialise, Serialise};
+
+macro_rules! ids {
+  ($($(#[$doc:meta])* $name:ident,)*) => {
+    $(
+      $(#[$doc])*
+      #[derive(Serialise, Deserialise, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+      #[serde(transparent)]
+      pub struct $name(String);
+
+      impl $name {
+        pub fn new<S: Into<String>>(id: S) -> $name {
+          $name(id.into())
+        }
+
+        pub fn as_str(&self) -> &str {
+          &self.0
+        }
+
+        pub fn into_string(self) -> String {
+          self.0
+        }
+      }
+
+      impl Deref for $name {
+        type Target = str;
+
+        fn deref(&self) -> &str {
+          &self.0
+        }
+      }
+
+      impl AsRef<str> for $name {
+        fn as_ref(&self) -> &str {
+          &self.0
+        }
+      }
+
+      impl fmt::Display for $name {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+          f.write_str(&self.0)
+        }
+      }
+
+      impl From<String> for $name {
+        fn from(id: String) -> $name {
+          $name(id)
+        }
+      }
+
+      impl From<&String> for $name {
+        fn from(id: &String) -> $name {
+          $name(id.to_owned())
+        }
+      }
+
+      impl From<&str> for $name {
+        fn from(id: &str) -> $name {
+          $name(id.to_owned())
+        }
+      }
+
+      impl From<&$name> for $name {
+        fn from(id: &$name) -> $name {
+          id.clone()
+        }
+      }
+
+      impl From<$name> for String {
+        fn from(id: $name) -> String {
+          id.0
+        }
+      }
+
+      impl PartialEq<str> for $name {
+        fn eq(&self, other: &str) -> bool {
+          self.0 == other
+        }
+      }
+
+      impl PartialEq<&str> for $name {
+        fn eq(&self, other: &&str) -> bool {
+          self.0 == *other
+        }
+      }
+
+      impl PartialEq<String> for $name {
+        fn eq(&self, other: &String) -> bool {
+          &self.0 == other
+        }
+      }
+    )*
+  };
+}
+
+// Plain strings convert into any of these, but one kind of id can't be passed where
+// another is expected
+ids!(
+  /// Any Twitch user, such as a chatter or moderator.
+  UserId,
+  /// The user whose channel something happens in.
+  BroadcasterId,
+  /// A chat message.
+  MessageId,
+  /// A custom channel points reward.
+  RewardId,
+  /// An EventSub subscription.
+  SubscriptionId,
+);
+
+// Every broadcaster is a user, so a broadcaster can be passed as e.g. a moderator
+impl From<BroadcasterId> for UserId {
+  fn from(id: BroadcasterId) -> UserId {
+    UserId(id.0)
+  }
+}
+
+impl From<&BroadcasterId> for UserId {
+  fn from(id: &BroadcasterId) -> UserId {
+    UserId(id.0.to_owned())
+  }
+}
@@ -0,0 +1,397 @@
+#[cfg(feature = "extra_fields")]
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use websocket::native_tls::{TlsConnector, TlsStream};
+
+use crate::modules::consts::{IRC_HOST, IRC_PORT};
+use crate::{
+  Badge, BroadcasterUser, ChatterUser, Cheer, Emote, Event, EventSubError, Fragments, Message,
+  MessageData, ParentUser, Reply, SecretString, ThreadUser,
+};
+
+/// How to log in to Twitch IRC.
+#[derive(Clone, Debug)]
+pub enum IrcAuth {
+  /// Read only, works without a token or any scopes.
+  Anonymous,
+  /// Needs a user token with the `chat:read` scope, and `chat:edit` to send messages.
+  User {
+    login: String,
+    access_token: SecretString,
+  },
+}
+
+/// A connection to Twitch's IRC chat, for reading chat where EventSub chat subscriptions
+/// aren't available. Chat messages and user notices come out as the same
+/// `Event::ChatMessage` EventSub sends.
+pub struct IrcChat {
+  stream: BufReader<TlsStream<TcpStream>>,
+}
+
+impl IrcChat {
+  pub fn connect(auth: IrcAuth) -> Result<IrcChat, EventSubError> {
+    let tcp = TcpStream::connect((IRC_HOST, IRC_PORT)).map_err(irc_error)?;
+    let tls = TlsConnector::new()
+      .map_err(irc_error)?
+      .connect(IRC_HOST, tcp)
+      .map_err(irc_error)?;
+
+    let mut irc = IrcChat {
+      stream: BufReader::new(tls),
+    };
+    irc.send_line("CAP REQ :twitch.tv/tags twitch.tv/commands")?;
+    match auth {
+      IrcAuth::Anonymous => {
+        // Any justinfan nick logs in anonymously
+        irc.send_line("NICK justinfan12345")?;
+      }
+      IrcAuth::User {
+        login,
+        access_token,
+      } => {
+        irc.send_line(&format!("PASS oauth:{}", access_token.expose()))?;
+        irc.send_line(&format!("NICK {}", login.to_lowercase()))?;
+      }
+    }
+
+    Ok(irc)
+  }
+
+  /// Joins a channel by its login name.
+  pub fn join(&mut self, channel_login: &str) -> Result<(), EventSubError> {
+    self.send_line(&format!("JOIN #{}", channel_login.to_lowercase()))
+  }
+
+  pub fn part(&mut self, channel_login: &str) -> Result<(), EventSubError> {
+    self.send_line(&format!("PART #{}", channel_login.to_lowercase()))
+  }
+
+  /// Sends a chat message, which needs a user login with the `chat:edit` scope.
+  pub fn send_message(&mut self, channel_login: &str, message: &str) -> Result<(), EventSubError> {
+    self.send_line(&format!(
+      "PRIVMSG #{} :{}",
+      channel_login.to_lowercase(),
+      message
+    ))
+  }
+
+  /// Waits this long at most in `recv`, so the caller can check on other things.
+  pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<(), EventSubError> {
+    self
+      .stream
+      .get_ref()
+      .get_ref()
+      .set_read_timeout(timeout)
+      .map_err(irc_error)
+  }
+
+  /// Reads the next line from Twitch, returning the chat event and the line it came from
+  /// when it was a chat message or user notice. Pings are answered here. `None` when the
+  /// line was something else or the read timed out.
+  pub fn recv(&mut self) -> Result<Option<(Event, String)>, EventSubError> {
+    let mut line = String::new();
+    match self.stream.read_line(&mut line) {
+      Ok(0) => return Err(irc_error("Connection closed by Twitch")),
+      Ok(_) => {}
+      Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+        return Ok(None);
+      }
+      Err(e) => return Err(irc_error(e)),
+    }
+
+    let line = line.trim_end_matches(['\r', '\n']);
+    if let Some(server) = line.strip_prefix("PING ") {
+      self.send_line(&format!("PONG {}", server))?;
+      return Ok(None);
+    }
+    if let Some(error) = server_error(line) {
+      return Err(error);
+    }
+
+    Ok(parse_chat_line(line).map(|event| (event, line.to_owned())))
+  }
+
+  fn send_line(&mut self, line: &str) -> Result<(), EventSubError> {
+    let stream = self.stream.get_mut();
+    stream
+      .write_all(format!("{}\r\n", line).as_bytes())
+      .and_then(|_| stream.flush())
+      .map_err(irc_error)
+  }
+}
+
+fn irc_error<E: ToString>(e: E) -> EventSubError {
+  EventSubError::IrcError(e.to_string())
+}
+
+/// The command and its parameters, skipping the tags and prefix.
+fn split_command(line: &str) -> Option<(&str, &str)> {
+  let line = match line.strip_prefix('@') {
+    Some(line) => line.split_once(' ')?.1,
+    None => line,
+  };
+  let line = match line.strip_prefix(':') {
+    Some(line) => line.split_once(' ')?.1,
+    None => line,
+  };
+  Some(line.split_once(' ').unwrap_or((line, "")))
+}
+
+// Lines from Twitch that end the connection
+fn server_error(line: &str) -> Option<EventSubError> {
+  match split_command(line)? {
+    ("RECONNECT", _) => Some(irc_error("Twitch asked for a reconnect")),
+    ("NOTICE", params) if params.starts_with("* :Login authentication failed") => Some(
+      EventSubError::AuthorisationError("Twitch IRC refused the login".to_owned()),
+    ),
+    _ => None,
+  }
+}
+
+/// Turns a `PRIVMSG` or `USERNOTICE` line, with tags, into a chat message event.
+pub fn parse_chat_line(line: &str) -> Option<Event> {
+  let (tags, rest) = match line.strip_prefix('@') {
+    Some(line) => {
+      let (tags, rest) = line.split_once(' ')?;
+      (parse_tags(tags), rest)
+    }
+    None => (HashMap::new(), line),
+  };
+
+  let rest = rest.strip_prefix(':')?;
+  let (prefix, rest) = rest.split_once(' ')?;
+  let (command, rest) = rest.split_once(' ')?;
+  let (channel, text) = match rest.split_once(" :") {
+    Some((channel, text)) => (channel, text),
+    None => (rest, ""),
+  };
+  let channel = channel.trim().strip_prefix('#')?;
+  let tag = |key: &str| tags.get(key).cloned().unwrap_or_default();
+
+  let (message_type, text) = match command {
+    "PRIVMSG" => {
+      let message_type = match tag("msg-id").as_str() {
+        "highlighted-message" => "channel_points_highlighted",
+        "user-intro" => "user_intro",
+        _ => "text",
+      };
+      // `/me` messages are wrapped in a CTCP ACTION
+      let text = text
+        .strip_prefix("\u{1}ACTION ")
+        .and_then(|text| text.strip_suffix('\u{1}'))
+        .unwrap_or(text);
+      (message_type.to_owned(), text.to_owned())
+    }
+    // Subs, raids and the like, the user's own message if they wrote one
+    "USERNOTICE" => {
+      let text = if text.is_empty() {
+        tag("system-msg")
+      } else {
+        text.to_owned()
+      };
+      (tag("msg-id"), text)
+    }
+    _ => return None,
+  };
+
+  let login = match tags.get("login") {
+    Some(login) => login.to_owned(),
+    None => prefix.split('!').next().unwrap_or_default().to_owned(),
+  };
+  let display_name = match tag("display-name") {
+    name if name.is_empty() => login.to_owned(),
+    name => name,
+  };
+
+  let reply = tags
+    .get("reply-parent-msg-id")
+    .map(|parent_message_id| Reply {
+      parent_user: ParentUser {
+        id: tag("reply-parent-user-id"),
+        login: tag("reply-parent-user-login"),
+        name: tag("reply-parent-display-name"),
+      },
+      thread_user: ThreadUser {
+        id: tag("reply-thread-parent-user-id"),
+        login: tag("reply-thread-parent-user-login"),
+        name: tag("reply-thread-parent-display-name"),
+      },
+      parent_message_id: parent_message_id.to_owned(),
+      parent_message_body: tag("reply-parent-msg-body"),
+      thread_message_id: tag("reply-thread-parent-msg-id"),
+    });
+
+  Some(Event::ChatMessage(MessageData {
+    broadcaster_user: BroadcasterUser {
+      id: tag("room-id"),
+      login: channel.to_owned(),
+      name: channel.to_owned(),
+    },
+    chatter_user: ChatterUser {
+      id: tag("user-id"),
+      name: display_name,
+      login,
+    },
+    message_id: tag("id"),
+    message: Message {
+      fragments: fragments(&text, &tag("emotes")),
+      text,
+    },
+    colour: tag("color"),
+    badges: badges(&tag("badges"), &tag("badge-info")),
+    message_type,
+    cheer: tags
+      .get("bits")
+      .and_then(|bits| bits.parse().ok())
+      .map(|bits| Cheer { bits }),
+    reply,
+    channel_points_custom_reward_id: tags.get("custom-reward-id").cloned(),
+    channel_points_animation_id: None,
+    #[cfg(feature = "extra_fields")]
+    extra: BTreeMap::new(),
+  }))
+}
+
+fn parse_tags(tags: &str) -> HashMap<String, String> {
+  tags
+    .split(';')
+    .filter_map(|tag| tag.split_once('='))
+    .filter(|(_, value)| !value.is_empty())
+    .map(|(key, value)| (key.to_owned(), unescape_tag(value)))
+    .collect()
+}
+
+fn unescape_tag(value: &str) -> String {
+  let mut unescaped = String::with_capacity(value.len());
+  let mut chars = value.chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      unescaped.push(c);
+      continue;
+    }
+
+    match chars.next() {
+      Some('s') => unescaped.push(' '),
+      Some(':') => unescaped.push(';'),
+      Some('r') => unescaped.push('\r'),
+      Some('n') => unescaped.push('\n'),
+      Some(c) => unescaped.push(c),
+      None => {}
+    }
+  }
+
+  unescaped
+}
+
+// `badges` is `set/id` pairs, and `badge-info` the extra info some sets have, such as
+// `subscriber/16` for the months subscribed
+fn badges(badges: &str, badge_info: &str) -> Vec<Badge> {
+  let info = badge_info
+    .split(',')
+    .filter_map(|badge| badge.split_once('/'))
+    .collect::<HashMap<_, _>>();
+
+  badges
+    .split(',')
+    .filter_map(|badge| badge.split_once('/'))
+    .map(|(set_id, id)| Badge {
+      set_id: set_id.to_owned(),
+      id: id.to_owned(),
+      info: info
+        .get(set_id)
+        .map(|info| info.to_string())
+        .unwrap_or_default(),
+    })
+    .collect()
+}
+
+// `emotes` is `id:start-end,start-end/id:start-end`, positions counted in characters
+fn fragments(text: &str, emotes: &str) -> Vec<Fragments> {
+  let mut ranges = emotes
+    .split('/')
+    .filter_map(|emote| emote.split_once(':'))
+    .flat_map(|(id, positions)| {
+      positions.split(',').filter_map(move |position| {
+        let (start, end) = position.split_once('-')?;
+        Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?, id))
+      })
+    })
+    .collect::<Vec<_>>();
+  ranges.sort_by_key(|(start, _, _)| *start);
+
+  let chars = text.chars().collect::<Vec<_>>();
+  let slice = |start: usize, end: usize| chars[start..end].iter().collect::<String>();
+  let text_fragment = |text: String| Fragments {
+    kind: "text".to_owned(),
+    text,
+    cheermote: None,
+    emote: None,
+    mention: None,
+  };
+
+  let mut fragments = Vec::new();
+  let mut position = 0;
+  for (start, end, id) in ranges {
+    if start < position || end < start || end >= chars.len() {
+      continue;
+    }
+    if start > position {
+      fragments.push(text_fragment(slice(position, start)));
+    }
+    fragments.push(Fragments {
+      kind: "emote".to_owned(),
+      text: slice(start, end + 1),
+      cheermote: None,
+      emote: Some(Emote {
+        id: id.to_owned(),
+        emote_set_id: String::new(),
+        owner_id: String::new(),
+        format: vec!["static".to_owned()],
+      }),
+      mention: None,
+    });
+    position = end + 1;
+  }
+  if position < chars.len() {
+    fragments.push(text_fragment(slice(position, chars.len())));
+  }
+
+  fragments
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn only_server_commands_end_the_connection() {
+    assert!(server_error(":tmi.twitch.tv RECONNECT").is_some());
+    assert!(server_error(":tmi.twitch.tv NOTICE * :Login authentication failed").is_some());
+
+    // Chatters can type the same words
+    let chat = "@display-name=A :a!a@a.tmi.twitch.tv PRIVMSG #b :pls RECONNECT";
+    assert!(server_error(chat).is_none());
+    let chat = ":a!a@a.tmi.twitch.tv PRIVMSG #b :x NOTICE * :Login authentication failed";
+    assert!(server_error(chat).is_none());
+    assert!(parse_chat_line(chat).is_some());
+  }
+
+  #[test]
+  fn skips_emote_ranges_that_dont_fit() {
+    let kinds = |emotes| {
+      fragments("Kappa hi", emotes)
+        .into_iter()
+        .map(|fragment| fragment.kind)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(kinds("25:0-4"), ["emote", "text"]);
+    // Backwards and past the end of the message
+    assert_eq!(kinds("25:4-0"), ["text"]);
+    assert_eq!(kinds("25:6-20"), ["text"]);
+  }
+}
@@ -0,0 +1,219 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::MessageType;
+
+/// What happens to new events when the client's message buffer is full because messages
+/// aren't being received fast enough.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+  /// Drops the oldest event waiting to be received to make room.
+  DropOldest,
+  /// Drops the event that just arrived.
+  DropNewest,
+  /// Waits for room, which stops reading from Twitch until then. Twitch closes the
+  /// connection if it stays blocked for too long.
+  Block,
+}
+
+// Messages other than events, such as errors and status changes, are always queued, so
+// the buffer can go over its capacity by those.
+fn is_droppable(message: &MessageType) -> bool {
+  matches!(
+    message,
+    MessageType::Event { .. } | MessageType::RawResponse(_)
+  )
+}
+
+struct State {
+  messages: VecDeque<MessageType>,
+  // How many of the messages are droppable, only those count towards the capacity
+  events: usize,
+  capacity: Option<usize>,
+  policy: OverflowPolicy,
+  // Events dropped since the receiver was last told
+  dropped: usize,
+  senders: usize,
+  receiver_alive: bool,
+}
+
+struct Shared {
+  state: Mutex<State>,
+  // Notified when a message is queued, or the last sender goes away
+  message_queued: Condvar,
+  // Notified when a message is received, or the receiver goes away
+  message_received: Condvar,
+}
+
+/// Creates the channel messages are sent to the client on, unbounded without a capacity.
+pub(crate) fn message_channel(
+  capacity: Option<usize>,
+  policy: OverflowPolicy,
+) -> (MessageSender, MessageReceiver) {
+  let shared = Arc::new(Shared {
+    state: Mutex::new(State {
+      messages: VecDeque::new(),
+      events: 0,
+      capacity,
+      policy,
+      dropped: 0,
+      senders: 1,
+      receiver_alive: true,
+    }),
+    message_queued: Condvar::new(),
+    message_received: Condvar::new(),
+  });
+
+  (
+    MessageSender {
+      shared: shared.clone(),
+    },
+    MessageReceiver { shared },
+  )
+}
+
+pub(crate) struct MessageSender {
+  shared: Arc<Shared>,
+}
+
+impl MessageSender {
+  // The message is dropped when the receiver has gone
+  pub fn send(&self, message: MessageType) -> Result<(), SendError<()>> {
+    let mut state = self.shared.state.lock().unwrap();
+    if !state.receiver_alive {
+      return Err(SendError(()));
+    }
+
+    if let (Some(capacity), true) = (state.capacity, is_droppable(&message)) {
+      match state.policy {
+        OverflowPolicy::DropOldest => {
+          if state.events >= capacity {
+            let oldest = state.messages.iter().position(is_droppable);
+            if let Some(oldest) = oldest {
+              state.messages.remove(oldest);
+              state.events -= 1;
+              state.dropped += 1;
+            }
+          }
+        }
+        OverflowPolicy::DropNewest => {
+          if state.events >= capacity {
+            state.dropped += 1;
+            return Ok(());
+          }
+        }
+        OverflowPolicy::Block => {
+          while state.events >= capacity && state.receiver_alive {
+            state = self.shared.message_received.wait(state).unwrap();
+          }
+          if !state.receiver_alive {
+            return Err(SendError(()));
+          }
+        }
+      }
+    }
+
+    if is_droppable(&message) {
+      state.events += 1;
+    }
+    state.messages.push_back(message);
+    self.shared.message_queued.notify_one();
+    Ok(())
+  }
+}
+
+impl Clone for MessageSender {
+  fn clone(&self) -> MessageSender {
+    self.shared.state.lock().unwrap().senders += 1;
+    MessageSender {
+      shared: self.shared.clone(),
+    }
+  }
+}
+
+impl Drop for MessageSender {
+  fn drop(&mut self) {
+    let mut state = self.shared.state.lock().unwrap();
+    state.senders -= 1;
+    if state.senders == 0 {
+      self.shared.message_queued.notify_all();
+    }
+  }
+}
+
+pub(crate) struct MessageReceiver {
+  shared: Arc<Shared>,
+}
+
+impl MessageReceiver {
+  pub fn recv(&self) -> Result<MessageType, RecvError> {
+    let mut state = self.shared.state.lock().unwrap();
+    loop {
+      if let Some(message) = self.take(&mut state) {
+        return Ok(message);
+      }
+      if state.senders == 0 {
+        return Err(RecvError);
+      }
+      state = self.shared.message_queued.wait(state).unwrap();
+    }
+  }
+
+  pub fn try_recv(&self) -> Result<MessageType, TryRecvError> {
+    let mut state = self.shared.state.lock().unwrap();
+    match self.take(&mut state) {
+      Some(message) => Ok(message),
+      None if state.senders == 0 => Err(TryRecvError::Disconnected),
+      None => Err(TryRecvError::Empty),
+    }
+  }
+
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<MessageType, RecvTimeoutError> {
+    let deadline = Instant::now() + timeout;
+    let mut state = self.shared.state.lock().unwrap();
+    loop {
+      if let Some(message) = self.take(&mut state) {
+        return Ok(message);
+      }
+      if state.senders == 0 {
+        return Err(RecvTimeoutError::Disconnected);
+      }
+
+      let remaining = deadline.saturating_duration_since(Instant::now());
+      if remaining.is_zero() {
+        return Err(RecvTimeoutError::Timeout);
+      }
+      state = self
+        .shared
+        .message_queued
+        .wait_timeout(state, remaining)
+        .unwrap()
+        .0;
+    }
+  }
+
+  // Dropped events are reported straight away, ahead of anything still queued
+  fn take(&self, state: &mut State) -> Option<MessageType> {
+    if state.dropped > 0 {
+      let dropped = state.dropped;
+      state.dropped = 0;
+      return Some(MessageType::EventsDropped(dropped));
+    }
+
+    let message = state.messages.pop_front()?;
+    if is_droppable(&message) {
+      state.events -= 1;
+      self.shared.message_received.notify_one();
+    }
+    Some(message)
+  }
+}
+
+impl Drop for MessageReceiver {
+  fn drop(&mut self) {
+    self.shared.state.lock().unwrap().receiver_alive = false;
+    self.shared.message_received.notify_all();
+  }
+}
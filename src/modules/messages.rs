@@ -1,26 +1,59 @@
+#[cfg(feature = "extra_fields")]
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::SystemTime;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::modules::status::parse_timestamp;
+
 use crate::{
-  Badge, Cheer, Deserialise, Emote, Event, EventSubError, Fragments, Message, Reward, Serialise,
-  Subscription,
+  Badge, Cheer, ConnectionStatus, Deserialise, Event, EventSubError, EventSubSubscriptionInfo,
+  EventSubSubscriptionStatus, Fragments, Message, Reward, RewardRedemptionStatus, Scope, Serialise,
+  Subscription, UnbanRequestStatus,
 };
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct TopContributions {
   #[serde(flatten)]
   pub user: User,
+  /// `bits`, `subscription` or `other`.
   #[serde(rename = "type")]
   pub kind: String,
   pub total: u32,
 }
 
+impl TopContributions {
+  pub fn is_bits(&self) -> bool {
+    self.kind == "bits"
+  }
+
+  pub fn is_subscription(&self) -> bool {
+    self.kind == "subscription"
+  }
+}
+
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct LastContribution {
   #[serde(flatten)]
   pub user: User,
+  /// `bits`, `subscription` or `other`.
   #[serde(rename = "type")]
   pub kind: String,
   pub total: u32,
 }
 
+impl LastContribution {
+  pub fn is_bits(&self) -> bool {
+    self.kind == "bits"
+  }
+
+  pub fn is_subscription(&self) -> bool {
+    self.kind == "subscription"
+  }
+}
+
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct HypeTrainEndData {
   pub id: String,
@@ -28,10 +61,23 @@ pub struct HypeTrainEndData {
   pub broadcaster: BroadcasterUser,
   pub level: u32,
   pub total: u32,
-  pub top_contributions: TopContributions,
+  pub top_contributions: Vec<TopContributions>,
   pub started_at: String,
   pub ended_at: String,
   pub cooldown_ends_at: String,
+  /// `regular`, `golden_kappa` or `treasure`, only sent in version 2.
+  #[serde(rename = "type")]
+  pub kind: Option<String>,
+  /// Only sent in version 2.
+  pub is_shared_train: Option<bool>,
+  /// The other channels on a shared hype train, only sent in version 2.
+  pub shared_train_participants: Option<Vec<BroadcasterUser>>,
+  /// Only sent in version 1.
+  pub is_golden_kappa_train: Option<bool>,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -41,12 +87,27 @@ pub struct HypeTrainProgressData {
   pub broadcaster: BroadcasterUser,
   pub level: u32,
   pub total: u32,
+  /// Points towards the next level, out of `goal`.
   pub progress: u32,
   pub goal: u32,
-  pub top_contributions: TopContributions,
-  pub last_contribution: LastContribution,
+  pub top_contributions: Vec<TopContributions>,
+  /// Only sent in version 1.
+  pub last_contribution: Option<LastContribution>,
   pub started_at: String,
   pub expires_at: String,
+  /// `regular`, `golden_kappa` or `treasure`, only sent in version 2.
+  #[serde(rename = "type")]
+  pub kind: Option<String>,
+  /// Only sent in version 2.
+  pub is_shared_train: Option<bool>,
+  /// The other channels on a shared hype train, only sent in version 2.
+  pub shared_train_participants: Option<Vec<BroadcasterUser>>,
+  /// Only sent in version 1.
+  pub is_golden_kappa_train: Option<bool>,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -55,12 +116,129 @@ pub struct HypeTrainBeginData {
   #[serde(flatten)]
   pub broadcaster: BroadcasterUser,
   pub total: u32,
+  /// Points towards the next level, out of `goal`.
   pub progress: u32,
-  pub top_contributions: TopContributions,
-  pub last_contribution: LastContribution,
+  pub top_contributions: Vec<TopContributions>,
+  /// Only sent in version 1.
+  pub last_contribution: Option<LastContribution>,
   pub level: u32,
+  pub goal: u32,
   pub started_at: String,
-  pub exires_at: String,
+  pub expires_at: String,
+  /// `regular`, `golden_kappa` or `treasure`, only sent in version 2.
+  #[serde(rename = "type")]
+  pub kind: Option<String>,
+  /// Only sent in version 2.
+  pub is_shared_train: Option<bool>,
+  /// The other channels on a shared hype train, only sent in version 2.
+  pub shared_train_participants: Option<Vec<BroadcasterUser>>,
+  /// The channel's highest level and total before this train, only sent in version 2.
+  pub all_time_high_level: Option<u32>,
+  pub all_time_high_total: Option<u32>,
+  /// Only sent in version 1.
+  pub is_golden_kappa_train: Option<bool>,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+// Accessors every hype train event has, hiding the differences between versions
+macro_rules! hype_train_accessors {
+  ($($data:ident),*) => {
+    $(
+      impl $data {
+        pub fn is_golden_kappa(&self) -> bool {
+          self.kind.as_deref() == Some("golden_kappa") || self.is_golden_kappa_train == Some(true)
+        }
+
+        pub fn is_treasure(&self) -> bool {
+          self.kind.as_deref() == Some("treasure")
+        }
+
+        pub fn is_shared(&self) -> bool {
+          self.is_shared_train.unwrap_or_default()
+        }
+
+        /// Whoever has contributed the most so far.
+        pub fn top_contributor(&self) -> Option<&TopContributions> {
+          self.top_contributions.iter().max_by_key(|contribution| contribution.total)
+        }
+      }
+    )*
+  };
+}
+
+hype_train_accessors!(HypeTrainBeginData, HypeTrainProgressData, HypeTrainEndData);
+
+// Accessors for driving a progress bar towards the next level
+macro_rules! hype_train_progress {
+  ($($data:ident),*) => {
+    $(
+      impl $data {
+        /// How far through the current level the train is, from 0 to 1.
+        pub fn level_progress(&self) -> f32 {
+          if self.goal == 0 {
+            return 0.0;
+          }
+          (self.progress as f32 / self.goal as f32).min(1.0)
+        }
+
+        /// Points still needed to reach the next level.
+        pub fn remaining(&self) -> u32 {
+          self.goal.saturating_sub(self.progress)
+        }
+
+        /// How long until the train ends if nobody contributes, zero once it has expired.
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn time_remaining(&self) -> Option<Duration> {
+          time_until(&self.expires_at)
+        }
+      }
+    )*
+  };
+}
+
+hype_train_progress!(HypeTrainBeginData, HypeTrainProgressData);
+
+/// How long until an RFC3339 timestamp from Twitch, zero if it has passed.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn time_until(timestamp: &str) -> Option<Duration> {
+  let at = parse_timestamp(timestamp)?;
+  Some(at.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// Notices when hype trains reach a new level, as Twitch only sends progress events.
+#[derive(Clone, Debug, Default)]
+pub struct HypeTrainLevels {
+  // The level each running train was last seen at, by hype train id
+  levels: HashMap<String, u32>,
+}
+
+impl HypeTrainLevels {
+  pub fn new() -> HypeTrainLevels {
+    HypeTrainLevels::default()
+  }
+
+  /// Keeps track of hype train events, returning the new level when one levels up.
+  pub fn update(&mut self, event: &Event) -> Option<u32> {
+    match event {
+      Event::HypeTrainBegin(train) => {
+        self.levels.insert(train.id.to_owned(), train.level);
+        None
+      }
+      Event::HypeTrainProgress(train) => {
+        let previous = self.levels.insert(train.id.to_owned(), train.level);
+        // A train that began before we were listening counts from its first progress
+        (previous.is_some_and(|previous| train.level > previous)).then_some(train.level)
+      }
+      Event::HypeTrainEnd(train) => {
+        self.levels.remove(&train.id);
+        None
+      }
+      _ => None,
+    }
+  }
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -77,8 +255,12 @@ pub struct Outcome {
   pub title: String,
   #[serde(rename = "color")]
   pub colour: String,
+  // Nobody has predicted yet when a prediction begins
+  #[serde(default)]
   pub users: u32,
+  #[serde(default)]
   pub channel_points: u32,
+  #[serde(default)]
   pub top_predictors: Vec<TopPredictors>,
 }
 
@@ -91,17 +273,25 @@ pub struct PredictionBeginData {
   pub outcomes: Vec<Outcome>,
   pub started_at: String,
   pub locks_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
-pub struct PredicitonProgressData {
+pub struct PredictionProgressData {
   pub id: String,
   #[serde(flatten)]
-  broadcaster: BroadcasterUser,
+  pub broadcaster: BroadcasterUser,
   pub title: String,
   pub outcomes: Vec<Outcome>,
   pub started_at: String,
   pub locks_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -113,31 +303,173 @@ pub struct PredictionLockData {
   pub outcomes: Vec<Outcome>,
   pub started_at: String,
   pub locked_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
-pub struct PredicitionEndData {
+pub struct PredictionEndData {
   pub id: String,
   #[serde(flatten)]
   pub broadcaster: BroadcasterUser,
   pub title: String,
-  pub winning_outcome_id: String,
+  /// `None` when the prediction was cancelled.
+  pub winning_outcome_id: Option<String>,
   pub outcomes: Vec<Outcome>,
-  pub status: String,
+  pub status: PredictionStatus,
   pub started_at: String,
   pub ended_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+// Aggregates over a prediction's outcomes, so overlays don't each work them out
+macro_rules! prediction_accessors {
+  ($($data:ident),*) => {
+    $(
+      impl $data {
+        /// Channel points spent on every outcome.
+        pub fn total_channel_points(&self) -> u32 {
+          self.outcomes.iter().map(|outcome| outcome.channel_points).sum()
+        }
+
+        /// How many users have predicted.
+        pub fn total_users(&self) -> u32 {
+          self.outcomes.iter().map(|outcome| outcome.users).sum()
+        }
+
+        /// The outcome with the most channel points on it, `None` until someone predicts.
+        pub fn leading_outcome(&self) -> Option<&Outcome> {
+          self
+            .outcomes
+            .iter()
+            .filter(|outcome| outcome.channel_points > 0)
+            .max_by_key(|outcome| outcome.channel_points)
+        }
+
+        /// Percentage of the channel points spent on the outcome, from 0 to 100.
+        pub fn percentage(&self, outcome: &Outcome) -> f32 {
+          percentage(outcome.channel_points, self.total_channel_points())
+        }
+
+        pub fn outcome_by_title(&self, title: &str) -> Option<&Outcome> {
+          self
+            .outcomes
+            .iter()
+            .find(|outcome| outcome.title.eq_ignore_ascii_case(title))
+        }
+      }
+    )*
+  };
+}
+
+#[deprecated(note = "renamed to PredictionProgressData")]
+pub type PredicitonProgressData = PredictionProgressData;
+#[deprecated(note = "renamed to PredictionEndData")]
+pub type PredicitionEndData = PredictionEndData;
+
+#[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PredictionStatus {
+  Active,
+  Locked,
+  Resolved,
+  Canceled,
+  #[serde(other)]
+  Unknown,
+}
+
+impl PredictionStatus {
+  pub fn as_str(&self) -> &str {
+    match self {
+      PredictionStatus::Active => "active",
+      PredictionStatus::Locked => "locked",
+      PredictionStatus::Resolved => "resolved",
+      PredictionStatus::Canceled => "canceled",
+      PredictionStatus::Unknown => "unknown",
+    }
+  }
+}
+
+prediction_accessors!(
+  PredictionBeginData,
+  PredictionProgressData,
+  PredictionLockData,
+  PredictionEndData
+);
+
+macro_rules! prediction_time_remaining {
+  ($($data:ident),*) => {
+    $(
+      impl $data {
+        /// How long until predictions are locked, zero once they are.
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn time_remaining(&self) -> Option<Duration> {
+          time_until(&self.locks_at)
+        }
+      }
+    )*
+  };
+}
+
+prediction_time_remaining!(PredictionBeginData, PredictionProgressData);
+
+impl PredictionEndData {
+  /// `None` when the prediction was cancelled.
+  pub fn winning_outcome(&self) -> Option<&Outcome> {
+    let winning_outcome_id = self.winning_outcome_id.as_deref()?;
+    self
+      .outcomes
+      .iter()
+      .find(|outcome| outcome.id == winning_outcome_id)
+  }
+}
+
+fn percentage(part: u32, total: u32) -> f32 {
+  if total == 0 {
+    return 0.0;
+  }
+  part as f32 * 100.0 / total as f32
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct GiftData {
-  #[serde(flatten)]
-  pub user: User,
+  /// `None` when the gift was anonymous.
+  #[serde(flatten, deserialize_with = "optional_user")]
+  pub user: Option<User>,
   #[serde(flatten)]
   pub broadcaster: BroadcasterUser,
   pub total: u32,
   pub tier: String,
-  pub cumulative_total: u32,
+  /// `None` when the gift was anonymous, or the gifter keeps their total private.
+  pub cumulative_total: Option<u32>,
   pub is_anonymous: bool,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+// Anonymous gifters and cheerers are sent with null user fields
+fn optional_user<'de, D: serde::Deserializer<'de>>(
+  deserialiser: D,
+) -> Result<Option<User>, D::Error> {
+  #[derive(Deserialise)]
+  struct NullableUser {
+    user_id: Option<String>,
+    user_login: Option<String>,
+    user_name: Option<String>,
+  }
+
+  let user = <NullableUser as serde::Deserialize>::deserialize(deserialiser)?;
+  Ok(match (user.user_id, user.user_login, user.user_name) {
+    (Some(id), Some(login), Some(name)) => Some(User { id, login, name }),
+    _ => None,
+  })
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -156,18 +488,32 @@ pub struct RewardEmote {
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct RewardMessageData {
   pub text: String,
-  pub emotes: Vec<Emote>,
+  pub emotes: Vec<RewardEmote>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
-pub struct AutoRewardData {
+pub struct AutoReward {
   #[serde(rename = "type")]
   pub kind: String,
   pub cost: u32,
   pub unlocked_emote: Option<UnlockedEmote>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct AutoRewardData {
+  pub id: String,
+  #[serde(flatten)]
+  pub broadcaster: Option<BroadcasterUser>,
+  #[serde(flatten)]
+  pub user: User,
+  pub reward: AutoReward,
   pub message: RewardMessageData,
   pub user_input: Option<String>,
   pub redeemed_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -182,15 +528,21 @@ pub struct ChannelPointsAutoRewardRedeemData {
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct CheerData {
-  #[serde(flatten)]
-  pub user: User,
+  /// `None` when the cheer was anonymous.
+  #[serde(flatten, deserialize_with = "optional_user")]
+  pub user: Option<User>,
   #[serde(flatten)]
   pub broadcaster: BroadcasterUser,
   pub is_anonymous: bool,
   pub message: String,
   pub bits: u32,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+/// Someone followed the broadcaster.
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct FollowData {
   #[serde(flatten)]
@@ -198,6 +550,10 @@ pub struct FollowData {
   #[serde(flatten)]
   pub broadcaster: BroadcasterUser,
   pub followed_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -208,6 +564,25 @@ pub struct SubscribeData {
   pub braodcaster: BroadcasterUser,
   pub tier: String,
   pub is_gift: bool,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// A subscription lapsed or was cancelled.
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct SubscriptionEndData {
+  #[serde(flatten)]
+  pub user: User,
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  pub tier: String,
+  pub is_gift: bool,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -221,6 +596,10 @@ pub struct SubscribeMessageData {
   pub cumulative_months: u32,
   pub streak_months: Option<u32>,
   pub duration_months: u32,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -253,6 +632,16 @@ pub struct BroadcasterUser {
   pub name: String,
 }
 
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct ModeratorUser {
+  #[serde(rename = "moderator_user_id")]
+  pub id: String,
+  #[serde(rename = "moderator_user_login")]
+  pub login: String,
+  #[serde(rename = "moderator_user_name")]
+  pub name: String,
+}
+
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct RequesterUser {
   #[serde(rename = "requester_user_id")]
@@ -322,6 +711,132 @@ pub struct AdBreakBeginData {
   pub duration_seconds: u32,
   pub started_at: String,
   pub is_automatic: bool,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// The broadcaster stopped streaming.
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct StreamOfflineData {
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// The broadcaster went live.
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct StreamOnlineData {
+  /// The id of the stream.
+  pub id: String,
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  /// `live`, `playlist`, `watch_party`, `premiere` or `rerun`.
+  #[serde(rename = "type")]
+  pub kind: String,
+  pub started_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// A creator goal, sent when one begins, its amount changes and it ends.
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct GoalData {
+  pub id: String,
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  /// What's being counted: `follower`, `subscription`, `subscription_count`,
+  /// `new_subscription`, `new_subscription_count`, `new_bit` or `new_cheerer`.
+  #[serde(rename = "type")]
+  pub kind: String,
+  pub description: String,
+  pub current_amount: i64,
+  pub target_amount: i64,
+  pub started_at: String,
+  /// Only sent when the goal ends.
+  pub is_achieved: Option<bool>,
+  /// Only sent when the goal ends.
+  pub ended_at: Option<String>,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl GoalData {
+  pub fn is_follower_goal(&self) -> bool {
+    self.kind == "follower"
+  }
+
+  pub fn is_subscription_goal(&self) -> bool {
+    self.kind.contains("subscription")
+  }
+
+  pub fn is_bits_goal(&self) -> bool {
+    self.kind == "new_bit" || self.kind == "new_cheerer"
+  }
+
+  /// How far towards the target the goal is, from 0 to 1.
+  pub fn progress(&self) -> f32 {
+    if self.target_amount <= 0 {
+      return 0.0;
+    }
+    (self.current_amount as f32 / self.target_amount as f32).clamp(0.0, 1.0)
+  }
+
+  /// How much more is needed to reach the target.
+  pub fn remaining(&self) -> i64 {
+    (self.target_amount - self.current_amount).max(0)
+  }
+}
+
+/// The broadcaster changed their stream title, category, language or content labels.
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct ChannelUpdateData {
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  pub title: String,
+  pub language: String,
+  pub category_id: String,
+  pub category_name: String,
+  /// Content classification label ids, e.g. `MatureGame` or `ProfanityVulgarity`.
+  #[serde(default)]
+  pub content_classification_labels: Vec<String>,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl ChannelUpdateData {
+  pub fn has_label(&self, label: &str) -> bool {
+    self
+      .content_classification_labels
+      .iter()
+      .any(|l| l.eq_ignore_ascii_case(label))
+  }
+}
+
+/// A user changed their account details.
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct UserUpdateData {
+  #[serde(flatten)]
+  pub user: User,
+  /// Only sent when the token has the `user:read:email` scope.
+  #[serde(default)]
+  pub email: Option<String>,
+  pub email_verified: bool,
+  pub description: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -335,6 +850,14 @@ pub struct Reply {
   pub thread_message_id: String,
 }
 
+impl Reply {
+  /// Whether the message being replied to is the first message of the thread, rather than
+  /// a reply further down it.
+  pub fn is_to_thread_start(&self) -> bool {
+    self.parent_message_id == self.thread_message_id
+  }
+}
+
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct MessageData {
   #[serde(flatten)]
@@ -351,6 +874,76 @@ pub struct MessageData {
   pub reply: Option<Reply>,
   pub channel_points_custom_reward_id: Option<String>,
   pub channel_points_animation_id: Option<String>,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl MessageData {
+  fn badge(&self, set_id: &str) -> Option<&Badge> {
+    self.badges.iter().find(|badge| badge.set_id() == set_id)
+  }
+
+  pub fn is_reply(&self) -> bool {
+    self.reply.is_some()
+  }
+
+  /// The id of the first message in the thread this message replies to, replying to it
+  /// keeps the response in the same thread.
+  pub fn thread_id(&self) -> Option<&str> {
+    self
+      .reply
+      .as_ref()
+      .map(|reply| reply.thread_message_id.as_str())
+  }
+
+  pub fn parent_message_id(&self) -> Option<&str> {
+    self
+      .reply
+      .as_ref()
+      .map(|reply| reply.parent_message_id.as_str())
+  }
+
+  pub fn is_broadcaster(&self) -> bool {
+    self.chatter_user.id == self.broadcaster_user.id || self.badge("broadcaster").is_some()
+  }
+
+  pub fn is_moderator(&self) -> bool {
+    self.badge("moderator").is_some() || self.badge("lead_moderator").is_some()
+  }
+
+  pub fn is_vip(&self) -> bool {
+    self.badge("vip").is_some()
+  }
+
+  /// Founders are subscribers too, they just have a different badge.
+  pub fn is_subscriber(&self) -> bool {
+    self.badge("subscriber").is_some() || self.badge("founder").is_some()
+  }
+
+  /// 1, 2 or 3, going by the subscriber badge. Founder badges don't say which tier so are
+  /// counted as tier 1.
+  pub fn sub_tier(&self) -> Option<u32> {
+    if let Some(badge) = self.badge("subscriber") {
+      // Tier 2 and 3 badge ids are 2000 and 3000 plus the badge's months
+      return Some(match badge.id().parse::<u32>().unwrap_or(0) / 1000 {
+        2 => 2,
+        3 => 3,
+        _ => 1,
+      });
+    }
+
+    self.badge("founder").map(|_| 1)
+  }
+
+  /// How many months the chatter has been subscribed for.
+  pub fn sub_months(&self) -> Option<u32> {
+    self
+      .badge("subscriber")
+      .or_else(|| self.badge("founder"))
+      .and_then(|badge| badge.info().parse().ok())
+  }
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -360,6 +953,10 @@ pub struct RaidData {
   #[serde(flatten)]
   pub to_broadcaster: ToBroadcasterUser,
   pub viewers: u32,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -378,11 +975,42 @@ pub struct ChannelPointsVoting {
 pub struct Choices {
   pub id: String,
   pub title: String,
+  // Votes aren't counted yet when a poll begins
+  #[serde(default, rename = "bits_votes")]
   pub bit_votes: u32,
+  #[serde(default)]
   pub channel_points_votes: u32,
+  #[serde(default)]
   pub votes: u32,
 }
 
+#[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PollStatus {
+  Active,
+  Completed,
+  Terminated,
+  Archived,
+  Moderated,
+  Invalid,
+  #[serde(other)]
+  Unknown,
+}
+
+impl PollStatus {
+  pub fn as_str(&self) -> &str {
+    match self {
+      PollStatus::Active => "active",
+      PollStatus::Completed => "completed",
+      PollStatus::Terminated => "terminated",
+      PollStatus::Archived => "archived",
+      PollStatus::Moderated => "moderated",
+      PollStatus::Invalid => "invalid",
+      PollStatus::Unknown => "unknown",
+    }
+  }
+}
+
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct PollEndData {
   pub id: String,
@@ -392,9 +1020,13 @@ pub struct PollEndData {
   pub choices: Vec<Choices>,
   pub bits_voting: BitsVotingData,
   pub channel_points_voting: ChannelPointsVoting,
-  pub status: String,
+  pub status: PollStatus,
   pub started_at: String,
   pub ended_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
@@ -408,21 +1040,82 @@ pub struct PollProgressData {
   pub channel_points_voting: ChannelPointsVoting,
   pub started_at: String,
   pub ends_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct PollBeginData {
   pub id: String,
   #[serde(flatten)]
-  pub braodcaster: BroadcasterUser,
+  pub broadcaster: BroadcasterUser,
   pub title: String,
   pub choices: Vec<Choices>,
   pub bits_voting: BitsVotingData,
   pub channel_points_voting: ChannelPointsVoting,
   pub started_at: String,
   pub ends_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+// Aggregates over a poll's choices, so overlays don't each work them out
+macro_rules! poll_accessors {
+  ($($data:ident),*) => {
+    $(
+      impl $data {
+        /// Votes for every choice, counting those bought with bits and channel points.
+        pub fn total_votes(&self) -> u32 {
+          self.choices.iter().map(|choice| choice.votes).sum()
+        }
+
+        /// The choice with the most votes, `None` until someone votes.
+        pub fn leading_choice(&self) -> Option<&Choices> {
+          self
+            .choices
+            .iter()
+            .filter(|choice| choice.votes > 0)
+            .max_by_key(|choice| choice.votes)
+        }
+
+        /// Percentage of the votes the choice has, from 0 to 100.
+        pub fn percentage(&self, choice: &Choices) -> f32 {
+          percentage(choice.votes, self.total_votes())
+        }
+
+        pub fn choice_by_title(&self, title: &str) -> Option<&Choices> {
+          self
+            .choices
+            .iter()
+            .find(|choice| choice.title.eq_ignore_ascii_case(title))
+        }
+      }
+    )*
+  };
+}
+
+poll_accessors!(PollBeginData, PollProgressData, PollEndData);
+
+macro_rules! poll_time_remaining {
+  ($($data:ident),*) => {
+    $(
+      impl $data {
+        /// How long until the poll ends, zero once it has.
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn time_remaining(&self) -> Option<Duration> {
+          time_until(&self.ends_at)
+        }
+      }
+    )*
+  };
 }
 
+poll_time_remaining!(PollBeginData, PollProgressData);
+
 #[derive(Serialise, Deserialise, Clone, Debug)]
 pub struct CustomPointsRewardRedeemData {
   pub id: String,
@@ -431,17 +1124,406 @@ pub struct CustomPointsRewardRedeemData {
   #[serde(flatten)]
   pub broadcaster: BroadcasterUser,
   pub user_input: String,
-  pub status: String,
+  #[serde(serialize_with = "lowercase_redemption_status")]
+  pub status: RewardRedemptionStatus,
   pub reward: Reward,
   pub redeemed_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+// EventSub sends redemption statuses in lowercase, unlike Helix
+fn lowercase_redemption_status<S: serde::Serializer>(
+  status: &RewardRedemptionStatus,
+  serialiser: S,
+) -> Result<S::Ok, S::Error> {
+  serialiser.serialize_str(&status.as_str().to_lowercase())
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct AutoModMessage {
+  pub text: String,
+  #[serde(default)]
+  pub fragments: Vec<Fragments>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct AutoModMessageHoldData {
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  #[serde(flatten)]
+  pub user: User,
+  pub message_id: String,
+  pub message: AutoModMessage,
+  pub category: String,
+  pub level: u32,
+  pub held_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct AutoModMessageUpdateData {
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  #[serde(flatten)]
+  pub user: User,
+  #[serde(flatten)]
+  pub moderator: ModeratorUser,
+  pub message_id: String,
+  pub message: AutoModMessage,
+  pub category: String,
+  pub level: u32,
+  pub status: String,
+  pub held_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct AutoModSettingsUpdateData {
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  #[serde(flatten)]
+  pub moderator: ModeratorUser,
+  pub overall_level: Option<u32>,
+  pub disability: u32,
+  pub aggression: u32,
+  pub sexuality_sex_or_gender: u32,
+  pub misogyny: u32,
+  pub bullying: u32,
+  pub swearing: u32,
+  pub race_ethnicity_or_religion: u32,
+  pub sex_based_terms: u32,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct AutoModTermsUpdateData {
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  #[serde(flatten)]
+  pub moderator: ModeratorUser,
+  pub action: String,
+  pub from_automod: bool,
+  pub terms: Vec<String>,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct ShieldModeBeginData {
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  #[serde(flatten)]
+  pub moderator: ModeratorUser,
+  pub started_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct ShieldModeEndData {
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  #[serde(flatten)]
+  pub moderator: ModeratorUser,
+  pub ended_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct UnbanRequestCreateData {
+  pub id: String,
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  #[serde(flatten)]
+  pub user: User,
+  pub text: String,
+  pub created_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct UnbanRequestResolveData {
+  pub id: String,
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  pub moderator_id: Option<String>,
+  pub moderator_login: Option<String>,
+  pub moderator_name: Option<String>,
+  #[serde(flatten)]
+  pub user: User,
+  pub resolution_text: Option<String>,
+  pub status: UnbanRequestStatus,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct CharityAmount {
+  pub value: i64,
+  pub decimal_places: u32,
+  pub currency: String,
+}
+
+impl CharityAmount {
+  /// The amount in whole units of the currency, `value` 1050 with 2 decimal places is 10.5.
+  pub fn amount(&self) -> f64 {
+    self.value as f64 / 10f64.powi(self.decimal_places as i32)
+  }
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct CharityDonationData {
+  pub id: String,
+  pub campaign_id: String,
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  #[serde(flatten)]
+  pub user: User,
+  pub charity_name: String,
+  pub charity_description: String,
+  pub charity_logo: String,
+  pub charity_website: String,
+  pub amount: CharityAmount,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct CharityCampaignStartData {
+  pub id: String,
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  pub charity_name: String,
+  pub charity_description: String,
+  pub charity_logo: String,
+  pub charity_website: String,
+  pub current_amount: CharityAmount,
+  pub target_amount: CharityAmount,
+  pub started_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct CharityCampaignProgressData {
+  pub id: String,
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  pub charity_name: String,
+  pub charity_description: String,
+  pub charity_logo: String,
+  pub charity_website: String,
+  pub current_amount: CharityAmount,
+  pub target_amount: CharityAmount,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug)]
+pub struct CharityCampaignStopData {
+  pub id: String,
+  #[serde(flatten)]
+  pub broadcaster: BroadcasterUser,
+  pub charity_name: String,
+  pub charity_description: String,
+  pub charity_logo: String,
+  pub charity_website: String,
+  pub current_amount: CharityAmount,
+  pub target_amount: CharityAmount,
+  pub stopped_at: String,
+  /// Fields Twitch sent that this version of the crate doesn't know about.
+  #[cfg(feature = "extra_fields")]
+  #[serde(flatten)]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// Sent after Twitch revokes a subscription, the client will already have tried to
+/// subscribe again by the time this is received.
+#[derive(Clone, Debug)]
+pub struct SubscriptionRevoked {
+  /// `None` when the client wasn't tracking the subscription, such as ones created from
+  /// custom subscription data.
+  pub subscription: Option<Subscription>,
+  pub reason: EventSubSubscriptionStatus,
+  pub details: EventSubSubscriptionInfo,
+}
+
+/// Which subscriptions were created when a websocket session started, sent once they've all
+/// been tried. The failures are sent as `MessageType::Error` too.
+#[derive(Clone, Debug)]
+pub struct SubscriptionsReady {
+  /// Which of the client's websocket connections the session is on.
+  pub connection: usize,
+  pub succeeded: Vec<Subscription>,
+  pub failed: Vec<(Subscription, FailureReason)>,
+}
+
+impl SubscriptionsReady {
+  pub fn all_succeeded(&self) -> bool {
+    self.failed.is_empty()
+  }
+
+  /// Scopes a new token needs for the subscriptions that failed for lack of them.
+  pub fn missing_scopes(&self) -> Vec<Scope> {
+    let mut missing = Vec::new();
+    for (_, reason) in &self.failed {
+      if let FailureReason::MissingScopes(scopes) = reason {
+        for scope in scopes {
+          if !missing.contains(scope) {
+            missing.push(scope.to_owned());
+          }
+        }
+      }
+    }
+    missing
+  }
+}
+
+/// Why a subscription couldn't be created.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FailureReason {
+  /// The token hasn't been granted these scopes, generating a new token with them fixes it.
+  MissingScopes(Vec<Scope>),
+  InvalidCondition {
+    missing: Vec<String>,
+    extraneous: Vec<String>,
+  },
+  /// It would go over the max total cost Twitch allows, given here.
+  CostLimitReached(u32),
+  /// Twitch refused it, with the reason it gave.
+  Rejected(String),
+  Other(EventSubError),
+}
+
+impl FailureReason {
+  pub fn new(subscription: &Subscription, error: EventSubError) -> FailureReason {
+    match error {
+      EventSubError::Unauthorized { .. } | EventSubError::TokenMissingScope => {
+        FailureReason::MissingScopes(subscription.required_scope())
+      }
+      EventSubError::MissingScopes(scopes) => FailureReason::MissingScopes(scopes),
+      EventSubError::InvalidCondition {
+        missing,
+        extraneous,
+        ..
+      } => FailureReason::InvalidCondition {
+        missing,
+        extraneous,
+      },
+      EventSubError::SubscriptionCostLimitReached(max) => FailureReason::CostLimitReached(max),
+      EventSubError::SubscriptionFailed { reason, .. } => FailureReason::Rejected(reason),
+      error => FailureReason::Other(error),
+    }
+  }
+}
+
+/// A message from Twitch that couldn't be parsed, sent instead of the event when lenient
+/// parsing is turned on.
+#[derive(Clone, Debug)]
+pub struct ParseFailure {
+  /// The subscription the message was for, if that much could be read.
+  pub subscription_type: Option<String>,
+  pub raw: String,
+  pub error: String,
+}
+
+impl ParseFailure {
+  pub fn new<S: Into<String>, T: ToString>(raw: S, error: T) -> ParseFailure {
+    let raw = raw.into();
+    let subscription_type = serde_json::from_str::<serde_json::Value>(&raw)
+      .ok()
+      .and_then(|message| {
+        message["metadata"]["subscription_type"]
+          .as_str()
+          .map(str::to_owned)
+      });
+
+    ParseFailure {
+      subscription_type,
+      raw,
+      error: error.to_string(),
+    }
+  }
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum MessageType {
-  Event(Event),
+  /// `raw` is the notification exactly as Twitch sent it, for logging or reading fields
+  /// the event doesn't have yet. `None` for chat read over IRC, which isn't json.
+  Event {
+    event: Event,
+    raw: Option<String>,
+  },
+  SubscriptionRevoked(SubscriptionRevoked),
+  SubscriptionsReady(SubscriptionsReady),
+  ParseError(ParseFailure),
+  ConnectionStatusChanged(ConnectionStatus),
+  /// A chat message is waiting in the queue to stay under the rate limit.
+  ChatMessageDelayed {
+    message: String,
+    delay: Duration,
+  },
+  /// A chat message was never sent, because the queue was full, the client shut down, or
+  /// Twitch dropped it, such as when AutoMod held it. `reason` says which.
+  ChatMessageDropped {
+    message: String,
+    reason: String,
+  },
   BanTimeoutUser,
   DeleteMessage,
   Error(EventSubError),
   RawResponse(String),
+  /// This many events were dropped because the message buffer was full, see
+  /// `TwitchEventSubApiBuilder::message_buffer`.
+  EventsDropped(usize),
   Close,
 }
+
+impl MessageType {
+  /// The json Twitch sent for this message, if it came from Twitch.
+  pub fn raw_json(&self) -> Option<&str> {
+    match self {
+      MessageType::Event { raw, .. } => raw.as_deref(),
+      MessageType::RawResponse(raw) => Some(raw),
+      MessageType::ParseError(failure) => Some(&failure.raw),
+      _ => None,
+    }
+  }
+
+  /// The id Twitch gave the notification, the same for every delivery of it.
+  pub fn message_id(&self) -> Option<String> {
+    let raw: serde_json::Value = serde_json::from_str(self.raw_json()?).ok()?;
+    raw["metadata"]["message_id"].as_str().map(str::to_owned)
+  }
+}
@@ -0,0 +1,72 @@
+// Counters reported through the `metrics` facade when the `metrics` feature is on, any
+// exporter installed by the application (e.g. Prometheus) picks them up:
+//
+// twitch_eventsub_notifications_total{subscription_type}
+// twitch_eventsub_parse_failures_total
+// twitch_eventsub_api_requests_total{method, endpoint, status}
+// twitch_eventsub_reconnects_total{reason}
+// twitch_eventsub_token_refreshes_total{result}
+//
+// Without the feature these do nothing.
+#![cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+
+#[cfg_attr(feature = "only_raw_responses", allow(dead_code))]
+pub(crate) fn notification_received(subscription_type: &str) {
+  #[cfg(feature = "metrics")]
+  ::metrics::counter!(
+    "twitch_eventsub_notifications_total",
+    "subscription_type" => subscription_type.to_owned()
+  )
+  .increment(1);
+}
+
+#[cfg_attr(feature = "only_raw_responses", allow(dead_code))]
+pub(crate) fn parse_failed() {
+  #[cfg(feature = "metrics")]
+  ::metrics::counter!("twitch_eventsub_parse_failures_total").increment(1);
+}
+
+/// `status` is 0 when the request never got a response.
+pub(crate) fn api_request(method: &str, url: &str, status: u32) {
+  #[cfg(feature = "metrics")]
+  ::metrics::counter!(
+    "twitch_eventsub_api_requests_total",
+    "method" => method.to_owned(),
+    "endpoint" => endpoint(url),
+    "status" => if status == 0 { "error".to_owned() } else { status.to_string() }
+  )
+  .increment(1);
+}
+
+/// `requested` is true when Twitch asked for the reconnect, rather than the connection
+/// being lost.
+#[cfg_attr(feature = "only_raw_responses", allow(dead_code))]
+pub(crate) fn reconnect(requested: bool) {
+  #[cfg(feature = "metrics")]
+  ::metrics::counter!(
+    "twitch_eventsub_reconnects_total",
+    "reason" => if requested { "requested" } else { "connection_lost" }
+  )
+  .increment(1);
+}
+
+pub(crate) fn token_refresh(succeeded: bool) {
+  #[cfg(feature = "metrics")]
+  ::metrics::counter!(
+    "twitch_eventsub_token_refreshes_total",
+    "result" => if succeeded { "success" } else { "failure" }
+  )
+  .increment(1);
+}
+
+// The path of the url without the host or query, so ids don't end up in labels
+#[cfg(feature = "metrics")]
+fn endpoint(url: &str) -> String {
+  let url = url.split_once('?').map_or(url, |(path, _)| path);
+  let path = url
+    .split_once("://")
+    .and_then(|(_, rest)| rest.split_once('/'))
+    .map_or("", |(_, path)| path);
+
+  format!("/{path}")
+}
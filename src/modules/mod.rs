@@ -1,7 +1,48 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod batch;
+#[cfg(target_arch = "wasm32")]
+pub mod browser;
+#[cfg(all(feature = "chat", not(target_arch = "wasm32")))]
+pub mod chat_queue;
+#[cfg(all(feature = "chat", not(target_arch = "wasm32")))]
+pub mod commands;
+#[cfg(all(feature = "config", not(target_arch = "wasm32")))]
+pub mod config;
 pub mod consts;
+#[cfg(all(not(feature = "only_raw_responses"), not(target_arch = "wasm32")))]
+pub mod dedup;
 pub mod errors;
+pub mod event_stream;
+#[cfg(all(feature = "ffi", not(target_arch = "wasm32")))]
+pub mod ffi;
 pub mod generic_message;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gifts;
+pub mod helix;
+pub mod ids;
+#[cfg(all(feature = "irc", not(target_arch = "wasm32")))]
+pub mod irc;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod message_channel;
 pub mod messages;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod metrics;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pagination;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod recording;
+pub mod scope;
+pub mod secret;
+pub mod session_stats;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod setup;
+pub mod sinks;
+pub mod status;
 pub mod subscriptions;
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub mod testing;
 pub mod token;
+pub mod token_storage;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod twitch_http;
+pub mod user_cache;
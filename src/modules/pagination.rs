@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::modules::consts::MAX_RATE_LIMIT_WAIT;
+use crate::{EventSubError, EventSubSubscriptionInfo, EventSubSubscriptions, HelixData};
+
+/// A page of results from a Helix list endpoint.
+pub trait Page {
+  type Item;
+
+  /// Cursor to pass as `after` to get the next page, if there is one.
+  fn cursor(&self) -> Option<String>;
+  fn into_items(self) -> Vec<Self::Item>;
+}
+
+impl<T> Page for HelixData<T> {
+  type Item = T;
+
+  fn cursor(&self) -> Option<String> {
+    HelixData::cursor(self)
+  }
+
+  fn into_items(self) -> Vec<T> {
+    self.data
+  }
+}
+
+impl Page for EventSubSubscriptions {
+  type Item = EventSubSubscriptionInfo;
+
+  fn cursor(&self) -> Option<String> {
+    EventSubSubscriptions::cursor(self)
+  }
+
+  fn into_items(self) -> Vec<EventSubSubscriptionInfo> {
+    self.data
+  }
+}
+
+/// Iterates over every item of a list endpoint, fetching the next page as the current one
+/// runs out. `fetch` is given the cursor of the page to get, e.g.
+///
+/// ```no_run
+/// # use twitch_eventsub::*;
+/// # fn moderators(api: &mut TwitchEventSubApi) -> Result<Vec<User>, EventSubError> {
+/// Paginated::new(|after| api.get_moderators(after))
+///   .max_items(500)
+///   .collect()
+/// # }
+/// ```
+///
+/// When Twitch rate limits a request it waits for the limit to reset, up to a minute, and
+/// tries once more. The first error ends the iteration.
+pub struct Paginated<P: Page, F> {
+  fetch: F,
+  items: VecDeque<P::Item>,
+  cursor: Option<String>,
+  finished: bool,
+  max_items: Option<usize>,
+  returned: usize,
+}
+
+impl<P: Page, F: FnMut(Option<String>) -> Result<P, EventSubError>> Paginated<P, F> {
+  pub fn new(fetch: F) -> Paginated<P, F> {
+    Paginated {
+      fetch,
+      items: VecDeque::new(),
+      cursor: None,
+      finished: false,
+      max_items: None,
+      returned: 0,
+    }
+  }
+
+  /// Stops after this many items, without fetching pages past them.
+  pub fn max_items(mut self, max: usize) -> Paginated<P, F> {
+    self.max_items = Some(max);
+    self
+  }
+
+  fn fetch_page(&mut self) -> Result<(), EventSubError> {
+    let page = match (self.fetch)(self.cursor.clone()) {
+      Err(EventSubError::RateLimited { reset_at }) => {
+        thread::sleep(wait_until(reset_at));
+        (self.fetch)(self.cursor.clone())?
+      }
+      page => page?,
+    };
+
+    self.cursor = page.cursor();
+    self.items.extend(page.into_items());
+    Ok(())
+  }
+}
+
+impl<P: Page, F: FnMut(Option<String>) -> Result<P, EventSubError>> Iterator for Paginated<P, F> {
+  type Item = Result<P::Item, EventSubError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.max_items.is_some_and(|max| self.returned >= max) {
+      return None;
+    }
+
+    while self.items.is_empty() {
+      if self.finished {
+        return None;
+      }
+
+      if let Err(e) = self.fetch_page() {
+        self.finished = true;
+        return Some(Err(e));
+      }
+      self.finished = self.cursor.is_none();
+    }
+
+    self.returned += 1;
+    self.items.pop_front().map(Ok)
+  }
+}
+
+// How long until the unix timestamp `reset_at`, capped so a bad clock can't stall forever
+pub(crate) fn wait_until(reset_at: u64) -> Duration {
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs();
+
+  Duration::from_secs(reset_at.saturating_sub(now)).min(MAX_RATE_LIMIT_WAIT)
+}
@@ -0,0 +1,123 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error;
+
+use crate::{Deserialise, EventSubError, Serialise};
+
+#[derive(Serialise, Deserialise)]
+struct RecordedMessage {
+  // Milliseconds since the unix epoch
+  received_at: u64,
+  message: serde_json::Value,
+}
+
+/// Appends every notification the client receives to a jsonl file, one line per message
+/// with the time it arrived, so the session can be played back with an [`EventReplayer`].
+#[derive(Debug)]
+pub struct EventRecorder {
+  file: Mutex<File>,
+}
+
+impl EventRecorder {
+  pub fn new<P: AsRef<Path>>(path: P) -> Result<EventRecorder, EventSubError> {
+    OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)
+      .map(|file| EventRecorder {
+        file: Mutex::new(file),
+      })
+      .map_err(|e| EventSubError::WriteError(e.to_string()))
+  }
+
+  #[cfg_attr(feature = "only_raw_responses", allow(dead_code))]
+  pub(crate) fn record(&self, raw: &str) {
+    let message = match serde_json::from_str(raw) {
+      Ok(message) => message,
+      Err(e) => {
+        error!("Not recording message that isn't json: {}", e);
+        return;
+      }
+    };
+
+    let received_at = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_millis() as u64;
+
+    let line = serde_json::to_string(&RecordedMessage {
+      received_at,
+      message,
+    })
+    .unwrap();
+
+    if let Err(e) = writeln!(self.file.lock().unwrap(), "{}", line) {
+      error!("Failed to record message: {}", e);
+    }
+  }
+}
+
+/// Plays a session recorded by an [`EventRecorder`] back through a client built with
+/// `TwitchEventSubApiBuilder::replay`, without connecting to Twitch.
+#[derive(Debug)]
+pub struct EventReplayer {
+  messages: Vec<(u64, String)>,
+  speed: f64,
+}
+
+impl EventReplayer {
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<EventReplayer, EventSubError> {
+    let recording =
+      fs::read_to_string(path).map_err(|e| EventSubError::ReadError(e.to_string()))?;
+
+    let messages = recording
+      .lines()
+      .filter(|line| !line.trim().is_empty())
+      .map(|line| {
+        serde_json::from_str::<RecordedMessage>(line)
+          .map(|recorded| (recorded.received_at, recorded.message.to_string()))
+          .map_err(|e| EventSubError::ParseError(e.to_string()))
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(EventReplayer {
+      messages,
+      speed: 1.0,
+    })
+  }
+
+  /// Plays back this many times faster than the messages were recorded, defaults to 1.
+  pub fn speed(mut self, speed: f64) -> EventReplayer {
+    self.speed = speed;
+    self
+  }
+
+  /// Plays back every message straight away.
+  pub fn instant(self) -> EventReplayer {
+    self.speed(f64::INFINITY)
+  }
+
+  /// Waits out the recorded gaps between messages and hands each to `deliver`, stopping
+  /// early if it returns false.
+  pub(crate) fn replay<F: FnMut(&str) -> bool>(&self, mut deliver: F) {
+    let mut previous = None;
+    for (received_at, message) in &self.messages {
+      if let Some(previous) = previous {
+        let gap = received_at.saturating_sub(previous) as f64 / 1000.0 / self.speed;
+        if gap.is_finite() && gap > 0.0 {
+          thread::sleep(Duration::from_secs_f64(gap));
+        }
+      }
+      previous = Some(*received_at);
+
+      if !deliver(message) {
+        return;
+      }
+    }
+  }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::modules::errors::EventSubError;
+
+macro_rules! scopes {
+  ($($variant:ident => $scope:literal,)*) => {
+    /// A Twitch OAuth scope.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum Scope {
+      $($variant,)*
+      /// A scope this version of the crate doesn't know about.
+      Other(String),
+    }
+
+    impl Scope {
+      /// Every scope Twitch has.
+      pub const ALL: &'static [Scope] = &[$(Scope::$variant,)*];
+
+      pub fn as_str(&self) -> &str {
+        match self {
+          $(Scope::$variant => $scope,)*
+          Scope::Other(scope) => scope,
+        }
+      }
+    }
+
+    impl FromStr for Scope {
+      type Err = EventSubError;
+
+      fn from_str(scope: &str) -> Result<Scope, EventSubError> {
+        match scope {
+          $($scope => Ok(Scope::$variant),)*
+          _ => Err(EventSubError::ParseError(format!("Unknown scope: {}", scope))),
+        }
+      }
+    }
+  };
+}
+
+scopes! {
+  AnalyticsReadExtensions => "analytics:read:extensions",
+  AnalyticsReadGames => "analytics:read:games",
+  BitsRead => "bits:read",
+  ChannelBot => "channel:bot",
+  ChannelManageAds => "channel:manage:ads",
+  ChannelReadAds => "channel:read:ads",
+  ChannelManageBroadcast => "channel:manage:broadcast",
+  ChannelReadCharity => "channel:read:charity",
+  ChannelEditCommercial => "channel:edit:commercial",
+  ChannelReadEditors => "channel:read:editors",
+  ChannelManageExtensions => "channel:manage:extensions",
+  ChannelReadGoals => "channel:read:goals",
+  ChannelReadGuestStar => "channel:read:guest_star",
+  ChannelManageGuestStar => "channel:manage:guest_star",
+  ChannelReadHypeTrain => "channel:read:hype_train",
+  ChannelManageModerators => "channel:manage:moderators",
+  ChannelReadPolls => "channel:read:polls",
+  ChannelManagePolls => "channel:manage:polls",
+  ChannelReadPredictions => "channel:read:predictions",
+  ChannelManagePredictions => "channel:manage:predictions",
+  ChannelManageRaids => "channel:manage:raids",
+  ChannelReadRedemptions => "channel:read:redemptions",
+  ChannelManageRedemptions => "channel:manage:redemptions",
+  ChannelManageSchedule => "channel:manage:schedule",
+  ChannelReadStreamKey => "channel:read:stream_key",
+  ChannelReadSubscriptions => "channel:read:subscriptions",
+  ChannelManageVideos => "channel:manage:videos",
+  ChannelReadVips => "channel:read:vips",
+  ChannelManageVips => "channel:manage:vips",
+  ChannelModerate => "channel:moderate",
+  ClipsEdit => "clips:edit",
+  ModerationRead => "moderation:read",
+  ModeratorManageAnnouncements => "moderator:manage:announcements",
+  ModeratorManageAutomod => "moderator:manage:automod",
+  ModeratorReadAutomodSettings => "moderator:read:automod_settings",
+  ModeratorManageAutomodSettings => "moderator:manage:automod_settings",
+  ModeratorReadBannedUsers => "moderator:read:banned_users",
+  ModeratorManageBannedUsers => "moderator:manage:banned_users",
+  ModeratorReadBlockedTerms => "moderator:read:blocked_terms",
+  ModeratorReadChatMessages => "moderator:read:chat_messages",
+  ModeratorManageBlockedTerms => "moderator:manage:blocked_terms",
+  ModeratorManageChatMessages => "moderator:manage:chat_messages",
+  ModeratorReadChatSettings => "moderator:read:chat_settings",
+  ModeratorManageChatSettings => "moderator:manage:chat_settings",
+  ModeratorReadChatters => "moderator:read:chatters",
+  ModeratorReadFollowers => "moderator:read:followers",
+  ModeratorReadGuestStar => "moderator:read:guest_star",
+  ModeratorManageGuestStar => "moderator:manage:guest_star",
+  ModeratorReadModerators => "moderator:read:moderators",
+  ModeratorReadShieldMode => "moderator:read:shield_mode",
+  ModeratorManageShieldMode => "moderator:manage:shield_mode",
+  ModeratorReadShoutouts => "moderator:read:shoutouts",
+  ModeratorManageShoutouts => "moderator:manage:shoutouts",
+  ModeratorReadSuspiciousUsers => "moderator:read:suspicious_users",
+  ModeratorReadUnbanRequests => "moderator:read:unban_requests",
+  ModeratorManageUnbanRequests => "moderator:manage:unban_requests",
+  ModeratorReadVips => "moderator:read:vips",
+  ModeratorReadWarnings => "moderator:read:warnings",
+  ModeratorManageWarnings => "moderator:manage:warnings",
+  UserBot => "user:bot",
+  UserEdit => "user:edit",
+  UserEditBroadcast => "user:edit:broadcast",
+  UserReadBlockedUsers => "user:read:blocked_users",
+  UserManageBlockedUsers => "user:manage:blocked_users",
+  UserReadBroadcast => "user:read:broadcast",
+  UserReadChat => "user:read:chat",
+  UserManageChatColor => "user:manage:chat_color",
+  UserReadEmail => "user:read:email",
+  UserReadEmotes => "user:read:emotes",
+  UserReadFollows => "user:read:follows",
+  UserReadModeratedChannels => "user:read:moderated_channels",
+  UserReadSubscriptions => "user:read:subscriptions",
+  UserManageWhispers => "user:manage:whispers",
+  UserReadWhispers => "user:read:whispers",
+  UserWriteChat => "user:write:chat",
+  ChatEdit => "chat:edit",
+  ChatRead => "chat:read",
+  WhispersRead => "whispers:read",
+}
+
+impl Scope {
+  /// Parses a scope, keeping unknown ones as `Other`.
+  pub fn parse_lenient(scope: &str) -> Scope {
+    scope
+      .parse()
+      .unwrap_or_else(|_| Scope::Other(scope.to_owned()))
+  }
+}
+
+impl fmt::Display for Scope {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+/// A token or secret that prints as `***` in Debug and Display output, so it can't leak into
+/// logs or error messages. Use `expose` where the real value is needed.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(String);
+
+impl SecretString {
+  pub fn new<S: Into<String>>(secret: S) -> SecretString {
+    SecretString(secret.into())
+  }
+
+  pub fn expose(&self) -> &str {
+    &self.0
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+}
+
+impl fmt::Debug for SecretString {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("***")
+  }
+}
+
+impl fmt::Display for SecretString {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("***")
+  }
+}
+
+impl From<String> for SecretString {
+  fn from(secret: String) -> SecretString {
+    SecretString(secret)
+  }
+}
+
+impl From<&String> for SecretString {
+  fn from(secret: &String) -> SecretString {
+    SecretString(secret.to_owned())
+  }
+}
+
+impl From<&str> for SecretString {
+  fn from(secret: &str) -> SecretString {
+    SecretString(secret.to_owned())
+  }
+}
+
+impl From<&SecretString> for SecretString {
+  fn from(secret: &SecretString) -> SecretString {
+    secret.clone()
+  }
+}
@@ -0,0 +1,184 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{Event, Serialise};
+
+/// Counters for one stream, from `stream.online` until `stream.offline`.
+#[derive(Serialise, Clone, Debug, Default, PartialEq)]
+pub struct StreamSummary {
+  pub broadcaster_id: String,
+  pub stream_id: String,
+  pub started_at: String,
+  pub messages: u32,
+  pub unique_chatters: u32,
+  pub follows: u32,
+  /// New subs and resubs by tier, `1000`, `2000` or `3000`, gifted subs included.
+  pub subs_by_tier: BTreeMap<String, u32>,
+  pub gifted_subs: u32,
+  pub bits: u32,
+  pub raids: u32,
+  pub raid_viewers: u32,
+}
+
+impl StreamSummary {
+  pub fn total_subs(&self) -> u32 {
+    self.subs_by_tier.values().sum()
+  }
+}
+
+#[derive(Debug, Default)]
+struct Session {
+  summary: StreamSummary,
+  chatters: HashSet<String>,
+}
+
+/// Counts what happened during each broadcaster's stream for post-stream recaps.
+///
+/// Needs the `stream.online` and `stream.offline` subscriptions, events outside of a stream
+/// aren't counted.
+#[derive(Debug, Default)]
+pub struct SessionStats {
+  sessions: HashMap<String, Session>,
+}
+
+impl SessionStats {
+  pub fn new() -> SessionStats {
+    SessionStats::default()
+  }
+
+  /// Counts the event, returning the stream's summary when it goes offline.
+  pub fn update(&mut self, event: &Event) -> Option<StreamSummary> {
+    let broadcaster_id = event.broadcaster_id()?;
+
+    match event {
+      Event::StreamOnline(stream) => {
+        let summary = StreamSummary {
+          broadcaster_id: broadcaster_id.to_owned(),
+          stream_id: stream.id.to_owned(),
+          started_at: stream.started_at.to_owned(),
+          ..Default::default()
+        };
+        self.sessions.insert(
+          broadcaster_id.to_owned(),
+          Session {
+            summary,
+            chatters: HashSet::new(),
+          },
+        );
+        return None;
+      }
+      Event::StreamOffline(_) => {
+        return self
+          .sessions
+          .remove(broadcaster_id)
+          .map(|session| session.summary);
+      }
+      _ => {}
+    }
+
+    let session = self.sessions.get_mut(broadcaster_id)?;
+    let summary = &mut session.summary;
+    match event {
+      Event::ChatMessage(message) => {
+        summary.messages += 1;
+        if session.chatters.insert(message.chatter_user.id.to_owned()) {
+          summary.unique_chatters += 1;
+        }
+      }
+      Event::Follow(_) => summary.follows += 1,
+      Event::Subscribe(sub) => {
+        *summary.subs_by_tier.entry(sub.tier.to_owned()).or_default() += 1;
+        if sub.is_gift {
+          summary.gifted_subs += 1;
+        }
+      }
+      Event::SubscriptionMessage(sub) => {
+        *summary.subs_by_tier.entry(sub.tier.to_owned()).or_default() += 1;
+      }
+      Event::Cheer(cheer) => summary.bits += cheer.bits,
+      Event::Raid(raid) => {
+        summary.raids += 1;
+        summary.raid_viewers += raid.viewers;
+      }
+      _ => {}
+    }
+    None
+  }
+
+  /// The counters so far for a broadcaster that's live.
+  pub fn stats(&self, broadcaster_id: &str) -> Option<&StreamSummary> {
+    self
+      .sessions
+      .get(broadcaster_id)
+      .map(|session| &session.summary)
+  }
+
+  pub fn is_live(&self, broadcaster_id: &str) -> bool {
+    self.sessions.contains_key(broadcaster_id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::{json, Value};
+
+  use super::*;
+  use crate::GenericMessage;
+
+  fn chat_message() -> Event {
+    let json = include_str!("../../tests/fixtures/notifications/channel.chat.message.json");
+    let message = serde_json::from_str::<GenericMessage>(json).unwrap();
+    message.payload.and_then(|payload| payload.event).unwrap()
+  }
+
+  // A notification for the chat fixture's broadcaster, from user 1
+  fn event_for(kind: &str, broadcaster_id: &str, fields: Value) -> Event {
+    let mut event = json!({
+      "user_id": "1",
+      "user_login": "a",
+      "user_name": "A",
+      "broadcaster_user_id": broadcaster_id,
+      "broadcaster_user_login": "b",
+      "broadcaster_user_name": "B",
+    });
+    for (key, value) in fields.as_object().unwrap() {
+      event[key] = value.clone();
+    }
+    Event::from_json(Some(kind), event).unwrap()
+  }
+
+  #[test]
+  fn counts_between_online_and_offline() {
+    let chat = chat_message();
+    let id = chat.broadcaster_id().unwrap().to_owned();
+    let mut stats = SessionStats::new();
+
+    // Nothing is counted before the stream starts
+    assert!(stats.update(&chat).is_none());
+    let online = json!({"id": "1", "type": "live", "started_at": "2023-11-16T10:11:12Z"});
+    stats.update(&event_for("stream.online", &id, online));
+    assert!(stats.is_live(&id));
+
+    stats.update(&chat);
+    stats.update(&chat);
+    stats.update(&event_for(
+      "channel.follow",
+      &id,
+      json!({"followed_at": ""}),
+    ));
+    let sub = json!({"tier": "1000", "is_gift": true});
+    stats.update(&event_for("channel.subscribe", &id, sub));
+    let cheer = json!({"is_anonymous": false, "message": "", "bits": 100});
+    stats.update(&event_for("channel.cheer", &id, cheer));
+    assert_eq!(stats.stats(&id).unwrap().messages, 2);
+
+    let summary = stats
+      .update(&event_for("stream.offline", &id, json!({})))
+      .unwrap();
+    assert_eq!(summary.unique_chatters, 1);
+    assert_eq!(summary.follows, 1);
+    assert_eq!(summary.total_subs(), 1);
+    assert_eq!(summary.gifted_subs, 1);
+    assert_eq!(summary.bits, 100);
+    assert!(!stats.is_live(&id));
+  }
+}
@@ -0,0 +1,239 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::{
+  EventSubError, Subscription, TokenAccess, TwitchApi, TwitchEventSubApi, TwitchEventSubApiBuilder,
+  TwitchKeys,
+};
+
+const DEFAULT_REDIRECT_URL: &str = "http://localhost:3000";
+
+// Keys the wizard writes, anything else already in the file is kept
+const SETUP_KEYS: [&str; 6] = [
+  "TWITCH_CLIENT_ID",
+  "TWITCH_CLIENT_SECRET",
+  "TWITCH_BROADCASTER_ID",
+  "TWITCH_USER_ACCESS_TOKEN",
+  "TWITCH_USER_REFRESH_TOKEN",
+  "TWITCH_SUBSCRIPTIONS",
+];
+
+type Preset = fn(TwitchEventSubApiBuilder) -> TwitchEventSubApiBuilder;
+
+const PRESETS: [(&str, Preset); 3] = [
+  (
+    "Chat bot: chat messages and announcements",
+    TwitchEventSubApiBuilder::preset_chat_bot,
+  ),
+  (
+    "Alerts overlay: follows, raids, subs, cheers, redemptions and hype trains",
+    TwitchEventSubApiBuilder::preset_alerts_overlay,
+  ),
+  (
+    "Moderation: held messages, bans, warnings, shield mode and unban requests",
+    TwitchEventSubApiBuilder::preset_moderation,
+  ),
+];
+
+/// Walks through setting up a Twitch app on the terminal: asks for the client id and secret
+/// and which events to subscribe to, opens the browser to authorise them, checks the token
+/// and saves it all to `env_file`, usually `.secrets.env`. Later runs load it with
+/// `TwitchKeys::from_secrets_env` and `add_subscriptions_from_env`.
+///
+/// Returns a builder set up with the new token and subscriptions.
+pub fn setup_wizard<P: AsRef<Path>>(
+  env_file: P,
+) -> Result<TwitchEventSubApiBuilder, EventSubError> {
+  let stdin = io::stdin();
+  let mut wizard = Wizard {
+    input: stdin.lock(),
+    output: io::stdout(),
+  };
+  wizard.run(env_file.as_ref())
+}
+
+struct Wizard<R: BufRead, W: Write> {
+  input: R,
+  output: W,
+}
+
+fn terminal_error(e: io::Error) -> EventSubError {
+  EventSubError::UnhandledError(format!("Setup couldn't use the terminal: {}", e))
+}
+
+impl<R: BufRead, W: Write> Wizard<R, W> {
+  fn run(&mut self, env_file: &Path) -> Result<TwitchEventSubApiBuilder, EventSubError> {
+    self.say(&format!(
+      "Create an application at https://dev.twitch.tv/console/apps with {} as an OAuth \
+       Redirect URL, then copy its details here.\n",
+      DEFAULT_REDIRECT_URL
+    ))?;
+    let client_id = self.ask("Client ID", None)?;
+    let client_secret = self.ask("Client Secret", None)?;
+    let redirect_url = self.ask("OAuth Redirect URL", Some(DEFAULT_REDIRECT_URL))?;
+    let (builder, event_types) = self.choose_subscriptions()?;
+
+    self.say("\nAuthorise the application in the browser that opens.")?;
+    let subscriptions = builder.subscriptions();
+    let token = TwitchApi::generate_user_token(
+      client_id.to_owned(),
+      client_secret.to_owned(),
+      redirect_url.to_owned(),
+      &subscriptions,
+    )?;
+
+    let validation = TwitchEventSubApi::validate_token(token.access.get_token())?;
+    if validation.is_error() {
+      return Err(EventSubError::InvalidOauthToken(
+        validation.message.unwrap_or_default(),
+      ));
+    }
+    let broadcaster_id = validation.user_id.unwrap_or_default();
+    self.say(&format!(
+      "Authorised as {} ({})",
+      validation.login.unwrap_or_default(),
+      broadcaster_id
+    ))?;
+
+    write_env_file(
+      env_file,
+      &[
+        client_id.as_str(),
+        client_secret.as_str(),
+        broadcaster_id.as_str(),
+        token.access.expose(),
+        token.refresh.expose(),
+        event_types.join(",").as_str(),
+      ],
+    )?;
+    self.say(&format!("Saved to {}", env_file.display()))?;
+
+    let mut keys = builder_keys(client_id, client_secret, broadcaster_id);
+    keys.access_token = Some(TokenAccess::User(token.access.get_token().into()));
+    keys.refresh_token = Some(token.refresh);
+    let mut builder = builder.set_redirect_url(redirect_url);
+    builder.set_keys(keys);
+    Ok(builder)
+  }
+
+  // Presets by number or event types by name, as many as wanted
+  fn choose_subscriptions(
+    &mut self,
+  ) -> Result<(TwitchEventSubApiBuilder, Vec<String>), EventSubError> {
+    self.say("\nWhich events do you want?")?;
+    for (i, (description, _)) in PRESETS.iter().enumerate() {
+      self.say(&format!("  {}. {}", i + 1, description))?;
+    }
+    self.say("or type event types such as channel.raid, `list` shows them all.")?;
+
+    'ask: loop {
+      let answer = self.ask("Choose one or more, separated by commas", Some("1,2"))?;
+      let mut builder =
+        TwitchEventSubApi::builder(builder_keys(String::new(), String::new(), String::new()));
+      for choice in answer.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        if choice == "list" {
+          for tag in Subscription::all().iter().map(Subscription::tag) {
+            if !tag.is_empty() {
+              self.say(&format!("  {}", tag))?;
+            }
+          }
+          continue 'ask;
+        }
+
+        match choice
+          .parse::<usize>()
+          .ok()
+          .and_then(|n| PRESETS.get(n.wrapping_sub(1)))
+        {
+          Some((_, preset)) => builder = preset(builder),
+          None => match Subscription::from_string(choice) {
+            Some(subscription) => builder = builder.add_subscription(subscription),
+            None => {
+              self.say(&format!("{} isn't a preset or event type", choice))?;
+              continue 'ask;
+            }
+          },
+        }
+      }
+
+      // Scope only entries can't be written as event types, the token already has them
+      let event_types = builder
+        .subscriptions()
+        .iter()
+        .map(Subscription::tag)
+        .filter(|tag| !tag.is_empty())
+        .collect::<Vec<_>>();
+      if event_types.is_empty() {
+        self.say("Choose at least one event")?;
+        continue;
+      }
+      return Ok((builder, event_types));
+    }
+  }
+
+  fn say(&mut self, text: &str) -> Result<(), EventSubError> {
+    writeln!(self.output, "{}", text).map_err(terminal_error)
+  }
+
+  // Asks until there's an answer, or uses the default for an empty one
+  fn ask(&mut self, question: &str, default: Option<&str>) -> Result<String, EventSubError> {
+    loop {
+      match default {
+        Some(default) => write!(self.output, "{} [{}]: ", question, default),
+        None => write!(self.output, "{}: ", question),
+      }
+      .and_then(|_| self.output.flush())
+      .map_err(terminal_error)?;
+
+      let mut answer = String::new();
+      if self.input.read_line(&mut answer).map_err(terminal_error)? == 0 {
+        return Err(EventSubError::UnhandledError(
+          "Setup ended before it was finished".to_owned(),
+        ));
+      }
+
+      match (answer.trim(), default) {
+        ("", Some(default)) => return Ok(default.to_owned()),
+        ("", None) => continue,
+        (answer, _) => return Ok(answer.to_owned()),
+      }
+    }
+  }
+}
+
+fn builder_keys(client_id: String, client_secret: String, broadcaster_id: String) -> TwitchKeys {
+  TwitchKeys {
+    authorisation_code: None,
+    access_token: None,
+    refresh_token: None,
+    client_id,
+    client_secret: client_secret.into(),
+    broadcaster_account_id: broadcaster_id.to_owned(),
+    sender_account_id: Some(broadcaster_id),
+    moderator_account_id: None,
+    chat_reader_account_id: None,
+    token_storage: None,
+  }
+}
+
+// Replaces the wizard's keys in the file, keeping everything else in it
+fn write_env_file(path: &Path, values: &[&str; SETUP_KEYS.len()]) -> Result<(), EventSubError> {
+  let existing = fs::read_to_string(path).unwrap_or_default();
+  let mut lines = existing
+    .lines()
+    .filter(|line| {
+      let key = line.split('=').next().unwrap_or_default().trim();
+      !SETUP_KEYS.contains(&key)
+    })
+    .map(str::to_owned)
+    .collect::<Vec<_>>();
+  lines.extend(
+    SETUP_KEYS
+      .iter()
+      .zip(values)
+      .map(|(key, value)| format!("{} = \"{}\"", key, value)),
+  );
+
+  fs::write(path, lines.join("\n") + "\n").map_err(|e| EventSubError::WriteError(e.to_string()))
+}
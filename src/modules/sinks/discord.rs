@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::modules::consts::{
+  DEFAULT_DISCORD_MIN_CHEER_BITS, MAX_DISCORD_WEBHOOK_RETRIES, TWITCH_PURPLE,
+};
+use crate::{error, Event, EventSink, TwitchHttpRequest};
+
+/// The events a `DiscordWebhookSink` posts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiscordAlert {
+  /// Needs `Subscription::StreamOnline`.
+  StreamOnline,
+  /// Needs `Subscription::ChannelRaid` with `RaidDirection::Incoming`.
+  Raid,
+  /// Needs `Subscription::ChannelCheer`.
+  Cheer,
+}
+
+/// What is posted for an alert. `{field}` anywhere in the text is replaced by that field of
+/// the event as Twitch sent it, such as `{broadcaster_user_name}`, with `.` for nested
+/// fields. Fields that don't exist are left as they are.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiscordEmbed {
+  pub title: String,
+  pub description: String,
+  pub url: Option<String>,
+  /// The colour down the side of the embed, as `0xRRGGBB`.
+  pub colour: u32,
+  /// Text posted above the embed, e.g. to mention a role.
+  pub content: Option<String>,
+}
+
+impl DiscordEmbed {
+  pub fn new<S: Into<String>, T: Into<String>>(title: S, description: T) -> DiscordEmbed {
+    DiscordEmbed {
+      title: title.into(),
+      description: description.into(),
+      url: None,
+      colour: TWITCH_PURPLE,
+      content: None,
+    }
+  }
+
+  pub fn url<S: Into<String>>(mut self, url: S) -> DiscordEmbed {
+    self.url = Some(url.into());
+    self
+  }
+
+  pub fn colour(mut self, colour: u32) -> DiscordEmbed {
+    self.colour = colour;
+    self
+  }
+
+  pub fn content<S: Into<String>>(mut self, content: S) -> DiscordEmbed {
+    self.content = Some(content.into());
+    self
+  }
+
+  fn payload(&self, fields: &Value, username: Option<&str>) -> Value {
+    let mut embed = json!({ "color": self.colour });
+    for (key, template) in [
+      ("title", Some(&self.title)),
+      ("description", Some(&self.description)),
+      ("url", self.url.as_ref()),
+    ] {
+      // Discord refuses embeds with empty fields
+      if let Some(text) = template.map(|t| fill_template(t, fields)) {
+        if !text.is_empty() {
+          embed[key] = Value::String(text);
+        }
+      }
+    }
+
+    let mut payload = json!({ "embeds": [embed] });
+    if let Some(content) = &self.content {
+      payload["content"] = Value::String(fill_template(content, fields));
+    }
+    if let Some(username) = username {
+      payload["username"] = Value::String(username.to_owned());
+    }
+    payload
+  }
+}
+
+/// Posts go-lives, raids and big cheers to a Discord channel through a webhook. Posting
+/// happens on its own thread so it never holds up events, and stops when this is dropped.
+///
+/// ```no_run
+/// # use twitch_eventsub::*;
+/// let mut discord = DiscordWebhookSink::new("https://discord.com/api/webhooks/...")
+///   .disable(DiscordAlert::Cheer)
+///   .embed(
+///     DiscordAlert::StreamOnline,
+///     DiscordEmbed::new("{broadcaster_user_name} is live!", "Come say hi")
+///       .url("https://twitch.tv/{broadcaster_user_login}")
+///       .content("@everyone"),
+///   );
+/// # let event: Event = unimplemented!();
+/// discord.consume(&event);
+/// ```
+pub struct DiscordWebhookSink {
+  posts: Sender<String>,
+  embeds: HashMap<DiscordAlert, DiscordEmbed>,
+  username: Option<String>,
+  min_raid_viewers: u32,
+  min_cheer_bits: u32,
+}
+
+impl DiscordWebhookSink {
+  /// Posts every alert with the default embeds, cheers from
+  /// `DEFAULT_DISCORD_MIN_CHEER_BITS` bits up.
+  pub fn new<S: Into<String>>(webhook_url: S) -> DiscordWebhookSink {
+    let webhook_url = webhook_url.into();
+    let (posts, queued) = channel::<String>();
+    thread::spawn(move || {
+      for body in queued {
+        post(&webhook_url, body);
+      }
+    });
+
+    DiscordWebhookSink {
+      posts,
+      embeds: HashMap::from([
+        (
+          DiscordAlert::StreamOnline,
+          DiscordEmbed::new(
+            "{broadcaster_user_name} is live!",
+            "https://twitch.tv/{broadcaster_user_login}",
+          )
+          .url("https://twitch.tv/{broadcaster_user_login}"),
+        ),
+        (
+          DiscordAlert::Raid,
+          DiscordEmbed::new(
+            "{from_broadcaster_user_name} raided with {viewers} viewers",
+            "Go check them out at https://twitch.tv/{from_broadcaster_user_login}",
+          )
+          .url("https://twitch.tv/{from_broadcaster_user_login}"),
+        ),
+        (
+          DiscordAlert::Cheer,
+          DiscordEmbed::new("{user_name} cheered {bits} bits!", "{message}"),
+        ),
+      ]),
+      username: None,
+      min_raid_viewers: 0,
+      min_cheer_bits: DEFAULT_DISCORD_MIN_CHEER_BITS,
+    }
+  }
+
+  /// Replaces the embed posted for an alert, turning it on if it was disabled.
+  pub fn embed(mut self, alert: DiscordAlert, embed: DiscordEmbed) -> DiscordWebhookSink {
+    self.embeds.insert(alert, embed);
+    self
+  }
+
+  pub fn disable(mut self, alert: DiscordAlert) -> DiscordWebhookSink {
+    self.embeds.remove(&alert);
+    self
+  }
+
+  /// Posts as this name instead of the one set on the webhook.
+  pub fn username<S: Into<String>>(mut self, username: S) -> DiscordWebhookSink {
+    self.username = Some(username.into());
+    self
+  }
+
+  pub fn min_raid_viewers(mut self, viewers: u32) -> DiscordWebhookSink {
+    self.min_raid_viewers = viewers;
+    self
+  }
+
+  pub fn min_cheer_bits(mut self, bits: u32) -> DiscordWebhookSink {
+    self.min_cheer_bits = bits;
+    self
+  }
+
+  fn alert_for(&self, event: &Event) -> Option<DiscordAlert> {
+    match event {
+      Event::StreamOnline(_) => Some(DiscordAlert::StreamOnline),
+      Event::Raid(raid) if raid.viewers >= self.min_raid_viewers => Some(DiscordAlert::Raid),
+      Event::Cheer(cheer) if cheer.bits >= self.min_cheer_bits => Some(DiscordAlert::Cheer),
+      _ => None,
+    }
+  }
+}
+
+impl EventSink for DiscordWebhookSink {
+  fn consume(&mut self, event: &Event) {
+    let Some(embed) = self
+      .alert_for(event)
+      .and_then(|alert| self.embeds.get(&alert))
+    else {
+      return;
+    };
+
+    let fields = match serde_json::to_value(event) {
+      Ok(fields) => fields,
+      Err(e) => {
+        error!("Couldn't fill in the Discord embed: {}", e);
+        return;
+      }
+    };
+    let payload = embed.payload(&fields, self.username.as_deref());
+    let _ = self.posts.send(payload.to_string());
+  }
+}
+
+// Discord answers 429 with how long to wait in seconds
+fn post(webhook_url: &str, body: String) {
+  for _ in 0..=MAX_DISCORD_WEBHOOK_RETRIES {
+    let response = TwitchHttpRequest::new(webhook_url)
+      .json_content()
+      .is_post(body.to_owned())
+      .send();
+    match response {
+      Ok(response) if response.status == 429 => {
+        let retry_after = serde_json::from_str::<Value>(&response.raw)
+          .ok()
+          .and_then(|body| body["retry_after"].as_f64())
+          .unwrap_or(1.0);
+        thread::sleep(Duration::from_secs_f64(retry_after.clamp(0.0, 60.0)));
+      }
+      Ok(response) if response.status >= 400 => {
+        error!(
+          "Discord refused the webhook post: {} {}",
+          response.status, response.raw
+        );
+        return;
+      }
+      Ok(_) => return,
+      Err(e) => {
+        error!("Posting to Discord failed: {}", e);
+        return;
+      }
+    }
+  }
+  error!("Gave up posting to Discord after being rate limited");
+}
+
+// Replaces `{path.to.field}` with the field's value, strings without their quotes
+fn fill_template(template: &str, fields: &Value) -> String {
+  let mut filled = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    filled.push_str(&rest[..start]);
+    let after = &rest[start + 1..];
+    let Some(end) = after.find('}') else {
+      rest = &rest[start..];
+      break;
+    };
+
+    let path = &after[..end];
+    let value = path
+      .split('.')
+      .try_fold(fields, |value, key| value.get(key));
+    match value {
+      Some(Value::String(text)) => filled.push_str(text),
+      Some(Value::Null) => {}
+      Some(value) => filled.push_str(&value.to_string()),
+      None => filled.push_str(&rest[start..start + end + 2]),
+    }
+    rest = &after[end + 1..];
+  }
+  filled.push_str(rest);
+  filled
+}
@@ -0,0 +1,140 @@
+#[cfg(all(feature = "discord", not(target_arch = "wasm32")))]
+pub mod discord;
+#[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+pub mod mqtt;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod overlay_server;
+#[cfg(all(feature = "redis", not(target_arch = "wasm32")))]
+pub mod redis;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub mod sqlite;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Stdout, Write};
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex};
+
+use crate::{error, Event, EventSubError};
+
+/// Somewhere events are sent as they arrive, such as a file or a webhook. Sinks added to the
+/// client see every event, alongside `receive_messages` and any event streams, on the thread
+/// that received it, so they shouldn't block for long.
+pub trait EventSink: Send {
+  fn consume(&mut self, event: &Event);
+
+  /// Called instead of `consume` for notifications from Twitch, with the message id that
+  /// stays the same when Twitch delivers it again. Events without one, such as chat read
+  /// over IRC, only go to `consume`.
+  fn consume_notification(&mut self, event: &Event, _message_id: &str) {
+    self.consume(event)
+  }
+}
+
+impl<F: FnMut(&Event) + Send> EventSink for F {
+  fn consume(&mut self, event: &Event) {
+    self(event)
+  }
+}
+
+/// Writes each event as a line of json, the same fields Twitch sent.
+pub struct JsonLinesSink<W: Write + Send> {
+  writer: W,
+}
+
+impl<W: Write + Send> JsonLinesSink<W> {
+  pub fn new(writer: W) -> JsonLinesSink<W> {
+    JsonLinesSink { writer }
+  }
+}
+
+impl JsonLinesSink<File> {
+  /// Appends to the file, creating it if it doesn't exist.
+  pub fn file<P: AsRef<Path>>(path: P) -> Result<JsonLinesSink<File>, EventSubError> {
+    OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)
+      .map(JsonLinesSink::new)
+      .map_err(|e| EventSubError::WriteError(e.to_string()))
+  }
+}
+
+impl JsonLinesSink<Stdout> {
+  pub fn stdout() -> JsonLinesSink<Stdout> {
+    JsonLinesSink::new(io::stdout())
+  }
+}
+
+impl<W: Write + Send> EventSink for JsonLinesSink<W> {
+  fn consume(&mut self, event: &Event) {
+    let written = serde_json::to_string(event)
+      .map_err(|e| e.to_string())
+      .and_then(|line| {
+        writeln!(self.writer, "{}", line)
+          .and_then(|_| self.writer.flush())
+          .map_err(|e| e.to_string())
+      });
+    if let Err(e) = written {
+      error!("Failed to write event: {}", e);
+    }
+  }
+}
+
+/// Forwards a copy of each event to a channel, for handing them to another thread.
+pub struct ChannelSink {
+  sender: Sender<Event>,
+}
+
+impl ChannelSink {
+  pub fn new() -> (ChannelSink, Receiver<Event>) {
+    let (sender, receiver) = channel();
+    (ChannelSink { sender }, receiver)
+  }
+}
+
+impl From<Sender<Event>> for ChannelSink {
+  fn from(sender: Sender<Event>) -> ChannelSink {
+    ChannelSink { sender }
+  }
+}
+
+impl EventSink for ChannelSink {
+  fn consume(&mut self, event: &Event) {
+    // Nothing to do once the receiver has gone
+    let _ = self.sender.send(event.clone());
+  }
+}
+
+/// The sinks shared between the client's threads.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Default)]
+pub(crate) struct EventSinks {
+  sinks: Arc<Mutex<Vec<Box<dyn EventSink>>>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl EventSinks {
+  pub fn add(&self, sink: Box<dyn EventSink>) {
+    self.sinks.lock().unwrap().push(sink);
+  }
+
+  pub fn consume(&self, event: &Event, message_id: Option<&str>) {
+    for sink in self.sinks.lock().unwrap().iter_mut() {
+      match message_id {
+        Some(message_id) => sink.consume_notification(event, message_id),
+        None => sink.consume(event),
+      }
+    }
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl fmt::Debug for EventSinks {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} event sinks", self.sinks.lock().unwrap().len())
+  }
+}
@@ -0,0 +1,95 @@
+use std::thread;
+use std::time::Duration;
+
+use rumqttc::{Client, MqttOptions, QoS};
+
+use crate::{error, Event, EventSink, EventSubError};
+
+/// Publishes each event's json to an MQTT broker, on a topic per event type with the dots
+/// turned into levels, such as `twitch/channel/chat/message`. Subscribe to `twitch/#` for
+/// everything. The connection is kept up on its own thread and closed when this is dropped.
+pub struct MqttSink {
+  client: Client,
+  topic_prefix: String,
+  qos: QoS,
+}
+
+impl MqttSink {
+  /// Connects to the broker at `host` and `port`, as `client_id`.
+  pub fn connect<S: Into<String>, T: Into<String>>(
+    client_id: S,
+    host: T,
+    port: u16,
+  ) -> Result<MqttSink, EventSubError> {
+    let mut options = MqttOptions::new(client_id, host, port);
+    options.set_keep_alive(Duration::from_secs(30));
+    MqttSink::with_options(options)
+  }
+
+  /// Connects with options for logins, TLS and the like.
+  pub fn with_options(options: MqttOptions) -> Result<MqttSink, EventSubError> {
+    let (client, mut connection) = Client::new(options, 64);
+    thread::Builder::new()
+      .name("mqtt sink".to_owned())
+      .spawn(move || {
+        for notification in connection.iter() {
+          match notification {
+            Ok(_) => {}
+            // Ends once the sink has been dropped
+            Err(rumqttc::ConnectionError::RequestsDone) => return,
+            Err(e) => {
+              error!("MQTT connection error: {}", e);
+              thread::sleep(Duration::from_secs(1));
+            }
+          }
+        }
+      })
+      .map_err(|e| EventSubError::UnhandledError(e.to_string()))?;
+
+    Ok(MqttSink {
+      client,
+      topic_prefix: "twitch".to_owned(),
+      qos: QoS::AtLeastOnce,
+    })
+  }
+
+  /// The first level of the topic, `twitch` by default.
+  pub fn topic_prefix<S: Into<String>>(mut self, prefix: S) -> MqttSink {
+    self.topic_prefix = prefix.into();
+    self
+  }
+
+  /// `QoS::AtLeastOnce` by default.
+  pub fn qos(mut self, qos: QoS) -> MqttSink {
+    self.qos = qos;
+    self
+  }
+}
+
+impl EventSink for MqttSink {
+  fn consume(&mut self, event: &Event) {
+    let payload = match serde_json::to_vec(event) {
+      Ok(payload) => payload,
+      Err(e) => {
+        error!("Failed to serialise event for MQTT: {}", e);
+        return;
+      }
+    };
+
+    let topic = format!(
+      "{}/{}",
+      self.topic_prefix,
+      event.subscription_type().replace('.', "/")
+    );
+    // Doesn't wait, events are dropped while the broker can't keep up
+    if let Err(e) = self.client.try_publish(topic, self.qos, false, payload) {
+      error!("Failed to publish event: {}", e);
+    }
+  }
+}
+
+impl Drop for MqttSink {
+  fn drop(&mut self) {
+    let _ = self.client.try_disconnect();
+  }
+}
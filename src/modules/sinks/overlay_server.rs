@@ -0,0 +1,205 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::json;
+use websocket::sender::Writer;
+use websocket::sync::{Client, Server};
+use websocket::OwnedMessage;
+
+use crate::{error, info, Deserialise, Event, EventSink, EventSubError};
+
+// Overlays that stop reading are dropped rather than holding up events for everyone
+const OVERLAY_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A local websocket server that sends every event to the overlays connected to it, such as
+/// OBS browser sources, as `{"type": "channel.chat.message", "event": {...}}` with the
+/// event's fields as Twitch sent them.
+///
+/// Overlays can pick the event types they want in the url, e.g.
+/// `ws://127.0.0.1:8080/?events=channel.chat.message,channel.poll.*`, or at any time by
+/// sending `{"events": [...]}`. Without a filter they get everything. The server stops when
+/// this is dropped.
+pub struct OverlayServer {
+  address: SocketAddr,
+  overlays: Arc<Mutex<Vec<Overlay>>>,
+  shutdown: Arc<AtomicBool>,
+}
+
+struct Overlay {
+  id: usize,
+  writer: Writer<TcpStream>,
+  // Event types the overlay wants, with a trailing `*` matching any ending. Empty for all
+  events: Vec<String>,
+}
+
+impl Overlay {
+  fn wants(&self, kind: &str) -> bool {
+    self.events.is_empty()
+      || self
+        .events
+        .iter()
+        .any(|wanted| match wanted.strip_suffix('*') {
+          Some(prefix) => kind.starts_with(prefix),
+          None => wanted == kind,
+        })
+  }
+}
+
+#[derive(Deserialise)]
+struct FilterMessage {
+  events: Vec<String>,
+}
+
+impl OverlayServer {
+  /// Starts listening, e.g. on `127.0.0.1:8080`. Use port 0 for any free port and
+  /// `address` to find out which.
+  pub fn bind<A: ToSocketAddrs>(address: A) -> Result<OverlayServer, EventSubError> {
+    let server_error =
+      |e: std::io::Error| EventSubError::UnhandledError(format!("Overlay server: {}", e));
+    let mut server = Server::bind(address).map_err(server_error)?;
+    let address = server.local_addr().map_err(server_error)?;
+
+    let overlays = Arc::new(Mutex::new(Vec::new()));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let accept_overlays = overlays.clone();
+    let accept_shutdown = shutdown.clone();
+    thread::spawn(move || {
+      let mut next_id = 0;
+      while !accept_shutdown.load(Ordering::Relaxed) {
+        let Ok(upgrade) = server.accept() else {
+          continue;
+        };
+        if accept_shutdown.load(Ordering::Relaxed) {
+          break;
+        }
+
+        let events = events_from_uri(&upgrade.uri());
+        match upgrade.accept() {
+          Ok(client) => {
+            next_id += 1;
+            if let Err(e) = add_overlay(&accept_overlays, client, next_id, events) {
+              error!("Failed to set up overlay connection: {}", e);
+            }
+          }
+          Err((_, e)) => error!("Overlay websocket handshake failed: {}", e),
+        }
+      }
+    });
+
+    info!("Overlay server listening on ws://{}", address);
+    Ok(OverlayServer {
+      address,
+      overlays,
+      shutdown,
+    })
+  }
+
+  pub fn address(&self) -> SocketAddr {
+    self.address
+  }
+
+  /// How many overlays are connected.
+  pub fn connected(&self) -> usize {
+    self.overlays.lock().unwrap().len()
+  }
+}
+
+fn add_overlay(
+  overlays: &Arc<Mutex<Vec<Overlay>>>,
+  client: Client<TcpStream>,
+  id: usize,
+  events: Vec<String>,
+) -> std::io::Result<()> {
+  client
+    .stream_ref()
+    .set_write_timeout(Some(OVERLAY_WRITE_TIMEOUT))?;
+  let (mut reader, writer) = client.split()?;
+  overlays
+    .lock()
+    .unwrap()
+    .push(Overlay { id, writer, events });
+
+  // Reads filter changes and pings until the overlay goes away
+  let overlays = overlays.clone();
+  thread::spawn(move || {
+    for message in reader.incoming_messages() {
+      let mut overlays = overlays.lock().unwrap();
+      let Some(overlay) = overlays.iter_mut().find(|overlay| overlay.id == id) else {
+        return;
+      };
+
+      match message {
+        Ok(OwnedMessage::Text(text)) => match serde_json::from_str::<FilterMessage>(&text) {
+          Ok(filter) => overlay.events = filter.events,
+          Err(e) => error!("Unknown message from overlay: {} {}", text, e),
+        },
+        Ok(OwnedMessage::Ping(data)) => {
+          let _ = overlay.writer.send_message(&OwnedMessage::Pong(data));
+        }
+        Ok(OwnedMessage::Close(_)) | Err(_) => break,
+        Ok(_) => {}
+      }
+    }
+
+    overlays.lock().unwrap().retain(|overlay| overlay.id != id);
+  });
+
+  Ok(())
+}
+
+// The `events` query parameter, comma separated
+fn events_from_uri(uri: &str) -> Vec<String> {
+  let Some((_, query)) = uri.split_once('?') else {
+    return Vec::new();
+  };
+
+  query
+    .split('&')
+    .filter_map(|pair| pair.strip_prefix("events="))
+    .flat_map(|events| {
+      let events = events.replace("%2C", ",").replace("%2A", "*");
+      events.split(',').map(str::to_owned).collect::<Vec<_>>()
+    })
+    .filter(|event| !event.is_empty())
+    .collect()
+}
+
+impl EventSink for OverlayServer {
+  fn consume(&mut self, event: &Event) {
+    let kind = event.subscription_type();
+    let message = json!({ "type": kind, "event": event }).to_string();
+    let message = OwnedMessage::Text(message);
+
+    self.overlays.lock().unwrap().retain_mut(|overlay| {
+      if !overlay.wants(kind) {
+        return true;
+      }
+      let sent = overlay.writer.send_message(&message).is_ok();
+      if !sent {
+        let _ = overlay.writer.shutdown_all();
+      }
+      sent
+    });
+  }
+}
+
+impl Drop for OverlayServer {
+  fn drop(&mut self) {
+    self.shutdown.store(true, Ordering::Relaxed);
+    // Wakes the accept thread so it sees the shutdown
+    let mut address = self.address;
+    if address.ip().is_unspecified() {
+      address.set_ip(match address {
+        SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+        SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+      });
+    }
+    let _ = TcpStream::connect_timeout(&address, Duration::from_secs(1));
+    for overlay in self.overlays.lock().unwrap().drain(..) {
+      let _ = overlay.writer.shutdown_all();
+    }
+  }
+}
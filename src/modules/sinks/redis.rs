@@ -0,0 +1,73 @@
+use ::redis::{Client, Connection};
+
+use crate::{error, Event, EventSink, EventSubError};
+
+/// Publishes each event's json to Redis pub/sub, on a channel per event type such as
+/// `twitch:channel.chat.message`, so other processes can share one Twitch connection.
+/// Subscribe to the pattern `twitch:*` for everything.
+pub struct RedisSink {
+  client: Client,
+  // Opened again on the next event after a failure
+  connection: Option<Connection>,
+  channel_prefix: String,
+}
+
+impl RedisSink {
+  /// Connects to a url such as `redis://127.0.0.1/`.
+  pub fn connect<S: AsRef<str>>(url: S) -> Result<RedisSink, EventSubError> {
+    let client = Client::open(url.as_ref()).map_err(redis_error)?;
+    let connection = client.get_connection().map_err(redis_error)?;
+
+    Ok(RedisSink {
+      client,
+      connection: Some(connection),
+      channel_prefix: "twitch:".to_owned(),
+    })
+  }
+
+  /// Put in front of the event type to make the channel name, `twitch:` by default.
+  pub fn channel_prefix<S: Into<String>>(mut self, prefix: S) -> RedisSink {
+    self.channel_prefix = prefix.into();
+    self
+  }
+
+  fn publish(&mut self, channel: String, payload: String) -> Result<(), EventSubError> {
+    let connection = match &mut self.connection {
+      Some(connection) => connection,
+      None => self
+        .connection
+        .insert(self.client.get_connection().map_err(redis_error)?),
+    };
+
+    ::redis::cmd("PUBLISH")
+      .arg(channel)
+      .arg(payload)
+      .query::<i64>(connection)
+      .map(|_| ())
+      .map_err(|e| {
+        self.connection = None;
+        redis_error(e)
+      })
+  }
+}
+
+fn redis_error<E: ToString>(e: E) -> EventSubError {
+  EventSubError::WriteError(format!("Redis: {}", e.to_string()))
+}
+
+impl EventSink for RedisSink {
+  fn consume(&mut self, event: &Event) {
+    let payload = match serde_json::to_string(event) {
+      Ok(payload) => payload,
+      Err(e) => {
+        error!("Failed to serialise event for Redis: {}", e);
+        return;
+      }
+    };
+
+    let channel = format!("{}{}", self.channel_prefix, event.subscription_type());
+    if let Err(e) = self.publish(channel, payload) {
+      error!("Failed to publish event: {}", e);
+    }
+  }
+}
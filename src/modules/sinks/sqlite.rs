@@ -0,0 +1,352 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+
+use crate::{error, Event, EventSink, EventSubError, User};
+
+/// The tables events are saved to. Users are saved with the id, login and name they had at
+/// the time, `received_at` is milliseconds since the unix epoch.
+pub const SQLITE_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS chat_messages (
+  message_id TEXT PRIMARY KEY,
+  broadcaster_id TEXT NOT NULL,
+  user_id TEXT NOT NULL,
+  user_login TEXT NOT NULL,
+  user_name TEXT NOT NULL,
+  text TEXT NOT NULL,
+  bits INTEGER NOT NULL,
+  received_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS chat_messages_user ON chat_messages (user_id);
+
+CREATE TABLE IF NOT EXISTS redemptions (
+  redemption_id TEXT PRIMARY KEY,
+  broadcaster_id TEXT NOT NULL,
+  user_id TEXT NOT NULL,
+  user_login TEXT NOT NULL,
+  user_name TEXT NOT NULL,
+  reward_id TEXT NOT NULL,
+  reward_title TEXT NOT NULL,
+  cost INTEGER NOT NULL,
+  user_input TEXT NOT NULL,
+  redeemed_at TEXT NOT NULL,
+  received_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS redemptions_reward ON redemptions (reward_id);
+
+-- kind is subscribe, resubscribe or gift. months is only set for resubscriptions and
+-- gifted, how many were given at once, only for gifts. message_id is the notification's,
+-- so events Twitch delivers again or that are replayed are only saved once. Anonymous
+-- gifts and cheers have empty user columns
+CREATE TABLE IF NOT EXISTS subscriptions (
+  id INTEGER PRIMARY KEY,
+  message_id TEXT UNIQUE,
+  broadcaster_id TEXT NOT NULL,
+  user_id TEXT NOT NULL,
+  user_login TEXT NOT NULL,
+  user_name TEXT NOT NULL,
+  kind TEXT NOT NULL,
+  tier TEXT NOT NULL,
+  is_gift INTEGER NOT NULL,
+  months INTEGER,
+  gifted INTEGER,
+  message TEXT,
+  received_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cheers (
+  id INTEGER PRIMARY KEY,
+  message_id TEXT UNIQUE,
+  broadcaster_id TEXT NOT NULL,
+  user_id TEXT NOT NULL,
+  user_login TEXT NOT NULL,
+  user_name TEXT NOT NULL,
+  is_anonymous INTEGER NOT NULL,
+  bits INTEGER NOT NULL,
+  message TEXT NOT NULL,
+  received_at INTEGER NOT NULL
+);
+";
+
+const INSERT_SUBSCRIPTION: &str = "INSERT OR IGNORE INTO subscriptions (broadcaster_id,
+  user_id, user_login, user_name, kind, tier, is_gift, months, gifted, message, received_at,
+  message_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)";
+
+/// How many chat messages a user has sent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChatterCount {
+  pub user_id: String,
+  pub user_login: String,
+  pub user_name: String,
+  pub messages: u64,
+}
+
+/// How often a reward has been redeemed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RewardCount {
+  pub reward_id: String,
+  pub reward_title: String,
+  pub redemptions: u64,
+  pub points_spent: u64,
+}
+
+/// Saves chat messages, redemptions, subs and cheers to a SQLite database, see
+/// `SQLITE_SCHEMA` for the tables. Once the sink has been added to the client, open the
+/// database again to query it while events are still being saved.
+pub struct SqliteSink {
+  connection: Connection,
+}
+
+fn database_error<E: ToString>(e: E) -> EventSubError {
+  EventSubError::DatabaseError(e.to_string())
+}
+
+impl SqliteSink {
+  /// Opens the database, creating it and its tables if they don't exist.
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<SqliteSink, EventSubError> {
+    let connection = Connection::open(path).map_err(database_error)?;
+    // Lets other connections read while events are written
+    connection
+      .query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+      .map_err(database_error)?;
+    connection
+      .busy_timeout(Duration::from_secs(5))
+      .map_err(database_error)?;
+    connection
+      .execute_batch(SQLITE_SCHEMA)
+      .map_err(database_error)?;
+
+    Ok(SqliteSink { connection })
+  }
+
+  /// The users who have sent the most chat messages, most first.
+  pub fn top_chatters(&self, limit: usize) -> Result<Vec<ChatterCount>, EventSubError> {
+    let mut statement = self
+      .connection
+      .prepare(
+        "SELECT user_id, user_login, user_name, COUNT(*) AS messages FROM chat_messages
+         GROUP BY user_id ORDER BY messages DESC LIMIT ?1",
+      )
+      .map_err(database_error)?;
+    let chatters = statement
+      .query_map([limit as i64], |row| {
+        Ok(ChatterCount {
+          user_id: row.get(0)?,
+          user_login: row.get(1)?,
+          user_name: row.get(2)?,
+          messages: row.get(3)?,
+        })
+      })
+      .map_err(database_error)?;
+
+    chatters.collect::<Result<_, _>>().map_err(database_error)
+  }
+
+  /// Every reward that has been redeemed, most redeemed first.
+  pub fn redemption_counts(&self) -> Result<Vec<RewardCount>, EventSubError> {
+    let mut statement = self
+      .connection
+      .prepare(
+        "SELECT reward_id, reward_title, COUNT(*) AS redemptions, SUM(cost) FROM redemptions
+         GROUP BY reward_id ORDER BY redemptions DESC",
+      )
+      .map_err(database_error)?;
+    let rewards = statement
+      .query_map([], |row| {
+        Ok(RewardCount {
+          reward_id: row.get(0)?,
+          reward_title: row.get(1)?,
+          redemptions: row.get(2)?,
+          points_spent: row.get(3)?,
+        })
+      })
+      .map_err(database_error)?;
+
+    rewards.collect::<Result<_, _>>().map_err(database_error)
+  }
+
+  fn save(&self, event: &Event, message_id: Option<&str>) -> rusqlite::Result<()> {
+    let received_at = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_millis() as i64;
+
+    match event {
+      Event::ChatMessage(data) => {
+        // Messages can arrive twice when replayed, they're only saved once
+        self.connection.execute(
+          "INSERT OR IGNORE INTO chat_messages VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+          params![
+            data.message_id,
+            data.broadcaster_user.id,
+            data.chatter_user.id,
+            data.chatter_user.login,
+            data.chatter_user.name,
+            data.message.text,
+            data.cheer.as_ref().map_or(0, |cheer| cheer.bits),
+            received_at,
+          ],
+        )?;
+      }
+      Event::PointsCustomRewardRedeem(data) => {
+        self.connection.execute(
+          "INSERT OR IGNORE INTO redemptions
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+          params![
+            data.id,
+            data.broadcaster.id,
+            data.user.id,
+            data.user.login,
+            data.user.name,
+            data.reward.id,
+            data.reward.title,
+            data.reward.cost,
+            data.user_input,
+            data.redeemed_at,
+            received_at,
+          ],
+        )?;
+      }
+      Event::Subscribe(data) => {
+        self.connection.execute(
+          INSERT_SUBSCRIPTION,
+          params![
+            data.braodcaster.id,
+            data.user.id,
+            data.user.login,
+            data.user.name,
+            "subscribe",
+            data.tier,
+            data.is_gift,
+            None::<u32>,
+            None::<u32>,
+            None::<String>,
+            received_at,
+            message_id,
+          ],
+        )?;
+      }
+      Event::SubscriptionMessage(data) => {
+        self.connection.execute(
+          INSERT_SUBSCRIPTION,
+          params![
+            data.broadcaster.id,
+            data.user.id,
+            data.user.login,
+            data.user.name,
+            "resubscribe",
+            data.tier,
+            false,
+            data.cumulative_months,
+            None::<u32>,
+            data.message.text,
+            received_at,
+            message_id,
+          ],
+        )?;
+      }
+      Event::SubscriptionGift(data) => {
+        let (user_id, user_login, user_name) = user_columns(data.user.as_ref());
+        self.connection.execute(
+          INSERT_SUBSCRIPTION,
+          params![
+            data.broadcaster.id,
+            user_id,
+            user_login,
+            user_name,
+            "gift",
+            data.tier,
+            true,
+            None::<u32>,
+            data.total,
+            None::<String>,
+            received_at,
+            message_id,
+          ],
+        )?;
+      }
+      Event::Cheer(data) => {
+        let (user_id, user_login, user_name) = user_columns(data.user.as_ref());
+        self.connection.execute(
+          "INSERT OR IGNORE INTO cheers (broadcaster_id, user_id, user_login, user_name,
+           is_anonymous, bits, message, received_at, message_id)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+          params![
+            data.broadcaster.id,
+            user_id,
+            user_login,
+            user_name,
+            data.is_anonymous,
+            data.bits,
+            data.message,
+            received_at,
+            message_id,
+          ],
+        )?;
+      }
+      _ => {}
+    }
+
+    Ok(())
+  }
+}
+
+// Anonymous gifters and cheerers are stored without a user
+fn user_columns(user: Option<&User>) -> (&str, &str, &str) {
+  user.map_or(("", "", ""), |user| {
+    (user.id.as_str(), user.login.as_str(), user.name.as_str())
+  })
+}
+
+impl EventSink for SqliteSink {
+  fn consume(&mut self, event: &Event) {
+    if let Err(e) = self.save(event, None) {
+      error!("Failed to save event to the database: {}", e);
+    }
+  }
+
+  fn consume_notification(&mut self, event: &Event, message_id: &str) {
+    if let Err(e) = self.save(event, Some(message_id)) {
+      error!("Failed to save event to the database: {}", e);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::GenericMessage;
+
+  fn fixture_event(json: &str) -> (Event, String) {
+    let message = serde_json::from_str::<GenericMessage>(json).unwrap();
+    let event = message.payload.and_then(|payload| payload.event).unwrap();
+    (event, message.metadata.message_id)
+  }
+
+  fn count(sink: &SqliteSink, table: &str) -> i64 {
+    sink
+      .connection
+      .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+        row.get(0)
+      })
+      .unwrap()
+  }
+
+  #[test]
+  fn saves_redelivered_subs_and_cheers_once() {
+    let mut sink = SqliteSink::open(":memory:").unwrap();
+    for json in [
+      include_str!("../../../tests/fixtures/notifications/channel.subscribe.json"),
+      include_str!("../../../tests/fixtures/notifications/channel.cheer.json"),
+      include_str!("../../../tests/fixtures/notifications/channel.cheer.anonymous.json"),
+    ] {
+      let (event, message_id) = fixture_event(json);
+      sink.consume_notification(&event, &message_id);
+      sink.consume_notification(&event, &message_id);
+    }
+
+    assert_eq!(count(&sink, "subscriptions"), 1);
+    assert_eq!(count(&sink, "cheers"), 2);
+  }
+}
@@ -0,0 +1,147 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::modules::consts::RECONNECT_ATTEMPTS;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionState {
+  /// Connected to the websocket, waiting for Twitch's welcome message.
+  Connecting,
+  Connected {
+    session_id: String,
+    since: Instant,
+  },
+  /// The connection was lost or Twitch asked for a new one, events may be missed until it's
+  /// connected again.
+  Reconnecting,
+  Disconnected {
+    reason: String,
+  },
+}
+
+impl ConnectionState {
+  #[cfg_attr(
+    any(feature = "only_raw_responses", target_arch = "wasm32"),
+    allow(dead_code)
+  )]
+  pub(crate) fn shut_down() -> ConnectionState {
+    ConnectionState::Disconnected {
+      reason: "Client shut down".to_owned(),
+    }
+  }
+}
+
+/// What the client does with notifications Twitch delivers more than once, which it may do
+/// around reconnects.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DeliveryGuarantee {
+  /// Notifications already seen within the deduplication window are dropped. Seen ids are
+  /// shared by every connection and kept across reconnects.
+  #[default]
+  AtMostOnce,
+  /// Every notification is passed on as Twitch sent it, duplicates included, see
+  /// `MessageType::message_id` for deduplicating them yourself.
+  AtLeastOnce,
+}
+
+/// How the client reconnects after losing its websocket connection. The delay doubles after
+/// each failed attempt, up to `max_delay`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReconnectPolicy {
+  pub attempts: u32,
+  pub initial_delay: Duration,
+  pub max_delay: Duration,
+}
+
+impl ReconnectPolicy {
+  /// Gives up as soon as the connection is lost.
+  pub const NEVER: ReconnectPolicy = ReconnectPolicy {
+    attempts: 0,
+    initial_delay: Duration::ZERO,
+    max_delay: Duration::ZERO,
+  };
+
+  /// How long to wait before the given attempt, counting from 0.
+  pub fn delay(&self, attempt: u32) -> Duration {
+    self
+      .initial_delay
+      .checked_mul(1 << attempt.min(31))
+      .unwrap_or(self.max_delay)
+      .min(self.max_delay)
+  }
+}
+
+impl Default for ReconnectPolicy {
+  fn default() -> ReconnectPolicy {
+    ReconnectPolicy {
+      attempts: RECONNECT_ATTEMPTS,
+      initial_delay: Duration::from_secs(1),
+      max_delay: Duration::from_secs(16),
+    }
+  }
+}
+
+/// How one of the client's websocket connections is doing.
+#[derive(Clone, Debug)]
+pub struct ConnectionStatus {
+  pub connection: usize,
+  pub state: ConnectionState,
+  /// Last time Twitch showed the connection is alive, by a keepalive or any other message.
+  pub last_keepalive: Option<Instant>,
+  /// How long the last message took to arrive after Twitch sent it. Worked out from the
+  /// message's timestamp, so it's off by however far the local clock is from Twitch's.
+  pub latency: Option<Duration>,
+}
+
+impl ConnectionStatus {
+  #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+  pub(crate) fn new(connection: usize) -> ConnectionStatus {
+    ConnectionStatus {
+      connection,
+      state: ConnectionState::Connecting,
+      last_keepalive: None,
+      latency: None,
+    }
+  }
+
+  pub fn is_connected(&self) -> bool {
+    matches!(self.state, ConnectionState::Connected { .. })
+  }
+
+  /// Records a message from Twitch sent at `message_timestamp`.
+  #[cfg_attr(
+    any(feature = "only_raw_responses", target_arch = "wasm32"),
+    allow(dead_code)
+  )]
+  pub(crate) fn message_received(&mut self, message_timestamp: &str) {
+    self.last_keepalive = Some(Instant::now());
+    self.latency = parse_timestamp(message_timestamp)
+      .and_then(|sent| SystemTime::now().duration_since(sent).ok());
+  }
+}
+
+// Parses Twitch's RFC3339 timestamps, e.g. 2023-07-19T14:56:51.634234626Z
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub(crate) fn parse_timestamp(timestamp: &str) -> Option<SystemTime> {
+  let (date, time) = timestamp.strip_suffix('Z')?.split_once('T')?;
+
+  let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+  let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+
+  let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+  let mut time = time.splitn(3, ':').map(|part| part.parse::<u64>().ok());
+  let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+
+  // Civil date to days since the epoch
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let year_of_era = year.rem_euclid(400);
+  let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+  let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  let days = u64::try_from(era * 146097 + day_of_era - 719468).ok()?;
+
+  let nanos = format!("{:0<9}", &fraction[..fraction.len().min(9)])
+    .parse::<u32>()
+    .ok()?;
+
+  Some(UNIX_EPOCH + Duration::new(days * 86400 + hours * 3600 + minutes * 60 + seconds, nanos))
+}
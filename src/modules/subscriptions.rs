@@ -1,33 +1,57 @@
-use crate::modules::generic_message::*;
-use crate::TwitchKeys;
+use crate::modules::{consts::*, generic_message::*, scope::Scope};
+use crate::{error, BroadcasterId, EventSubError, RewardId, TwitchKeys, UserId};
 
 use crate::{Deserialise, Serialise};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+  EventSubSubscriptionFilter, EventSubSubscriptionInfo, EventSubSubscriptionStatus, SubscriptionId,
+  TwitchEventSubApi,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+  thread,
+  time::{Duration, Instant},
+};
 
 macro_rules! from_string {
-    ($enum_name:ident { $($variant:ident),* }) => {
+    ($enum_name:ident { $($variant:ident $({ $($field:ident: $value:expr),* })?),* }) => {
         pub fn from_string(t: &str) -> Option<$enum_name> {
             $(
-                if $enum_name::$variant.tag() == t {
-                    return Some($enum_name::$variant);
+                let subscription = $enum_name::$variant $({ $($field: $value),* })?;
+                if subscription.tag() == t {
+                    return Some(subscription);
                 }
             )*
             None
         }
+
+        /// Every subscription, with the defaults for the ones that take options.
+        pub fn all() -> Vec<$enum_name> {
+            vec![$($enum_name::$variant $({ $($field: $value),* })?),*]
+        }
     };
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum Subscription {
   UserUpdate,
   ChannelFollow,
-  ChannelRaid,
+  ChannelRaid {
+    direction: RaidDirection,
+  },
   ChannelUpdate,
+  StreamOnline,
+  StreamOffline,
   ChannelSubscribe,
   ChannelSubscriptionEnd,
   ChannelSubscriptionGift,
   ChannelSubscriptionMessage,
   ChannelCheer,
-  ChannelPointsCustomRewardRedeem,
+  /// Redemptions of every custom reward, or only the one with `reward_id`.
+  ChannelPointsCustomRewardRedeem {
+    reward_id: Option<RewardId>,
+  },
   ChannelPointsAutoRewardRedeem,
   ChannelPollBegin,
   ChannelPollProgress,
@@ -48,10 +72,54 @@ pub enum Subscription {
   BanTimeoutUser,
   DeleteMessage,
   AdBreakBegin,
+  ReadModerators,
+  ManageModerators,
+  ReadVips,
+  ManageVips,
+  ReadBlockedTerms,
+  ManageBlockedTerms,
+  AutoModMessageHold,
+  AutoModMessageUpdate,
+  AutoModSettingsUpdate,
+  AutoModTermsUpdate,
+  ReadAutoModSettings,
+  ManageAutoModSettings,
+  CheckAutoModStatus,
+  WarnUser,
+  ManageCustomRewards,
+  ManageSchedule,
+  ChannelShieldModeBegin,
+  ChannelShieldModeEnd,
+  ManageShieldMode,
+  ChannelCharityCampaignDonate,
+  ChannelCharityCampaignStart,
+  ChannelCharityCampaignProgress,
+  ChannelCharityCampaignStop,
+  ReadBlockedUsers,
+  ManageBlockedUsers,
+  ManageChatColour,
+  ChannelUnbanRequestCreate,
+  ChannelUnbanRequestResolve,
+  ManageUnbanRequests,
+  ReadModeratedChannels,
+  ReadFollows,
+  ManageBroadcast,
+  ManageChatSettings,
   Custom((String, String, EventSubscription)),
+  // Asks for a scope during authorisation that no subscription needs
+  AdditionalScope(Scope),
+}
+
+/// Which raids a `Subscription::ChannelRaid` is for, add one of each to get both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RaidDirection {
+  /// Other channels raiding the broadcaster.
+  Incoming,
+  /// The broadcaster raiding other channels.
+  Outgoing,
 }
 
-#[derive(Serialise, Deserialise, Debug, Clone)]
+#[derive(Serialise, Deserialise, Debug, Clone, PartialEq)]
 pub struct EventSubscription {
   #[serde(rename = "type")]
   pub kind: String,
@@ -64,14 +132,16 @@ impl Subscription {
   from_string!(Subscription {
     UserUpdate,
     ChannelFollow,
-    ChannelRaid,
+    ChannelRaid { direction: RaidDirection::Incoming },
     ChannelUpdate,
+    StreamOnline,
+    StreamOffline,
     ChannelSubscribe,
     ChannelSubscriptionEnd,
     ChannelSubscriptionGift,
     ChannelSubscriptionMessage,
     ChannelCheer,
-    ChannelPointsCustomRewardRedeem,
+    ChannelPointsCustomRewardRedeem { reward_id: None },
     ChannelPointsAutoRewardRedeem,
     ChannelPollBegin,
     ChannelPollProgress,
@@ -91,124 +161,273 @@ impl Subscription {
     ChatMessage,
     BanTimeoutUser,
     DeleteMessage,
-    AdBreakBegin
+    AdBreakBegin,
+    ReadModerators,
+    ManageModerators,
+    ReadVips,
+    ManageVips,
+    ReadBlockedTerms,
+    ManageBlockedTerms,
+    AutoModMessageHold,
+    AutoModMessageUpdate,
+    AutoModSettingsUpdate,
+    AutoModTermsUpdate,
+    ReadAutoModSettings,
+    ManageAutoModSettings,
+    CheckAutoModStatus,
+    WarnUser,
+    ManageCustomRewards,
+    ManageSchedule,
+    ChannelShieldModeBegin,
+    ChannelShieldModeEnd,
+    ManageShieldMode,
+    ChannelCharityCampaignDonate,
+    ChannelCharityCampaignStart,
+    ChannelCharityCampaignProgress,
+    ChannelCharityCampaignStop,
+    ReadBlockedUsers,
+    ManageBlockedUsers,
+    ManageChatColour,
+    ChannelUnbanRequestCreate,
+    ChannelUnbanRequestResolve,
+    ManageUnbanRequests,
+    ReadModeratedChannels,
+    ReadFollows,
+    ManageBroadcast,
+    ManageChatSettings
   });
 
-  fn details(&self) -> (String, String, String) {
+  fn details(&self) -> (String, Vec<Scope>, String) {
     let details = match self {
-      Subscription::UserUpdate => ("user.update", "", "1"),
-      Subscription::ChannelFollow => ("channel.follow", "moderator:read:followers", "2"),
-      Subscription::ChannelRaid => ("channel.raid", "", "1"),
+      Subscription::UserUpdate => ("user.update", vec![], "1"),
+      Subscription::ChannelFollow => ("channel.follow", vec![Scope::ModeratorReadFollowers], "2"),
+      Subscription::ChannelRaid { .. } => ("channel.raid", vec![], "1"),
       Subscription::ChatMessage => (
         "channel.chat.message",
-        "user:read:chat+user:write:chat",
+        vec![Scope::UserReadChat, Scope::UserWriteChat],
         "1",
       ),
-      Subscription::ChannelPointsCustomRewardRedeem => (
+      Subscription::ChannelPointsCustomRewardRedeem { .. } => (
         "channel.channel_points_custom_reward_redemption.add",
-        "channel:read:redemptions",
+        vec![Scope::ChannelReadRedemptions],
+        "1",
+      ),
+      Subscription::AdBreakBegin => ("channel.ad_break.begin", vec![Scope::ChannelReadAds], "1"),
+      Subscription::ChannelUpdate => ("channel.update", vec![], "2"),
+      Subscription::StreamOnline => ("stream.online", vec![], "1"),
+      Subscription::StreamOffline => ("stream.offline", vec![], "1"),
+      Subscription::BanTimeoutUser => ("", vec![Scope::ModeratorManageBannedUsers], ""),
+      Subscription::DeleteMessage => ("", vec![Scope::ModeratorManageChatMessages], ""),
+      Subscription::ReadModerators => ("", vec![Scope::ModerationRead], ""),
+      Subscription::ManageModerators => ("", vec![Scope::ChannelManageModerators], ""),
+      Subscription::ReadVips => ("", vec![Scope::ChannelReadVips], ""),
+      Subscription::ManageVips => ("", vec![Scope::ChannelManageVips], ""),
+      Subscription::ReadBlockedTerms => ("", vec![Scope::ModeratorReadBlockedTerms], ""),
+      Subscription::ManageBlockedTerms => ("", vec![Scope::ModeratorManageBlockedTerms], ""),
+      Subscription::AutoModMessageHold => (
+        "automod.message.hold",
+        vec![Scope::ModeratorManageAutomod],
+        "1",
+      ),
+      Subscription::AutoModMessageUpdate => (
+        "automod.message.update",
+        vec![Scope::ModeratorManageAutomod],
+        "1",
+      ),
+      Subscription::AutoModSettingsUpdate => (
+        "automod.settings.update",
+        vec![Scope::ModeratorReadAutomodSettings],
+        "1",
+      ),
+      Subscription::AutoModTermsUpdate => (
+        "automod.terms.update",
+        vec![Scope::ModeratorManageAutomod],
+        "1",
+      ),
+      Subscription::ReadAutoModSettings => ("", vec![Scope::ModeratorReadAutomodSettings], ""),
+      Subscription::ManageAutoModSettings => ("", vec![Scope::ModeratorManageAutomodSettings], ""),
+      Subscription::CheckAutoModStatus => ("", vec![Scope::ModerationRead], ""),
+      Subscription::WarnUser => ("", vec![Scope::ModeratorManageWarnings], ""),
+      Subscription::ManageCustomRewards => ("", vec![Scope::ChannelManageRedemptions], ""),
+      Subscription::ManageSchedule => ("", vec![Scope::ChannelManageSchedule], ""),
+      Subscription::ChannelShieldModeBegin => (
+        "channel.shield_mode.begin",
+        vec![Scope::ModeratorReadShieldMode],
+        "1",
+      ),
+      Subscription::ChannelShieldModeEnd => (
+        "channel.shield_mode.end",
+        vec![Scope::ModeratorReadShieldMode],
+        "1",
+      ),
+      Subscription::ManageShieldMode => ("", vec![Scope::ModeratorManageShieldMode], ""),
+      Subscription::ChannelCharityCampaignDonate => (
+        "channel.charity_campaign.donate",
+        vec![Scope::ChannelReadCharity],
+        "1",
+      ),
+      Subscription::ChannelCharityCampaignStart => (
+        "channel.charity_campaign.start",
+        vec![Scope::ChannelReadCharity],
+        "1",
+      ),
+      Subscription::ChannelCharityCampaignProgress => (
+        "channel.charity_campaign.progress",
+        vec![Scope::ChannelReadCharity],
+        "1",
+      ),
+      Subscription::ChannelCharityCampaignStop => (
+        "channel.charity_campaign.stop",
+        vec![Scope::ChannelReadCharity],
+        "1",
+      ),
+      Subscription::ChannelSubscribe => (
+        "channel.subscribe",
+        vec![Scope::ChannelReadSubscriptions],
         "1",
       ),
-      Subscription::AdBreakBegin => ("channel.ad_break.begin", "channel:read:ads", "1"),
-      Subscription::ChannelUpdate => ("channel.update", "", "2"),
-      Subscription::BanTimeoutUser => ("", "moderator:manage:banned_users", ""),
-      Subscription::DeleteMessage => ("", "moderator:manage:chat_messages", ""),
-      Subscription::ChannelSubscribe => ("channel.subscribe", "channel:read:subscriptions", "1"),
       Subscription::ChannelSubscriptionEnd => (
         "channel.subscription.end",
-        "channel:read:subscriptions",
+        vec![Scope::ChannelReadSubscriptions],
         "1",
       ),
       Subscription::ChannelSubscriptionGift => (
         "channel.subscription.gift",
-        "channel:read:subscriptions",
+        vec![Scope::ChannelReadSubscriptions],
         "1",
       ),
       Subscription::ChannelSubscriptionMessage => (
         "channel.subscription.message",
-        "channel:read:subscriptions",
+        vec![Scope::ChannelReadSubscriptions],
         "1",
       ),
-      Subscription::ChannelCheer => ("channel.cheer", "bits:read", "1"),
+      Subscription::ChannelCheer => ("channel.cheer", vec![Scope::BitsRead], "1"),
       Subscription::ChannelPointsAutoRewardRedeem => (
         "channel.channel_points_automatic_reward_redemption.add",
-        "channel:read:redemptions",
+        vec![Scope::ChannelReadRedemptions],
         "1",
       ),
       Subscription::ChannelPollBegin => (
         "channel.poll.begin",
-        "channel:read:polls+channel:manage:polls",
+        vec![Scope::ChannelReadPolls, Scope::ChannelManagePolls],
         "1",
       ),
       Subscription::ChannelPollProgress => (
         "channel.poll.progress",
-        "channel:read:polls+channel:manage:polls",
+        vec![Scope::ChannelReadPolls, Scope::ChannelManagePolls],
         "1",
       ),
       Subscription::ChannelPollEnd => (
         "channel.poll.end",
-        "channel:read:polls+channel:manage:polls",
+        vec![Scope::ChannelReadPolls, Scope::ChannelManagePolls],
         "1",
       ),
       Subscription::ChannelPredictionBegin => (
         "channel.prediction.begin",
-        "channel:read:predictions+channel:manage:predictions",
+        vec![
+          Scope::ChannelReadPredictions,
+          Scope::ChannelManagePredictions,
+        ],
         "1",
       ),
       Subscription::ChannelPredictionProgress => (
         "channel.prediction.progress",
-        "channel:read:predictions+channel:manage:predictions",
+        vec![
+          Scope::ChannelReadPredictions,
+          Scope::ChannelManagePredictions,
+        ],
         "1",
       ),
       Subscription::ChannelPredictionLock => (
         "channel.prediction.lock",
-        "channel:read:predictions+channel:manage:predictions",
+        vec![
+          Scope::ChannelReadPredictions,
+          Scope::ChannelManagePredictions,
+        ],
         "1",
       ),
       Subscription::ChannelPredictionEnd => (
         "channel.prediction.end",
-        "channel:read:predictions+channel:manage:predictions",
+        vec![
+          Scope::ChannelReadPredictions,
+          Scope::ChannelManagePredictions,
+        ],
         "1",
       ),
-      Subscription::ChannelGoalBegin => ("channel.goal.begin", "channel:read:goals", "1"),
-      Subscription::ChannelGoalProgress => ("channel.goal.progress", "channel:read:goals", "1"),
-      Subscription::ChannelGoalEnd => ("channel.goal.end", "channel:read:goals", "1"),
-      Subscription::ChannelHypeTrainBegin => {
-        ("channel.hype_train.begin", "channel:read:hype_train", "1")
+      Subscription::ChannelGoalBegin => ("channel.goal.begin", vec![Scope::ChannelReadGoals], "1"),
+      Subscription::ChannelGoalProgress => {
+        ("channel.goal.progress", vec![Scope::ChannelReadGoals], "1")
       }
+      Subscription::ChannelGoalEnd => ("channel.goal.end", vec![Scope::ChannelReadGoals], "1"),
+      Subscription::ChannelHypeTrainBegin => (
+        "channel.hype_train.begin",
+        vec![Scope::ChannelReadHypeTrain],
+        "2",
+      ),
       Subscription::ChannelHypeTrainProgress => (
         "channel.hype_train.progress",
-        "channel:read:hype_train",
-        "1",
+        vec![Scope::ChannelReadHypeTrain],
+        "2",
+      ),
+      Subscription::ChannelHypeTrainEnd => (
+        "channel.hype_train.end",
+        vec![Scope::ChannelReadHypeTrain],
+        "2",
       ),
-      Subscription::ChannelHypeTrainEnd => {
-        ("channel.hype_train.end", "channel:read:hype_train", "1")
-      }
       Subscription::ChannelShoutoutCreate => (
         "channel.shoutout.create",
-        "moderator:read:shoutouts+moderator:manage:shoutouts",
+        vec![
+          Scope::ModeratorReadShoutouts,
+          Scope::ModeratorManageShoutouts,
+        ],
         "1",
       ),
       Subscription::ChannelShoutoutReceive => (
         "channel.shoutout.receive",
-        "moderator:read:shoutouts+moderator:manage:shoutouts",
+        vec![
+          Scope::ModeratorReadShoutouts,
+          Scope::ModeratorManageShoutouts,
+        ],
+        "1",
+      ),
+      Subscription::ReadBlockedUsers => ("", vec![Scope::UserReadBlockedUsers], ""),
+      Subscription::ManageBlockedUsers => ("", vec![Scope::UserManageBlockedUsers], ""),
+      Subscription::ManageChatColour => ("", vec![Scope::UserManageChatColor], ""),
+      Subscription::ChannelUnbanRequestCreate => (
+        "channel.unban_request.create",
+        vec![Scope::ModeratorReadUnbanRequests],
+        "1",
+      ),
+      Subscription::ChannelUnbanRequestResolve => (
+        "channel.unban_request.resolve",
+        vec![Scope::ModeratorReadUnbanRequests],
         "1",
       ),
-      Subscription::Custom((tag, scope, ..)) => (tag.as_str(), scope.as_str(), ""),
+      Subscription::ManageUnbanRequests => ("", vec![Scope::ModeratorManageUnbanRequests], ""),
+      Subscription::ReadModeratedChannels => ("", vec![Scope::UserReadModeratedChannels], ""),
+      Subscription::ReadFollows => ("", vec![Scope::UserReadFollows], ""),
+      Subscription::ManageBroadcast => ("", vec![Scope::ChannelManageBroadcast], ""),
+      Subscription::ManageChatSettings => ("", vec![Scope::ModeratorManageChatSettings], ""),
+      Subscription::Custom((tag, scope, ..)) => (
+        tag.as_str(),
+        scope
+          .split('+')
+          .filter(|s| !s.is_empty())
+          .map(Scope::parse_lenient)
+          .collect(),
+        "",
+      ),
+      Subscription::AdditionalScope(scope) => ("", vec![scope.to_owned()], ""),
     };
 
-    (
-      details.0.to_owned(),
-      details.1.to_owned(),
-      details.2.to_owned(),
-    )
+    (details.0.to_owned(), details.1, details.2.to_owned())
   }
 
   pub fn tag(&self) -> String {
     self.details().0
   }
 
-  pub fn required_scope(&self) -> String {
+  pub fn required_scope(&self) -> Vec<Scope> {
     self.details().1
   }
 
@@ -217,36 +436,167 @@ impl Subscription {
   }
 
   pub fn construct_data(&self, session_id: &str, twitch_keys: &TwitchKeys) -> EventSubscription {
+    self.construct_data_for_broadcaster(
+      session_id,
+      &twitch_keys.broadcaster_account_id,
+      twitch_keys.moderator_id(),
+      twitch_keys.chat_reader_id(),
+    )
+  }
+
+  /// Builds the subscription for any broadcaster's channel, `moderator_id` is used for
+  /// follow, shoutout, automod and other moderator only events and `chat_reader_id` for
+  /// chat messages. Both have to be the account the token belongs to.
+  pub fn construct_data_for_broadcaster(
+    &self,
+    session_id: &str,
+    broadcaster_id: &str,
+    moderator_id: &str,
+    chat_reader_id: &str,
+  ) -> EventSubscription {
     let transport = Transport::new(session_id);
 
     let event_subscription = EventSubscription::new(self, transport);
-    let condition =
-      Condition::new().broadcaster_user_id(twitch_keys.broadcaster_account_id.to_owned());
+    let condition = Condition::new().broadcaster_user_id(broadcaster_id);
 
     match self {
-      Subscription::UserUpdate => event_subscription
-        .condition(Condition::new().user_id(twitch_keys.broadcaster_account_id.to_owned())),
-      Subscription::ChannelFollow => event_subscription.condition(
-        condition
-          .moderator_user_id(twitch_keys.broadcaster_account_id.to_owned())
-          .user_id(twitch_keys.broadcaster_account_id.to_owned()),
-      ),
-      Subscription::ChatMessage => event_subscription
-        .condition(condition.user_id(twitch_keys.broadcaster_account_id.to_owned())),
-      Subscription::ChannelPointsCustomRewardRedeem => event_subscription.condition(condition),
-      Subscription::AdBreakBegin => event_subscription.condition(condition),
-      Subscription::ChannelRaid => event_subscription.condition(condition),
-      Subscription::ChannelUpdate => event_subscription.condition(condition),
-      Subscription::ChannelSubscribe => event_subscription.condition(condition),
-      Subscription::ChannelSubscriptionEnd => event_subscription.condition(condition),
-      Subscription::ChannelSubscriptionGift => event_subscription.condition(condition),
-      Subscription::ChannelSubscriptionMessage => event_subscription.condition(condition),
+      Subscription::UserUpdate => {
+        event_subscription.condition(Condition::new().user_id(broadcaster_id))
+      }
+      Subscription::ChannelRaid { direction } => event_subscription.condition(match direction {
+        RaidDirection::Incoming => Condition::new().to_broadcaster_user_id(broadcaster_id),
+        RaidDirection::Outgoing => Condition::new().from_broadcaster_user_id(broadcaster_id),
+      }),
+      Subscription::ChatMessage => event_subscription.condition(condition.user_id(chat_reader_id)),
+      Subscription::ChannelPointsCustomRewardRedeem {
+        reward_id: Some(reward_id),
+      } => event_subscription.condition(condition.reward_id(reward_id)),
+      Subscription::ChannelFollow
+      | Subscription::AutoModMessageHold
+      | Subscription::AutoModMessageUpdate
+      | Subscription::AutoModSettingsUpdate
+      | Subscription::AutoModTermsUpdate
+      | Subscription::ChannelShieldModeBegin
+      | Subscription::ChannelShieldModeEnd
+      | Subscription::ChannelShoutoutCreate
+      | Subscription::ChannelShoutoutReceive
+      | Subscription::ChannelUnbanRequestCreate
+      | Subscription::ChannelUnbanRequestResolve => {
+        event_subscription.condition(condition.moderator_user_id(moderator_id))
+      }
       Subscription::Custom((_, _, event)) => {
         let mut event = event.to_owned();
         event = event.transport(Transport::new(session_id));
         event.to_owned()
       }
-      _ => event_subscription,
+      _ => event_subscription.condition(condition),
+    }
+  }
+
+  /// The condition fields Twitch expects for this subscription, `None` for custom
+  /// subscriptions and ones that aren't EventSub events.
+  pub fn condition_fields(&self) -> Option<ConditionFields> {
+    const BROADCASTER: &[&str] = &["broadcaster_user_id"];
+    const BROADCASTER_AND_MODERATOR: &[&str] = &["broadcaster_user_id", "moderator_user_id"];
+
+    if self.tag().is_empty() {
+      return None;
+    }
+
+    let fields = match self {
+      Subscription::Custom(_) => return None,
+      Subscription::UserUpdate => ConditionFields::required(&["user_id"]),
+      Subscription::ChannelRaid { .. } => ConditionFields {
+        one_of: &["from_broadcaster_user_id", "to_broadcaster_user_id"],
+        ..ConditionFields::required(&[])
+      },
+      Subscription::ChatMessage => ConditionFields::required(&["broadcaster_user_id", "user_id"]),
+      Subscription::ChannelPointsCustomRewardRedeem { .. } => ConditionFields {
+        optional: &["reward_id"],
+        ..ConditionFields::required(BROADCASTER)
+      },
+      Subscription::ChannelFollow
+      | Subscription::AutoModMessageHold
+      | Subscription::AutoModMessageUpdate
+      | Subscription::AutoModSettingsUpdate
+      | Subscription::AutoModTermsUpdate
+      | Subscription::ChannelShieldModeBegin
+      | Subscription::ChannelShieldModeEnd
+      | Subscription::ChannelShoutoutCreate
+      | Subscription::ChannelShoutoutReceive
+      | Subscription::ChannelUnbanRequestCreate
+      | Subscription::ChannelUnbanRequestResolve => {
+        ConditionFields::required(BROADCASTER_AND_MODERATOR)
+      }
+      _ => ConditionFields::required(BROADCASTER),
+    };
+
+    Some(fields)
+  }
+
+  /// Checks the condition has every field the subscription needs and none it doesn't take.
+  pub fn validate_condition(&self, condition: &Condition) -> Result<(), EventSubError> {
+    let Some(fields) = self.condition_fields() else {
+      return Ok(());
+    };
+
+    let set = condition.set_fields();
+    let mut missing = fields
+      .required
+      .iter()
+      .filter(|field| !set.contains(field))
+      .map(|field| field.to_string())
+      .collect::<Vec<_>>();
+    let mut extraneous = set
+      .iter()
+      .filter(|field| {
+        !fields.required.contains(field)
+          && !fields.one_of.contains(field)
+          && !fields.optional.contains(field)
+      })
+      .map(|field| field.to_string())
+      .collect::<Vec<_>>();
+
+    if !fields.one_of.is_empty() {
+      let one_of_set = fields
+        .one_of
+        .iter()
+        .filter(|field| set.contains(field))
+        .collect::<Vec<_>>();
+      match one_of_set.as_slice() {
+        [] => missing.push(fields.one_of.join(" or ")),
+        [_] => {}
+        [_, rest @ ..] => extraneous.extend(rest.iter().map(|field| field.to_string())),
+      }
+    }
+
+    if missing.is_empty() && extraneous.is_empty() {
+      Ok(())
+    } else {
+      Err(EventSubError::InvalidCondition {
+        kind: self.tag(),
+        missing,
+        extraneous,
+      })
+    }
+  }
+}
+
+/// Which condition fields a subscription takes, by their names in Twitch's api.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConditionFields {
+  pub required: &'static [&'static str],
+  /// Exactly one of these has to be set.
+  pub one_of: &'static [&'static str],
+  pub optional: &'static [&'static str],
+}
+
+impl ConditionFields {
+  fn required(required: &'static [&'static str]) -> ConditionFields {
+    ConditionFields {
+      required,
+      one_of: &[],
+      optional: &[],
     }
   }
 }
@@ -272,15 +622,15 @@ impl EventSubscription {
   }
 }
 
-#[derive(Serialise, Deserialise, Debug, Clone, Default)]
+#[derive(Serialise, Deserialise, Debug, Clone, Default, PartialEq)]
 pub struct Condition {
-  pub user_id: Option<String>,
-  pub moderator_user_id: Option<String>,
-  pub broadcaster_user_id: Option<String>,
-  pub reward_id: Option<String>,
-  pub from_broadcaster_user_id: Option<String>,
-  pub to_broadcaster_user_id: Option<String>,
-  #[serde(rename = "organisation_id")]
+  pub user_id: Option<UserId>,
+  pub moderator_user_id: Option<UserId>,
+  pub broadcaster_user_id: Option<BroadcasterId>,
+  pub reward_id: Option<RewardId>,
+  pub from_broadcaster_user_id: Option<BroadcasterId>,
+  pub to_broadcaster_user_id: Option<BroadcasterId>,
+  #[serde(rename = "organization_id")]
   pub organisation_id: Option<String>,
   pub category_id: Option<String>,
   pub campaign_id: Option<String>,
@@ -294,27 +644,30 @@ impl Condition {
     }
   }
 
-  pub fn user_id<S: Into<String>>(mut self, user_id: S) -> Condition {
+  pub fn user_id<S: Into<UserId>>(mut self, user_id: S) -> Condition {
     self.user_id = Some(user_id.into());
     self
   }
 
-  pub fn moderator_user_id<S: Into<String>>(mut self, moderator_user_id: S) -> Condition {
+  pub fn moderator_user_id<S: Into<UserId>>(mut self, moderator_user_id: S) -> Condition {
     self.moderator_user_id = Some(moderator_user_id.into());
     self
   }
 
-  pub fn broadcaster_user_id<S: Into<String>>(mut self, broadcaster_user_id: S) -> Condition {
+  pub fn broadcaster_user_id<S: Into<BroadcasterId>>(
+    mut self,
+    broadcaster_user_id: S,
+  ) -> Condition {
     self.broadcaster_user_id = Some(broadcaster_user_id.into());
     self
   }
 
-  pub fn reward_id<S: Into<String>>(mut self, reward_id: S) -> Condition {
+  pub fn reward_id<S: Into<RewardId>>(mut self, reward_id: S) -> Condition {
     self.reward_id = Some(reward_id.into());
     self
   }
 
-  pub fn from_broadcaster_user_id<S: Into<String>>(
+  pub fn from_broadcaster_user_id<S: Into<BroadcasterId>>(
     mut self,
     from_broadcaster_user_id: S,
   ) -> Condition {
@@ -322,8 +675,286 @@ impl Condition {
     self
   }
 
-  pub fn to_broadcaster_user_id<S: Into<String>>(mut self, to_broadcaster_user_id: S) -> Condition {
+  pub fn to_broadcaster_user_id<S: Into<BroadcasterId>>(
+    mut self,
+    to_broadcaster_user_id: S,
+  ) -> Condition {
     self.to_broadcaster_user_id = Some(to_broadcaster_user_id.into());
     self
   }
+
+  /// Names of the fields that are set, as Twitch calls them.
+  pub fn set_fields(&self) -> Vec<&'static str> {
+    [
+      ("user_id", self.user_id.is_some()),
+      ("moderator_user_id", self.moderator_user_id.is_some()),
+      ("broadcaster_user_id", self.broadcaster_user_id.is_some()),
+      ("reward_id", self.reward_id.is_some()),
+      (
+        "from_broadcaster_user_id",
+        self.from_broadcaster_user_id.is_some(),
+      ),
+      (
+        "to_broadcaster_user_id",
+        self.to_broadcaster_user_id.is_some(),
+      ),
+      ("organization_id", self.organisation_id.is_some()),
+      ("category_id", self.category_id.is_some()),
+      ("campaign_id", self.campaign_id.is_some()),
+      ("extension_client_id", self.extension_client_id.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(field, set)| set.then_some(field))
+    .collect()
+  }
+}
+
+/// A subscription the client keeps alive, it is created again whenever a new session
+/// starts or Twitch revokes it.
+#[derive(Clone, Debug)]
+pub struct RegisteredSubscription {
+  pub subscription: Subscription,
+  /// `None` for the broadcaster in `TwitchKeys`.
+  pub broadcaster_id: Option<String>,
+  /// Which of the client's websocket connections the subscription lives on.
+  pub connection: usize,
+  /// Id Twitch assigned on the current session, `None` until it has been created.
+  pub id: Option<String>,
+  pub condition: Option<Condition>,
+  pub cost: u32,
+}
+
+impl RegisteredSubscription {
+  pub fn kind(&self) -> String {
+    self.subscription.tag()
+  }
+}
+
+/// A subscription added with `TwitchEventSubApi::add_subscription`, for checking on or
+/// deleting it later.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug)]
+pub struct SubscriptionHandle {
+  pub id: SubscriptionId,
+  pub kind: String,
+  /// The status Twitch last reported, `status` asks for the current one.
+  pub status: EventSubSubscriptionStatus,
+  pub subscription: Subscription,
+  /// `None` for the broadcaster in `TwitchKeys`.
+  pub broadcaster_id: Option<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SubscriptionHandle {
+  pub(crate) fn new(
+    subscription: Subscription,
+    broadcaster_id: Option<String>,
+    info: &EventSubSubscriptionInfo,
+  ) -> SubscriptionHandle {
+    SubscriptionHandle {
+      id: info.id.as_str().into(),
+      kind: info.kind.to_owned(),
+      status: info.status.to_owned(),
+      subscription,
+      broadcaster_id,
+    }
+  }
+
+  /// Asks Twitch for the subscription's current status, `None` once Twitch no longer has
+  /// it. When the client has created it again on a new session the new one is followed.
+  pub fn status(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+  ) -> Result<Option<EventSubSubscriptionStatus>, EventSubError> {
+    let recreated = twitch.registered_subscriptions().into_iter().find_map(|r| {
+      (r.subscription == self.subscription && r.broadcaster_id == self.broadcaster_id)
+        .then_some(r.id)
+        .flatten()
+    });
+    if let Some(id) = recreated {
+      self.id = id.into();
+    }
+
+    let status = twitch
+      .get_eventsub_subscriptions(
+        EventSubSubscriptionFilter::SubscriptionId(self.id.to_string()),
+        None,
+      )?
+      .data
+      .into_iter()
+      .next()
+      .map(|info| info.status);
+    if let Some(status) = &status {
+      self.status = status.to_owned();
+    }
+    Ok(status)
+  }
+
+  /// Checks the status every half a second until the subscription is enabled, failing with
+  /// `SubscriptionFailed` if Twitch disables it or `TimedOut` after `timeout`.
+  pub fn wait_until_enabled(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    timeout: Duration,
+  ) -> Result<(), EventSubError> {
+    let started = Instant::now();
+    loop {
+      match self.status(twitch)? {
+        Some(EventSubSubscriptionStatus::Enabled) => return Ok(()),
+        Some(EventSubSubscriptionStatus::WebhookCallbackVerificationPending) => {}
+        Some(status) => {
+          return Err(EventSubError::SubscriptionFailed {
+            kind: self.kind.to_owned(),
+            reason: status.as_str().to_owned(),
+          })
+        }
+        None => {
+          return Err(EventSubError::SubscriptionFailed {
+            kind: self.kind.to_owned(),
+            reason: "Subscription no longer exists".to_owned(),
+          })
+        }
+      }
+
+      if started.elapsed() >= timeout {
+        return Err(EventSubError::TimedOut);
+      }
+      thread::sleep(Duration::from_millis(500));
+    }
+  }
+
+  /// Deletes the subscription from Twitch and stops the client creating it again.
+  pub fn delete(self, twitch: &mut TwitchEventSubApi) -> Result<(), EventSubError> {
+    twitch.unregister_subscription(self.subscription, self.broadcaster_id)
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SubscriptionRegistry {
+  subscriptions: Vec<RegisteredSubscription>,
+}
+
+impl SubscriptionRegistry {
+  pub fn new(
+    subscriptions: &[Subscription],
+    broadcaster_subscriptions: &[(String, Subscription)],
+  ) -> SubscriptionRegistry {
+    let mut registry = SubscriptionRegistry::default();
+    for subscription in subscriptions {
+      if !subscription.tag().is_empty()
+        && registry.register(subscription.to_owned(), None).is_none()
+      {
+        error!(
+          "Every connection is full, {} was not subscribed to",
+          subscription.tag()
+        );
+      }
+    }
+    for (broadcaster_id, subscription) in broadcaster_subscriptions {
+      if !subscription.tag().is_empty()
+        && registry
+          .register(subscription.to_owned(), Some(broadcaster_id.to_owned()))
+          .is_none()
+      {
+        error!(
+          "Every connection is full, {} was not subscribed to",
+          subscription.tag()
+        );
+      }
+    }
+    registry
+  }
+
+  /// Subscriptions with no tag only exist to request a scope, so they are never tracked.
+  ///
+  /// Returns the connection the subscription was put on, `None` if every connection is full.
+  pub fn register(
+    &mut self,
+    subscription: Subscription,
+    broadcaster_id: Option<String>,
+  ) -> Option<usize> {
+    if subscription.tag().is_empty() {
+      return None;
+    }
+
+    let connection = self.available_connection()?;
+    self.insert(subscription, broadcaster_id, connection);
+    Some(connection)
+  }
+
+  pub fn insert(
+    &mut self,
+    subscription: Subscription,
+    broadcaster_id: Option<String>,
+    connection: usize,
+  ) -> &mut RegisteredSubscription {
+    self.subscriptions.push(RegisteredSubscription {
+      subscription,
+      broadcaster_id,
+      connection,
+      id: None,
+      condition: None,
+      cost: 0,
+    });
+    self.subscriptions.last_mut().unwrap()
+  }
+
+  /// The first connection with room for another subscription.
+  pub fn available_connection(&self) -> Option<usize> {
+    (0..MAX_WEBSOCKET_CONNECTIONS)
+      .find(|&connection| self.connection_count(connection) < MAX_SUBSCRIPTIONS_PER_CONNECTION)
+  }
+
+  pub fn connection_count(&self, connection: usize) -> usize {
+    self
+      .subscriptions
+      .iter()
+      .filter(|s| s.connection == connection)
+      .count()
+  }
+
+  /// How many connections are needed to hold every subscription, always at least one.
+  pub fn connections_needed(&self) -> usize {
+    self
+      .subscriptions
+      .iter()
+      .map(|s| s.connection + 1)
+      .max()
+      .unwrap_or(1)
+  }
+
+  pub fn remove(&mut self, subscription: &Subscription, broadcaster_id: Option<&str>) {
+    self
+      .subscriptions
+      .retain(|s| s.subscription != *subscription || s.broadcaster_id.as_deref() != broadcaster_id);
+  }
+
+  pub fn find_by_id(&mut self, id: &str) -> Option<&mut RegisteredSubscription> {
+    self
+      .subscriptions
+      .iter_mut()
+      .find(|s| s.id.as_deref() == Some(id))
+  }
+
+  /// Copies the id, condition and cost of a subscription created without the registry
+  /// locked back into it, unless it was unsubscribed from in the meantime.
+  pub fn update(&mut self, created: &RegisteredSubscription) {
+    if let Some(registered) = self.subscriptions.iter_mut().find(|s| {
+      s.subscription == created.subscription
+        && s.broadcaster_id == created.broadcaster_id
+        && s.connection == created.connection
+    }) {
+      registered.id = created.id.to_owned();
+      registered.condition = created.condition.to_owned();
+      registered.cost = created.cost;
+    }
+  }
+
+  pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut RegisteredSubscription> {
+    self.subscriptions.iter_mut()
+  }
+
+  pub fn subscriptions(&self) -> Vec<RegisteredSubscription> {
+    self.subscriptions.clone()
+  }
 }
@@ -0,0 +1,504 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use websocket::sync::{Client, Server};
+use websocket::OwnedMessage;
+
+use crate::modules::{
+  generic_message::SendMessage,
+  helix::{
+    EventSubSubscriptionInfo, EventSubSubscriptionStatus, EventSubSubscriptions, EventSubTransport,
+    SendAnnouncementRequest,
+  },
+  scope::Scope,
+  subscriptions::EventSubscription,
+  twitch_http::{set_url_overrides, ApiUrls},
+};
+
+struct MockConnection {
+  session_id: String,
+  client: Client<TcpStream>,
+}
+
+#[derive(Default)]
+struct MockState {
+  connections: Vec<MockConnection>,
+  subscriptions: Vec<EventSubSubscriptionInfo>,
+  chat_messages: Vec<String>,
+  // Sessions that were asked to reconnect, their subscriptions move to the next connections
+  migrating: VecDeque<String>,
+  // The last notification and the session it was sent to, for redelivering
+  last_notification: Option<(OwnedMessage, Option<String>)>,
+  next_id: u64,
+}
+
+impl MockState {
+  fn next_id(&mut self, prefix: &str) -> String {
+    self.next_id += 1;
+    format!("mock-{}-{}", prefix, self.next_id)
+  }
+}
+
+/// A local stand in for Twitch's EventSub websocket and the Helix endpoints the client
+/// uses, so bots can be tested end to end without hitting Twitch.
+///
+/// Starting the server points every client in the process at it until it's dropped, so
+/// only run one at a time.
+pub struct MockEventSubServer {
+  websocket_address: SocketAddr,
+  http_address: SocketAddr,
+  state: Arc<Mutex<MockState>>,
+}
+
+impl MockEventSubServer {
+  pub fn start() -> std::io::Result<MockEventSubServer> {
+    let state = Arc::new(Mutex::new(MockState::default()));
+
+    let websocket_server = Server::bind("127.0.0.1:0")?;
+    let websocket_address = websocket_server.local_addr()?;
+    let websocket_state = state.clone();
+    thread::spawn(move || {
+      for request in websocket_server.filter_map(Result::ok) {
+        let Ok(client) = request.use_protocol("rust-websocket-events").accept() else {
+          continue;
+        };
+
+        let mut state = websocket_state.lock().unwrap();
+        let session_id = state.next_id("session");
+        if let Some(old_session_id) = state.migrating.pop_front() {
+          for subscription in &mut state.subscriptions {
+            if subscription.transport.session_id.as_ref() == Some(&old_session_id) {
+              subscription.transport.session_id = Some(session_id.to_owned());
+            }
+          }
+        }
+
+        let mut connection = MockConnection { session_id, client };
+        let welcome = message(
+          "session_welcome",
+          &state.next_id("message"),
+          json!({ "session": session(&connection.session_id, None) }),
+        );
+        if connection.client.send_message(&welcome).is_ok() {
+          state.connections.push(connection);
+        }
+      }
+    });
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let http_address = listener.local_addr()?;
+    let http_state = state.clone();
+    thread::spawn(move || {
+      for stream in listener.incoming().filter_map(Result::ok) {
+        let _ = handle_http(stream, &http_state);
+      }
+    });
+
+    set_url_overrides(Some(ApiUrls {
+      websocket: format!("ws://{}", websocket_address),
+      helix: format!("http://{}/helix", http_address),
+      auth: format!("http://{}/oauth2", http_address),
+    }));
+
+    Ok(MockEventSubServer {
+      websocket_address,
+      http_address,
+      state,
+    })
+  }
+
+  pub fn websocket_url(&self) -> String {
+    format!("ws://{}", self.websocket_address)
+  }
+
+  pub fn http_url(&self) -> String {
+    format!("http://{}", self.http_address)
+  }
+
+  /// Subscriptions the client has created and not deleted.
+  pub fn created_subscriptions(&self) -> Vec<EventSubSubscriptionInfo> {
+    self.state.lock().unwrap().subscriptions.clone()
+  }
+
+  /// Chat messages and announcements the client has sent, oldest first.
+  pub fn sent_chat_messages(&self) -> Vec<String> {
+    self.state.lock().unwrap().chat_messages.clone()
+  }
+
+  /// Waits until at least `count` subscriptions have been created.
+  pub fn wait_for_subscriptions(&self, count: usize, timeout: Duration) -> bool {
+    let started = Instant::now();
+    while started.elapsed() < timeout {
+      if self.created_subscriptions().len() >= count {
+        return true;
+      }
+      thread::sleep(Duration::from_millis(10));
+    }
+
+    false
+  }
+
+  /// Panics unless a subscription of the given type, such as `channel.follow`, was created.
+  pub fn assert_subscribed<S: AsRef<str>>(&self, kind: S) {
+    let subscriptions = self.created_subscriptions();
+    assert!(
+      subscriptions.iter().any(|s| s.kind == kind.as_ref()),
+      "No {} subscription was created, created subscriptions: {:?}",
+      kind.as_ref(),
+      subscriptions.iter().map(|s| &s.kind).collect::<Vec<_>>()
+    );
+  }
+
+  /// Sends a notification with the given event body, on the connection its subscription
+  /// was created on.
+  pub fn send_notification<S: Into<String>>(&self, kind: S, event: Value) {
+    let kind = kind.into();
+    let mut state = self.state.lock().unwrap();
+    let message_id = state.next_id("message");
+
+    let subscription = match state.subscriptions.iter().find(|s| s.kind == kind) {
+      Some(subscription) => subscription.to_owned(),
+      None => {
+        let session_id = state.connections.first().map(|c| c.session_id.to_owned());
+        let id = state.next_id("subscription");
+        subscription_info(id, &kind, "1", Default::default(), session_id)
+      }
+    };
+
+    let mut notification = message(
+      "notification",
+      &message_id,
+      json!({ "subscription": subscription, "event": event }),
+    );
+    if let OwnedMessage::Text(text) = &mut notification {
+      let mut value: Value = serde_json::from_str(text).unwrap();
+      value["metadata"]["subscription_type"] = json!(kind);
+      value["metadata"]["subscription_version"] = json!(subscription.version);
+      *text = value.to_string();
+    }
+
+    let session_id = subscription.transport.session_id;
+    send_to(&mut state, session_id.as_deref(), &notification);
+    state.last_notification = Some((notification, session_id));
+  }
+
+  /// Sends the last notification again with the same message id, like Twitch does when it
+  /// isn't sure the first one arrived.
+  pub fn redeliver_last_notification(&self) {
+    let mut state = self.state.lock().unwrap();
+    if let Some((notification, session_id)) = state.last_notification.clone() {
+      send_to(&mut state, session_id.as_deref(), &notification);
+    }
+  }
+
+  /// Revokes a created subscription, like Twitch does when a user deauthorises the app.
+  pub fn send_revocation<S: AsRef<str>>(
+    &self,
+    subscription_id: S,
+    status: EventSubSubscriptionStatus,
+  ) {
+    let mut state = self.state.lock().unwrap();
+    let Some(index) = state
+      .subscriptions
+      .iter()
+      .position(|s| s.id == subscription_id.as_ref())
+    else {
+      return;
+    };
+
+    let mut subscription = state.subscriptions.remove(index);
+    subscription.status = status;
+    let message_id = state.next_id("message");
+    let revocation = message(
+      "revocation",
+      &message_id,
+      json!({ "subscription": subscription }),
+    );
+
+    let session_id = subscription.transport.session_id;
+    send_to(&mut state, session_id.as_deref(), &revocation);
+  }
+
+  /// Asks every connection to move to a new one, subscriptions are kept.
+  pub fn send_reconnect(&self) {
+    let reconnect_url = self.websocket_url();
+    let mut state = self.state.lock().unwrap();
+    let mut connections = std::mem::take(&mut state.connections);
+
+    for connection in &mut connections {
+      let message_id = state.next_id("message");
+      let reconnect = message(
+        "session_reconnect",
+        &message_id,
+        json!({ "session": session(&connection.session_id, Some(&reconnect_url)) }),
+      );
+      if connection.client.send_message(&reconnect).is_ok() {
+        state.migrating.push_back(connection.session_id.to_owned());
+      }
+    }
+  }
+
+  pub fn send_keepalive(&self) {
+    let mut state = self.state.lock().unwrap();
+    let message_id = state.next_id("message");
+    let keepalive = message("session_keepalive", &message_id, json!({}));
+    send_to(&mut state, None, &keepalive);
+  }
+
+  /// Sends any message as is, to every connection.
+  pub fn send_raw<S: Into<String>>(&self, text: S) {
+    let mut state = self.state.lock().unwrap();
+    send_to(&mut state, None, &OwnedMessage::Text(text.into()));
+  }
+}
+
+impl Drop for MockEventSubServer {
+  fn drop(&mut self) {
+    set_url_overrides(None);
+  }
+}
+
+/// Sends to the connection with the session id, or every connection when there is none.
+/// Connections that have gone away are dropped.
+fn send_to(state: &mut MockState, session_id: Option<&str>, message: &OwnedMessage) {
+  state.connections.retain_mut(|connection| {
+    if session_id.is_some_and(|id| id != connection.session_id) {
+      return true;
+    }
+
+    connection.client.send_message(message).is_ok()
+  });
+}
+
+fn message(message_type: &str, message_id: &str, payload: Value) -> OwnedMessage {
+  OwnedMessage::Text(
+    json!({
+      "metadata": {
+        "message_id": message_id,
+        "message_type": message_type,
+        "message_timestamp": timestamp(),
+      },
+      "payload": payload,
+    })
+    .to_string(),
+  )
+}
+
+fn session(session_id: &str, reconnect_url: Option<&str>) -> Value {
+  json!({
+    "id": session_id,
+    "status": if reconnect_url.is_some() { "reconnecting" } else { "connected" },
+    "connected_at": timestamp(),
+    "keepalive_timeout_seconds": 30,
+    "reconnect_url": reconnect_url,
+    "recovery_url": null,
+  })
+}
+
+fn subscription_info(
+  id: String,
+  kind: &str,
+  version: &str,
+  condition: crate::Condition,
+  session_id: Option<String>,
+) -> EventSubSubscriptionInfo {
+  EventSubSubscriptionInfo {
+    id,
+    status: EventSubSubscriptionStatus::Enabled,
+    kind: kind.to_owned(),
+    version: version.to_owned(),
+    condition,
+    created_at: timestamp(),
+    transport: EventSubTransport {
+      method: "websocket".to_owned(),
+      callback: None,
+      session_id,
+      connected_at: Some(timestamp()),
+      disconnected_at: None,
+    },
+    cost: 0,
+  }
+}
+
+fn handle_http(stream: TcpStream, state: &Mutex<MockState>) -> std::io::Result<()> {
+  let mut reader = BufReader::new(stream.try_clone()?);
+  let mut request_line = String::new();
+  reader.read_line(&mut request_line)?;
+  let mut parts = request_line.split_whitespace();
+  let method = parts.next().unwrap_or_default().to_owned();
+  let target = parts.next().unwrap_or_default().to_owned();
+  let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+
+  let mut content_length = 0;
+  let mut expects_continue = false;
+  loop {
+    let mut header = String::new();
+    if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+      break;
+    }
+
+    if let Some((key, value)) = header.split_once(':') {
+      match key.trim().to_ascii_lowercase().as_str() {
+        "content-length" => content_length = value.trim().parse().unwrap_or(0),
+        "expect" => expects_continue = value.trim().eq_ignore_ascii_case("100-continue"),
+        _ => {}
+      }
+    }
+  }
+
+  let mut writer = stream;
+  if expects_continue {
+    writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+  }
+
+  let mut body = vec![0; content_length];
+  reader.read_exact(&mut body)?;
+
+  let (status, response) = route(&method, path, query, &body, state);
+  let response = response.map(|r| r.to_string()).unwrap_or_default();
+  write!(
+    writer,
+    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+    status,
+    response.len(),
+    response
+  )?;
+  writer.flush()
+}
+
+fn route(
+  method: &str,
+  path: &str,
+  query: &str,
+  body: &[u8],
+  state: &Mutex<MockState>,
+) -> (u32, Option<Value>) {
+  let mut state = state.lock().unwrap();
+
+  match (method, path) {
+    ("GET", "/oauth2/validate") => (
+      200,
+      Some(json!({
+        "client_id": "mock",
+        "login": "mock",
+        "scopes": Scope::ALL.iter().map(Scope::as_str).collect::<Vec<_>>(),
+        "user_id": "1",
+        "expires_in": 3600,
+      })),
+    ),
+    ("POST", "/helix/eventsub/subscriptions") => {
+      let subscription = match serde_json::from_slice::<EventSubscription>(body) {
+        Ok(subscription) => subscription,
+        Err(e) => return error(400, &e.to_string()),
+      };
+
+      let id = state.next_id("subscription");
+      let info = subscription_info(
+        id,
+        &subscription.kind,
+        &subscription.version,
+        subscription.condition,
+        Some(subscription.transport.session_id),
+      );
+      state.subscriptions.push(info.to_owned());
+
+      (202, Some(json!(subscriptions_response(vec![info]))))
+    }
+    ("GET", "/helix/eventsub/subscriptions") => (
+      200,
+      Some(json!(subscriptions_response(state.subscriptions.clone()))),
+    ),
+    ("DELETE", "/helix/eventsub/subscriptions") => {
+      let id = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("id="))
+        .unwrap_or_default();
+      let count = state.subscriptions.len();
+      state.subscriptions.retain(|s| s.id != id);
+
+      if state.subscriptions.len() == count {
+        error(404, "subscription not found")
+      } else {
+        (204, None)
+      }
+    }
+    ("POST", "/helix/chat/messages") => {
+      let message = match serde_json::from_slice::<SendMessage>(body) {
+        Ok(message) => message,
+        Err(e) => return error(400, &e.to_string()),
+      };
+
+      state.chat_messages.push(message.message);
+      let message_id = state.next_id("chat");
+      (
+        200,
+        Some(json!({
+          "data": [{ "message_id": message_id, "is_sent": true, "drop_reason": null }]
+        })),
+      )
+    }
+    ("POST", "/helix/chat/announcements") => {
+      let announcement = match serde_json::from_slice::<SendAnnouncementRequest>(body) {
+        Ok(announcement) => announcement,
+        Err(e) => return error(400, &e.to_string()),
+      };
+
+      state.chat_messages.push(announcement.message);
+      (204, None)
+    }
+    _ => error(404, &format!("{} {} isn't mocked", method, path)),
+  }
+}
+
+fn subscriptions_response(data: Vec<EventSubSubscriptionInfo>) -> EventSubSubscriptions {
+  EventSubSubscriptions {
+    total: data.len() as u32,
+    total_cost: data.iter().map(|s| s.cost).sum(),
+    max_total_cost: 10000,
+    data,
+    pagination: None,
+  }
+}
+
+fn error(status: u32, message: &str) -> (u32, Option<Value>) {
+  (
+    status,
+    Some(json!({ "error": "Mock Error", "status": status, "message": message })),
+  )
+}
+
+/// Current time in the RFC3339 format Twitch uses.
+fn timestamp() -> String {
+  let seconds = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs() as i64;
+  let (days, time) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+  // Days since the epoch to a civil date
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let day_of_era = z.rem_euclid(146097);
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let mp = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+    year,
+    month,
+    day,
+    time / 3600,
+    time % 3600 / 60,
+    time % 60
+  )
+}
@@ -1,21 +1,23 @@
-use log::{error, info};
+use crate::{error, info};
 
-use crate::modules::errors::*;
+use crate::modules::{errors::*, secret::SecretString, token_storage::TokenStorage};
 
 use std::fs;
 use std::io::Write;
+use std::sync::Arc;
 
+#[derive(Debug)]
 pub struct Token {
   pub access: TokenAccess,
-  pub refresh: String,
+  pub refresh: SecretString,
   //  expires_in: f32,
 }
 
 impl Token {
-  pub fn new(access: TokenAccess, refresh: String, _expires_in: f32) -> Token {
+  pub fn new<S: Into<SecretString>>(access: TokenAccess, refresh: S, _expires_in: f32) -> Token {
     Token {
       access,
-      refresh,
+      refresh: refresh.into(),
       //    expires_in,
     }
   }
@@ -33,7 +35,7 @@ impl Token {
       .open(format!("./{}", token_file.into()))
     {
       Ok(mut writer) => {
-        if let Err(e) = writer.write(format!("{}", self.access.get_token()).as_bytes()) {
+        if let Err(e) = writer.write(self.access.get_token().as_bytes()) {
           info!("Saving token failed: {}", e);
           return Err(EventSubError::WriteError(e.to_string()));
         }
@@ -50,7 +52,7 @@ impl Token {
       .truncate(true)
       .open(refresh_file.into())
     {
-      if let Err(e) = writer.write(format!("{}", self.refresh).as_bytes()) {
+      if let Err(e) = writer.write(self.refresh.expose().as_bytes()) {
         info!("Saving token failed: {}", e);
         return Err(EventSubError::WriteError(e.to_string()));
       }
@@ -60,25 +62,30 @@ impl Token {
   }
 
   pub fn new_user_token(access_token: String, refresh: String, expires_in: f32) -> Token {
-    Token::new(TokenAccess::User(access_token), refresh, expires_in)
+    Token::new(TokenAccess::User(access_token.into()), refresh, expires_in)
   }
 
   pub fn new_app_token(access_token: String, refresh: String, expires_in: f32) -> Token {
-    Token::new(TokenAccess::App(access_token), refresh, expires_in)
+    Token::new(TokenAccess::App(access_token.into()), refresh, expires_in)
   }
 }
 
 #[derive(Clone, Debug)]
 pub enum TokenAccess {
-  App(String),
-  User(String),
+  App(SecretString),
+  User(SecretString),
 }
 
 impl TokenAccess {
+  /// The raw token, for sending to Twitch.
   pub fn get_token(&self) -> String {
+    self.expose().to_owned()
+  }
+
+  pub fn expose(&self) -> &str {
     match self {
-      TokenAccess::User(token) => token.to_string(),
-      TokenAccess::App(token) => token.to_string(),
+      TokenAccess::User(token) => token.expose(),
+      TokenAccess::App(token) => token.expose(),
     }
   }
 }
@@ -89,61 +96,94 @@ impl TokenAccess {
 #[derive(Clone)]
 pub struct TwitchKeys {
   //  pub oauth: String,
-  pub authorisation_code: Option<String>,
+  pub authorisation_code: Option<SecretString>,
   pub access_token: Option<TokenAccess>,
-  pub refresh_token: Option<String>,
+  pub refresh_token: Option<SecretString>,
   pub client_id: String,
-  pub client_secret: String,
+  pub client_secret: SecretString,
 
   pub broadcaster_account_id: String,
   pub sender_account_id: Option<String>,
+  /// The account that moderates the channel, when that isn't the broadcaster.
+  pub moderator_account_id: Option<String>,
+  /// The account chat is read as, when that isn't the broadcaster.
+  pub chat_reader_account_id: Option<String>,
+
+  pub token_storage: Option<Arc<dyn TokenStorage>>,
 }
 
 impl TwitchKeys {
+  /// Loads `.example.env` and `.secrets.env` from the working directory into the
+  /// environment, then reads the keys the same as `from_env`.
   pub fn from_secrets_env() -> Result<TwitchKeys, TwitchKeysError> {
     simple_env_load::load_env_from([".example.env", ".secrets.env"]);
 
-    fn get(key: &str) -> Result<String, String> {
-      std::env::var(key).map_err(|_| format!("please set {key} in .example.env"))
-    }
-
-    let client_id = match get("TWITCH_CLIENT_ID") {
-      Ok(s) => s,
-      Err(e) => {
-        error!("{}", e);
-        return Err(TwitchKeysError::ClientIdNotFound);
-      }
-    };
+    TwitchKeys::from_env().inspect_err(|e| error!("{}, set them in .secrets.env", e))
+  }
 
-    let client_secret = match get("TWITCH_CLIENT_SECRET") {
-      Ok(s) => s,
-      Err(e) => {
-        error!("{}", e);
-        return Err(TwitchKeysError::ClientSecretNotFound);
-      }
-    };
+  /// Reads the keys from environment variables only, for containers and anywhere else
+  /// without an env file. `TWITCH_CLIENT_ID`, `TWITCH_CLIENT_SECRET` and
+  /// `TWITCH_BROADCASTER_ID` are required, `TWITCH_BOT_ID`, `TWITCH_MODERATOR_ID`,
+  /// `TWITCH_CHAT_READER_ID`, `TWITCH_USER_ACCESS_TOKEN` and `TWITCH_USER_REFRESH_TOKEN` are
+  /// optional. The tokens can also be given as `TWITCH_ACCESS_TOKEN` and
+  /// `TWITCH_REFRESH_TOKEN`.
+  pub fn from_env() -> Result<TwitchKeys, TwitchKeysError> {
+    fn get(key: &str) -> Option<String> {
+      std::env::var(key).ok().filter(|value| !value.is_empty())
+    }
 
-    let broadcaster_id = match get("TWITCH_BROADCASTER_ID") {
-      Ok(s) => s,
-      Err(e) => {
-        error!("{}", e);
-        return Err(TwitchKeysError::ClientSecretNotFound);
-      }
+    let mut missing = Vec::new();
+    let mut required = |key: &str| {
+      get(key).unwrap_or_else(|| {
+        missing.push(key.to_owned());
+        String::new()
+      })
     };
+    let client_id = required("TWITCH_CLIENT_ID");
+    let client_secret = required("TWITCH_CLIENT_SECRET");
+    let broadcaster_id = required("TWITCH_BROADCASTER_ID");
+    if !missing.is_empty() {
+      return Err(TwitchKeysError::MissingVariables(missing));
+    }
 
     let bot_account_id = get("TWITCH_BOT_ID").unwrap_or(broadcaster_id.to_owned());
+    let moderator_account_id = get("TWITCH_MODERATOR_ID");
+    let chat_reader_account_id = get("TWITCH_CHAT_READER_ID");
 
-    let user_access_token = get("TWITCH_USER_ACCESS_TOKEN").ok().map(TokenAccess::User);
-    let user_refresh_token = get("TWITCH_USER_REFRESH_TOKEN").ok();
+    let user_access_token = get("TWITCH_USER_ACCESS_TOKEN")
+      .or_else(|| get("TWITCH_ACCESS_TOKEN"))
+      .map(|token| TokenAccess::User(token.into()));
+    let user_refresh_token = get("TWITCH_USER_REFRESH_TOKEN")
+      .or_else(|| get("TWITCH_REFRESH_TOKEN"))
+      .map(SecretString::from);
 
     Ok(TwitchKeys {
       authorisation_code: None,
       access_token: user_access_token,
       refresh_token: user_refresh_token,
       client_id,
-      client_secret,
+      client_secret: client_secret.into(),
       broadcaster_account_id: broadcaster_id,
       sender_account_id: Some(bot_account_id),
+      moderator_account_id,
+      chat_reader_account_id,
+      token_storage: None,
     })
   }
+
+  /// The account used wherever Twitch wants a moderator, falls back to the broadcaster.
+  pub fn moderator_id(&self) -> &str {
+    self
+      .moderator_account_id
+      .as_deref()
+      .unwrap_or(&self.broadcaster_account_id)
+  }
+
+  /// The account chat messages are read as, falls back to the broadcaster.
+  pub fn chat_reader_id(&self) -> &str {
+    self
+      .chat_reader_account_id
+      .as_deref()
+      .unwrap_or(&self.broadcaster_account_id)
+  }
 }
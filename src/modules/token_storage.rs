@@ -0,0 +1,350 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::info;
+
+use crate::modules::{
+  errors::EventSubError,
+  token::{Token, TokenAccess},
+};
+use crate::{Deserialise, Serialise};
+
+/// Somewhere tokens are kept between runs, refreshed tokens are saved to it automatically.
+pub trait TokenStorage: Send + Sync {
+  /// Returns `None` when nothing has been saved yet.
+  fn load(&self) -> Result<Option<Token>, EventSubError>;
+  fn save(&self, token: &Token) -> Result<(), EventSubError>;
+  fn delete(&self) -> Result<(), EventSubError>;
+}
+
+#[derive(Serialise, Deserialise)]
+struct StoredToken {
+  access_token: String,
+  refresh_token: String,
+}
+
+impl StoredToken {
+  fn from_token(token: &Token) -> StoredToken {
+    StoredToken {
+      access_token: token.access.get_token(),
+      refresh_token: token.refresh.expose().to_owned(),
+    }
+  }
+
+  fn into_token(self) -> Token {
+    Token {
+      access: TokenAccess::User(self.access_token.into()),
+      refresh: self.refresh_token.into(),
+    }
+  }
+
+  fn to_json(token: &Token) -> Result<String, EventSubError> {
+    serde_json::to_string(&StoredToken::from_token(token))
+      .map_err(|e| EventSubError::WriteError(e.to_string()))
+  }
+
+  fn from_json(json: &str) -> Result<Token, EventSubError> {
+    serde_json::from_str::<StoredToken>(json)
+      .map(StoredToken::into_token)
+      .map_err(|e| EventSubError::ParseError(e.to_string()))
+  }
+
+  fn to_toml(token: &Token) -> Result<String, EventSubError> {
+    // Json strings are valid toml basic strings, so two keys don't need a toml writer
+    let quote = |value: String| {
+      serde_json::to_string(&value).map_err(|e| EventSubError::WriteError(e.to_string()))
+    };
+    let stored = StoredToken::from_token(token);
+    Ok(format!(
+      "access_token = {}\nrefresh_token = {}\n",
+      quote(stored.access_token)?,
+      quote(stored.refresh_token)?
+    ))
+  }
+
+  #[cfg(feature = "config")]
+  fn from_toml(text: &str) -> Result<Token, EventSubError> {
+    toml::from_str::<StoredToken>(text)
+      .map(StoredToken::into_token)
+      .map_err(|e| EventSubError::ParseError(e.to_string()))
+  }
+
+  #[cfg(not(feature = "config"))]
+  fn from_toml(_text: &str) -> Result<Token, EventSubError> {
+    Err(EventSubError::ParseError(
+      "Reading toml token files needs the config feature".to_owned(),
+    ))
+  }
+}
+
+/// Keeps the access and refresh token together in a single file, toml if the path ends in
+/// `.toml` and json otherwise.
+#[derive(Clone, Debug)]
+pub struct FileTokenStorage {
+  path: PathBuf,
+}
+
+impl FileTokenStorage {
+  pub fn new<P: Into<PathBuf>>(path: P) -> FileTokenStorage {
+    FileTokenStorage { path: path.into() }
+  }
+
+  fn is_toml(&self) -> bool {
+    self
+      .path
+      .extension()
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("toml"))
+  }
+}
+
+impl TokenStorage for FileTokenStorage {
+  fn load(&self) -> Result<Option<Token>, EventSubError> {
+    if !self.path.exists() {
+      return Ok(None);
+    }
+
+    let text =
+      fs::read_to_string(&self.path).map_err(|e| EventSubError::ReadError(e.to_string()))?;
+    if self.is_toml() {
+      StoredToken::from_toml(&text).map(Some)
+    } else {
+      StoredToken::from_json(&text).map(Some)
+    }
+  }
+
+  fn save(&self, token: &Token) -> Result<(), EventSubError> {
+    info!("Saving tokens to {}", self.path.display());
+    let text = if self.is_toml() {
+      StoredToken::to_toml(token)?
+    } else {
+      StoredToken::to_json(token)?
+    };
+    fs::write(&self.path, text).map_err(|e| EventSubError::WriteError(e.to_string()))
+  }
+
+  fn delete(&self) -> Result<(), EventSubError> {
+    if !self.path.exists() {
+      return Ok(());
+    }
+
+    fs::remove_file(&self.path).map_err(|e| EventSubError::WriteError(e.to_string()))
+  }
+}
+
+/// Keeps tokens in the OS keyring, under the given service and user name.
+#[cfg(feature = "keyring")]
+pub struct KeyringTokenStorage {
+  entry: keyring::Entry,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringTokenStorage {
+  pub fn new<S: Into<String>, T: Into<String>>(
+    service: S,
+    user: T,
+  ) -> Result<KeyringTokenStorage, EventSubError> {
+    keyring::Entry::new(&service.into(), &user.into())
+      .map(|entry| KeyringTokenStorage { entry })
+      .map_err(|e| EventSubError::WriteError(e.to_string()))
+  }
+}
+
+#[cfg(feature = "keyring")]
+impl TokenStorage for KeyringTokenStorage {
+  fn load(&self) -> Result<Option<Token>, EventSubError> {
+    match self.entry.get_password() {
+      Ok(json) => StoredToken::from_json(&json).map(Some),
+      Err(keyring::Error::NoEntry) => Ok(None),
+      Err(e) => Err(EventSubError::ReadError(e.to_string())),
+    }
+  }
+
+  fn save(&self, token: &Token) -> Result<(), EventSubError> {
+    self
+      .entry
+      .set_password(&StoredToken::to_json(token)?)
+      .map_err(|e| EventSubError::WriteError(e.to_string()))
+  }
+
+  fn delete(&self) -> Result<(), EventSubError> {
+    match self.entry.delete_credential() {
+      Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+      Err(e) => Err(EventSubError::WriteError(e.to_string())),
+    }
+  }
+}
+
+#[cfg(feature = "encrypted_storage")]
+enum EncryptionKey {
+  Passphrase(String),
+  Key([u8; 32]),
+}
+
+/// Like `FileTokenStorage`, but the file is encrypted so sharing the folder it's in doesn't
+/// leak the tokens.
+#[cfg(feature = "encrypted_storage")]
+pub struct EncryptedFileStorage {
+  path: PathBuf,
+  key: EncryptionKey,
+}
+
+#[cfg(feature = "encrypted_storage")]
+impl EncryptedFileStorage {
+  const SALT_LENGTH: usize = 16;
+  const NONCE_LENGTH: usize = 12;
+
+  /// Encrypts with a key derived from the passphrase.
+  pub fn with_passphrase<P: Into<PathBuf>, S: Into<String>>(
+    path: P,
+    passphrase: S,
+  ) -> EncryptedFileStorage {
+    EncryptedFileStorage {
+      path: path.into(),
+      key: EncryptionKey::Passphrase(passphrase.into()),
+    }
+  }
+
+  pub fn with_key<P: Into<PathBuf>>(path: P, key: [u8; 32]) -> EncryptedFileStorage {
+    EncryptedFileStorage {
+      path: path.into(),
+      key: EncryptionKey::Key(key),
+    }
+  }
+
+  /// Encrypts with a random key kept in the OS keyring, which is created on first use.
+  #[cfg(feature = "keyring")]
+  pub fn with_keyring_key<P: Into<PathBuf>, S: Into<String>, T: Into<String>>(
+    path: P,
+    service: S,
+    user: T,
+  ) -> Result<EncryptedFileStorage, EventSubError> {
+    use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+
+    let entry = keyring::Entry::new(&service.into(), &user.into())
+      .map_err(|e| EventSubError::WriteError(e.to_string()))?;
+
+    let key = match entry.get_secret() {
+      Ok(secret) => secret
+        .try_into()
+        .map_err(|_| EventSubError::ParseError("Keyring key isn't 32 bytes".to_owned()))?,
+      Err(keyring::Error::NoEntry) => {
+        let mut key = [0; 32];
+        OsRng.fill_bytes(&mut key);
+        entry
+          .set_secret(&key)
+          .map_err(|e| EventSubError::WriteError(e.to_string()))?;
+        key
+      }
+      Err(e) => return Err(EventSubError::ReadError(e.to_string())),
+    };
+
+    Ok(EncryptedFileStorage::with_key(path, key))
+  }
+
+  fn cipher(&self, salt: &[u8]) -> Result<chacha20poly1305::ChaCha20Poly1305, EventSubError> {
+    use chacha20poly1305::KeyInit;
+
+    let key = match &self.key {
+      EncryptionKey::Passphrase(passphrase) => {
+        let mut key = [0; 32];
+        argon2::Argon2::default()
+          .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+          .map_err(|e| EventSubError::WriteError(e.to_string()))?;
+        key
+      }
+      EncryptionKey::Key(key) => *key,
+    };
+
+    Ok(chacha20poly1305::ChaCha20Poly1305::new(&key.into()))
+  }
+}
+
+#[cfg(feature = "encrypted_storage")]
+impl TokenStorage for EncryptedFileStorage {
+  fn load(&self) -> Result<Option<Token>, EventSubError> {
+    use chacha20poly1305::aead::Aead;
+
+    if !self.path.exists() {
+      return Ok(None);
+    }
+
+    let contents = fs::read(&self.path).map_err(|e| EventSubError::ReadError(e.to_string()))?;
+    if contents.len() < Self::SALT_LENGTH + Self::NONCE_LENGTH {
+      return Err(EventSubError::ParseError(
+        "Encrypted token file is too short".to_owned(),
+      ));
+    }
+
+    let (salt, rest) = contents.split_at(Self::SALT_LENGTH);
+    let (nonce, ciphertext) = rest.split_at(Self::NONCE_LENGTH);
+    let json = self
+      .cipher(salt)?
+      .decrypt(nonce.into(), ciphertext)
+      .map_err(|_| {
+        EventSubError::ParseError("Failed to decrypt tokens, is the key right?".to_owned())
+      })?;
+
+    StoredToken::from_json(&String::from_utf8_lossy(&json)).map(Some)
+  }
+
+  fn save(&self, token: &Token) -> Result<(), EventSubError> {
+    use chacha20poly1305::aead::{rand_core::RngCore, Aead, AeadCore, OsRng};
+
+    let mut salt = [0; Self::SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = chacha20poly1305::ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let ciphertext = self
+      .cipher(&salt)?
+      .encrypt(&nonce, StoredToken::to_json(token)?.as_bytes())
+      .map_err(|e| EventSubError::WriteError(e.to_string()))?;
+
+    let mut contents = salt.to_vec();
+    contents.extend_from_slice(&nonce);
+    contents.extend_from_slice(&ciphertext);
+
+    info!("Saving encrypted tokens to {}", self.path.display());
+    fs::write(&self.path, contents).map_err(|e| EventSubError::WriteError(e.to_string()))
+  }
+
+  fn delete(&self) -> Result<(), EventSubError> {
+    if !self.path.exists() {
+      return Ok(());
+    }
+
+    fs::remove_file(&self.path).map_err(|e| EventSubError::WriteError(e.to_string()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn read_failures_are_read_errors() {
+    // A directory exists but can't be read as a file
+    let storage = FileTokenStorage::new(std::env::temp_dir());
+
+    assert!(matches!(storage.load(), Err(EventSubError::ReadError(_))));
+  }
+
+  #[cfg(feature = "config")]
+  #[test]
+  fn file_storage_round_trips_json_and_toml() {
+    let token = Token {
+      access: TokenAccess::User("access \"token\"".into()),
+      refresh: "refresh\\token".into(),
+    };
+
+    for name in ["tokens_round_trip.json", "tokens_round_trip.toml"] {
+      let path = std::env::temp_dir().join(name);
+      let storage = FileTokenStorage::new(&path);
+      storage.save(&token).unwrap();
+      let loaded = storage.load().unwrap().unwrap();
+      storage.delete().unwrap();
+
+      assert_eq!(loaded.access.get_token(), token.access.get_token());
+      assert_eq!(loaded.refresh.expose(), token.refresh.expose());
+    }
+  }
+}
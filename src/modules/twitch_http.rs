@@ -1,59 +1,94 @@
-use crate::{EventSubError, SendMessage, Subscription, Token, TwitchEventSubApi, Validation};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "moderation")]
+use crate::{
+  modules::generic_message::{SendTimeoutRequest, TimeoutRequestData},
+  MessageId,
+};
+#[cfg(any(feature = "chat", feature = "moderation"))]
+use crate::{BroadcasterId, UserId};
+use crate::{
+  Deserialise, EventSubError, SecretString, Subscription, Token, TokenStorage, TwitchEventSubApi,
+  Validation,
+};
+#[cfg(feature = "chat")]
+use crate::{HelixData, SendMessage, SentMessage};
+
 use curl::easy::{Easy, List};
 
-use log::{error, info};
+use crate::{error, info, warn};
+
+use serde::de::DeserializeOwned;
 
 use crate::modules::{
   consts::*,
-  generic_message::{SendTimeoutRequest, TimeoutRequestData},
+  generic_message::{DeviceCode, NewAccessTokenResponse},
+  helix::parse_response,
+  metrics,
 };
 
 pub struct TwitchApi;
 
 impl TwitchApi {
   /// Returns EventSubError::
-  pub fn send_chat_message<S: Into<String>, T: Into<String>, V: Into<String>, X: Into<String>>(
+  #[cfg(feature = "chat")]
+  pub fn send_chat_message<
+    S: Into<String>,
+    T: Into<String>,
+    V: Into<String>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
+  >(
     message: S,
     access_token: T,
     client_id: V,
     broadcaster_account_id: X,
-    sender_account_id: Option<V>,
+    sender_account_id: Option<Z>,
     is_reply_parent_message_id: Option<String>,
-  ) -> Result<String, EventSubError> {
+  ) -> Result<TwitchApiResponse<HelixData<SentMessage>>, EventSubError> {
     let message = message.into();
-    if message.len() > 500 {
+    if message.chars().count() > MAX_CHAT_MESSAGE_LENGTH {
       return Err(EventSubError::MessageTooLong);
     }
 
     let broadcaster_account_id = broadcaster_account_id.into();
+    let sender_account_id = sender_account_id
+      .map(Into::into)
+      .unwrap_or_else(|| UserId::from(&broadcaster_account_id));
     TwitchHttpRequest::new(SEND_MESSAGE_URL)
       .json_content()
       .full_auth(access_token, client_id)
       .is_post(
         serde_json::to_string(&SendMessage {
-          broadcaster_id: broadcaster_account_id.to_owned(),
-          sender_id: sender_account_id
-            .ok_or(broadcaster_account_id)
-            .map(|s| s.into())
-            .unwrap(),
-          message: message.into(),
+          broadcaster_id: broadcaster_account_id.into_string(),
+          sender_id: sender_account_id.into_string(),
+          message,
           reply_parent_message_id: is_reply_parent_message_id,
         })
         .unwrap(),
       )
-      .run()
+      .send()
+      .and_then(TwitchApiResponse::parse)
   }
 
-  pub fn generate_token_from_refresh_token<S: Into<String>, T: Into<String>, V: Into<String>>(
+  pub fn generate_token_from_refresh_token<
+    S: Into<String>,
+    T: Into<SecretString>,
+    V: Into<SecretString>,
+  >(
     client_id: S,
     client_secret: T,
     refresh_token: V,
   ) -> Result<Token, EventSubError> {
     let post_data = format!(
       "grant_type=refresh_token&refresh_token={}&client_id={}&client_secret={}",
-      refresh_token.into(),
+      refresh_token.into().expose(),
       client_id.into(),
-      client_secret.into()
+      client_secret.into().expose()
     );
 
     TwitchEventSubApi::process_token_query(post_data)
@@ -61,8 +96,8 @@ impl TwitchApi {
 
   pub fn get_user_token_from_authorisation_code<
     S: Into<String>,
-    T: Into<String>,
-    V: Into<String>,
+    T: Into<SecretString>,
+    V: Into<SecretString>,
     W: Into<String>,
   >(
     client_id: S,
@@ -73,8 +108,8 @@ impl TwitchApi {
     let post_data = format!(
       "client_id={}&client_secret={}&code={}&grant_type=authorization_code&redirect_uri={}",
       client_id.into(),
-      client_secret.into(),
-      authorisation_code.into(),
+      client_secret.into().expose(),
+      authorisation_code.into().expose(),
       redirect_url.into()
     );
 
@@ -84,29 +119,17 @@ impl TwitchApi {
   pub fn get_authorisation_code<S: Into<String>, T: Into<String>>(
     client_id: S,
     redirect_url: T,
-    scopes: &Vec<Subscription>,
+    scopes: &[Subscription],
   ) -> Result<String, EventSubError> {
     let redirect_url = redirect_url.into();
 
-    let scope = &scopes
-      .iter()
-      .map(|s| s.required_scope())
-      .filter(|s| !s.is_empty())
-      .collect::<Vec<String>>()
-      .join("+");
-
-    let get_authorisation_code_request = format!(
-      "{}authorize?response_type=code&client_id={}&redirect_uri={}&scope={}",
-      TWITCH_AUTHORISE_URL,
-      client_id.into(),
-      redirect_url.to_owned(),
-      scope
-    );
+    let get_authorisation_code_request =
+      TwitchApi::authorisation_url(client_id, redirect_url.to_owned(), scopes);
 
     match TwitchEventSubApi::open_browser(get_authorisation_code_request, redirect_url) {
       Ok(http_response) => {
         if http_response.contains("error") {
-          Err(EventSubError::UnhandledError(format!("{}", http_response)))
+          Err(EventSubError::AuthorisationError(http_response))
         } else {
           let auth_code = http_response.split('&').collect::<Vec<_>>()[0]
             .split('=')
@@ -118,36 +141,217 @@ impl TwitchApi {
     }
   }
 
-  pub fn generate_user_token<S: Into<String>, T: Into<String>, V: Into<String>>(
+  /// The page the user has to visit to authorise the app for the subscriptions' scopes.
+  pub fn authorisation_url<S: Into<String>, T: Into<String>>(
+    client_id: S,
+    redirect_url: T,
+    scopes: &[Subscription],
+  ) -> String {
+    format!(
+      "{}authorize?response_type=code&client_id={}&redirect_uri={}&scope={}",
+      TWITCH_AUTHORISE_URL,
+      client_id.into(),
+      redirect_url.into(),
+      scope_list(scopes)
+    )
+  }
+
+  /// Gets an authorisation code without opening a browser or listening on a port.
+  /// `prompt` is given the url to show the user and returns what they paste back, either
+  /// the url they were redirected to or just the code in it.
+  pub fn get_authorisation_code_manually<
+    S: Into<String>,
+    T: Into<String>,
+    F: FnOnce(&str) -> String,
+  >(
+    client_id: S,
+    redirect_url: T,
+    scopes: &[Subscription],
+    prompt: F,
+  ) -> Result<String, EventSubError> {
+    let url = TwitchApi::authorisation_url(client_id, redirect_url, scopes);
+    let pasted = prompt(&url);
+    let pasted = pasted.trim();
+
+    if pasted.contains("error=") {
+      return Err(EventSubError::AuthorisationError(pasted.to_owned()));
+    }
+
+    let code = match pasted.split_once("code=") {
+      Some((_, code)) => code.split(['&', ' ']).next().unwrap_or_default(),
+      None => pasted,
+    };
+
+    if code.is_empty() {
+      return Err(EventSubError::AuthorisationError(
+        "No authorisation code was entered".to_owned(),
+      ));
+    }
+
+    Ok(code.to_owned())
+  }
+
+  pub fn generate_user_token_manually<
+    S: Into<String>,
+    T: Into<SecretString>,
+    V: Into<String>,
+    F: FnOnce(&str) -> String,
+  >(
     client_id: S,
     client_secret: T,
     redirect_url: V,
-    subscriptions: &Vec<Subscription>,
+    subscriptions: &[Subscription],
+    prompt: F,
   ) -> Result<Token, EventSubError> {
     let client_id = client_id.into();
-    let client_secret = client_secret.into();
     let redirect_url = redirect_url.into();
 
-    TwitchApi::get_authorisation_code(
+    TwitchApi::get_authorisation_code_manually(
       client_id.to_owned(),
       redirect_url.to_owned(),
-      &subscriptions,
+      subscriptions,
+      prompt,
     )
     .and_then(|authorisation_code| {
       TwitchApi::get_user_token_from_authorisation_code(
-        client_id.to_owned(),
-        client_secret.to_owned(),
-        authorisation_code.to_owned(),
-        redirect_url.to_owned(),
+        client_id,
+        client_secret,
+        authorisation_code,
+        redirect_url,
       )
     })
   }
 
+  pub fn generate_user_token<S: Into<String>, T: Into<SecretString>, V: Into<String>>(
+    client_id: S,
+    client_secret: T,
+    redirect_url: V,
+    subscriptions: &[Subscription],
+  ) -> Result<Token, EventSubError> {
+    let client_id = client_id.into();
+    let client_secret = client_secret.into();
+    let redirect_url = redirect_url.into();
+
+    TwitchApi::get_authorisation_code(client_id.to_owned(), redirect_url.to_owned(), subscriptions)
+      .and_then(|authorisation_code| {
+        TwitchApi::get_user_token_from_authorisation_code(
+          client_id.to_owned(),
+          client_secret.to_owned(),
+          authorisation_code.to_owned(),
+          redirect_url.to_owned(),
+        )
+      })
+  }
+
+  /// Invalidates an access token so it can no longer be used.
+  pub fn revoke_token<S: Into<String>, T: Into<SecretString>>(
+    client_id: S,
+    token: T,
+  ) -> Result<(), EventSubError> {
+    let post_data = format!(
+      "client_id={}&token={}",
+      client_id.into(),
+      token.into().expose()
+    );
+
+    TwitchHttpRequest::new(TWITCH_REVOKE_URL)
+      .url_encoded_content()
+      .is_post(post_data)
+      .send()
+      .and_then(|response| response.error_for_status())
+  }
+
+  /// Starts the device code flow, for hosts that can't open a browser.
+  pub fn get_device_code<S: Into<String>>(
+    client_id: S,
+    scopes: &[Subscription],
+  ) -> Result<DeviceCode, EventSubError> {
+    let post_data = format!(
+      "client_id={}&scopes={}",
+      client_id.into(),
+      scope_list(scopes)
+    );
+
+    TwitchHttpRequest::new(TWITCH_DEVICE_URL)
+      .url_encoded_content()
+      .is_post(post_data)
+      .send()
+      .and_then(TwitchApiResponse::parse)
+      .map(|response| response.data)
+  }
+
+  /// Waits for the user to enter the device code, then returns their token.
+  pub fn get_user_token_from_device_code<S: Into<String>>(
+    client_id: S,
+    device_code: &DeviceCode,
+    scopes: &[Subscription],
+  ) -> Result<Token, EventSubError> {
+    let post_data = format!(
+      "client_id={}&scopes={}&device_code={}&grant_type=urn:ietf:params:oauth:grant-type:device_code",
+      client_id.into(),
+      scope_list(scopes),
+      device_code.device_code
+    );
+
+    let started = Instant::now();
+    let mut interval = Duration::from_secs(device_code.interval);
+    loop {
+      thread::sleep(interval);
+
+      if started.elapsed() > Duration::from_secs(device_code.expires_in) {
+        return Err(EventSubError::AuthorisationError(
+          "Device code expired before it was entered".to_owned(),
+        ));
+      }
+
+      let response = TwitchHttpRequest::new(TWITCH_TOKEN_URL)
+        .url_encoded_content()
+        .is_post(post_data.to_owned())
+        .send()
+        .and_then(TwitchApiResponse::parse::<NewAccessTokenResponse>);
+
+      match response {
+        Ok(response) => {
+          let token = response.data;
+          return Ok(Token::new_user_token(
+            token.access_token,
+            token.refresh_token.unwrap_or_default(),
+            token.expires_in as f32,
+          ));
+        }
+        Err(EventSubError::BadRequest(message)) if message == "authorization_pending" => {}
+        Err(EventSubError::BadRequest(message)) if message == "slow_down" => {
+          interval += Duration::from_secs(5);
+        }
+        Err(e) => return Err(e),
+      }
+    }
+  }
+
+  /// Runs the whole device code flow, `display` is given the code to show the user.
+  pub fn generate_user_token_from_device_code<S: Into<String>, F: FnOnce(&DeviceCode)>(
+    client_id: S,
+    subscriptions: &[Subscription],
+    display: F,
+  ) -> Result<Token, EventSubError> {
+    let client_id = client_id.into();
+
+    let device_code = TwitchApi::get_device_code(client_id.to_owned(), subscriptions)?;
+    info!(
+      "Waiting for device code {} to be entered at {}",
+      device_code.user_code, device_code.verification_uri
+    );
+    display(&device_code);
+
+    TwitchApi::get_user_token_from_device_code(client_id, &device_code, subscriptions)
+  }
+
+  #[cfg(feature = "moderation")]
   pub fn delete_message<
     U: Into<String>,
-    S: Into<String>,
-    X: Into<String>,
-    Z: Into<String>,
+    S: Into<MessageId>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
     F: Into<String>,
   >(
     broadcaster_id: X,
@@ -169,12 +373,13 @@ impl TwitchApi {
       .run()
   }
 
+  #[cfg(feature = "moderation")]
   pub fn timeout_user<
     T: Into<String>,
     S: Into<String>,
-    V: Into<String>,
-    X: Into<String>,
-    Z: Into<String>,
+    V: Into<UserId>,
+    X: Into<BroadcasterId>,
+    Z: Into<UserId>,
     O: Into<String>,
   >(
     access_token: T,
@@ -193,7 +398,7 @@ impl TwitchApi {
 
     let post_data = SendTimeoutRequest {
       data: TimeoutRequestData {
-        user_id: user_id.into(),
+        user_id: user_id.into().into_string(),
         duration: duration_secs,
         reason: reason.into(),
       },
@@ -213,6 +418,8 @@ impl TwitchApi {
 #[derive(PartialEq, Clone, Debug)]
 pub enum RequestType {
   Post(String),
+  Put(String),
+  Patch(String),
   Delete,
 }
 
@@ -222,23 +429,39 @@ pub enum AuthType {
   OAuth,
 }
 
-impl AuthType {
-  pub fn to_string(&self) -> String {
+impl fmt::Display for AuthType {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     match self {
-      AuthType::Bearer => "Bearer",
-      AuthType::OAuth => "OAuth",
+      AuthType::Bearer => write!(f, "Bearer"),
+      AuthType::OAuth => write!(f, "OAuth"),
     }
-    .into()
   }
 }
 
 impl RequestType {
+  pub fn method(&self) -> &'static str {
+    match self {
+      RequestType::Post(_) => "POST",
+      RequestType::Put(_) => "PUT",
+      RequestType::Patch(_) => "PATCH",
+      RequestType::Delete => "DELETE",
+    }
+  }
+
   pub fn apply(&self, handle: &mut Easy) {
     match self {
       RequestType::Post(data) => {
         handle.post(true).unwrap();
         handle.post_fields_copy(data.as_bytes()).unwrap();
       }
+      RequestType::Put(data) => {
+        let _ = handle.custom_request("PUT");
+        handle.post_fields_copy(data.as_bytes()).unwrap();
+      }
+      RequestType::Patch(data) => {
+        let _ = handle.custom_request("PATCH");
+        handle.post_fields_copy(data.as_bytes()).unwrap();
+      }
       RequestType::Delete => {
         let _ = handle.custom_request("DELETE");
       }
@@ -246,60 +469,350 @@ impl RequestType {
   }
 }
 
+/// Builds a url's query string, for calling endpoints with `TwitchHttpRequest`.
+#[derive(Clone, Debug, Default)]
 pub struct RequestBuilder {
   data: Vec<(String, String)>,
 }
 
 impl RequestBuilder {
-  fn new() -> RequestBuilder {
+  pub fn new() -> RequestBuilder {
     RequestBuilder { data: Vec::new() }
   }
 
-  fn add_key_value<S: Into<String>, T: Into<String>>(mut self, key: S, value: T) -> RequestBuilder {
+  pub fn add_key_value<S: Into<String>, T: Into<String>>(
+    mut self,
+    key: S,
+    value: T,
+  ) -> RequestBuilder {
     self.data.push((key.into(), value.into()));
     self
   }
 
-  fn build<S: Into<String>>(self, url: S) -> String {
-    let mut request = url.into();
+  pub fn add_optional_key_value<S: Into<String>, T: Into<String>>(
+    self,
+    key: S,
+    value: Option<T>,
+  ) -> RequestBuilder {
+    match value {
+      Some(value) => self.add_key_value(key, value),
+      None => self,
+    }
+  }
 
-    if !self.data.is_empty() {
-      request = format!("{}?", request);
+  /// Adds the key once for each value, e.g. `id=1&id=2`.
+  pub fn add_key_values<S: Into<String>, T: Into<String>, I: IntoIterator<Item = T>>(
+    mut self,
+    key: S,
+    values: I,
+  ) -> RequestBuilder {
+    let key = key.into();
+    for value in values {
+      self.data.push((key.to_owned(), value.into()));
     }
+    self
+  }
 
-    for (key, value) in self.data {
-      request = format!("{}&{}={}", request, key, value);
+  /// The url with the keys and values percent-encoded and added to its query.
+  pub fn build<S: Into<String>>(self, url: S) -> String {
+    let url = url.into();
+    if self.data.is_empty() {
+      return url;
     }
 
-    request
+    let query = self
+      .data
+      .iter()
+      .map(|(key, value)| format!("{}={}", percent_encode(key), percent_encode(value)))
+      .collect::<Vec<_>>()
+      .join("&");
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}{}", url, separator, query)
+  }
+}
+
+// Everything but letters, digits and `-._~` is encoded, as UTF-8 bytes
+fn percent_encode(text: &str) -> String {
+  let mut encoded = String::with_capacity(text.len());
+  for byte in text.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+        encoded.push(byte as char)
+      }
+      _ => encoded.push_str(&format!("%{:02X}", byte)),
+    }
   }
+  encoded
 }
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum Header {
-  Auth((AuthType, String)),
+  Auth((AuthType, SecretString)),
   ClientId(String),
   ContentJson,
   ContentUrlEncoded,
+  /// Any other header, as its name and value.
+  Custom(String, String),
 }
 
 impl Header {
   pub fn generate(&self) -> String {
     match self {
       Header::Auth((auth_type, token)) => {
-        format!("Authorization: {} {}", auth_type.to_string(), token)
+        format!("Authorization: {} {}", auth_type, token.expose())
       }
       Header::ClientId(id) => {
         format!("Client-Id: {}", id)
       }
-      Header::ContentJson => {
-        format!("Content-Type: application/json")
-      }
-      Header::ContentUrlEncoded => {
-        format!("Content-Type: application/x-www-form-urlencoded")
-      }
+      Header::ContentJson => "Content-Type: application/json".to_string(),
+      Header::ContentUrlEncoded => "Content-Type: application/x-www-form-urlencoded".to_string(),
+      Header::Custom(name, value) => format!("{}: {}", name, value),
+    }
+  }
+}
+
+/// Where requests go instead of Twitch, used to talk to mock servers.
+#[derive(Clone, Debug)]
+pub struct ApiUrls {
+  pub websocket: String,
+  /// Replaces `https://api.twitch.tv/helix`.
+  pub helix: String,
+  /// Replaces `https://id.twitch.tv/oauth2`.
+  pub auth: String,
+}
+
+impl Default for ApiUrls {
+  fn default() -> ApiUrls {
+    ApiUrls {
+      websocket: CONNECTION_EVENTS.to_owned(),
+      helix: TWITCH_HELIX_URL.to_owned(),
+      auth: TWITCH_OAUTH_URL.to_owned(),
+    }
+  }
+}
+
+impl ApiUrls {
+  /// Swaps every url to its unencrypted scheme, for local servers without TLS.
+  pub fn without_tls(self) -> ApiUrls {
+    let no_tls = |url: String| {
+      url
+        .replacen("wss://", "ws://", 1)
+        .replacen("https://", "http://", 1)
+    };
+    ApiUrls {
+      websocket: no_tls(self.websocket),
+      helix: no_tls(self.helix),
+      auth: no_tls(self.auth),
+    }
+  }
+}
+
+static URL_OVERRIDES: RwLock<Option<ApiUrls>> = RwLock::new(None);
+
+pub(crate) fn set_url_overrides(urls: Option<ApiUrls>) {
+  *URL_OVERRIDES.write().unwrap() = urls;
+}
+
+pub(crate) fn websocket_url() -> String {
+  match &*URL_OVERRIDES.read().unwrap() {
+    Some(urls) => urls.websocket.to_owned(),
+    None => CONNECTION_EVENTS.to_owned(),
+  }
+}
+
+fn override_url(url: String) -> String {
+  match &*URL_OVERRIDES.read().unwrap() {
+    Some(urls) => {
+      url
+        .replacen(TWITCH_HELIX_URL, &urls.helix, 1)
+        .replacen(TWITCH_OAUTH_URL, &urls.auth, 1)
+    }
+    None => url,
+  }
+}
+
+/// How long requests can take before failing with `EventSubError::TimedOut`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HttpTimeouts {
+  /// For connecting to the server.
+  pub connect: Option<Duration>,
+  /// For nothing being received, the request fails once the server goes quiet this long.
+  pub read: Option<Duration>,
+  /// For the whole request from start to finish.
+  pub total: Option<Duration>,
+}
+
+impl HttpTimeouts {
+  pub const DEFAULT: HttpTimeouts = HttpTimeouts {
+    connect: Some(Duration::from_secs(10)),
+    read: Some(Duration::from_secs(30)),
+    total: None,
+  };
+}
+
+impl Default for HttpTimeouts {
+  fn default() -> HttpTimeouts {
+    HttpTimeouts::DEFAULT
+  }
+}
+
+static DEFAULT_TIMEOUTS: RwLock<HttpTimeouts> = RwLock::new(HttpTimeouts::DEFAULT);
+
+/// Timeouts for every request that doesn't set its own.
+pub fn set_default_http_timeouts(timeouts: HttpTimeouts) {
+  *DEFAULT_TIMEOUTS.write().unwrap() = timeouts;
+}
+
+/// Stops a request from another thread, it fails with `EventSubError::Cancelled`. Clones
+/// share the same token, so one can be kept while the other is given to the request.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+  cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+  pub fn new() -> CancellationToken {
+    CancellationToken::default()
+  }
+
+  /// Cancels requests running with the token, and any started with it after.
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::Relaxed);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::Relaxed)
+  }
+}
+
+impl PartialEq for CancellationToken {
+  fn eq(&self, other: &CancellationToken) -> bool {
+    Arc::ptr_eq(&self.cancelled, &other.cancelled)
+  }
+}
+
+/// Sees every request before it's sent and every response that comes back, for adding
+/// headers, logging traffic or caching responses. Both do nothing unless overridden.
+pub trait HttpMiddleware: Send + Sync {
+  /// Can change the request before it's sent. Returning a response sends that back instead,
+  /// without the request going to Twitch.
+  fn before(&self, _request: &mut TwitchHttpRequest) -> Option<TwitchApiResponse<String>> {
+    None
+  }
+
+  fn after(&self, _request: &TwitchHttpRequest, _response: &TwitchApiResponse<String>) {}
+}
+
+static MIDDLEWARE: RwLock<Vec<Arc<dyn HttpMiddleware>>> = RwLock::new(Vec::new());
+
+/// Runs the middleware for every request made in the process, after any added before it.
+pub fn add_http_middleware(middleware: Arc<dyn HttpMiddleware>) {
+  MIDDLEWARE.write().unwrap().push(middleware);
+}
+
+/// Removes all the middleware added with `add_http_middleware`.
+pub fn clear_http_middleware() {
+  MIDDLEWARE.write().unwrap().clear();
+}
+
+/// Hands out a new access token when Twitch rejects a request's, so the request can be
+/// retried with it. Closures returning the new token work too.
+pub trait TokenProvider: Send + Sync {
+  fn refresh_access_token(&self) -> Result<String, EventSubError>;
+}
+
+impl<F: Fn() -> Result<String, EventSubError> + Send + Sync> TokenProvider for F {
+  fn refresh_access_token(&self) -> Result<String, EventSubError> {
+    self()
+  }
+}
+
+/// Refreshes user access tokens with the refresh token, saving each new pair to the
+/// storage if there is one. Shared between requests, so a token refreshed by one is used by
+/// the rest.
+pub struct RefreshTokenProvider {
+  client_id: String,
+  client_secret: SecretString,
+  token: Mutex<Token>,
+  storage: Option<Arc<dyn TokenStorage>>,
+}
+
+impl RefreshTokenProvider {
+  pub fn new<S: Into<String>, T: Into<SecretString>>(
+    client_id: S,
+    client_secret: T,
+    token: Token,
+  ) -> RefreshTokenProvider {
+    RefreshTokenProvider {
+      client_id: client_id.into(),
+      client_secret: client_secret.into(),
+      token: Mutex::new(token),
+      storage: None,
+    }
+  }
+
+  pub fn storage(mut self, storage: Arc<dyn TokenStorage>) -> RefreshTokenProvider {
+    self.storage = Some(storage);
+    self
+  }
+
+  /// The newest access token.
+  pub fn access_token(&self) -> String {
+    self.token.lock().unwrap().access.get_token()
+  }
+}
+
+impl TokenProvider for RefreshTokenProvider {
+  fn refresh_access_token(&self) -> Result<String, EventSubError> {
+    let mut token = self.token.lock().unwrap();
+    *token = refresh_user_token(
+      &self.client_id,
+      &self.client_secret,
+      &token.refresh,
+      self.storage.as_deref(),
+    )?;
+    Ok(token.access.get_token())
+  }
+}
+
+/// Gets a new token pair from the refresh token and saves it to the storage.
+pub(crate) fn refresh_user_token(
+  client_id: &str,
+  client_secret: &SecretString,
+  refresh_token: &SecretString,
+  storage: Option<&dyn TokenStorage>,
+) -> Result<Token, EventSubError> {
+  let token = TwitchApi::generate_token_from_refresh_token(
+    client_id,
+    client_secret.to_owned(),
+    refresh_token.to_owned(),
+  );
+  metrics::token_refresh(token.is_ok());
+  let token = token?;
+
+  info!("Generated new keys as 401 was returned!");
+  if let Some(storage) = storage {
+    if let Err(e) = storage.save(&token) {
+      warn!("Failed to save refreshed tokens: {:?}", e);
     }
   }
+  Ok(token)
+}
+
+// Requests are compared and printed, providers can't be so only which one it is counts
+#[derive(Clone)]
+struct SharedTokenProvider(Arc<dyn TokenProvider>);
+
+impl PartialEq for SharedTokenProvider {
+  fn eq(&self, other: &SharedTokenProvider) -> bool {
+    Arc::ptr_eq(&self.0, &other.0)
+  }
+}
+
+impl fmt::Debug for SharedTokenProvider {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "TokenProvider")
+  }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -307,17 +820,46 @@ pub struct TwitchHttpRequest {
   url: String,
   headers: Vec<Header>,
   request_type: Option<RequestType>,
+  token_provider: Option<SharedTokenProvider>,
+  // Boxed as requests are carried in EventSubError::TokenRequiresRefreshing
+  timeouts: Option<Box<HttpTimeouts>>,
+  cancellation: Option<CancellationToken>,
 }
 
 impl TwitchHttpRequest {
   pub fn new<S: Into<String>>(url: S) -> TwitchHttpRequest {
     TwitchHttpRequest {
-      url: url.into(),
+      url: override_url(url.into()),
       headers: Vec::new(),
       request_type: None,
+      token_provider: None,
+      timeouts: None,
+      cancellation: None,
     }
   }
 
+  /// When Twitch rejects the access token, gets a new one from the provider and retries the
+  /// request once with it, instead of failing with `EventSubError::TokenRequiresRefreshing`
+  /// or a 401.
+  #[must_use]
+  pub fn token_provider(mut self, provider: Arc<dyn TokenProvider>) -> TwitchHttpRequest {
+    self.token_provider = Some(SharedTokenProvider(provider));
+    self
+  }
+
+  /// Uses these timeouts instead of the defaults set with `set_default_http_timeouts`.
+  #[must_use]
+  pub fn timeouts(mut self, timeouts: HttpTimeouts) -> TwitchHttpRequest {
+    self.timeouts = Some(Box::new(timeouts));
+    self
+  }
+
+  #[must_use]
+  pub fn cancel_with(mut self, cancellation: CancellationToken) -> TwitchHttpRequest {
+    self.cancellation = Some(cancellation);
+    self
+  }
+
   #[must_use]
   pub fn full_auth<S: Into<String>, T: Into<String>>(
     self,
@@ -341,7 +883,9 @@ impl TwitchHttpRequest {
     token: S,
     auth_type: AuthType,
   ) -> TwitchHttpRequest {
-    self.headers.push(Header::Auth((auth_type, token.into())));
+    self
+      .headers
+      .push(Header::Auth((auth_type, SecretString::new(token))));
     self
   }
 
@@ -375,23 +919,164 @@ impl TwitchHttpRequest {
     self
   }
 
+  #[must_use]
+  pub fn is_put<S: Into<String>>(mut self, data: S) -> TwitchHttpRequest {
+    self.request_type = Some(RequestType::Put(data.into()));
+    self
+  }
+
+  #[must_use]
+  pub fn is_patch<S: Into<String>>(mut self, data: S) -> TwitchHttpRequest {
+    self.request_type = Some(RequestType::Patch(data.into()));
+    self
+  }
+
+  pub fn url(&self) -> &str {
+    &self.url
+  }
+
+  pub fn method(&self) -> &'static str {
+    self
+      .request_type
+      .as_ref()
+      .map_or("GET", RequestType::method)
+  }
+
+  /// The data sent with POST, PUT and PATCH requests.
+  pub fn body(&self) -> Option<&str> {
+    match &self.request_type {
+      Some(RequestType::Post(data) | RequestType::Put(data) | RequestType::Patch(data)) => {
+        Some(data)
+      }
+      _ => None,
+    }
+  }
+
+  pub fn headers(&self) -> &[Header] {
+    &self.headers
+  }
+
+  /// Adds a header, or replaces the custom header with the same name.
+  pub fn set_header(&mut self, header: Header) {
+    if let Header::Custom(name, _) = &header {
+      self.headers.retain(
+        |existing| !matches!(existing, Header::Custom(existing, _) if existing.eq_ignore_ascii_case(name)),
+      );
+    }
+    self.headers.push(header);
+  }
+
   pub fn update_token<S: Into<String>>(&mut self, new_token: S) {
     for header in &mut self.headers {
       if let Header::Auth((_, ref mut token)) = header {
-        *token = new_token.into();
+        *token = SecretString::new(new_token);
         break;
       }
     }
   }
 
+  /// Runs the request and returns the body, error status codes are returned as the matching
+  /// EventSubError. A 401 for anything other than a missing scope is returned as
+  /// `TokenRequiresRefreshing` so the request can be rerun with a new token.
   pub fn run(&self) -> Result<String, EventSubError> {
+    let response = self.send()?;
+    match response.error_for_status() {
+      Ok(()) => Ok(response.raw),
+      Err(EventSubError::Unauthorized {
+        missing_scope: None,
+      }) => Err(EventSubError::TokenRequiresRefreshing(self.to_owned())),
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Runs the request, keeping the status code and rate limit headers alongside the body.
+  pub fn send(&self) -> Result<TwitchApiResponse<String>, EventSubError> {
+    let response = self.send_once();
+    let Some(SharedTokenProvider(provider)) = &self.token_provider else {
+      return response;
+    };
+    // A new token won't have any more scopes than the old one
+    let rejected = match &response {
+      Ok(response) => matches!(
+        response.error_for_status(),
+        Err(EventSubError::Unauthorized {
+          missing_scope: None
+        })
+      ),
+      Err(EventSubError::TokenRequiresRefreshing(_)) => true,
+      Err(_) => false,
+    };
+    if !rejected {
+      return response;
+    }
+
+    warn!("Access token was rejected, refreshing it and trying again");
+    let mut retry = self.to_owned();
+    retry.update_token(provider.refresh_access_token()?);
+    retry.send_once()
+  }
+
+  fn send_once(&self) -> Result<TwitchApiResponse<String>, EventSubError> {
+    let middleware = MIDDLEWARE.read().unwrap().to_owned();
+    if middleware.is_empty() {
+      return self.perform();
+    }
+
+    let mut request = self.to_owned();
+    let response = match middleware.iter().find_map(|m| m.before(&mut request)) {
+      Some(response) => response,
+      None => request.perform()?,
+    };
+    for m in &middleware {
+      m.after(&request, &response);
+    }
+    Ok(response)
+  }
+
+  fn perform(&self) -> Result<TwitchApiResponse<String>, EventSubError> {
     let mut data = Vec::new();
+    let mut response_headers = Vec::new();
 
-    info!("Running curl command with:");
-    info!("    url: {}", self.url);
-    let mut handle = Easy::new();
+    let method = self.method();
+    let url = redact_url(&self.url);
+    #[cfg(feature = "tracing")]
+    let span = tracing::info_span!(
+      "http_request",
+      method,
+      url = %url,
+      status = tracing::field::Empty
+    )
+    .entered();
+    info!("{} {}", method, url);
+    if self
+      .cancellation
+      .as_ref()
+      .is_some_and(CancellationToken::is_cancelled)
+    {
+      return Err(EventSubError::Cancelled);
+    }
+
+    let mut handle = take_handle();
     {
       handle.url(&self.url).unwrap();
+      let timeouts = match &self.timeouts {
+        Some(timeouts) => **timeouts,
+        None => *DEFAULT_TIMEOUTS.read().unwrap(),
+      };
+      if let Some(connect) = timeouts.connect {
+        handle.connect_timeout(connect).unwrap();
+      }
+      if let Some(read) = timeouts.read {
+        // Less than a byte a second for that long counts as nothing being received
+        handle.low_speed_limit(1).unwrap();
+        handle.low_speed_time(read).unwrap();
+      }
+      if let Some(total) = timeouts.total {
+        handle.timeout(total).unwrap();
+      }
+      if self.cancellation.is_some() {
+        handle.progress(true).unwrap();
+      }
       if let Some(request) = &self.request_type {
         request.apply(&mut handle);
       }
@@ -412,8 +1097,24 @@ impl TwitchHttpRequest {
         data.extend_from_slice(new_data);
         Ok(new_data.len())
       });
+      let _ = handle.header_function(|header| {
+        response_headers.push(String::from_utf8_lossy(header).trim().to_owned());
+        true
+      });
+      if let Some(cancellation) = &self.cancellation {
+        let _ = handle.progress_function(|_, _, _, _| !cancellation.is_cancelled());
+      }
 
       if let Err(e) = handle.perform() {
+        metrics::api_request(method, &self.url, 0);
+        if e.is_aborted_by_callback() {
+          warn!("{} {} was cancelled", method, url);
+          return Err(EventSubError::Cancelled);
+        }
+        if e.is_operation_timedout() {
+          warn!("{} {} timed out: {}", method, url, e);
+          return Err(EventSubError::TimedOut);
+        }
         if let Ok(error) = serde_json::from_str::<Validation>(&e.to_string()) {
           if error.is_error() {
             if error.status.unwrap() == 401 {
@@ -430,6 +1131,533 @@ impl TwitchHttpRequest {
       }
     }
 
-    Ok(String::from_utf8_lossy(&data).to_string())
+    let status = handle.response_code().unwrap_or_default();
+    let raw = String::from_utf8_lossy(&data).to_string();
+    #[cfg(feature = "tracing")]
+    span.record("status", status);
+    metrics::api_request(method, &self.url, status);
+    return_handle(handle);
+
+    Ok(TwitchApiResponse {
+      status,
+      rate_limit: RateLimit::from_headers(&response_headers),
+      data: raw.to_owned(),
+      raw,
+    })
+  }
+}
+
+// Curl keeps a handle's connections open after a request, so handing the same handles out
+// again saves a TCP and TLS handshake on every request to the same host
+static HANDLE_POOL: Mutex<Vec<Easy>> = Mutex::new(Vec::new());
+
+fn take_handle() -> Easy {
+  match HANDLE_POOL.lock().unwrap().pop() {
+    Some(mut handle) => {
+      // Clears the options of the last request but keeps its connections
+      handle.reset();
+      handle
+    }
+    None => Easy::new(),
+  }
+}
+
+// Handles whose request failed aren't returned, their connection may be broken
+fn return_handle(handle: Easy) {
+  let mut pool = HANDLE_POOL.lock().unwrap();
+  if pool.len() < MAX_POOLED_HTTP_HANDLES {
+    pool.push(handle);
+  }
+}
+
+// Query parameters that carry secrets, which mustn't end up in logs
+const SECRET_QUERY_KEYS: &[&str] = &[
+  "client_secret",
+  "refresh_token",
+  "access_token",
+  "code",
+  "device_code",
+  "token",
+];
+
+/// The url with webhook tokens and the values of any secret query parameters replaced by
+/// `***`.
+pub(crate) fn redact_url(url: &str) -> String {
+  // Webhook urls carry their token as the last part of the path
+  let url = match url.split_once("/webhooks/") {
+    Some((host, hook)) => match hook.split_once('/') {
+      Some((id, _)) => format!("{host}/webhooks/{id}/***"),
+      None => url.to_owned(),
+    },
+    None => url.to_owned(),
+  };
+  let Some((base, query)) = url.split_once('?') else {
+    return url;
+  };
+
+  let query = query
+    .split('&')
+    .map(|pair| match pair.split_once('=') {
+      Some((key, _)) if SECRET_QUERY_KEYS.contains(&key) => format!("{key}=***"),
+      _ => pair.to_owned(),
+    })
+    .collect::<Vec<_>>()
+    .join("&");
+
+  format!("{base}?{query}")
+}
+
+/// Scopes needed by the subscriptions, in the `+` separated form Twitch takes.
+fn scope_list(scopes: &[Subscription]) -> String {
+  let mut scope_list: Vec<String> = Vec::new();
+  for scope in scopes.iter().flat_map(|s| s.required_scope()) {
+    if !scope_list.iter().any(|s| s == scope.as_str()) {
+      scope_list.push(scope.to_string());
+    }
+  }
+
+  scope_list.join("+")
+}
+
+/// Helix rate limit bucket, read from the `Ratelimit-*` response headers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimit {
+  pub limit: u32,
+  pub remaining: u32,
+  /// Unix timestamp of when the bucket is refilled.
+  pub reset: u64,
+}
+
+impl RateLimit {
+  fn from_headers(headers: &[String]) -> Option<RateLimit> {
+    let find = |name: &str| {
+      headers.iter().find_map(|header| {
+        let (key, value) = header.split_once(':')?;
+        if key.trim().eq_ignore_ascii_case(name) {
+          Some(value.trim().to_owned())
+        } else {
+          None
+        }
+      })
+    };
+
+    Some(RateLimit {
+      limit: find("ratelimit-limit")?.parse().ok()?,
+      remaining: find("ratelimit-remaining")?.parse().ok()?,
+      reset: find("ratelimit-reset")?.parse().ok()?,
+    })
+  }
+}
+
+/// A response from the Twitch API with its body parsed into `T`.
+#[derive(Clone, Debug)]
+pub struct TwitchApiResponse<T> {
+  pub status: u32,
+  pub data: T,
+  pub rate_limit: Option<RateLimit>,
+  /// The body exactly as Twitch sent it.
+  pub raw: String,
+}
+
+impl TwitchApiResponse<String> {
+  pub fn parse<T: DeserializeOwned>(self) -> Result<TwitchApiResponse<T>, EventSubError> {
+    self.error_for_status()?;
+
+    let data = parse_response::<T>(self.raw.to_owned())?;
+    Ok(TwitchApiResponse {
+      status: self.status,
+      data,
+      rate_limit: self.rate_limit,
+      raw: self.raw,
+    })
+  }
+}
+
+impl<T> TwitchApiResponse<T> {
+  /// Turns error status codes into the matching EventSubError.
+  pub fn error_for_status(&self) -> Result<(), EventSubError> {
+    if self.status < 400 {
+      return Ok(());
+    }
+
+    let message = TwitchError::from_body(&self.raw).message;
+    Err(match self.status {
+      400 => EventSubError::BadRequest(message),
+      401 => EventSubError::Unauthorized {
+        missing_scope: message
+          .strip_prefix("Missing scope:")
+          .map(|scope| scope.trim().to_owned()),
+      },
+      403 => EventSubError::Forbidden(message),
+      404 => EventSubError::NotFound(message),
+      409 => EventSubError::Conflict(message),
+      429 => EventSubError::RateLimited {
+        reset_at: self
+          .rate_limit
+          .as_ref()
+          .map(|rate_limit| rate_limit.reset)
+          .unwrap_or_default(),
+      },
+      status @ 500.. => EventSubError::ServerError {
+        status,
+        twitch_message: message,
+      },
+      status => EventSubError::HttpError {
+        status,
+        twitch_message: message,
+      },
+    })
+  }
+
+  pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> TwitchApiResponse<U> {
+    TwitchApiResponse {
+      status: self.status,
+      data: f(self.data),
+      rate_limit: self.rate_limit,
+      raw: self.raw,
+    }
+  }
+}
+
+/// The body Twitch sends with an error status, e.g.
+/// `{"error": "Not Found", "status": 404, "message": "..."}`.
+#[derive(Deserialise, Debug, Clone, PartialEq)]
+pub struct TwitchError {
+  #[serde(default)]
+  pub error: String,
+  #[serde(default)]
+  pub status: u32,
+  #[serde(default)]
+  pub message: String,
+}
+
+impl TwitchError {
+  /// Parses an error body, anything that isn't one has the whole body as its message.
+  pub fn from_body(raw: &str) -> TwitchError {
+    serde_json::from_str::<TwitchError>(raw)
+      .ok()
+      .filter(|error| !error.message.is_empty())
+      .unwrap_or(TwitchError {
+        error: String::new(),
+        status: 0,
+        message: raw.to_owned(),
+      })
+  }
+}
+
+/// Anything a request can be rerun into, so a request can be retried once its token
+/// has been refreshed.
+pub trait FromHttpRequest: Sized {
+  fn from_request(request: &TwitchHttpRequest) -> Result<Self, EventSubError>;
+}
+
+impl FromHttpRequest for String {
+  fn from_request(request: &TwitchHttpRequest) -> Result<Self, EventSubError> {
+    request.run()
+  }
+}
+
+impl<T: DeserializeOwned> FromHttpRequest for TwitchApiResponse<T> {
+  fn from_request(request: &TwitchHttpRequest) -> Result<Self, EventSubError> {
+    request.send().and_then(TwitchApiResponse::parse)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::atomic::AtomicUsize;
+
+  use super::*;
+
+  #[test]
+  fn builds_url_without_parameters() {
+    assert_eq!(
+      RequestBuilder::new().build("https://api.twitch.tv/helix/users"),
+      "https://api.twitch.tv/helix/users"
+    );
+  }
+
+  #[test]
+  fn joins_parameters() {
+    let url = RequestBuilder::new()
+      .add_key_value("broadcaster_id", "123")
+      .add_optional_key_value("after", None::<String>)
+      .add_key_value("first", "50")
+      .build("https://api.twitch.tv/helix/moderation/banned");
+    assert_eq!(
+      url,
+      "https://api.twitch.tv/helix/moderation/banned?broadcaster_id=123&first=50"
+    );
+  }
+
+  #[test]
+  fn repeats_keys() {
+    let url = RequestBuilder::new()
+      .add_key_values("id", ["1", "2"])
+      .add_key_values("login", Vec::<String>::new())
+      .add_key_value("login", "someone")
+      .build("https://api.twitch.tv/helix/users");
+    assert_eq!(
+      url,
+      "https://api.twitch.tv/helix/users?id=1&id=2&login=someone"
+    );
+  }
+
+  #[test]
+  fn percent_encodes_keys_and_values() {
+    let url = RequestBuilder::new()
+      .add_key_value("reason", "spam & links, 100% 🙃")
+      .add_key_value("color", "#9146FF")
+      .add_key_value("a+b", "c=d/e?")
+      .add_key_value("unreserved", "aZ09-._~")
+      .build("https://example.com");
+    assert_eq!(
+      url,
+      "https://example.com?reason=spam%20%26%20links%2C%20100%25%20%F0%9F%99%83\
+       &color=%239146FF&a%2Bb=c%3Dd%2Fe%3F&unreserved=aZ09-._~"
+    );
+  }
+
+  #[test]
+  fn appends_to_an_existing_query() {
+    let url = RequestBuilder::new()
+      .add_key_value("first", "10")
+      .build("https://example.com/path?type=all");
+    assert_eq!(url, "https://example.com/path?type=all&first=10");
+  }
+
+  fn response(status: u32, raw: &str) -> TwitchApiResponse<String> {
+    TwitchApiResponse {
+      status,
+      data: raw.to_owned(),
+      rate_limit: None,
+      raw: raw.to_owned(),
+    }
+  }
+
+  fn error_body(status: u32, message: &str) -> String {
+    serde_json::json!({ "error": "Error", "status": status, "message": message }).to_string()
+  }
+
+  #[test]
+  fn maps_error_statuses() {
+    assert_eq!(response(204, "").error_for_status(), Ok(()));
+    assert_eq!(
+      response(400, &error_body(400, "Invalid broadcaster_id")).error_for_status(),
+      Err(EventSubError::BadRequest(
+        "Invalid broadcaster_id".to_owned()
+      ))
+    );
+    assert_eq!(
+      response(403, &error_body(403, "Not a moderator")).error_for_status(),
+      Err(EventSubError::Forbidden("Not a moderator".to_owned()))
+    );
+    assert_eq!(
+      response(404, &error_body(404, "No poll")).error_for_status(),
+      Err(EventSubError::NotFound("No poll".to_owned()))
+    );
+    assert_eq!(
+      response(409, &error_body(409, "Already exists")).error_for_status(),
+      Err(EventSubError::Conflict("Already exists".to_owned()))
+    );
+    assert_eq!(
+      response(503, "upstream unavailable").error_for_status(),
+      Err(EventSubError::ServerError {
+        status: 503,
+        twitch_message: "upstream unavailable".to_owned(),
+      })
+    );
+    assert_eq!(
+      response(422, &error_body(422, "Unprocessable")).error_for_status(),
+      Err(EventSubError::HttpError {
+        status: 422,
+        twitch_message: "Unprocessable".to_owned(),
+      })
+    );
+  }
+
+  #[test]
+  fn reads_missing_scope_from_unauthorised() {
+    assert_eq!(
+      response(
+        401,
+        &error_body(401, "Missing scope: moderator:manage:banned_users")
+      )
+      .error_for_status(),
+      Err(EventSubError::Unauthorized {
+        missing_scope: Some("moderator:manage:banned_users".to_owned()),
+      })
+    );
+    assert_eq!(
+      response(401, &error_body(401, "Invalid OAuth token")).error_for_status(),
+      Err(EventSubError::Unauthorized {
+        missing_scope: None,
+      })
+    );
+  }
+
+  const CANNED_URL: &str = "https://example.invalid/";
+
+  // Middleware is global, so tests using it mustn't clear each other's
+  static MIDDLEWARE_TESTS: Mutex<()> = Mutex::new(());
+
+  struct CannedResponses {
+    seen: Mutex<Vec<String>>,
+  }
+
+  impl HttpMiddleware for CannedResponses {
+    fn before(&self, request: &mut TwitchHttpRequest) -> Option<TwitchApiResponse<String>> {
+      // Other tests' requests run at the same time
+      if !request.url().starts_with(CANNED_URL) {
+        return None;
+      }
+      request.set_header(Header::Custom("X-Test".to_owned(), "1".to_owned()));
+      Some(response(200, request.url()))
+    }
+
+    fn after(&self, request: &TwitchHttpRequest, response: &TwitchApiResponse<String>) {
+      if !request.url().starts_with(CANNED_URL) {
+        return;
+      }
+      assert!(request
+        .headers()
+        .contains(&Header::Custom("X-Test".to_owned(), "1".to_owned())));
+      self.seen.lock().unwrap().push(response.raw.to_owned());
+    }
+  }
+
+  #[test]
+  fn middleware_can_answer_requests() {
+    let _guard = MIDDLEWARE_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+    let middleware = Arc::new(CannedResponses {
+      seen: Mutex::new(Vec::new()),
+    });
+    add_http_middleware(middleware.clone());
+
+    let url = format!("{}helix/users", CANNED_URL);
+    let body = TwitchHttpRequest::new(url.to_owned()).run();
+    clear_http_middleware();
+
+    assert_eq!(body, Ok(url.to_owned()));
+    assert_eq!(*middleware.seen.lock().unwrap(), vec![url]);
+  }
+
+  // Accepts only the token "new", and never has the scope for `scope` urls
+  struct ExpiringToken {
+    requests: AtomicUsize,
+  }
+
+  impl HttpMiddleware for ExpiringToken {
+    fn before(&self, request: &mut TwitchHttpRequest) -> Option<TwitchApiResponse<String>> {
+      if !request.url().starts_with(&format!("{}tokens/", CANNED_URL)) {
+        return None;
+      }
+      self.requests.fetch_add(1, Ordering::SeqCst);
+
+      let token = request.headers().iter().find_map(|header| match header {
+        Header::Auth((_, token)) => Some(token.expose().to_owned()),
+        _ => None,
+      });
+      Some(if request.url().ends_with("scope") {
+        response(401, &error_body(401, "Missing scope: channel:read:vips"))
+      } else if token.as_deref() == Some("new") {
+        response(200, "{}")
+      } else {
+        response(401, &error_body(401, "Invalid OAuth token"))
+      })
+    }
+  }
+
+  #[test]
+  fn refreshes_expired_tokens_but_not_missing_scopes() {
+    let _guard = MIDDLEWARE_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+    let middleware = Arc::new(ExpiringToken {
+      requests: AtomicUsize::new(0),
+    });
+    add_http_middleware(middleware.clone());
+
+    let refreshes = Arc::new(AtomicUsize::new(0));
+    let provider = {
+      let refreshes = refreshes.clone();
+      Arc::new(move || {
+        refreshes.fetch_add(1, Ordering::SeqCst);
+        Ok("new".to_owned())
+      })
+    };
+    let request = |path: &str| {
+      TwitchHttpRequest::new(format!("{}tokens/{}", CANNED_URL, path))
+        .full_auth("old", "client id")
+        .token_provider(provider.clone())
+        .send()
+    };
+
+    let expired = request("expired");
+    let (expired_requests, expired_refreshes) = (
+      middleware.requests.swap(0, Ordering::SeqCst),
+      refreshes.swap(0, Ordering::SeqCst),
+    );
+    let missing_scope = request("scope");
+    clear_http_middleware();
+
+    assert_eq!(expired.unwrap().status, 200);
+    assert_eq!((expired_requests, expired_refreshes), (2, 1));
+    assert_eq!(missing_scope.unwrap().status, 401);
+    assert_eq!(middleware.requests.load(Ordering::SeqCst), 1);
+    assert_eq!(refreshes.load(Ordering::SeqCst), 0);
+  }
+
+  #[test]
+  fn cancelled_requests_are_not_sent() {
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+    let result = TwitchHttpRequest::new("http://127.0.0.1:1/")
+      .cancel_with(cancellation)
+      .run();
+    assert_eq!(result, Err(EventSubError::Cancelled));
+  }
+
+  #[test]
+  fn times_out_when_nothing_is_received() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    // Accepts the connection but never answers
+    let server = thread::spawn(move || listener.accept());
+
+    let result = TwitchHttpRequest::new(url)
+      .timeouts(HttpTimeouts {
+        connect: Some(Duration::from_secs(5)),
+        read: Some(Duration::from_secs(1)),
+        total: None,
+      })
+      .run();
+    assert_eq!(result, Err(EventSubError::TimedOut));
+    let _ = server.join();
+  }
+
+  #[test]
+  fn cancels_from_another_thread() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let server = thread::spawn(move || listener.accept());
+
+    let cancellation = CancellationToken::new();
+    let canceller = cancellation.clone();
+    thread::spawn(move || {
+      thread::sleep(Duration::from_millis(200));
+      canceller.cancel();
+    });
+
+    let started = Instant::now();
+    let result = TwitchHttpRequest::new(url)
+      .cancel_with(cancellation)
+      .timeouts(HttpTimeouts {
+        connect: None,
+        read: None,
+        total: Some(Duration::from_secs(30)),
+      })
+      .run();
+    assert_eq!(result, Err(EventSubError::Cancelled));
+    assert!(started.elapsed() < Duration::from_secs(10));
+    let _ = server.join();
   }
 }
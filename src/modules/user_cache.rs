@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::Event;
+
+/// A user's id, login and display name as last seen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedUser {
+  pub id: String,
+  pub login: String,
+  pub display_name: String,
+}
+
+/// Remembers which id, login and display name belong together so users that were seen in
+/// chat or looked up recently don't need another request to Twitch. Entries expire after the
+/// ttl, and the oldest are dropped once there are more than `max_users`.
+#[derive(Debug)]
+pub struct UserCache {
+  ttl: Duration,
+  max_users: usize,
+  users: HashMap<String, (CachedUser, Instant)>,
+  // Lowercase login to id
+  logins: HashMap<String, String>,
+}
+
+impl UserCache {
+  /// A ttl of zero or `max_users` of zero disables the cache.
+  pub fn new(ttl: Duration, max_users: usize) -> UserCache {
+    UserCache {
+      ttl,
+      max_users,
+      users: HashMap::new(),
+      logins: HashMap::new(),
+    }
+  }
+
+  pub fn insert<S: Into<String>, T: Into<String>, U: Into<String>>(
+    &mut self,
+    id: S,
+    login: T,
+    display_name: U,
+  ) {
+    if self.ttl.is_zero() || self.max_users == 0 {
+      return;
+    }
+
+    let user = CachedUser {
+      id: id.into(),
+      login: login.into(),
+      display_name: display_name.into(),
+    };
+    if user.id.is_empty() || user.login.is_empty() {
+      return;
+    }
+
+    // Logins can be changed, so the old one mustn't point at this user any more
+    if let Some((old, _)) = self.users.remove(&user.id) {
+      self.logins.remove(&old.login.to_lowercase());
+    }
+    // And a login can be taken by someone else after it's been given up
+    if let Some(previous_id) = self
+      .logins
+      .insert(user.login.to_lowercase(), user.id.to_owned())
+    {
+      if previous_id != user.id {
+        self.users.remove(&previous_id);
+      }
+    }
+
+    self
+      .users
+      .insert(user.id.to_owned(), (user, Instant::now()));
+    while self.users.len() > self.max_users {
+      self.remove_oldest();
+    }
+  }
+
+  pub fn by_id(&self, id: &str) -> Option<CachedUser> {
+    self
+      .users
+      .get(id)
+      .filter(|(_, cached_at)| cached_at.elapsed() < self.ttl)
+      .map(|(user, _)| user.to_owned())
+  }
+
+  /// Matches the login ignoring case and a leading `@`, so display names and mentions typed
+  /// in chat can be looked up directly.
+  pub fn by_login(&self, login: &str) -> Option<CachedUser> {
+    let login = login.trim_start_matches('@').to_lowercase();
+    self.logins.get(&login).and_then(|id| self.by_id(id))
+  }
+
+  /// Remembers the users an event names, e.g. the chatter of a chat message.
+  pub fn record_event(&mut self, event: &Event) {
+    match event {
+      Event::ChatMessage(message) => {
+        let chatter = &message.chatter_user;
+        self.insert(&chatter.id, &chatter.login, &chatter.name);
+        let broadcaster = &message.broadcaster_user;
+        self.insert(&broadcaster.id, &broadcaster.login, &broadcaster.name);
+      }
+      Event::Raid(raid) => {
+        let from = &raid.from_broadcaster;
+        self.insert(&from.id, &from.login, &from.name);
+        let to = &raid.to_broadcaster;
+        self.insert(&to.id, &to.login, &to.name);
+      }
+      Event::Follow(follow) => {
+        let user = &follow.user;
+        self.insert(&user.id, &user.login, &user.name);
+      }
+      Event::UserUpdate(update) => {
+        let user = &update.user;
+        self.insert(&user.id, &user.login, &user.name);
+      }
+      _ => {}
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.users.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.users.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.users.clear();
+    self.logins.clear();
+  }
+
+  fn remove_oldest(&mut self) {
+    let Some(oldest) = self
+      .users
+      .iter()
+      .min_by_key(|(_, (_, cached_at))| *cached_at)
+      .map(|(id, _)| id.to_owned())
+    else {
+      return;
+    };
+
+    if let Some((user, _)) = self.users.remove(&oldest) {
+      self.logins.remove(&user.login.to_lowercase());
+    }
+  }
+}
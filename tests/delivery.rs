@@ -0,0 +1,69 @@
+#![cfg(feature = "testing")]
+
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+use twitch_eventsub::{
+  DeliveryGuarantee, Event, MessageType, MockEventSubServer, Subscription, TokenAccess,
+  TwitchEventSubApi, TwitchKeys,
+};
+
+fn keys() -> TwitchKeys {
+  TwitchKeys {
+    authorisation_code: None,
+    access_token: Some(TokenAccess::User("token".into())),
+    refresh_token: None,
+    client_id: "mock".to_owned(),
+    client_secret: "secret".into(),
+    broadcaster_account_id: "1".to_owned(),
+    sender_account_id: None,
+    moderator_account_id: None,
+    chat_reader_account_id: None,
+    token_storage: None,
+  }
+}
+
+// How many follows arrive when the mock sends one and then redelivers it
+fn follows_received(guarantee: DeliveryGuarantee) -> usize {
+  let server = MockEventSubServer::start().unwrap();
+  let twitch = TwitchEventSubApi::builder(keys())
+    .add_subscription(Subscription::ChannelFollow)
+    .delivery_guarantee(guarantee)
+    .build()
+    .unwrap();
+  assert!(server.wait_for_subscriptions(1, Duration::from_secs(5)));
+
+  server.send_notification(
+    "channel.follow",
+    json!({
+      "user_id": "2",
+      "user_login": "follower",
+      "user_name": "Follower",
+      "broadcaster_user_id": "1",
+      "broadcaster_user_login": "mock",
+      "broadcaster_user_name": "Mock",
+      "followed_at": "2023-11-16T10:11:12Z",
+    }),
+  );
+  server.redeliver_last_notification();
+
+  let mut follows = 0;
+  let until = Instant::now() + Duration::from_secs(1);
+  while let Some(remaining) = until.checked_duration_since(Instant::now()) {
+    if let Some(MessageType::Event {
+      event: Event::Follow(_),
+      ..
+    }) = twitch.recv_timeout(remaining)
+    {
+      follows += 1;
+    }
+  }
+  follows
+}
+
+// One test, as the mock server redirects every client in the process
+#[test]
+fn redelivered_notifications_follow_the_delivery_guarantee() {
+  assert_eq!(follows_received(DeliveryGuarantee::AtMostOnce), 1);
+  assert_eq!(follows_received(DeliveryGuarantee::AtLeastOnce), 2);
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "testing")]
+
+use std::time::Duration;
+
+use serde_json::json;
+use twitch_eventsub::{
+  MockEventSubServer, OverflowPolicy, RaidData, RaidDirection, Subscription, TokenAccess,
+  TwitchEventSubApi, TwitchKeys,
+};
+
+fn keys() -> TwitchKeys {
+  TwitchKeys {
+    authorisation_code: None,
+    access_token: Some(TokenAccess::User("token".into())),
+    refresh_token: None,
+    client_id: "mock".to_owned(),
+    client_secret: "secret".into(),
+    broadcaster_account_id: "1".to_owned(),
+    sender_account_id: None,
+    moderator_account_id: None,
+    chat_reader_account_id: None,
+    token_storage: None,
+  }
+}
+
+#[test]
+fn streams_only_queue_the_events_they_want() {
+  let server = MockEventSubServer::start().unwrap();
+  let twitch = TwitchEventSubApi::builder(keys())
+    .add_subscription(Subscription::ChannelFollow)
+    .add_subscription(Subscription::ChannelRaid {
+      direction: RaidDirection::Incoming,
+    })
+    .message_buffer(1, OverflowPolicy::DropOldest)
+    .build()
+    .unwrap();
+  assert!(server.wait_for_subscriptions(2, Duration::from_secs(5)));
+  let raids = twitch.event_stream().only::<RaidData>();
+
+  let user = |id: &str| {
+    json!({
+      "user_id": id,
+      "user_login": "viewer",
+      "user_name": "Viewer",
+      "broadcaster_user_id": "1",
+      "broadcaster_user_login": "mock",
+      "broadcaster_user_name": "Mock",
+    })
+  };
+  let raid = json!({
+    "from_broadcaster_user_id": "2",
+    "from_broadcaster_user_login": "raider",
+    "from_broadcaster_user_name": "Raider",
+    "to_broadcaster_user_id": "1",
+    "to_broadcaster_user_login": "mock",
+    "to_broadcaster_user_name": "Mock",
+    "viewers": 10,
+  });
+  server.send_notification("channel.raid", raid);
+  // With a buffer of one, these would push the raid out if they were queued
+  for id in ["3", "4", "5"] {
+    let mut follow = user(id);
+    follow["followed_at"] = json!("2023-11-16T10:11:12Z");
+    server.send_notification("channel.follow", follow);
+  }
+
+  let raid = raids.recv_timeout(Duration::from_secs(5)).unwrap();
+  assert_eq!(raid.from_broadcaster.id, "2");
+  assert!(raids.recv_timeout(Duration::from_millis(500)).is_none());
+}
@@ -0,0 +1,113 @@
+#![cfg(all(feature = "ffi", feature = "testing"))]
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use twitch_eventsub::MockEventSubServer;
+
+// Called through the C ABI, the same as include/twitch_eventsub.h
+#[repr(C)]
+struct TwitchEventSubClient {
+  _private: [u8; 0],
+}
+
+extern "C" {
+  fn twitch_eventsub_create(
+    client_id: *const c_char,
+    client_secret: *const c_char,
+    broadcaster_id: *const c_char,
+    user_access_token: *const c_char,
+    refresh_token: *const c_char,
+    redirect_url: *const c_char,
+    subscriptions: *const *const c_char,
+    subscription_count: usize,
+  ) -> *mut TwitchEventSubClient;
+  fn twitch_eventsub_destroy(client: *mut TwitchEventSubClient);
+  fn twitch_eventsub_poll_event(client: *mut TwitchEventSubClient) -> *mut c_char;
+  fn twitch_eventsub_access_token(client: *const TwitchEventSubClient) -> *mut c_char;
+  fn twitch_eventsub_last_error() -> *const c_char;
+  fn twitch_eventsub_free_string(string: *mut c_char);
+}
+
+unsafe fn take_string(string: *mut c_char) -> String {
+  let owned = CStr::from_ptr(string).to_str().unwrap().to_owned();
+  twitch_eventsub_free_string(string);
+  owned
+}
+
+#[test]
+fn clients_can_be_created_and_polled_through_the_c_abi() {
+  let server = MockEventSubServer::start().unwrap();
+  let client_id = CString::new("mock").unwrap();
+  let client_secret = CString::new("secret").unwrap();
+  let broadcaster_id = CString::new("1").unwrap();
+  let token = CString::new("token").unwrap();
+  let follow = CString::new("channel.follow").unwrap();
+  let subscriptions = [follow.as_ptr()];
+
+  unsafe {
+    // No token and no redirect url to generate one with
+    let client = twitch_eventsub_create(
+      client_id.as_ptr(),
+      client_secret.as_ptr(),
+      broadcaster_id.as_ptr(),
+      ptr::null(),
+      ptr::null(),
+      ptr::null(),
+      subscriptions.as_ptr(),
+      subscriptions.len(),
+    );
+    assert!(client.is_null());
+    let error = CStr::from_ptr(twitch_eventsub_last_error())
+      .to_str()
+      .unwrap();
+    assert_eq!(error, "No access token provided");
+
+    let client = twitch_eventsub_create(
+      client_id.as_ptr(),
+      client_secret.as_ptr(),
+      broadcaster_id.as_ptr(),
+      token.as_ptr(),
+      ptr::null(),
+      ptr::null(),
+      subscriptions.as_ptr(),
+      subscriptions.len(),
+    );
+    assert!(!client.is_null());
+    assert!(server.wait_for_subscriptions(1, Duration::from_secs(5)));
+    assert_eq!(take_string(twitch_eventsub_access_token(client)), "token");
+
+    server.send_notification(
+      "channel.follow",
+      json!({
+        "user_id": "2",
+        "user_login": "viewer",
+        "user_name": "Viewer",
+        "broadcaster_user_id": "1",
+        "broadcaster_user_login": "mock",
+        "broadcaster_user_name": "Mock",
+        "followed_at": "2023-11-16T10:11:12Z",
+      }),
+    );
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut follow = None;
+    while follow.is_none() && Instant::now() < deadline {
+      let message = twitch_eventsub_poll_event(client);
+      if message.is_null() {
+        std::thread::sleep(Duration::from_millis(10));
+        continue;
+      }
+      let message = serde_json::from_str::<Value>(&take_string(message)).unwrap();
+      if message["metadata"]["subscription_type"] == "channel.follow" {
+        follow = Some(message);
+      }
+    }
+
+    let follow = follow.expect("the follow never arrived");
+    assert_eq!(follow["payload"]["event"]["user_id"], "2");
+    twitch_eventsub_destroy(client);
+  }
+}
@@ -0,0 +1,319 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::json;
+use twitch_eventsub::{
+  Event, GenericMessage, HypeTrainLevels, MessageType, PollStatus, PredictionStatus,
+  RewardRedemptionStatus,
+};
+
+// Every notification fixture and the event variant it should parse into, fixtures for newer
+// versions or other cases of an event end in a suffix
+const FIXTURES: &[(&str, &str)] = &[
+  ("channel.chat.message", "ChatMessage"),
+  ("channel.raid", "Raid"),
+  ("channel.follow", "Follow"),
+  ("channel.update", "ChannelUpdate"),
+  ("channel.subscription.end", "SubscriptionEnd"),
+  ("stream.online", "StreamOnline"),
+  ("stream.offline", "StreamOffline"),
+  ("channel.prediction.end.canceled", "PredictionEnd"),
+  ("channel.subscription.gift.anonymous", "SubscriptionGift"),
+  ("channel.cheer.anonymous", "Cheer"),
+  ("user.update", "UserUpdate"),
+  (
+    "channel.channel_points_custom_reward_redemption.add",
+    "PointsCustomRewardRedeem",
+  ),
+  ("channel.ad_break.begin", "AdBreakBegin"),
+  ("channel.subscribe", "Subscribe"),
+  ("channel.subscription.gift", "SubscriptionGift"),
+  ("channel.subscription.message", "SubscriptionMessage"),
+  ("channel.cheer", "Cheer"),
+  (
+    "channel.channel_points_automatic_reward_redemption.add",
+    "ChannelPointsAutoRewardRedeem",
+  ),
+  ("channel.poll.begin", "PollBegin"),
+  ("channel.poll.progress", "PollProgress"),
+  ("channel.poll.end", "PollEnd"),
+  ("channel.prediction.begin", "PredictionBegin"),
+  ("channel.prediction.progress", "PredictionProgress"),
+  ("channel.prediction.lock", "PredictionLock"),
+  ("channel.prediction.end", "PredictionEnd"),
+  ("channel.hype_train.begin", "HypeTrainBegin"),
+  ("channel.hype_train.progress", "HypeTrainProgress"),
+  ("channel.hype_train.end", "HypeTrainEnd"),
+  ("channel.hype_train.progress.v2", "HypeTrainProgress"),
+  ("automod.message.hold", "AutoModMessageHold"),
+  ("automod.message.update", "AutoModMessageUpdate"),
+  ("automod.settings.update", "AutoModSettingsUpdate"),
+  ("automod.terms.update", "AutoModTermsUpdate"),
+  ("channel.shield_mode.begin", "ShieldModeBegin"),
+  ("channel.shield_mode.end", "ShieldModeEnd"),
+  ("channel.charity_campaign.donate", "CharityDonation"),
+  ("channel.charity_campaign.start", "CharityCampaignStart"),
+  (
+    "channel.charity_campaign.progress",
+    "CharityCampaignProgress",
+  ),
+  ("channel.charity_campaign.stop", "CharityCampaignStop"),
+  ("channel.unban_request.create", "UnbanRequestCreate"),
+  ("channel.unban_request.resolve", "UnbanRequestResolve"),
+  ("channel.goal.begin", "GoalBegin"),
+  ("channel.goal.progress", "GoalProgress"),
+  ("channel.goal.end", "GoalEnd"),
+];
+
+const FIXTURE_SUFFIXES: &[&str] = &[".v2", ".canceled", ".anonymous"];
+
+fn fixtures_dir() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/notifications")
+}
+
+fn parse(json: &str) -> GenericMessage {
+  serde_json::from_str(json).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn event(message: &GenericMessage) -> &Event {
+  message
+    .payload
+    .as_ref()
+    .and_then(|payload| payload.event.as_ref())
+    .expect("notification has no event")
+}
+
+fn variant_name(event: &Event) -> String {
+  format!("{:?}", event).split('(').next().unwrap().to_owned()
+}
+
+#[test]
+fn fixtures_parse_into_expected_events() {
+  for (kind, variant) in FIXTURES {
+    let json = fs::read_to_string(fixtures_dir().join(format!("{}.json", kind)))
+      .unwrap_or_else(|e| panic!("missing fixture for {}: {}", kind, e));
+
+    let message = parse(&json);
+    let subscription_type = FIXTURE_SUFFIXES
+      .iter()
+      .find_map(|suffix| kind.strip_suffix(suffix))
+      .unwrap_or(kind);
+    assert_eq!(
+      message.metadata.subscription_type.as_deref(),
+      Some(subscription_type)
+    );
+    assert_eq!(variant_name(event(&message)), *variant, "fixture {}", kind);
+  }
+}
+
+#[test]
+fn fixtures_round_trip() {
+  for (kind, _) in FIXTURES {
+    let json = fs::read_to_string(fixtures_dir().join(format!("{}.json", kind))).unwrap();
+
+    let message = parse(&json);
+    let serialised = serde_json::to_string(&message).unwrap();
+    let reparsed = parse(&serialised);
+
+    assert_eq!(
+      format!("{:?}", message),
+      format!("{:?}", reparsed),
+      "fixture {} changed after a round trip",
+      kind
+    );
+    assert_eq!(serialised, serde_json::to_string(&reparsed).unwrap());
+  }
+}
+
+#[test]
+fn every_fixture_is_checked() {
+  for entry in fs::read_dir(fixtures_dir()).unwrap() {
+    let path = entry.unwrap().path();
+    let kind = path.file_stem().unwrap().to_string_lossy().into_owned();
+    assert!(
+      FIXTURES.iter().any(|(fixture, _)| *fixture == kind),
+      "{} has no expected event",
+      path.display()
+    );
+  }
+}
+
+fn fixture_event(kind: &str) -> Event {
+  let json = fs::read_to_string(fixtures_dir().join(format!("{}.json", kind))).unwrap();
+  event(&parse(&json)).to_owned()
+}
+
+#[test]
+fn hype_train_versions_have_the_same_accessors() {
+  let Event::HypeTrainProgress(v1) = fixture_event("channel.hype_train.progress") else {
+    panic!("not a hype train");
+  };
+  let Event::HypeTrainProgress(v2) = fixture_event("channel.hype_train.progress.v2") else {
+    panic!("not a hype train");
+  };
+
+  for train in [&v1, &v2] {
+    assert_eq!(train.level_progress(), 0.2);
+    assert_eq!(train.remaining(), 800);
+  }
+  assert!(v1.last_contribution.as_ref().unwrap().is_bits());
+  assert_eq!(v1.top_contributor().unwrap().user.login, "pogchamp");
+  assert!(!v1.is_golden_kappa() && !v1.is_shared());
+
+  assert!(v2.last_contribution.is_none());
+  assert!(v2.top_contributor().unwrap().is_subscription());
+  assert!(v2.is_golden_kappa() && v2.is_shared());
+  assert_eq!(v2.shared_train_participants.as_ref().unwrap().len(), 2);
+  // Ended years ago
+  assert_eq!(v2.time_remaining(), Some(std::time::Duration::ZERO));
+}
+
+#[test]
+fn hype_train_levels_notice_level_ups() {
+  let begin = fixture_event("channel.hype_train.begin");
+  let Event::HypeTrainProgress(mut progress) = fixture_event("channel.hype_train.progress") else {
+    panic!("not a hype train");
+  };
+  let mut levels = HypeTrainLevels::new();
+
+  assert_eq!(levels.update(&begin), None);
+  progress.level = 2;
+  assert_eq!(
+    levels.update(&Event::HypeTrainProgress(progress.clone())),
+    None
+  );
+  progress.level = 3;
+  assert_eq!(levels.update(&Event::HypeTrainProgress(progress)), Some(3));
+}
+
+#[test]
+fn poll_and_prediction_totals() {
+  let Event::PollProgress(poll) = fixture_event("channel.poll.progress") else {
+    panic!("not a poll");
+  };
+  assert_eq!(poll.total_votes(), 340);
+  let leading = poll.leading_choice().unwrap();
+  assert_eq!(leading.title, "Yellow");
+  assert!((poll.percentage(leading) - 41.17647).abs() < 0.001);
+  assert_eq!(poll.choice_by_title("green").unwrap().votes, 80);
+
+  let Event::PredictionEnd(prediction) = fixture_event("channel.prediction.end") else {
+    panic!("not a prediction");
+  };
+  assert_eq!(prediction.total_channel_points(), 19000);
+  assert_eq!(prediction.total_users(), 13);
+  assert_eq!(prediction.leading_outcome().unwrap().title, "Yeah!");
+  let no = prediction.outcome_by_title("No!").unwrap();
+  assert!((prediction.percentage(no) - 21.052631).abs() < 0.001);
+  // The fixture's winner isn't one of its outcomes
+  assert!(prediction.winning_outcome().is_none());
+
+  let Event::PredictionProgress(progress) = fixture_event("channel.prediction.progress") else {
+    panic!("not a prediction");
+  };
+  assert_eq!(progress.time_remaining(), Some(Duration::ZERO));
+}
+
+#[test]
+fn goal_progress() {
+  let Event::GoalProgress(goal) = fixture_event("channel.goal.progress") else {
+    panic!("not a goal");
+  };
+  assert!(goal.is_subscription_goal());
+  assert_eq!(goal.remaining(), 100);
+  assert!((goal.progress() - 0.54545).abs() < 0.001);
+
+  let Event::GoalEnd(goal) = fixture_event("channel.goal.end") else {
+    panic!("not a goal");
+  };
+  assert_eq!(goal.is_achieved, Some(false));
+}
+
+#[test]
+fn channel_and_user_updates() {
+  let Event::ChannelUpdate(update) = fixture_event("channel.update") else {
+    panic!("not a channel update");
+  };
+  assert!(update.has_label("maturegame"));
+  assert_eq!(update.category_name, "Grand Theft Auto");
+
+  let Event::UserUpdate(update) = fixture_event("user.update") else {
+    panic!("not a user update");
+  };
+  assert_eq!(update.email.as_deref(), Some("user@email.com"));
+
+  // Without the email scope Twitch leaves the email out entirely
+  let event = json!({
+    "user_id": "1337",
+    "user_login": "cool_user",
+    "user_name": "Cool_User",
+    "email_verified": true,
+    "description": "",
+  });
+  let Ok(Event::UserUpdate(update)) = Event::from_json(Some("user.update"), event) else {
+    panic!("not a user update");
+  };
+  assert_eq!(update.email, None);
+}
+
+#[test]
+fn typed_statuses() {
+  let Event::PollEnd(poll) = fixture_event("channel.poll.end") else {
+    panic!("not a poll");
+  };
+  assert_eq!(poll.status, PollStatus::Completed);
+
+  let Event::PredictionEnd(prediction) = fixture_event("channel.prediction.end") else {
+    panic!("not a prediction");
+  };
+  assert_eq!(prediction.status, PredictionStatus::Resolved);
+
+  let Event::PointsCustomRewardRedeem(redeem) =
+    fixture_event("channel.channel_points_custom_reward_redemption.add")
+  else {
+    panic!("not a redemption");
+  };
+  assert_eq!(redeem.status, RewardRedemptionStatus::Unfulfilled);
+}
+
+#[test]
+fn anonymous_cheers_have_no_cheerer() {
+  let Event::Cheer(cheer) = fixture_event("channel.cheer.anonymous") else {
+    panic!("not a cheer");
+  };
+  assert!(cheer.is_anonymous);
+  assert!(cheer.user.is_none());
+  assert_eq!(cheer.bits, 1000);
+}
+
+#[test]
+fn anonymous_gifts_have_no_gifter() {
+  let Event::SubscriptionGift(gift) = fixture_event("channel.subscription.gift.anonymous") else {
+    panic!("not a gift");
+  };
+  assert!(gift.is_anonymous);
+  assert!(gift.user.is_none());
+  assert_eq!(gift.cumulative_total, None);
+}
+
+#[test]
+fn redelivered_events_share_a_message_id() {
+  let raw = fs::read_to_string(fixtures_dir().join("channel.raid.json")).unwrap();
+  let message = MessageType::Event {
+    event: fixture_event("channel.raid"),
+    raw: Some(raw.clone()),
+  };
+  assert_eq!(message.message_id(), Some(parse(&raw).metadata.message_id));
+  assert_eq!(MessageType::Close.message_id(), None);
+}
+
+#[test]
+fn canceled_predictions_have_no_winner() {
+  let Event::PredictionEnd(prediction) = fixture_event("channel.prediction.end.canceled") else {
+    panic!("not a prediction");
+  };
+  assert_eq!(prediction.status, PredictionStatus::Canceled);
+  assert_eq!(prediction.winning_outcome_id, None);
+  assert!(prediction.winning_outcome().is_none());
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value};
+use twitch_eventsub::{Condition, GenericMessage};
+
+// Every field in `original` is in `round_tripped` with the same value, nulls and fields
+// that were never sent aren't counted as lost
+fn missing_fields(path: &str, original: &Value, round_tripped: &Value, missing: &mut Vec<String>) {
+  match (original, round_tripped) {
+    (Value::Object(original), Value::Object(round_tripped)) => {
+      for (key, value) in original {
+        let path = format!("{}.{}", path, key);
+        match round_tripped.get(key) {
+          Some(round_tripped) => missing_fields(&path, value, round_tripped, missing),
+          None if value.is_null() => {}
+          None => missing.push(path),
+        }
+      }
+    }
+    (Value::Array(original), Value::Array(round_tripped)) => {
+      for (i, value) in original.iter().enumerate() {
+        let path = format!("{}[{}]", path, i);
+        match round_tripped.get(i) {
+          Some(round_tripped) => missing_fields(&path, value, round_tripped, missing),
+          None => missing.push(path),
+        }
+      }
+    }
+    (original, round_tripped) if original != round_tripped => {
+      missing.push(format!("{} ({} became {})", path, original, round_tripped))
+    }
+    _ => {}
+  }
+}
+
+#[test]
+fn condition_uses_twitchs_field_names() {
+  let condition = Condition {
+    organisation_id: Some("123".to_owned()),
+    ..Condition::new().broadcaster_user_id("1")
+  };
+
+  let json = serde_json::to_value(&condition).unwrap();
+  assert_eq!(json["organization_id"], json!("123"));
+  assert_eq!(json["broadcaster_user_id"], json!("1"));
+
+  let parsed: Condition = serde_json::from_value(json!({
+    "organization_id": "456",
+    "to_broadcaster_user_id": "2",
+  }))
+  .unwrap();
+  assert_eq!(parsed.organisation_id.as_deref(), Some("456"));
+  assert_eq!(parsed.to_broadcaster_user_id.as_deref(), Some("2"));
+}
+
+#[test]
+fn conditions_round_trip() {
+  let condition = Condition::new()
+    .broadcaster_user_id("1")
+    .moderator_user_id("2")
+    .reward_id("reward");
+
+  let json = serde_json::to_string(&condition).unwrap();
+  let parsed: Condition = serde_json::from_str(&json).unwrap();
+  assert_eq!(json, serde_json::to_string(&parsed).unwrap());
+}
+
+#[test]
+fn fixtures_keep_every_field() {
+  let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/notifications");
+
+  let mut missing = Vec::new();
+  for entry in fs::read_dir(dir).unwrap() {
+    let path = entry.unwrap().path();
+    let original: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+    let message: GenericMessage = serde_json::from_value(original.clone()).unwrap();
+    let round_tripped = serde_json::to_value(&message).unwrap();
+
+    let name = path.file_stem().unwrap().to_string_lossy();
+    missing_fields(&name, &original, &round_tripped, &mut missing);
+  }
+
+  assert!(missing.is_empty(), "fields lost: {:#?}", missing);
+}
+
+#[cfg(feature = "extra_fields")]
+#[test]
+fn unknown_fields_are_kept() {
+  let json = fs::read_to_string(
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/notifications/channel.raid.json"),
+  )
+  .unwrap();
+  let mut original: Value = serde_json::from_str(&json).unwrap();
+  original["payload"]["event"]["some_new_field"] = json!({ "nested": [1, 2, 3] });
+
+  let message: GenericMessage = serde_json::from_value(original.clone()).unwrap();
+  let Some(twitch_eventsub::Event::Raid(raid)) = message.payload.as_ref().unwrap().event.as_ref()
+  else {
+    panic!("not a raid");
+  };
+  assert_eq!(raid.extra["some_new_field"], json!({ "nested": [1, 2, 3] }));
+
+  let round_tripped = serde_json::to_value(&message).unwrap();
+  assert_eq!(
+    round_tripped["payload"]["event"]["some_new_field"],
+    original["payload"]["event"]["some_new_field"]
+  );
+}